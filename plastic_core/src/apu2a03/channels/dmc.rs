@@ -1,9 +1,13 @@
 use super::super::channel::{APUChannel, TimedAPUChannel};
+use crate::common::Region;
 use serde::{Deserialize, Serialize};
 
 const DMC_PERIOD_RATES_NTSC: [u16; 0x10] = [
     428, 380, 340, 320, 286, 254, 226, 214, 190, 160, 142, 128, 106, 84, 72, 54,
 ];
+const DMC_PERIOD_RATES_PAL: [u16; 0x10] = [
+    398, 354, 316, 298, 276, 236, 210, 198, 176, 148, 132, 118, 98, 78, 66, 50,
+];
 
 #[derive(Serialize, Deserialize)]
 pub struct Dmc {
@@ -74,9 +78,15 @@ impl Dmc {
         self.loop_flag = flag;
     }
 
-    pub(crate) fn set_rate_index(&mut self, rate_index: u8) {
+    pub(crate) fn set_rate_index(&mut self, rate_index: u8, region: Region) {
+        let table = if region.uses_pal_apu() {
+            &DMC_PERIOD_RATES_PAL
+        } else {
+            &DMC_PERIOD_RATES_NTSC
+        };
+
         // since the table is in CPU clocks, /2 to make it in APU clocks periods
-        self.period = DMC_PERIOD_RATES_NTSC[rate_index as usize & 0xF] / 2;
+        self.period = table[rate_index as usize & 0xF] / 2;
     }
 
     pub(crate) fn set_direct_output_level_load(&mut self, output_level: u8) {
@@ -5,5 +5,5 @@ mod triangle;
 
 pub use dmc::Dmc;
 pub use noise::NoiseWave;
-pub use square::SquarePulse;
+pub use square::{SquarePulse, DUTY_CYCLE_SEQUENCES};
 pub use triangle::TriangleWave;
@@ -1,11 +1,14 @@
 use super::super::channel::{APUChannel, TimedAPUChannel};
 use super::super::envelope::{EnvelopeGenerator, EnvelopedChannel};
+use crate::common::Region;
 use serde::{Deserialize, Serialize};
 
-/// Table for NTSC only
-const NOISE_PERIODS_TABLE: [u16; 0x10] = [
+const NOISE_PERIODS_TABLE_NTSC: [u16; 0x10] = [
     4, 8, 16, 32, 64, 96, 128, 160, 202, 254, 380, 508, 762, 1016, 2034, 4068,
 ];
+const NOISE_PERIODS_TABLE_PAL: [u16; 0x10] = [
+    4, 8, 14, 30, 60, 88, 118, 148, 188, 236, 354, 472, 708, 944, 1890, 3778,
+];
 
 #[derive(Serialize, Deserialize)]
 pub struct NoiseWave {
@@ -33,8 +36,14 @@ impl NoiseWave {
         }
     }
 
-    pub(crate) fn set_period(&mut self, period_index_index: u8) {
-        self.period = NOISE_PERIODS_TABLE[period_index_index as usize & 0xF];
+    pub(crate) fn set_period(&mut self, period_index_index: u8, region: Region) {
+        let table = if region.uses_pal_apu() {
+            &NOISE_PERIODS_TABLE_PAL
+        } else {
+            &NOISE_PERIODS_TABLE_NTSC
+        };
+
+        self.period = table[period_index_index as usize & 0xF];
     }
 
     pub(crate) fn set_mode_flag(&mut self, flag: bool) {
@@ -48,7 +48,7 @@ impl Sweeper {
     }
 }
 
-const DUTY_CYCLE_SEQUENCES: [[u8; 8]; 4] = [
+pub const DUTY_CYCLE_SEQUENCES: [[u8; 8]; 4] = [
     [0, 1, 0, 0, 0, 0, 0, 0],
     [0, 1, 1, 0, 0, 0, 0, 0],
     [0, 1, 1, 1, 1, 0, 0, 0],
@@ -0,0 +1,306 @@
+use super::{APUChannel, TimedAPUChannel};
+use serde::{Deserialize, Serialize};
+
+/// the values added to the modulation counter for each entry of the modulation table
+const MODULATION_ADJUSTMENTS: [i8; 8] = [0, 1, 2, 4, 0, -4, -2, -1];
+/// the entry in the modulation table that resets the counter
+const MODULATION_RESET: u8 = 4;
+
+/// the output is scaled by `2/(2 + master_volume)`
+const MASTER_VOLUME_DIVIDERS: [f32; 4] = [2., 3., 4., 5.];
+
+#[derive(Serialize, Deserialize)]
+struct Envelope {
+    /// ($4080/$4084)
+    /// 7  bit  0
+    /// ---- ----
+    /// MDSS SSSS
+    /// |||| ||||
+    /// ||++-++++- (M=0) Envelope speed
+    /// ||         (M=1) Volume/Gain
+    /// |+-------- (M=0) Envelope direction (0: decrease; 1: increase)
+    /// +--------- Envelope mode (0: envelope on; 1: envelope off)
+    speed: u8,
+    increase: bool,
+    disabled: bool,
+
+    gain: u8,
+    counter: u32,
+}
+
+impl Envelope {
+    fn new() -> Self {
+        Self {
+            speed: 0,
+            increase: false,
+            disabled: true,
+            gain: 0,
+            counter: 0,
+        }
+    }
+
+    fn write(&mut self, data: u8, master_speed: u8) {
+        self.speed = data & 0x3F;
+        self.increase = data & 0x40 != 0;
+        self.disabled = data & 0x80 != 0;
+
+        if self.disabled {
+            self.gain = self.speed;
+        }
+        self.reset_counter(master_speed);
+    }
+
+    fn reset_counter(&mut self, master_speed: u8) {
+        self.counter = 8 * (self.speed as u32 + 1) * master_speed as u32;
+    }
+
+    fn clock(&mut self, master_speed: u8) {
+        if self.disabled {
+            return;
+        }
+
+        if self.counter == 0 {
+            self.reset_counter(master_speed);
+
+            if self.increase {
+                if self.gain < 32 {
+                    self.gain += 1;
+                }
+            } else if self.gain > 0 {
+                self.gain -= 1;
+            }
+        } else {
+            self.counter -= 1;
+        }
+    }
+}
+
+/// Famicom Disk System audio, a single wavetable channel with 64 6bit samples,
+/// with a frequency modulation unit and two envelopes (volume and modulation).
+#[derive(Serialize, Deserialize)]
+pub struct FdsAudio {
+    /// ($4040-$407F)
+    wave_table: Vec<u8>,
+
+    volume_envelope: Envelope,
+    modulation_envelope: Envelope,
+
+    /// ($4082/$4083) 12 bits
+    wave_frequency: u16,
+    /// (`$4083` bit 7)
+    wave_halt: bool,
+    /// (`$4083` bit 6)
+    envelopes_halt: bool,
+    wave_accumulator: u32,
+    wave_position: u8,
+
+    /// ($4085) 7 bit signed
+    modulation_counter: i8,
+    /// ($4086/$4087) 12 bits
+    modulation_frequency: u16,
+    /// (`$4087` bit 7)
+    modulation_halt: bool,
+    /// ($4088) each write fills 2 entries
+    modulation_table: Vec<u8>,
+    modulation_accumulator: u32,
+    modulation_position: u8,
+
+    /// ($4089)
+    /// 7  bit  0
+    /// ---- ----
+    /// W... ..VV
+    /// |      ||
+    /// |      ++- Master volume (0: full; 1: 2/3; 2: 2/4; 3: 2/5)
+    /// +--------- Wavetable write enable
+    master_volume: u8,
+    wave_write_enabled: bool,
+
+    /// ($408A) envelopes clock multiplier
+    envelope_speed: u8,
+
+    output: f32,
+}
+
+impl FdsAudio {
+    pub fn new() -> Self {
+        Self {
+            wave_table: vec![0; 64],
+
+            volume_envelope: Envelope::new(),
+            modulation_envelope: Envelope::new(),
+
+            wave_frequency: 0,
+            wave_halt: true,
+            envelopes_halt: false,
+            wave_accumulator: 0,
+            wave_position: 0,
+
+            modulation_counter: 0,
+            modulation_frequency: 0,
+            modulation_halt: true,
+            modulation_table: vec![0; 64],
+            modulation_accumulator: 0,
+            modulation_position: 0,
+
+            master_volume: 0,
+            wave_write_enabled: false,
+
+            envelope_speed: 0xE8,
+
+            output: 0.,
+        }
+    }
+
+    pub fn read(&self, address: u16) -> Option<u8> {
+        match address {
+            0x4040..=0x407F => Some(self.wave_table[address as usize & 0x3F] | 0x40),
+            0x4090 => Some(self.volume_envelope.gain | 0x40),
+            0x4092 => Some(self.modulation_envelope.gain | 0x40),
+            _ => None,
+        }
+    }
+
+    pub fn write(&mut self, address: u16, data: u8) {
+        match address {
+            0x4040..=0x407F if self.wave_write_enabled => {
+                self.wave_table[address as usize & 0x3F] = data & 0x3F;
+            }
+            0x4080 => self.volume_envelope.write(data, self.envelope_speed),
+            0x4082 => self.wave_frequency = (self.wave_frequency & 0xF00) | data as u16,
+            0x4083 => {
+                self.wave_frequency = (self.wave_frequency & 0xFF) | (data as u16 & 0xF) << 8;
+                self.wave_halt = data & 0x80 != 0;
+                self.envelopes_halt = data & 0x40 != 0;
+
+                if self.wave_halt {
+                    self.wave_accumulator = 0;
+                    self.wave_position = 0;
+                }
+                if self.envelopes_halt {
+                    self.volume_envelope.reset_counter(self.envelope_speed);
+                    self.modulation_envelope.reset_counter(self.envelope_speed);
+                }
+            }
+            0x4084 => self.modulation_envelope.write(data, self.envelope_speed),
+            0x4085 => {
+                // sign extend the 7 bits
+                self.modulation_counter = ((data & 0x7F) << 1) as i8 >> 1;
+            }
+            0x4086 => self.modulation_frequency = (self.modulation_frequency & 0xF00) | data as u16,
+            0x4087 => {
+                self.modulation_frequency =
+                    (self.modulation_frequency & 0xFF) | (data as u16 & 0xF) << 8;
+                self.modulation_halt = data & 0x80 != 0;
+
+                if self.modulation_halt {
+                    self.modulation_accumulator = 0;
+                }
+            }
+            // can only be written while the modulation is halted
+            0x4088 if self.modulation_halt => {
+                let position = self.modulation_position as usize;
+                self.modulation_table[position] = data & 7;
+                self.modulation_table[(position + 1) & 0x3F] = data & 7;
+                self.modulation_position = (self.modulation_position + 2) & 0x3F;
+            }
+            0x4089 => {
+                self.master_volume = data & 3;
+                self.wave_write_enabled = data & 0x80 != 0;
+            }
+            0x408A => self.envelope_speed = data,
+            _ => {}
+        }
+    }
+
+    fn clock_modulation(&mut self) {
+        if self.modulation_halt || self.modulation_frequency == 0 {
+            return;
+        }
+
+        self.modulation_accumulator += self.modulation_frequency as u32;
+        if self.modulation_accumulator < 0x10000 {
+            return;
+        }
+        self.modulation_accumulator &= 0xFFFF;
+
+        let entry = self.modulation_table[self.modulation_position as usize];
+        if entry == MODULATION_RESET {
+            self.modulation_counter = 0;
+        } else {
+            // wrap around in 7 bits
+            let counter = self.modulation_counter + MODULATION_ADJUSTMENTS[entry as usize];
+            self.modulation_counter = (counter << 1) >> 1;
+        }
+        self.modulation_position = (self.modulation_position + 1) & 0x3F;
+    }
+
+    /// the wave frequency after applying the modulation
+    fn modulated_pitch(&self) -> u32 {
+        let counter = self.modulation_counter as i32;
+        let mut temp = counter * self.modulation_envelope.gain as i32;
+        let remainder = temp & 0xF;
+        temp >>= 4;
+        if remainder > 0 && temp & 0x80 == 0 {
+            if counter < 0 {
+                temp -= 1;
+            } else {
+                temp += 2;
+            }
+        }
+
+        if temp >= 192 {
+            temp -= 256;
+        } else if temp < -64 {
+            temp += 256;
+        }
+
+        temp *= self.wave_frequency as i32;
+        let remainder = temp & 0x3F;
+        temp >>= 6;
+        if remainder >= 32 {
+            temp += 1;
+        }
+
+        (self.wave_frequency as i32 + temp).max(0) as u32
+    }
+}
+
+impl APUChannel for FdsAudio {
+    fn get_output(&mut self) -> f32 {
+        self.output
+    }
+}
+
+impl TimedAPUChannel for FdsAudio {
+    fn timer_clock(&mut self) {
+        if !self.envelopes_halt && !self.wave_halt && self.envelope_speed != 0 {
+            self.volume_envelope.clock(self.envelope_speed);
+            self.modulation_envelope.clock(self.envelope_speed);
+        }
+
+        self.clock_modulation();
+
+        if !self.wave_halt {
+            let pitch = if self.modulation_halt {
+                self.wave_frequency as u32
+            } else {
+                self.modulated_pitch()
+            };
+
+            self.wave_accumulator += pitch;
+            while self.wave_accumulator >= 0x10000 {
+                self.wave_accumulator -= 0x10000;
+                self.wave_position = (self.wave_position + 1) & 0x3F;
+            }
+        }
+
+        // the output holds the last value while the wavetable is being written
+        if !self.wave_write_enabled {
+            let sample = self.wave_table[self.wave_position as usize] as f32;
+            let gain = self.volume_envelope.gain.min(32) as f32;
+
+            self.output = sample * gain / (63. * 32.) * 2.
+                / MASTER_VOLUME_DIVIDERS[self.master_volume as usize];
+        }
+    }
+}
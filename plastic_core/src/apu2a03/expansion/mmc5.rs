@@ -0,0 +1,169 @@
+use super::super::channels::DUTY_CYCLE_SEQUENCES;
+use super::super::envelope::EnvelopeGenerator;
+use super::super::length_counter::LengthCounter;
+use super::super::sequencer::Sequencer;
+use super::{APUChannel, TimedAPUChannel};
+use serde::{Deserialize, Serialize};
+
+/// the MMC5 frame counter clocks the envelope and length counter at a fixed 240Hz
+const FRAME_COUNTER_PERIOD: u16 = 7457;
+
+/// Similar to the APU pulse channel, but without the sweep unit
+#[derive(Serialize, Deserialize)]
+struct Mmc5Pulse {
+    period: u16,
+    current_timer: u16,
+
+    envelope_generator: EnvelopeGenerator,
+    sequencer: Sequencer,
+    length_counter: LengthCounter,
+}
+
+impl Mmc5Pulse {
+    fn new() -> Self {
+        Self {
+            period: 0,
+            current_timer: 0,
+
+            envelope_generator: EnvelopeGenerator::new(),
+            sequencer: Sequencer::new(),
+            length_counter: LengthCounter::empty(),
+        }
+    }
+
+    fn write(&mut self, register: u16, data: u8) {
+        match register {
+            0 => {
+                let halt = data & 0x20 != 0;
+
+                self.sequencer
+                    .set_sequence(&DUTY_CYCLE_SEQUENCES[data as usize >> 6]);
+                self.envelope_generator
+                    .set_volume(data & 0xF, data & 0x10 != 0);
+                self.envelope_generator.set_loop_flag(halt);
+                self.envelope_generator.set_start_flag(true);
+                self.length_counter.set_halt(halt);
+            }
+            // no sweep unit
+            1 => {}
+            2 => self.period = (self.period & 0xFF00) | data as u16,
+            3 => {
+                self.period = (self.period & 0xFF) | ((data as u16 & 0b111) << 8);
+                self.length_counter.reload_counter(data >> 3);
+                self.envelope_generator.set_start_flag(true);
+                self.sequencer.reset();
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn output(&mut self) -> f32 {
+        if self.length_counter.counter() == 0 || self.sequencer.get_current_value() == 0 {
+            0.
+        } else {
+            self.envelope_generator.get_current_volume()
+        }
+    }
+
+    fn timer_clock(&mut self) {
+        if self.current_timer == 0 {
+            self.sequencer.clock();
+
+            self.current_timer = self.period;
+        } else {
+            self.current_timer -= 1;
+        }
+    }
+}
+
+/// MMC5 audio, 2 pulse channels and a raw PCM channel
+///
+/// The PCM read mode (sampling reads from `$8000-$BFFF`) and its IRQ are not
+/// implemented, only the raw writes to `$5011`
+#[derive(Serialize, Deserialize)]
+pub struct Mmc5Audio {
+    pulse_1: Mmc5Pulse,
+    pulse_2: Mmc5Pulse,
+
+    /// ($5010) bit 0
+    pcm_read_mode: bool,
+    /// ($5011)
+    pcm_output: u8,
+
+    cycle: u16,
+    is_odd_cycle: bool,
+}
+
+impl Mmc5Audio {
+    pub fn new() -> Self {
+        Self {
+            pulse_1: Mmc5Pulse::new(),
+            pulse_2: Mmc5Pulse::new(),
+
+            pcm_read_mode: false,
+            pcm_output: 0,
+
+            cycle: 0,
+            is_odd_cycle: false,
+        }
+    }
+
+    /// read from the audio registers `$5000-$5015`, returns `None` for
+    /// unreadable registers
+    pub fn read(&self, address: u16) -> Option<u8> {
+        match address {
+            0x5010 => Some(self.pcm_read_mode as u8),
+            0x5015 => Some(
+                ((self.pulse_2.length_counter.counter() != 0) as u8) << 1
+                    | (self.pulse_1.length_counter.counter() != 0) as u8,
+            ),
+            _ => None,
+        }
+    }
+
+    /// write to the audio registers `$5000-$5015`
+    pub fn write(&mut self, address: u16, data: u8) {
+        match address {
+            0x5000..=0x5003 => self.pulse_1.write(address & 3, data),
+            0x5004..=0x5007 => self.pulse_2.write(address & 3, data),
+            0x5010 => self.pcm_read_mode = data & 1 != 0,
+            // writing 0 is ignored
+            0x5011 if !self.pcm_read_mode && data != 0 => self.pcm_output = data,
+            0x5015 => {
+                self.pulse_1.length_counter.set_enabled(data & 1 != 0);
+                self.pulse_2.length_counter.set_enabled(data & 2 != 0);
+            }
+            _ => {}
+        }
+    }
+}
+
+impl APUChannel for Mmc5Audio {
+    fn get_output(&mut self) -> f32 {
+        let pulses = (self.pulse_1.output() + self.pulse_2.output()) / 15.;
+        let pcm = self.pcm_output as f32 / 255.;
+
+        (pulses + pcm) / 3.
+    }
+}
+
+impl TimedAPUChannel for Mmc5Audio {
+    fn timer_clock(&mut self) {
+        // pulse timers are clocked every other CPU cycle like the APU
+        self.is_odd_cycle = !self.is_odd_cycle;
+        if !self.is_odd_cycle {
+            self.pulse_1.timer_clock();
+            self.pulse_2.timer_clock();
+        }
+
+        self.cycle += 1;
+        if self.cycle == FRAME_COUNTER_PERIOD {
+            self.cycle = 0;
+
+            for pulse in [&mut self.pulse_1, &mut self.pulse_2] {
+                pulse.envelope_generator.clock();
+                pulse.length_counter.decrement();
+            }
+        }
+    }
+}
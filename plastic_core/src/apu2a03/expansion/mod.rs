@@ -0,0 +1,65 @@
+//! Audio chips that are found in some Famicom cartridges.
+//!
+//! The chips are owned and clocked by the mappers, the APU only takes their
+//! output and mix it with the internal channels.
+//! Each chip implements [`TimedAPUChannel`][super::channel::TimedAPUChannel],
+//! where `timer_clock` is called on every CPU cycle and `get_output` returns
+//! a value in the range `0.0..=1.0`.
+
+mod fds;
+mod mmc5;
+mod namco163;
+mod sunsoft5b;
+mod vrc6;
+mod vrc7;
+
+pub(crate) use super::channel::{APUChannel, TimedAPUChannel};
+pub use fds::FdsAudio;
+pub use mmc5::Mmc5Audio;
+pub use namco163::Namco163Audio;
+pub use sunsoft5b::Sunsoft5BAudio;
+pub use vrc6::Vrc6Audio;
+pub use vrc7::Vrc7Audio;
+
+/// The level of one APU pulse channel at full volume in the APU mixer output,
+/// used as a reference for the expansion chips levels
+pub(super) const PULSE_FULL_VOLUME_LEVEL: f32 = 95.88 / ((8128. / (15. / 2.2)) + 100.);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExpansionAudioChip {
+    Mmc5,
+    Vrc6,
+    Sunsoft5B,
+    Namco163,
+    Vrc7,
+    Fds,
+}
+
+impl ExpansionAudioChip {
+    /// The level of the chip output at full scale, relative to one
+    /// APU pulse channel at full volume
+    pub fn mix_level(&self) -> f32 {
+        match self {
+            // 2 pulse channels similar to the APU ones, and the PCM channel
+            ExpansionAudioChip::Mmc5 => 3.0,
+            // the pulse channels are close to the APU pulse channels, and the
+            // sawtooth is twice as loud
+            ExpansionAudioChip::Vrc6 => 4.0,
+            // 3 square channels, which are a lot louder than the APU at full volume
+            ExpansionAudioChip::Sunsoft5B => 6.0,
+            // the output of the channels is averaged, so the level does not depend
+            // on the number of enabled channels
+            ExpansionAudioChip::Namco163 => 4.0,
+            // the output is the average of the 6 channels, one channel at full
+            // volume is close to one APU pulse channel
+            ExpansionAudioChip::Vrc7 => 6.0,
+            // the wave channel at full volume is 2.4 times one APU pulse channel
+            ExpansionAudioChip::Fds => 2.4,
+        }
+    }
+
+    /// Scale the `output` of this chip (`0.0..=1.0`) to be mixed with the APU
+    pub fn mix(&self, output: f32) -> f32 {
+        output * self.mix_level()
+    }
+}
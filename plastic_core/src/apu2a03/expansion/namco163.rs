@@ -0,0 +1,186 @@
+use super::{APUChannel, TimedAPUChannel};
+use serde::{Deserialize, Serialize};
+use std::cell::Cell;
+
+/// the number of CPU cycles needed to update one channel
+const CHANNEL_UPDATE_CYCLES: u8 = 15;
+const RAM_SIZE: usize = 0x80;
+/// the channels registers are at the end of the RAM, 8 bytes for each channel,
+/// the first channel (channel 0) registers are at `$40`
+const CHANNELS_REGISTERS_START: usize = 0x40;
+
+/// Namco 163 audio, a wavetable synthesizer with up to 8 channels.
+///
+/// The waveforms and the channels registers are stored in 128 bytes of internal
+/// RAM, which can be used by the game as general purpose RAM as well, and is
+/// battery backed in some games.
+///
+/// The chip updates one channel at a time, so with more channels enabled, each
+/// channel updates slower. The hardware outputs the channels one after the
+/// other, here they are averaged, which is how it sounds after filtering.
+#[derive(Serialize, Deserialize)]
+pub struct Namco163Audio {
+    ram: Vec<u8>,
+
+    /// ($F800-$FFFF)
+    /// 7  bit  0
+    /// ---- ----
+    /// IAAA AAAA
+    /// |||| ||||
+    /// |+++-++++- Address
+    /// +--------- Auto-increment
+    ram_address: Cell<u8>,
+    auto_increment: bool,
+
+    /// (`$E000` bit 6)
+    sound_disabled: bool,
+
+    /// the last output of each channel
+    channels_output: [u8; 8],
+    current_channel: u8,
+    cycles_counter: u8,
+}
+
+impl Namco163Audio {
+    pub fn new() -> Self {
+        Self {
+            ram: vec![0; RAM_SIZE],
+
+            ram_address: Cell::new(0),
+            auto_increment: false,
+
+            sound_disabled: false,
+
+            channels_output: [0; 8],
+            current_channel: 7,
+            cycles_counter: 0,
+        }
+    }
+
+    /// ($F800-$FFFF)
+    pub fn write_address(&mut self, data: u8) {
+        self.ram_address.set(data & 0x7F);
+        self.auto_increment = data & 0x80 != 0;
+    }
+
+    pub fn set_sound_disabled(&mut self, disabled: bool) {
+        self.sound_disabled = disabled;
+    }
+
+    fn increment_address(&self) {
+        if self.auto_increment {
+            self.ram_address.set((self.ram_address.get() + 1) & 0x7F);
+        }
+    }
+
+    /// ($4800-$4FFF)
+    pub fn read_data(&self) -> u8 {
+        let data = self.ram[self.ram_address.get() as usize];
+        self.increment_address();
+
+        data
+    }
+
+    /// ($4800-$4FFF)
+    pub fn write_data(&mut self, data: u8) {
+        self.ram[self.ram_address.get() as usize] = data;
+        self.increment_address();
+    }
+
+    pub fn ram(&self) -> &[u8] {
+        &self.ram
+    }
+
+    pub fn load_ram(&mut self, data: &[u8]) {
+        self.ram.copy_from_slice(data);
+    }
+
+    /// (`$7F` bits 4-6) the number of enabled channels minus one
+    fn enabled_channels(&self) -> u8 {
+        ((self.ram[0x7F] >> 4) & 7) + 1
+    }
+
+    /// Each channel has 8 registers
+    ///
+    /// +0: Low 8 bits of frequency
+    /// +1: Low 8 bits of phase
+    /// +2: Middle 8 bits of frequency
+    /// +3: Middle 8 bits of phase
+    /// +4: High 2 bits of frequency, and wave length (`256 - (value & 0xFC)`)
+    /// +5: High 8 bits of phase
+    /// +6: Wave address (in 4bit samples)
+    /// +7: Volume (low 4 bits)
+    fn update_channel(&mut self, channel: u8) {
+        let base = CHANNELS_REGISTERS_START + channel as usize * 8;
+        let registers = &mut self.ram[base..base + 8];
+
+        let frequency =
+            registers[0] as u32 | (registers[2] as u32) << 8 | (registers[4] as u32 & 3) << 16;
+        let mut phase =
+            registers[1] as u32 | (registers[3] as u32) << 8 | (registers[5] as u32) << 16;
+        let length = 256 - (registers[4] as u32 & 0xFC);
+        let wave_address = registers[6] as u32;
+        let volume = registers[7] & 0xF;
+
+        phase = (phase + frequency) % (length << 16);
+
+        registers[1] = phase as u8;
+        registers[3] = (phase >> 8) as u8;
+        registers[5] = (phase >> 16) as u8;
+
+        let sample_address = ((wave_address + (phase >> 16)) & 0xFF) as usize;
+        let sample_byte = self.ram[sample_address >> 1];
+        let sample = if sample_address & 1 == 0 {
+            sample_byte & 0xF
+        } else {
+            sample_byte >> 4
+        };
+
+        self.channels_output[channel as usize] = sample * volume;
+    }
+}
+
+impl APUChannel for Namco163Audio {
+    fn get_output(&mut self) -> f32 {
+        if self.sound_disabled {
+            return 0.;
+        }
+
+        let enabled_channels = self.enabled_channels();
+        let sum = self.channels_output[(8 - enabled_channels) as usize..]
+            .iter()
+            .map(|&output| output as f32)
+            .sum::<f32>();
+
+        sum / (enabled_channels as f32 * 225.)
+    }
+}
+
+impl TimedAPUChannel for Namco163Audio {
+    fn timer_clock(&mut self) {
+        if self.sound_disabled {
+            return;
+        }
+
+        self.cycles_counter += 1;
+        if self.cycles_counter < CHANNEL_UPDATE_CYCLES {
+            return;
+        }
+        self.cycles_counter = 0;
+
+        // the enabled channels are the last ones, and they are updated
+        // from channel 7 downwards
+        let enabled_channels = self.enabled_channels();
+        if self.current_channel < 8 - enabled_channels {
+            self.current_channel = 7;
+        }
+
+        self.update_channel(self.current_channel);
+
+        if self.current_channel == 8 - enabled_channels {
+            self.current_channel = 7;
+        } else {
+            self.current_channel -= 1;
+        }
+    }
+}
@@ -0,0 +1,268 @@
+use super::{APUChannel, TimedAPUChannel};
+use serde::{Deserialize, Serialize};
+
+/// the internal clock of the chip is the CPU clock divided by 16
+const TONE_CLOCK_DIVIDER: u8 = 16;
+/// the envelope has 32 steps, so it is clocked twice as fast
+const ENVELOPE_CLOCK_DIVIDER: u8 = 8;
+
+#[derive(Serialize, Deserialize)]
+struct ToneChannel {
+    period: u16,
+    counter: u16,
+    output: bool,
+
+    /// 7  bit  0
+    /// ---- ----
+    /// xxxE VVVV
+    ///    | ||||
+    ///    | ++++- Volume
+    ///    +------ Use envelope instead of the volume
+    volume: u8,
+    use_envelope: bool,
+}
+
+impl ToneChannel {
+    fn new() -> Self {
+        Self {
+            period: 0,
+            counter: 0,
+            output: false,
+            volume: 0,
+            use_envelope: false,
+        }
+    }
+
+    fn clock(&mut self) {
+        self.counter += 1;
+        if self.counter >= self.period.max(1) {
+            self.counter = 0;
+            self.output = !self.output;
+        }
+    }
+}
+
+/// Sunsoft 5B audio, a variant of the AY-3-8910/YM2149, it has 3 square
+/// channels, that can be mixed with noise and an envelope.
+#[derive(Serialize, Deserialize)]
+pub struct Sunsoft5BAudio {
+    /// ($C000) the register to write to on `$E000`
+    register_select: u8,
+
+    channels: [ToneChannel; 3],
+
+    /// (R6) 5 bits
+    noise_period: u8,
+    noise_counter: u8,
+    noise_shift_register: u32,
+
+    /// (R7)
+    /// 7  bit  0
+    /// ---- ----
+    /// xxCB Acba
+    ///   || ||||
+    ///   || |+++- Disable tone on channels A, B and C
+    ///   ++-+---- Disable noise on channels A, B and C
+    mixer: u8,
+
+    /// (RB, RC)
+    envelope_period: u16,
+    envelope_counter: u16,
+    /// (RD)
+    /// 7  bit  0
+    /// ---- ----
+    /// xxxx CAaH
+    ///      ||||
+    ///      |||+- Hold
+    ///      ||+-- Alternate
+    ///      |+--- Attack
+    ///      +---- Continue
+    envelope_shape: u8,
+    envelope_attack: bool,
+    envelope_holding: bool,
+    envelope_step: u8,
+    envelope_value: u8,
+
+    tone_clock_counter: u8,
+    envelope_clock_counter: u8,
+}
+
+impl Sunsoft5BAudio {
+    pub fn new() -> Self {
+        Self {
+            register_select: 0,
+
+            channels: [ToneChannel::new(), ToneChannel::new(), ToneChannel::new()],
+
+            noise_period: 0,
+            noise_counter: 0,
+            noise_shift_register: 1,
+
+            mixer: 0xFF,
+
+            envelope_period: 0,
+            envelope_counter: 0,
+            envelope_shape: 0,
+            envelope_attack: false,
+            envelope_holding: true,
+            envelope_step: 0,
+            envelope_value: 0,
+
+            tone_clock_counter: 0,
+            envelope_clock_counter: 0,
+        }
+    }
+
+    /// ($C000-$DFFF)
+    pub fn write_register_select(&mut self, data: u8) {
+        self.register_select = data & 0xF;
+    }
+
+    /// ($E000-$FFFF)
+    pub fn write_register(&mut self, data: u8) {
+        match self.register_select {
+            0..=5 => {
+                let channel = &mut self.channels[self.register_select as usize / 2];
+
+                if self.register_select & 1 == 0 {
+                    channel.period = (channel.period & 0xF00) | data as u16;
+                } else {
+                    channel.period = (channel.period & 0xFF) | (data as u16 & 0xF) << 8;
+                }
+            }
+            6 => self.noise_period = data & 0x1F,
+            7 => self.mixer = data,
+            8..=0xA => {
+                let channel = &mut self.channels[self.register_select as usize - 8];
+
+                channel.volume = data & 0xF;
+                channel.use_envelope = data & 0x10 != 0;
+            }
+            0xB => self.envelope_period = (self.envelope_period & 0xFF00) | data as u16,
+            0xC => self.envelope_period = (self.envelope_period & 0xFF) | (data as u16) << 8,
+            0xD => {
+                self.envelope_shape = data & 0xF;
+                self.envelope_attack = data & 4 != 0;
+                self.envelope_holding = false;
+                self.envelope_step = 0;
+                self.envelope_counter = 0;
+                self.envelope_value = if self.envelope_attack { 0 } else { 31 };
+            }
+            // IO ports
+            _ => {}
+        }
+    }
+
+    fn clock_noise(&mut self) {
+        // the noise is clocked at half the rate of the tone channels
+        self.noise_counter += 1;
+        if self.noise_counter >= self.noise_period.max(1) * 2 {
+            self.noise_counter = 0;
+
+            let feedback = (self.noise_shift_register ^ (self.noise_shift_register >> 3)) & 1;
+            self.noise_shift_register = (self.noise_shift_register >> 1) | feedback << 16;
+        }
+    }
+
+    fn clock_envelope(&mut self) {
+        self.envelope_counter += 1;
+        if self.envelope_counter < self.envelope_period.max(1) {
+            return;
+        }
+        self.envelope_counter = 0;
+
+        if self.envelope_holding {
+            return;
+        }
+
+        self.envelope_step += 1;
+        if self.envelope_step < 32 {
+            self.envelope_value = if self.envelope_attack {
+                self.envelope_step
+            } else {
+                31 - self.envelope_step
+            };
+            return;
+        }
+
+        let is_continue = self.envelope_shape & 8 != 0;
+        let is_alternate = self.envelope_shape & 2 != 0;
+        let is_hold = self.envelope_shape & 1 != 0;
+
+        if !is_continue {
+            self.envelope_holding = true;
+            self.envelope_value = 0;
+        } else if is_hold {
+            self.envelope_holding = true;
+            self.envelope_value = if self.envelope_attack ^ is_alternate {
+                31
+            } else {
+                0
+            };
+        } else {
+            if is_alternate {
+                self.envelope_attack = !self.envelope_attack;
+            }
+            self.envelope_step = 0;
+            self.envelope_value = if self.envelope_attack { 0 } else { 31 };
+        }
+    }
+
+    /// the volume is logarithmic, 1.5dB per envelope step,
+    /// and 3dB for the 4bit volume
+    fn level(envelope_volume: u8) -> f32 {
+        if envelope_volume == 0 {
+            0.
+        } else {
+            10f32.powf((envelope_volume as f32 - 31.) * 1.5 / 20.)
+        }
+    }
+}
+
+impl APUChannel for Sunsoft5BAudio {
+    fn get_output(&mut self) -> f32 {
+        let noise = self.noise_shift_register & 1 != 0;
+
+        let mut output = 0.;
+        for (i, channel) in self.channels.iter().enumerate() {
+            let tone_disabled = self.mixer & (1 << i) != 0;
+            let noise_disabled = self.mixer & (1 << (i + 3)) != 0;
+
+            if (channel.output || tone_disabled) && (noise || noise_disabled) {
+                let volume = if channel.use_envelope {
+                    self.envelope_value
+                } else if channel.volume == 0 {
+                    0
+                } else {
+                    // convert to the 5bit envelope scale
+                    channel.volume * 2 + 1
+                };
+
+                output += Self::level(volume);
+            }
+        }
+
+        output / 3.
+    }
+}
+
+impl TimedAPUChannel for Sunsoft5BAudio {
+    fn timer_clock(&mut self) {
+        self.tone_clock_counter += 1;
+        if self.tone_clock_counter == TONE_CLOCK_DIVIDER {
+            self.tone_clock_counter = 0;
+
+            for channel in self.channels.iter_mut() {
+                channel.clock();
+            }
+            self.clock_noise();
+        }
+
+        self.envelope_clock_counter += 1;
+        if self.envelope_clock_counter == ENVELOPE_CLOCK_DIVIDER {
+            self.envelope_clock_counter = 0;
+
+            self.clock_envelope();
+        }
+    }
+}
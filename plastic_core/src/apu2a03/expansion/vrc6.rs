@@ -0,0 +1,222 @@
+use super::{APUChannel, TimedAPUChannel};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize)]
+struct Vrc6Pulse {
+    /// 7  bit  0
+    /// ---- ----
+    /// MDDD VVVV
+    /// |||| ||||
+    /// |||| ++++- Volume
+    /// |+++------ Duty Cycle
+    /// +--------- Mode (1: ignore duty)
+    volume: u8,
+    duty: u8,
+    ignore_duty: bool,
+
+    period: u16,
+    enabled: bool,
+
+    current_timer: u16,
+    /// goes from 15 down to 0
+    duty_position: u8,
+}
+
+impl Vrc6Pulse {
+    fn new() -> Self {
+        Self {
+            volume: 0,
+            duty: 0,
+            ignore_duty: false,
+            period: 0,
+            enabled: false,
+            current_timer: 0,
+            duty_position: 15,
+        }
+    }
+
+    fn write(&mut self, register: u16, data: u8) {
+        match register {
+            0 => {
+                self.volume = data & 0xF;
+                self.duty = (data >> 4) & 0x7;
+                self.ignore_duty = data & 0x80 != 0;
+            }
+            1 => self.period = (self.period & 0xF00) | data as u16,
+            2 => {
+                self.period = (self.period & 0xFF) | (data as u16 & 0xF) << 8;
+                self.enabled = data & 0x80 != 0;
+
+                if !self.enabled {
+                    self.duty_position = 15;
+                }
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn timer_clock(&mut self, period_shift: u8) {
+        if !self.enabled {
+            return;
+        }
+
+        if self.current_timer == 0 {
+            self.current_timer = self.period >> period_shift;
+            self.duty_position = self.duty_position.checked_sub(1).unwrap_or(15);
+        } else {
+            self.current_timer -= 1;
+        }
+    }
+
+    fn output(&self) -> u8 {
+        if self.enabled && (self.ignore_duty || self.duty_position <= self.duty) {
+            self.volume
+        } else {
+            0
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Vrc6Sawtooth {
+    /// 6 bits, added to the accumulator every other step
+    accumulator_rate: u8,
+    accumulator: u8,
+
+    period: u16,
+    enabled: bool,
+
+    current_timer: u16,
+    /// 0 to 13, the accumulator is reset at the end
+    step: u8,
+}
+
+impl Vrc6Sawtooth {
+    fn new() -> Self {
+        Self {
+            accumulator_rate: 0,
+            accumulator: 0,
+            period: 0,
+            enabled: false,
+            current_timer: 0,
+            step: 0,
+        }
+    }
+
+    fn write(&mut self, register: u16, data: u8) {
+        match register {
+            0 => self.accumulator_rate = data & 0x3F,
+            1 => self.period = (self.period & 0xF00) | data as u16,
+            2 => {
+                self.period = (self.period & 0xFF) | (data as u16 & 0xF) << 8;
+                self.enabled = data & 0x80 != 0;
+
+                if !self.enabled {
+                    self.accumulator = 0;
+                    self.step = 0;
+                }
+            }
+            // unused
+            _ => {}
+        }
+    }
+
+    fn timer_clock(&mut self, period_shift: u8) {
+        if !self.enabled {
+            return;
+        }
+
+        if self.current_timer == 0 {
+            self.current_timer = self.period >> period_shift;
+
+            self.step += 1;
+            if self.step == 14 {
+                self.step = 0;
+                self.accumulator = 0;
+            } else if self.step & 1 == 0 {
+                self.accumulator = self.accumulator.wrapping_add(self.accumulator_rate);
+            }
+        } else {
+            self.current_timer -= 1;
+        }
+    }
+
+    fn output(&self) -> u8 {
+        // the top 5 bits
+        self.accumulator >> 3
+    }
+}
+
+/// Konami VRC6 audio, 2 pulse channels with 8 duty cycles and a sawtooth channel
+#[derive(Serialize, Deserialize)]
+pub struct Vrc6Audio {
+    pulse_1: Vrc6Pulse,
+    pulse_2: Vrc6Pulse,
+    sawtooth: Vrc6Sawtooth,
+
+    /// ($9003)
+    /// 7  bit  0
+    /// ---- ----
+    /// xxxx xABH
+    ///       |||
+    ///       ||+- Halt all channels
+    ///       |+-- 16x frequency (period shifted right by 4)
+    ///       +--- 256x frequency (period shifted right by 8)
+    halt: bool,
+    period_shift: u8,
+}
+
+impl Vrc6Audio {
+    pub fn new() -> Self {
+        Self {
+            pulse_1: Vrc6Pulse::new(),
+            pulse_2: Vrc6Pulse::new(),
+            sawtooth: Vrc6Sawtooth::new(),
+
+            halt: false,
+            period_shift: 0,
+        }
+    }
+
+    /// write to the audio registers `$9000-$B002`, `address` should
+    /// already have its lines fixed (i.e. `$x000-$x003`)
+    pub fn write(&mut self, address: u16, data: u8) {
+        match address {
+            0x9003 => {
+                self.halt = data & 1 != 0;
+                self.period_shift = if data & 4 != 0 {
+                    8
+                } else if data & 2 != 0 {
+                    4
+                } else {
+                    0
+                };
+            }
+            0x9000..=0x9002 => self.pulse_1.write(address & 3, data),
+            0xA000..=0xA002 => self.pulse_2.write(address & 3, data),
+            0xB000..=0xB002 => self.sawtooth.write(address & 3, data),
+            _ => {}
+        }
+    }
+}
+
+impl APUChannel for Vrc6Audio {
+    fn get_output(&mut self) -> f32 {
+        let output = self.pulse_1.output() + self.pulse_2.output() + self.sawtooth.output();
+
+        // max is 15 + 15 + 31
+        output as f32 / 61.
+    }
+}
+
+impl TimedAPUChannel for Vrc6Audio {
+    fn timer_clock(&mut self) {
+        if self.halt {
+            return;
+        }
+
+        self.pulse_1.timer_clock(self.period_shift);
+        self.pulse_2.timer_clock(self.period_shift);
+        self.sawtooth.timer_clock(self.period_shift);
+    }
+}
@@ -0,0 +1,529 @@
+use super::{APUChannel, TimedAPUChannel};
+use serde::{Deserialize, Serialize};
+use std::f32::consts::PI;
+
+/// the chip produces one sample every 72 cycles of its 3.58MHz clock,
+/// which is 36 CPU cycles
+const SAMPLE_CPU_CYCLES: u8 = 36;
+const SAMPLE_RATE: f32 = 1789773. / SAMPLE_CPU_CYCLES as f32;
+
+/// the envelope attenuation is in 0.375dB steps, with 128 steps (48dB)
+const ENVELOPE_STEP_DB: f32 = 0.375;
+const ENVELOPE_MAX: f32 = 127.;
+
+/// the phase is a 19 bit counter for one full cycle
+const PHASE_CYCLE: f32 = (1 << 19) as f32;
+
+/// multiplier values times 2, as `MULT=0` is `0.5`
+const MULTIPLIER_TABLE: [u32; 16] = [1, 2, 4, 6, 8, 10, 12, 14, 16, 18, 20, 20, 24, 24, 30, 30];
+
+/// the key scale level attenuation in dB for the high 4 bits of the frequency,
+/// at the highest octave (block 7)
+const KEY_SCALE_LEVEL_TABLE: [f32; 16] = [
+    0., 18., 24., 27.75, 30., 32.25, 33.75, 35.25, 36., 37.5, 38.25, 39., 39.75, 40.5, 41.25, 42.,
+];
+
+/// built-in instruments 1-15 of the VRC7, instrument 0 is the custom instrument
+/// in registers `$00-$07`
+const BUILTIN_PATCHES: [[u8; 8]; 15] = [
+    [0x03, 0x21, 0x05, 0x06, 0xE8, 0x81, 0x42, 0x27],
+    [0x13, 0x41, 0x14, 0x0D, 0xD8, 0xF6, 0x23, 0x12],
+    [0x11, 0x11, 0x08, 0x08, 0xFA, 0xB2, 0x20, 0x12],
+    [0x31, 0x61, 0x0C, 0x07, 0xA8, 0x64, 0x61, 0x27],
+    [0x32, 0x21, 0x1E, 0x06, 0xE1, 0x76, 0x01, 0x28],
+    [0x02, 0x01, 0x06, 0x00, 0xA3, 0xE2, 0xF4, 0xF4],
+    [0x21, 0x61, 0x1D, 0x07, 0x82, 0x81, 0x11, 0x07],
+    [0x23, 0x21, 0x22, 0x17, 0xA2, 0x72, 0x01, 0x17],
+    [0x35, 0x11, 0x25, 0x00, 0x40, 0x73, 0x72, 0x01],
+    [0xB5, 0x01, 0x0F, 0x0F, 0xA8, 0xA5, 0x51, 0x02],
+    [0x17, 0xC1, 0x24, 0x07, 0xF8, 0xF8, 0x22, 0x12],
+    [0x71, 0x23, 0x11, 0x06, 0x65, 0x74, 0x18, 0x16],
+    [0x01, 0x02, 0xD3, 0x05, 0xC9, 0x95, 0x03, 0x02],
+    [0x61, 0x63, 0x0C, 0x00, 0x94, 0xC0, 0x33, 0xF6],
+    [0x21, 0x72, 0x0D, 0x00, 0xC1, 0xD5, 0x56, 0x06],
+];
+
+/// tremolo (AM) is 4.8dB at 3.7Hz
+const TREMOLO_DEPTH_DB: f32 = 4.8;
+const TREMOLO_FREQUENCY: f32 = 3.7;
+/// vibrato is +-7 cents at 6.4Hz
+const VIBRATO_DEPTH: f32 = 0.0041;
+const VIBRATO_FREQUENCY: f32 = 6.4;
+
+/// the parameters of one operator decoded from the instrument patch
+///
+/// Patch layout (registers `$00-$07` for the custom instrument):
+///
+/// $00/$01 (modulator/carrier)
+/// 7  bit  0
+/// ---- ----
+/// TVSK MMMM
+/// |||| ||||
+/// |||| ++++- Multiplier
+/// |||+------ Key scale rate
+/// ||+------- Sustained tone (0: percussive, 1: sustained)
+/// |+-------- Vibrato
+/// +--------- Tremolo
+///
+/// $02: KKOO OOOO, modulator key scale level (K), modulator total level (O)
+/// $03: KK.C MFFF, carrier key scale level (K), carrier rectified wave (C),
+///      modulator rectified wave (M), modulator feedback (F)
+/// $04/$05: AAAA DDDD, attack rate (A), decay rate (D)
+/// $06/$07: SSSS RRRR, sustain level (S), release rate (R)
+struct OperatorPatch {
+    tremolo: bool,
+    vibrato: bool,
+    sustained: bool,
+    key_scale_rate: bool,
+    multiplier: u8,
+    key_scale_level: u8,
+    /// only for the modulator, in 0.75dB steps
+    total_level: u8,
+    rectified: bool,
+    /// only for the modulator
+    feedback: u8,
+    attack_rate: u8,
+    decay_rate: u8,
+    sustain_level: u8,
+    release_rate: u8,
+}
+
+impl OperatorPatch {
+    fn new(patch: &[u8; 8], is_carrier: bool) -> Self {
+        let i = is_carrier as usize;
+
+        Self {
+            tremolo: patch[i] & 0x80 != 0,
+            vibrato: patch[i] & 0x40 != 0,
+            sustained: patch[i] & 0x20 != 0,
+            key_scale_rate: patch[i] & 0x10 != 0,
+            multiplier: patch[i] & 0xF,
+            key_scale_level: patch[2 + i] >> 6,
+            total_level: if is_carrier { 0 } else { patch[2] & 0x3F },
+            rectified: patch[3] & (if is_carrier { 0x10 } else { 0x08 }) != 0,
+            feedback: if is_carrier { 0 } else { patch[3] & 7 },
+            attack_rate: patch[4 + i] >> 4,
+            decay_rate: patch[4 + i] & 0xF,
+            sustain_level: patch[6 + i] >> 4,
+            release_rate: patch[6 + i] & 0xF,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+enum EnvelopeState {
+    Attack,
+    Decay,
+    Sustain,
+    Release,
+}
+
+/// the state that is shared between the two operators of a channel
+struct ChannelState {
+    frequency: u16,
+    block: u8,
+    sustain: bool,
+    /// tremolo attenuation in dB
+    tremolo: f32,
+    /// vibrato frequency factor
+    vibrato: f32,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Operator {
+    /// in `PHASE_CYCLE` units
+    phase: f32,
+    /// attenuation in `ENVELOPE_STEP_DB` units
+    envelope: f32,
+    envelope_state: EnvelopeState,
+    /// the last 2 outputs, used for feedback
+    last_outputs: [f32; 2],
+}
+
+impl Operator {
+    fn new() -> Self {
+        Self {
+            phase: 0.,
+            envelope: ENVELOPE_MAX,
+            envelope_state: EnvelopeState::Release,
+            last_outputs: [0.; 2],
+        }
+    }
+
+    fn key_on(&mut self) {
+        self.phase = 0.;
+        self.envelope_state = EnvelopeState::Attack;
+    }
+
+    fn key_off(&mut self) {
+        self.envelope_state = EnvelopeState::Release;
+    }
+
+    /// the rate after applying key scaling, in the range `0..=63`
+    fn effective_rate(rate: u8, patch: &OperatorPatch, channel: &ChannelState) -> u8 {
+        if rate == 0 {
+            return 0;
+        }
+
+        let key_code = (channel.block << 1) | (channel.frequency >> 8) as u8;
+        let key_scale = if patch.key_scale_rate {
+            key_code
+        } else {
+            key_code >> 2
+        };
+
+        (rate * 4 + key_scale).min(63)
+    }
+
+    /// the envelope change per sample for a `rate`, in `ENVELOPE_STEP_DB` units
+    fn rate_increment(rate: u8) -> f32 {
+        if rate == 0 {
+            0.
+        } else {
+            (4 + (rate & 3)) as f32 / 4. * 2f32.powi(rate as i32 / 4) / 8192.
+        }
+    }
+
+    fn clock_envelope(&mut self, patch: &OperatorPatch, channel: &ChannelState) {
+        let sustain_level = patch.sustain_level as f32 * 8.;
+
+        match self.envelope_state {
+            EnvelopeState::Attack => {
+                let rate = Self::effective_rate(patch.attack_rate, patch, channel);
+
+                if rate >= 60 {
+                    self.envelope = 0.;
+                } else {
+                    // the attack is exponential
+                    self.envelope -= Self::rate_increment(rate) * (self.envelope / 16. + 1.);
+                }
+
+                if self.envelope <= 0. {
+                    self.envelope = 0.;
+                    self.envelope_state = EnvelopeState::Decay;
+                }
+            }
+            EnvelopeState::Decay => {
+                let rate = Self::effective_rate(patch.decay_rate, patch, channel);
+                self.envelope += Self::rate_increment(rate);
+
+                if self.envelope >= sustain_level {
+                    self.envelope = sustain_level;
+                    self.envelope_state = EnvelopeState::Sustain;
+                }
+            }
+            EnvelopeState::Sustain => {
+                // sustained tones stay at the sustain level until key off
+                if !patch.sustained {
+                    let rate = Self::effective_rate(patch.release_rate, patch, channel);
+                    self.envelope += Self::rate_increment(rate);
+                }
+            }
+            EnvelopeState::Release => {
+                let release_rate = if channel.sustain {
+                    5
+                } else if patch.sustained {
+                    patch.release_rate
+                } else {
+                    7
+                };
+                let rate = Self::effective_rate(release_rate, patch, channel);
+                self.envelope += Self::rate_increment(rate);
+            }
+        }
+
+        self.envelope = self.envelope.min(ENVELOPE_MAX);
+    }
+
+    /// advance the operator by one sample and return its output in the range `-1.0..=1.0`.
+    ///
+    /// `phase_modulation` is in cycles, and `attenuation` is in `ENVELOPE_STEP_DB` units
+    fn clock(
+        &mut self,
+        patch: &OperatorPatch,
+        channel: &ChannelState,
+        phase_modulation: f32,
+        attenuation: f32,
+    ) -> f32 {
+        self.clock_envelope(patch, channel);
+
+        let mut increment = ((channel.frequency as u32) << channel.block) as f32
+            * MULTIPLIER_TABLE[patch.multiplier as usize] as f32
+            / 2.;
+        if patch.vibrato {
+            increment *= channel.vibrato;
+        }
+        self.phase = (self.phase + increment / PHASE_CYCLE).fract();
+
+        if self.envelope >= ENVELOPE_MAX {
+            self.last_outputs = [self.last_outputs[1], 0.];
+            return 0.;
+        }
+
+        let key_scale_level = if patch.key_scale_level == 0 {
+            0.
+        } else {
+            let level = KEY_SCALE_LEVEL_TABLE[(channel.frequency >> 5) as usize & 0xF]
+                - 6. * (7 - channel.block) as f32;
+            // 1.5dB, 3dB and 6dB per octave
+            level.max(0.) / (1 << (3 - patch.key_scale_level)) as f32
+        };
+
+        let mut attenuation_db = (self.envelope + attenuation) * ENVELOPE_STEP_DB + key_scale_level;
+        if patch.tremolo {
+            attenuation_db += channel.tremolo;
+        }
+
+        let mut wave = (2. * PI * (self.phase + phase_modulation)).sin();
+        if patch.rectified && wave < 0. {
+            wave = 0.;
+        }
+
+        let output = wave * 10f32.powf(-attenuation_db / 20.);
+        self.last_outputs = [self.last_outputs[1], output];
+
+        output
+    }
+
+    /// the self modulation of the modulator, in cycles
+    fn feedback(&self, feedback: u8) -> f32 {
+        if feedback == 0 {
+            0.
+        } else {
+            // from pi/16 to 4pi
+            (self.last_outputs[0] + self.last_outputs[1]) / 2. * 2f32.powi(feedback as i32 - 6)
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Channel {
+    /// ($10-$15) low 8 bits, and bit 0 of ($20-$25)
+    frequency: u16,
+
+    /// ($20-$25)
+    /// 7  bit  0
+    /// ---- ----
+    /// ..ST OOOH
+    ///   || ||||
+    ///   || |||+- High bit of the frequency
+    ///   || +++-- Octave (block)
+    ///   |+------ Trigger (key on)
+    ///   +------- Sustain
+    block: u8,
+    key_on: bool,
+    sustain: bool,
+
+    /// ($30-$35)
+    /// 7  bit  0
+    /// ---- ----
+    /// IIII VVVV
+    /// |||| ||||
+    /// |||| ++++- Volume (3dB steps)
+    /// ++++------ Instrument
+    instrument: u8,
+    volume: u8,
+
+    modulator: Operator,
+    carrier: Operator,
+}
+
+impl Channel {
+    fn new() -> Self {
+        Self {
+            frequency: 0,
+            block: 0,
+            key_on: false,
+            sustain: false,
+            instrument: 0,
+            volume: 0,
+            modulator: Operator::new(),
+            carrier: Operator::new(),
+        }
+    }
+
+    fn set_key_on(&mut self, key_on: bool) {
+        if key_on && !self.key_on {
+            self.modulator.key_on();
+            self.carrier.key_on();
+        } else if !key_on && self.key_on {
+            self.modulator.key_off();
+            self.carrier.key_off();
+        }
+
+        self.key_on = key_on;
+    }
+}
+
+/// Konami VRC7 audio, a reduced version of the Yamaha YM2413 (OPLL) FM synthesizer,
+/// with 6 channels, each one is made of two operators (modulator and carrier),
+/// and 15 built-in instruments plus one custom instrument.
+#[derive(Serialize, Deserialize)]
+pub struct Vrc7Audio {
+    /// ($9010) the register to write to on `$9030`
+    register_select: u8,
+
+    /// ($00-$07)
+    custom_patch: [u8; 8],
+    channels: [Channel; 6],
+
+    /// (`$E000` bit 6) the chip is held in reset
+    silenced: bool,
+
+    /// low frequency oscillators phases (in cycles)
+    tremolo_phase: f32,
+    vibrato_phase: f32,
+
+    output: f32,
+    cycles_counter: u8,
+}
+
+impl Vrc7Audio {
+    pub fn new() -> Self {
+        Self {
+            register_select: 0,
+
+            custom_patch: [0; 8],
+            channels: [
+                Channel::new(),
+                Channel::new(),
+                Channel::new(),
+                Channel::new(),
+                Channel::new(),
+                Channel::new(),
+            ],
+
+            silenced: false,
+
+            tremolo_phase: 0.,
+            vibrato_phase: 0.,
+
+            output: 0.,
+            cycles_counter: 0,
+        }
+    }
+
+    /// (`$E000` bit 6) silence and reset the chip
+    pub fn set_silenced(&mut self, silenced: bool) {
+        if silenced {
+            let register_select = self.register_select;
+            *self = Self::new();
+            self.register_select = register_select;
+        }
+
+        self.silenced = silenced;
+    }
+
+    /// ($9010)
+    pub fn write_register_select(&mut self, data: u8) {
+        self.register_select = data;
+    }
+
+    /// ($9030)
+    pub fn write_register(&mut self, data: u8) {
+        if self.silenced {
+            return;
+        }
+
+        let register = self.register_select;
+        let channel_index = (register & 0xF) as usize;
+
+        match register {
+            0x00..=0x07 => self.custom_patch[register as usize] = data,
+            0x10..=0x15 | 0x20..=0x25 | 0x30..=0x35 => {
+                let channel = &mut self.channels[channel_index];
+
+                match register >> 4 {
+                    1 => channel.frequency = (channel.frequency & 0x100) | data as u16,
+                    2 => {
+                        channel.frequency = (channel.frequency & 0xFF) | (data as u16 & 1) << 8;
+                        channel.block = (data >> 1) & 7;
+                        channel.sustain = data & 0x20 != 0;
+                        channel.set_key_on(data & 0x10 != 0);
+                    }
+                    3 => {
+                        channel.instrument = data >> 4;
+                        channel.volume = data & 0xF;
+                    }
+                    _ => unreachable!(),
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn patch(&self, instrument: u8) -> &[u8; 8] {
+        if instrument == 0 {
+            &self.custom_patch
+        } else {
+            &BUILTIN_PATCHES[instrument as usize - 1]
+        }
+    }
+
+    fn generate_sample(&mut self) -> f32 {
+        self.tremolo_phase = (self.tremolo_phase + TREMOLO_FREQUENCY / SAMPLE_RATE).fract();
+        self.vibrato_phase = (self.vibrato_phase + VIBRATO_FREQUENCY / SAMPLE_RATE).fract();
+
+        let tremolo = TREMOLO_DEPTH_DB * (1. - (2. * PI * self.tremolo_phase).cos()) / 2.;
+        let vibrato = 1. + VIBRATO_DEPTH * (2. * PI * self.vibrato_phase).sin();
+
+        let mut output = 0.;
+        for i in 0..self.channels.len() {
+            let patch = *self.patch(self.channels[i].instrument);
+            let modulator_patch = OperatorPatch::new(&patch, false);
+            let carrier_patch = OperatorPatch::new(&patch, true);
+
+            let channel = &mut self.channels[i];
+            let state = ChannelState {
+                frequency: channel.frequency,
+                block: channel.block,
+                sustain: channel.sustain,
+                tremolo,
+                vibrato,
+            };
+
+            let feedback = channel.modulator.feedback(modulator_patch.feedback);
+            let modulator_output = channel.modulator.clock(
+                &modulator_patch,
+                &state,
+                feedback,
+                modulator_patch.total_level as f32 * 2.,
+            );
+            // the modulator shifts the carrier phase by up to 4pi
+            output += channel.carrier.clock(
+                &carrier_patch,
+                &state,
+                modulator_output * 2.,
+                channel.volume as f32 * 8.,
+            );
+        }
+
+        output / self.channels.len() as f32
+    }
+}
+
+impl APUChannel for Vrc7Audio {
+    fn get_output(&mut self) -> f32 {
+        if self.silenced {
+            return 0.;
+        }
+
+        // convert from `-1.0..=1.0`
+        (self.output + 1.) / 2.
+    }
+}
+
+impl TimedAPUChannel for Vrc7Audio {
+    fn timer_clock(&mut self) {
+        if self.silenced {
+            return;
+        }
+
+        self.cycles_counter += 1;
+        if self.cycles_counter == SAMPLE_CPU_CYCLES {
+            self.cycles_counter = 0;
+
+            self.output = self.generate_sample();
+        }
+    }
+}
@@ -2,13 +2,14 @@ mod apu2a03_registers;
 mod channel;
 mod channels;
 mod envelope;
+pub(crate) mod expansion;
 mod length_counter;
 mod sequencer;
 
 use crate::common::{
-    interconnection::{APUCPUConnection, CPUIrqProvider},
+    interconnection::{APUCPUConnection, CPUIrqProvider, ExpansionAudioProvider},
     save_state::{Savable, SaveError},
-    CPU_FREQ,
+    Region,
 };
 use apu2a03_registers::Register;
 use channel::{BufferedChannel, Dac, TimedAPUChannel};
@@ -16,16 +17,18 @@ use channels::{Dmc, NoiseWave, SquarePulse, TriangleWave};
 use envelope::EnvelopedChannel;
 use length_counter::LengthCountedChannel;
 use serde::{Deserialize, Serialize};
-use std::cell::Cell;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
 
 // for performance
 /// The sample rate expected to get from [`NES::audio_buffer`](crate::NES::audio_buffer)
 /// Do note that the audio is mono, i.e. 1 channel
 pub const SAMPLE_RATE: u32 = 44100;
 
-// after how many apu clocks a sample should be recorded
-// APU, is clocked on every CPU clock
-const SAMPLES_EVERY_N_APU_CLOCK: f64 = CPU_FREQ / (SAMPLE_RATE as f64);
+/// The CPU cycles of the frame counter steps: the 3 first steps, then the last step
+/// of the 4-step sequence and the last step of the 5-step sequence
+const FRAME_COUNTER_STEPS_NTSC: [u16; 5] = [7457, 14913, 22371, 29829, 37281];
+const FRAME_COUNTER_STEPS_PAL: [u16; 5] = [8313, 16627, 24939, 33253, 41565];
 
 #[derive(Serialize, Deserialize)]
 pub struct APU2A03 {
@@ -51,10 +54,20 @@ pub struct APU2A03 {
 
     interrupt_flag: Cell<bool>,
     request_interrupt_flag_change: Cell<bool>,
+
+    /// the cartridge, to get the output of the expansion audio chips
+    #[serde(skip)]
+    expansion_audio_provider: Option<Rc<RefCell<dyn ExpansionAudioProvider>>>,
+    /// used for the high-pass filter of the expansion audio, similar to [`Dac`]
+    expansion_audio_capacitor: f32,
+
+    /// not part of the state, it depends on the emulator and not the game
+    #[serde(skip)]
+    region: Region,
 }
 
 impl APU2A03 {
-    pub fn new() -> Self {
+    pub fn new(region: Region) -> Self {
         let buffered_channel = BufferedChannel::new();
 
         Self {
@@ -83,9 +96,21 @@ impl APU2A03 {
 
             interrupt_flag: Cell::new(false),
             request_interrupt_flag_change: Cell::new(false),
+
+            expansion_audio_provider: None,
+            expansion_audio_capacitor: 0.,
+
+            region,
         }
     }
 
+    pub fn set_expansion_audio_provider(
+        &mut self,
+        provider: Rc<RefCell<dyn ExpansionAudioProvider>>,
+    ) {
+        self.expansion_audio_provider = Some(provider);
+    }
+
     pub(crate) fn read_register(&self, register: Register) -> u8 {
         match register {
             Register::Status => {
@@ -298,7 +323,7 @@ impl APU2A03 {
             }
             Register::Noise3 => {
                 self.noise.channel_mut().set_mode_flag(data & 0x80 != 0);
-                self.noise.channel_mut().set_period(data & 0xF);
+                self.noise.channel_mut().set_period(data & 0xF, self.region);
             }
             Register::Noise4 => {
                 self.noise.length_counter_mut().reload_counter(data >> 3);
@@ -308,7 +333,7 @@ impl APU2A03 {
                 let loop_flag = data & 0x40 != 0;
                 let irq_enabled = data & 0x80 != 0;
 
-                self.dmc.set_rate_index(rate_index);
+                self.dmc.set_rate_index(rate_index, self.region);
                 self.dmc.set_loop_flag(loop_flag);
                 self.dmc.set_irq_enabled_flag(irq_enabled);
             }
@@ -403,7 +428,20 @@ impl APU2A03 {
             159.79 / ((1. / ((triangle / 8227.) + (noise / 12241.) + (dmc / 22638.))) + 100.)
         };
 
-        pulse_out + tnd_out
+        pulse_out + tnd_out + self.get_expansion_audio_output()
+    }
+
+    fn get_expansion_audio_output(&mut self) -> f32 {
+        let expansion_in = if let Some(provider) = &self.expansion_audio_provider {
+            provider.borrow_mut().expansion_audio_output() * expansion::PULSE_FULL_VOLUME_LEVEL
+        } else {
+            return 0.;
+        };
+        let expansion_out = expansion_in - self.expansion_audio_capacitor;
+
+        self.expansion_audio_capacitor = expansion_in - expansion_out * 0.996;
+
+        expansion_out
     }
 
     /// clock the APU **at** CPU clock rate, the clocks are handled correctly
@@ -425,13 +463,17 @@ impl APU2A03 {
             std::cmp::Ordering::Greater => self.wait_reset -= 1,
         }
 
+        // after how many apu clocks a sample should be recorded
+        // APU, is clocked on every CPU clock
+        let samples_every_n_apu_clock = self.region.cpu_frequency() / SAMPLE_RATE as f64;
+
         self.sample_counter += 1.;
-        if self.sample_counter >= SAMPLES_EVERY_N_APU_CLOCK {
+        if self.sample_counter >= samples_every_n_apu_clock {
             let output = self.get_mixer_output();
 
             self.buffered_channel.recored_sample(output);
 
-            self.sample_counter -= SAMPLES_EVERY_N_APU_CLOCK;
+            self.sample_counter -= samples_every_n_apu_clock;
         }
 
         // clocked on every CPU cycle
@@ -446,37 +488,40 @@ impl APU2A03 {
 
         self.cycle += 1;
 
+        let steps = if self.region.uses_pal_apu() {
+            FRAME_COUNTER_STEPS_PAL
+        } else {
+            FRAME_COUNTER_STEPS_NTSC
+        };
+
         // this is clocked in every CPU cycle, so the numbers are multiplied by 2
         match self.cycle {
-            7457 => {
+            cycle if cycle == steps[0] || cycle == steps[2] => {
                 self.generate_quarter_frame_clock();
             }
-            14913 => {
+            cycle if cycle == steps[1] => {
                 self.generate_quarter_frame_clock();
                 self.generate_half_frame_clock();
             }
-            22371 => {
-                self.generate_quarter_frame_clock();
-            }
-            29828 if self.is_4_step_squence_mode => {
+            cycle if self.is_4_step_squence_mode && cycle == steps[3] - 1 => {
                 self.update_irq_pin();
             }
-            29829 if self.is_4_step_squence_mode => {
+            cycle if self.is_4_step_squence_mode && cycle == steps[3] => {
                 self.generate_quarter_frame_clock();
                 self.generate_half_frame_clock();
 
                 self.update_irq_pin();
             }
-            29830 if self.is_4_step_squence_mode => {
+            cycle if self.is_4_step_squence_mode && cycle == steps[3] + 1 => {
                 self.update_irq_pin();
 
                 self.cycle = 0;
             }
-            37281 if !self.is_4_step_squence_mode => {
+            cycle if !self.is_4_step_squence_mode && cycle == steps[4] => {
                 self.generate_quarter_frame_clock();
                 self.generate_half_frame_clock();
             }
-            37282 if !self.is_4_step_squence_mode => {
+            cycle if !self.is_4_step_squence_mode && cycle == steps[4] + 1 => {
                 self.cycle = 0;
             }
             _ => {
@@ -513,7 +558,7 @@ impl CPUIrqProvider for APU2A03 {
 
 impl Default for APU2A03 {
     fn default() -> Self {
-        Self::new()
+        Self::new(Region::default())
     }
 }
 
@@ -538,11 +583,15 @@ impl Savable for APU2A03 {
     }
 
     fn load<R: std::io::Read>(&mut self, reader: &mut R) -> Result<(), SaveError> {
-        let state: APU2A03 = bincode::deserialize_from(reader).map_err(|err| match *err {
+        let mut state: APU2A03 = bincode::deserialize_from(reader).map_err(|err| match *err {
             bincode::ErrorKind::Io(err) => SaveError::IoError(err),
             _ => SaveError::SerializationError,
         })?;
 
+        // not part of the state
+        state.expansion_audio_provider = self.expansion_audio_provider.take();
+        state.region = self.region;
+
         let _ = std::mem::replace(self, state);
 
         Ok(())
@@ -0,0 +1,97 @@
+//! Compressed ROMs in `.zip`, `.7z` and `.gz` archives.
+//!
+//! Zip and 7z archives can contain multiple files, the first file with a supported
+//! extension is loaded, and gzip archives contain only one file.
+
+use super::error::CartridgeError;
+use std::io::{Cursor, Read};
+
+const ZIP_MAGIC: &[u8] = b"PK\x03\x04";
+/// empty zip archives start with the end of central directory record
+const EMPTY_ZIP_MAGIC: &[u8] = b"PK\x05\x06";
+const SEVEN_ZIP_MAGIC: &[u8] = b"7z\xBC\xAF\x27\x1C";
+const GZIP_MAGIC: &[u8] = b"\x1F\x8B";
+
+/// the extensions of the files that can be loaded from zip and 7z archives
+const ROM_EXTENSIONS: [&str; 6] = ["nes", "unf", "unif", "fds", "nsf", "nsfe"];
+
+pub(super) fn is_archive(data: &[u8]) -> bool {
+    [ZIP_MAGIC, EMPTY_ZIP_MAGIC, SEVEN_ZIP_MAGIC, GZIP_MAGIC]
+        .iter()
+        .any(|magic| data.starts_with(magic))
+}
+
+/// Decompress the ROM file inside the archive `data`
+pub(super) fn extract_rom(data: &[u8]) -> Result<Vec<u8>, CartridgeError> {
+    if data.starts_with(GZIP_MAGIC) {
+        extract_gzip(data)
+    } else if data.starts_with(SEVEN_ZIP_MAGIC) {
+        extract_7z(data)
+    } else {
+        extract_zip(data)
+    }
+}
+
+fn extract_gzip(data: &[u8]) -> Result<Vec<u8>, CartridgeError> {
+    let mut rom = Vec::new();
+    flate2::read::GzDecoder::new(data)
+        .read_to_end(&mut rom)
+        .map_err(|_| CartridgeError::ArchiveError)?;
+
+    Ok(rom)
+}
+
+fn extract_zip(data: &[u8]) -> Result<Vec<u8>, CartridgeError> {
+    let mut archive =
+        zip::ZipArchive::new(Cursor::new(data)).map_err(|_| CartridgeError::ArchiveError)?;
+
+    for i in 0..archive.len() {
+        let mut file = archive
+            .by_index(i)
+            .map_err(|_| CartridgeError::ArchiveError)?;
+
+        if file.is_file() && is_rom_file_name(file.name()) {
+            let mut rom = Vec::new();
+            file.read_to_end(&mut rom)
+                .map_err(|_| CartridgeError::ArchiveError)?;
+
+            return Ok(rom);
+        }
+    }
+
+    Err(CartridgeError::ArchiveError)
+}
+
+fn extract_7z(data: &[u8]) -> Result<Vec<u8>, CartridgeError> {
+    let mut archive = sevenz_rust::SevenZReader::new(
+        Cursor::new(data),
+        data.len() as u64,
+        sevenz_rust::Password::empty(),
+    )
+    .map_err(|_| CartridgeError::ArchiveError)?;
+
+    let mut rom = None;
+    archive
+        .for_each_entries(|entry, reader| {
+            if rom.is_none() && !entry.is_directory() && is_rom_file_name(entry.name()) {
+                let mut data = Vec::new();
+                reader.read_to_end(&mut data)?;
+                rom = Some(data);
+            }
+            // continue until we get a ROM
+            Ok(rom.is_none())
+        })
+        .map_err(|_| CartridgeError::ArchiveError)?;
+
+    rom.ok_or(CartridgeError::ArchiveError)
+}
+
+fn is_rom_file_name(name: &str) -> bool {
+    name.rsplit_once('.')
+        .map(|(_, extension)| {
+            ROM_EXTENSIONS
+                .iter()
+                .any(|ext| extension.eq_ignore_ascii_case(ext))
+        })
+        .unwrap_or(false)
+}
@@ -24,6 +24,32 @@ pub enum CartridgeError {
 
     /// The mapper type is not implemented.
     MapperNotImplemented(u16),
+
+    /// The Famicom Disk System BIOS (`disksys.rom`) was not found, or has the wrong size.
+    FdsBiosError,
+
+    /// The Famicom Disk System image is invalid or corrupted.
+    FdsImageError,
+
+    /// The NSF/NSFe music file is invalid or corrupted.
+    NsfFileError,
+
+    /// The UNIF file is invalid or corrupted.
+    UnifFileError,
+
+    /// The board of the UNIF file is not known or not supported.
+    /// Contains the board name.
+    UnknownUnifBoard(String),
+
+    /// The archive is invalid or corrupted, or does not contain a supported file.
+    ArchiveError,
+
+    /// The IPS/BPS/UPS patch file is invalid or corrupted.
+    PatchError,
+
+    /// The BPS/UPS patch was made for a different ROM, the size or the checksum
+    /// of the ROM does not match.
+    PatchMismatch,
 }
 
 impl CartridgeError {
@@ -37,7 +63,23 @@ impl CartridgeError {
                 size
             ),
             Self::MapperNotImplemented(id) => format!("Mapper {} is not yet implemented", id),
-            Self::ExtensionError => "The cartridge file must end with `.nes` extension".to_owned(),
+            Self::ExtensionError => "The cartridge file must end with `.nes`, `.unf`, `.unif`, \
+                                     `.fds`, `.nsf`, `.nsfe`, `.zip`, `.7z` or `.gz` extension"
+                .to_owned(),
+            Self::FdsBiosError => "The Famicom Disk System BIOS `disksys.rom` was not found \
+                                   or is not 8kb"
+                .to_owned(),
+            Self::FdsImageError => "This is not a valid Famicom Disk System image".to_owned(),
+            Self::NsfFileError => "This is not a valid NSF/NSFe file".to_owned(),
+            Self::UnifFileError => "This is not a valid UNIF file".to_owned(),
+            Self::UnknownUnifBoard(board) => format!("UNIF board `{}` is not supported", board),
+            Self::ArchiveError => "The archive is not valid or does not contain a `.nes`, \
+                                   `.unf`, `.fds`, `.nsf` or `.nsfe` file"
+                .to_owned(),
+            Self::PatchError => "The patch file is not a valid IPS, BPS or UPS patch".to_owned(),
+            Self::PatchMismatch => "The patch was made for a different ROM, \
+                                    the ROM checksum does not match"
+                .to_owned(),
         }
     }
 }
@@ -66,6 +108,7 @@ pub enum SramError {
     NoSramFileFound,
     SramFileSizeDoesNotMatch,
     FailedToSaveSramFile,
+    InvalidDiskDiffFile,
     Others,
 }
 
@@ -76,6 +119,8 @@ impl SramError {
             Self::SramFileSizeDoesNotMatch => "There is a conflict in the size \
                                             of SRAM save file in the INES header and the file in disk",
             Self::FailedToSaveSramFile => "Could not save cartridge save file",
+            Self::InvalidDiskDiffFile => "The disk save file is corrupted or does not \
+                                          match the disk image",
             Self::Others => "Unknown error occured while trying to save/load \
                           cartridge save file",
         }
@@ -0,0 +1,161 @@
+//! Famicom Disk System disk images.
+//!
+//! `.fds` files store the blocks of each disk side without the gaps and CRCs that
+//! are on the real disk. The drive emulation needs them, so the sides are converted
+//! to the raw format when loaded.
+//!
+//! Writes to the disk are not saved into the image, instead, the difference between
+//! the raw sides of the image and the modified sides is saved to a side-car file.
+
+use super::error::{CartridgeError, SramError};
+use std::io::Read;
+
+const FDS_HEADER_MAGIC: &[u8] = b"FDS\x1A";
+const FDS_HEADER_SIZE: usize = 16;
+/// the size of one side in `.fds` images
+const DISK_SIDE_SIZE: usize = 65500;
+const DISK_VERIFICATION: &[u8] = b"\x01*NINTENDO-HVC*";
+
+/// 28300 bits of gap before the first block
+const LEADING_GAP_SIZE: usize = 28300 / 8;
+/// 976 bits of gap between blocks
+const BLOCK_GAP_SIZE: usize = 976 / 8;
+const BLOCK_START_MARK: u8 = 0x80;
+/// the raw sides are padded to this size, so that new files can be written
+const RAW_DISK_SIDE_SIZE: usize = DISK_SIDE_SIZE + LEADING_GAP_SIZE + 0x2000;
+
+/// Check if `data` is a `.fds` image, with or without the header
+pub(super) fn is_fds_image(data: &[u8]) -> bool {
+    data.starts_with(FDS_HEADER_MAGIC) || data.starts_with(DISK_VERIFICATION)
+}
+
+/// Split the `.fds` image `data` into sides in the raw format,
+/// supports images with and without the header
+pub(super) fn raw_disk_sides(data: &[u8]) -> Result<Vec<Vec<u8>>, CartridgeError> {
+    let data = if data.starts_with(FDS_HEADER_MAGIC) {
+        &data[FDS_HEADER_SIZE..]
+    } else {
+        data
+    };
+
+    if data.is_empty() || data.len() % DISK_SIDE_SIZE != 0 {
+        return Err(CartridgeError::FdsImageError);
+    }
+
+    data.chunks(DISK_SIDE_SIZE)
+        .map(|side| {
+            if side.starts_with(DISK_VERIFICATION) {
+                Ok(side_to_raw(side))
+            } else {
+                Err(CartridgeError::FdsImageError)
+            }
+        })
+        .collect()
+}
+
+/// Add the gaps and the block start marks to the blocks of `side`
+fn side_to_raw(side: &[u8]) -> Vec<u8> {
+    let mut raw = vec![0; LEADING_GAP_SIZE];
+
+    let mut position = 0;
+    let mut file_size = 0;
+    while position < side.len() {
+        let block_size = match side[position] {
+            // disk info
+            1 => 56,
+            // file amount
+            2 => 2,
+            // file header
+            3 => {
+                if position + 16 > side.len() {
+                    break;
+                }
+                file_size = u16::from_le_bytes([side[position + 13], side[position + 14]]) as usize;
+                16
+            }
+            // file data
+            4 => file_size + 1,
+            // no more blocks
+            _ => break,
+        };
+
+        if position + block_size > side.len() {
+            break;
+        }
+
+        raw.push(BLOCK_START_MARK);
+        raw.extend_from_slice(&side[position..position + block_size]);
+        // CRC, not checked
+        raw.extend_from_slice(&[0, 0]);
+        raw.extend(std::iter::repeat_n(0, BLOCK_GAP_SIZE));
+
+        position += block_size;
+    }
+
+    if raw.len() < RAW_DISK_SIDE_SIZE {
+        raw.resize(RAW_DISK_SIDE_SIZE, 0);
+    }
+
+    raw
+}
+
+/// Create the diff between the `original` sides and the `modified` ones.
+///
+/// The diff is a list of records, each one is:
+/// side (u8), offset (u32 LE), length (u32 LE), and `length` bytes of data
+pub(super) fn create_disk_diff(original: &[Vec<u8>], modified: &[Vec<u8>]) -> Vec<u8> {
+    let mut diff = Vec::new();
+
+    for (side, (original, modified)) in original.iter().zip(modified).enumerate() {
+        let mut offset = 0;
+        while offset < original.len() {
+            if original[offset] == modified[offset] {
+                offset += 1;
+                continue;
+            }
+
+            let start = offset;
+            while offset < original.len() && original[offset] != modified[offset] {
+                offset += 1;
+            }
+
+            diff.push(side as u8);
+            diff.extend_from_slice(&(start as u32).to_le_bytes());
+            diff.extend_from_slice(&((offset - start) as u32).to_le_bytes());
+            diff.extend_from_slice(&modified[start..offset]);
+        }
+    }
+
+    diff
+}
+
+/// Apply the diff created by [`create_disk_diff`] to `sides`
+pub(super) fn apply_disk_diff<R: Read>(
+    sides: &mut [Vec<u8>],
+    mut reader: R,
+) -> Result<(), SramError> {
+    let mut diff = Vec::new();
+    reader.read_to_end(&mut diff)?;
+
+    let mut diff = diff.as_slice();
+    while !diff.is_empty() {
+        if diff.len() < 9 {
+            return Err(SramError::InvalidDiskDiffFile);
+        }
+
+        let side = diff[0] as usize;
+        let offset = u32::from_le_bytes(diff[1..5].try_into().unwrap()) as usize;
+        let length = u32::from_le_bytes(diff[5..9].try_into().unwrap()) as usize;
+        diff = &diff[9..];
+
+        let side = sides.get_mut(side).ok_or(SramError::InvalidDiskDiffFile)?;
+        if diff.len() < length || offset + length > side.len() {
+            return Err(SramError::InvalidDiskDiffFile);
+        }
+
+        side[offset..offset + length].copy_from_slice(&diff[..length]);
+        diff = &diff[length..];
+    }
+
+    Ok(())
+}
@@ -0,0 +1,245 @@
+//! Game database in the NES 2.0 XML format (the format of `nes20db.xml`).
+//!
+//! Many iNES 1.0 files have wrong mapper numbers, mirroring, battery flags
+//! or RAM sizes, so the header of these files is corrected using the database
+//! entry matching the CRC32 (and SHA-1) of the PRG and CHR ROM.
+//!
+//! A database is built-in, and a `nes20db.xml` file next to the ROM or in the
+//! current directory is also searched, which allows using the full database.
+
+use super::{ConsoleType, INesHeader, TimingMode};
+use crate::common::Region;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, OnceLock},
+    time::SystemTime,
+};
+
+/// the name of the database file searched next to the ROM
+const GAME_DB_FILE_NAME: &str = "nes20db.xml";
+
+#[cfg(not(test))]
+const BUILTIN_GAME_DB_XML: &str = include_str!("game_db.xml");
+/// the tests use a database of the test ROMs in `test_roms` instead
+#[cfg(test)]
+const BUILTIN_GAME_DB_XML: &str = include_str!("test_game_db.xml");
+
+static BUILTIN_GAME_DB: OnceLock<GameDb> = OnceLock::new();
+
+/// the database files already read, by path, with their modification time to
+/// read them again when they change
+type FileGameDbs = HashMap<PathBuf, (SystemTime, Option<Arc<GameDb>>)>;
+static FILE_GAME_DBS: OnceLock<Mutex<FileGameDbs>> = OnceLock::new();
+
+/// Information about the loaded game from the game database
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GameInfo {
+    /// The name of the game, from the comment in the database entry
+    pub title: String,
+    pub region: Region,
+    /// The iNES mapper emulating the board of the game
+    pub mapper_id: u16,
+    pub submapper_id: u8,
+}
+
+/// A database entry, with all the NES 2.0 header fields that are overridden
+#[derive(Debug, Clone)]
+pub(super) struct GameDbEntry {
+    pub(super) info: GameInfo,
+
+    sha1: Option<String>,
+    /// `H`, `V` or `4`, other values are controlled by the mapper
+    mirroring: Option<String>,
+    has_battery: bool,
+    prg_wram_size: u32,
+    prg_sram_size: u32,
+    chr_wram_size: u32,
+    chr_sram_size: u32,
+    console_type: ConsoleType,
+    timing_mode: TimingMode,
+    default_expansion_device: u8,
+}
+
+impl GameDbEntry {
+    /// Replace the fields of `header` with the ones from the database,
+    /// the ROM sizes and the trainer are kept as they are needed to read the file
+    pub(super) fn apply(&self, header: &mut INesHeader) {
+        header.mapper_id = self.info.mapper_id;
+        header.submapper_id = self.info.submapper_id;
+        header.hardwired_mirroring_vertical = self.mirroring.as_deref() == Some("V");
+        header.use_hardwaired_4_screen_mirroring = self.mirroring.as_deref() == Some("4");
+        header.has_prg_ram_battery = self.has_battery;
+        header.prg_wram_size = self.prg_wram_size;
+        header.prg_sram_size = self.prg_sram_size;
+        if header.is_chr_ram {
+            header.chr_wram_size = self.chr_wram_size;
+            header.chr_sram_size = self.chr_sram_size;
+        }
+        header.console_type = self.console_type;
+        header.timing_mode = self.timing_mode;
+        header.default_expansion_device = self.default_expansion_device;
+    }
+}
+
+pub(super) struct GameDb {
+    /// the entries by the CRC32 of the PRG and CHR ROM
+    entries: HashMap<u32, Vec<GameDbEntry>>,
+}
+
+impl GameDb {
+    pub(super) fn from_xml(xml: &str) -> Result<Self, roxmltree::Error> {
+        let document = roxmltree::Document::parse(xml)?;
+        let mut entries = HashMap::<u32, Vec<GameDbEntry>>::new();
+
+        for game in document
+            .root_element()
+            .children()
+            .filter(|node| node.has_tag_name("game"))
+        {
+            // skip invalid entries
+            if let Some((crc32, entry)) = Self::parse_game(game) {
+                entries.entry(crc32).or_default().push(entry);
+            }
+        }
+
+        Ok(Self { entries })
+    }
+
+    fn builtin() -> &'static Self {
+        BUILTIN_GAME_DB.get_or_init(|| {
+            Self::from_xml(BUILTIN_GAME_DB_XML).expect("The built-in game database is valid")
+        })
+    }
+
+    fn parse_game(game: roxmltree::Node) -> Option<(u32, GameDbEntry)> {
+        let child = |name: &str| game.children().find(|node| node.has_tag_name(name));
+        let attribute = |name: &str, attribute: &str| child(name)?.attribute(attribute);
+        let number = |name: &str, attribute_name: &str| -> Option<u32> {
+            attribute(name, attribute_name)?.parse().ok()
+        };
+
+        // the title is in a comment inside the entry, or just before it
+        let title = game
+            .children()
+            .find(|node| node.is_comment())
+            .or_else(|| {
+                std::iter::successors(game.prev_sibling(), |node| node.prev_sibling())
+                    .take_while(|node| !node.is_element())
+                    .find(|node| node.is_comment())
+            })
+            .and_then(|node| node.text())
+            .map(read_title)
+            .unwrap_or_default();
+
+        let crc32 = u32::from_str_radix(attribute("rom", "crc32")?, 16).ok()?;
+        let sha1 = attribute("rom", "sha1").map(|sha1| sha1.to_ascii_lowercase());
+
+        let timing_mode = match number("console", "region").unwrap_or(0) {
+            0 => TimingMode::Ntsc,
+            1 => TimingMode::Pal,
+            2 => TimingMode::MultipleRegion,
+            _ => TimingMode::Dendy,
+        };
+        let console_type = match number("console", "type").unwrap_or(0) {
+            0 => ConsoleType::Nes,
+            1 => ConsoleType::VsSystem {
+                ppu_type: number("vs", "ppu").unwrap_or(0) as u8,
+                hardware_type: number("vs", "hardware").unwrap_or(0) as u8,
+            },
+            2 => ConsoleType::Playchoice10,
+            console_type => ConsoleType::Extended(console_type as u8),
+        };
+
+        Some((
+            crc32,
+            GameDbEntry {
+                info: GameInfo {
+                    title,
+                    region: timing_mode.region(),
+                    mapper_id: number("pcb", "mapper")? as u16,
+                    submapper_id: number("pcb", "submapper").unwrap_or(0) as u8,
+                },
+                sha1,
+                mirroring: attribute("pcb", "mirroring").map(str::to_owned),
+                has_battery: number("pcb", "battery").unwrap_or(0) != 0,
+                prg_wram_size: number("prgram", "size").unwrap_or(0),
+                prg_sram_size: number("prgnvram", "size").unwrap_or(0),
+                chr_wram_size: number("chrram", "size").unwrap_or(0),
+                chr_sram_size: number("chrnvram", "size").unwrap_or(0),
+                console_type,
+                timing_mode,
+                default_expansion_device: number("expansion", "type").unwrap_or(0) as u8,
+            },
+        ))
+    }
+
+    /// The entry of the game with the PRG and CHR ROM `rom`
+    pub(super) fn find(&self, rom: &[u8]) -> Option<&GameDbEntry> {
+        let entries = self.entries.get(&crc32fast::hash(rom))?;
+
+        // only compute the SHA-1 if needed
+        let mut sha1 = None;
+        entries.iter().find(|entry| match &entry.sha1 {
+            Some(entry_sha1) => {
+                entry_sha1
+                    == sha1.get_or_insert_with(|| sha1_smol::Sha1::from(rom).digest().to_string())
+            }
+            None => true,
+        })
+    }
+}
+
+/// Find the entry of the game with the PRG and CHR ROM `rom`, first in the
+/// `nes20db.xml` file next to `file_path` or in the current directory,
+/// then in the built-in database
+pub(super) fn lookup(file_path: &Path, rom: &[u8]) -> Option<GameDbEntry> {
+    let db_path = file_path
+        .parent()
+        .map(|parent| parent.join(GAME_DB_FILE_NAME))
+        .into_iter()
+        .chain(std::iter::once(PathBuf::from(GAME_DB_FILE_NAME)))
+        .find(|path| path.is_file());
+
+    let file_db = db_path.and_then(|path| file_game_db(&path));
+
+    file_db
+        .as_deref()
+        .and_then(|db| db.find(rom))
+        .or_else(|| GameDb::builtin().find(rom))
+        .cloned()
+}
+
+/// Read and parse the database file at `path`, it is only parsed again if
+/// the file was modified since the last time
+fn file_game_db(path: &Path) -> Option<Arc<GameDb>> {
+    let modified = std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()?;
+
+    let mut file_dbs = FILE_GAME_DBS.get_or_init(Default::default).lock().unwrap();
+    if let Some((cached_modified, db)) = file_dbs.get(path) {
+        if *cached_modified == modified {
+            return db.clone();
+        }
+    }
+
+    let xml = std::fs::read_to_string(path).ok()?;
+    let db = match GameDb::from_xml(&xml) {
+        Ok(db) => Some(Arc::new(db)),
+        Err(err) => {
+            println!("[ERROR] {:?} is not a valid game database: {}", path, err);
+            None
+        }
+    };
+    file_dbs.insert(path.to_owned(), (modified, db.clone()));
+
+    db
+}
+
+/// The title comments are the file name of the dump, like `'Game (USA).nes'`
+fn read_title(comment: &str) -> String {
+    let title = comment.trim().trim_matches('\'').trim();
+
+    title.strip_suffix(".nes").unwrap_or(title).to_owned()
+}
@@ -1,12 +1,28 @@
-use crate::common::{Device, MirroringMode};
+use super::mappers::Nsf;
+use crate::common::{Device, MirroringMode, NametableMapping, Region};
 
 pub enum MappingResult {
+    /// address into the default memory of the accessed range, for the CPU,
+    /// `$6000-$7FFF` is PRG RAM and `$8000-$FFFF` is PRG ROM, for the PPU,
+    /// it is always CHR memory
     Allowed(usize),
+    /// address into PRG RAM, can be used from any CPU address
+    PrgRam(usize),
+    /// address into PRG ROM, can be used from any CPU address
+    PrgRom(usize),
+    /// (start, size) of PRG ROM to erase (fill with `0xFF`), used by
+    /// flash chips, only valid for writes
+    PrgRomErase(usize, usize),
+    /// the mapper provided the data by itself (internal registers or RAM),
+    /// only valid for reads
+    Data(u8),
     Denied,
 }
 
 pub trait Mapper {
-    fn init(&mut self, pgr_count: u8, is_chr_ram: bool, chr_count: u8, sram_count: u8);
+    /// `prg_count` is in 16kb units, `chr_count` in 8kb units (ROM or RAM)
+    /// and `prg_ram_size` in bytes
+    fn init(&mut self, prg_count: u16, is_chr_ram: bool, chr_count: u16, prg_ram_size: u32);
 
     /// takes `address` to map from and `device`, then return `result`
     /// if `result` is `MappingResult::Allowed`, then the `real_address` is
@@ -38,6 +54,99 @@ pub trait Mapper {
 
     fn clear_irq_request_pin(&mut self) {}
 
+    /// Called on every nametable access (`$2000-$2FFF`) from the PPU, if it returns `None`
+    /// the address will be mapped using the mirroring mode.
+    ///
+    /// If the result is `NametableMapping::Cartridge`, the access will go to
+    /// [`map_read`][Mapper::map_read] and [`map_write`][Mapper::map_write]
+    fn map_nametable(&self, _address: u16) -> Option<NametableMapping> {
+        None
+    }
+
+    /// Same as [`map_read`][Mapper::map_read] for the CPU, but without the side effects
+    /// of the access on the mapper (e.g. the MMC5 leaving the frame on the NMI vector read),
+    /// used by the debugger
+    fn peek_cpu(&self, address: u16) -> MappingResult {
+        self.map_read(address, Device::Cpu)
+    }
+
+    /// Same as [`map_read`][Mapper::map_read] for the PPU, but without the side effects
+    /// of the access on the mapper (e.g. the MMC2 latches or the MMC3 IRQ counter),
+    /// used by the debugger views
+    fn peek_ppu(&self, address: u16) -> MappingResult {
+        self.map_read(address, Device::Ppu)
+    }
+
+    /// Same as [`map_write`][Mapper::map_write] for the PPU, without side effects
+    fn poke_ppu(&mut self, address: u16, data: u8) -> MappingResult {
+        self.map_write(address, data, Device::Ppu)
+    }
+
+    /// Same as [`map_nametable`][Mapper::map_nametable], without side effects
+    fn peek_nametable(&self, address: u16) -> Option<NametableMapping> {
+        self.map_nametable(address)
+    }
+
+    /// Boards where the registers are in the PRG ROM range and the ROM is not disabled
+    /// on writes, so the written value is ANDed with the value of PRG ROM at that address
+    fn has_bus_conflicts(&self) -> bool {
+        false
+    }
+
+    /// The PRG ROM is a flash chip written by the game to save, so it is saved in the
+    /// `.sav` file after the CHR NVRAM, and in the save states
+    fn has_prg_flash(&self) -> bool {
+        false
+    }
+
+    /// Called on every CPU cycle (M2), used by mappers with CPU-cycle counters
+    fn clock(&mut self) {}
+
+    /// Called when the CPU writes to one of the PPU registers `$2000-$2007`, used by
+    /// mappers that listen to the PPU configuration
+    fn snoop_ppu_register_write(&mut self, _address: u16, _data: u8) {}
+
+    /// The output of the expansion audio chips in the cartridge, each chip output
+    /// should be scaled with [`ExpansionAudioChip::mix`][crate::apu2a03::expansion::ExpansionAudioChip::mix]
+    fn expansion_audio_output(&mut self) -> f32 {
+        0.
+    }
+
+    /// Internal RAM of the mapper that is battery backed, it is saved in the
+    /// `.sav` file after the PRG RAM, only if the cartridge has a battery
+    fn battery_backed_ram(&self) -> &[u8] {
+        &[]
+    }
+
+    /// Load the data saved from [`battery_backed_ram`][Mapper::battery_backed_ram],
+    /// `data` has the same length
+    fn load_battery_backed_ram(&mut self, _data: &[u8]) {}
+
+    /// The disk sides of the Famicom Disk System, empty for normal cartridges
+    fn disk_sides(&self) -> &[Vec<u8>] {
+        &[]
+    }
+
+    /// The disk side that is inserted in the drive, `None` if the disk is ejected
+    fn inserted_disk_side(&self) -> Option<usize> {
+        None
+    }
+
+    /// Insert a disk side into the drive, or eject the disk if `side` is `None`
+    fn set_inserted_disk_side(&mut self, _side: Option<usize>) {}
+
+    /// Called when the emulator region is selected, before the game starts
+    fn set_region(&mut self, _region: Region) {}
+
+    /// The NSF player, `None` for normal cartridges
+    fn nsf(&self) -> Option<&Nsf> {
+        None
+    }
+
+    fn nsf_mut(&mut self) -> Option<&mut Nsf> {
+        None
+    }
+
     fn save_state_size(&self) -> usize;
 
     fn save_state(&self) -> Vec<u8>;
@@ -0,0 +1,233 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+enum EepromState {
+    /// waiting for a start condition
+    Idle,
+    /// receiving the device address (24C02), or the word address and
+    /// the direction (24C01)
+    Address,
+    /// receiving the word address (24C02 only)
+    WordAddress,
+    /// receiving data bytes to write
+    Write,
+    /// sending data bytes
+    Read,
+}
+
+/// Serial EEPROM with an I²C interface, 24C01 (128 bytes) or 24C02 (256 bytes),
+/// controlled by the mapper writing the clock (SCL) and data (SDA) lines.
+///
+/// The 24C02 uses the standard I²C protocol (device address, word address,
+/// then data, MSB first), the 24C01 has no device address, and the word
+/// address, the direction and the data are sent LSB first.
+///
+/// Writes are finished immediately, so there is no busy time after a stop condition.
+#[derive(Serialize, Deserialize)]
+pub struct I2cEeprom {
+    data: Vec<u8>,
+    is_24c01: bool,
+
+    state: EepromState,
+    /// the state to go to after the acknowledge clock of the current byte
+    next_state: EepromState,
+
+    /// the last values of the lines written by the mapper
+    scl: bool,
+    sda: bool,
+
+    /// the byte being received or sent
+    shift: u8,
+    /// the number of clocks in the current byte, the 9th is the acknowledge,
+    /// counted on the rising edge
+    clocks: u8,
+    address: u8,
+    /// the master acknowledged the byte sent, so send the next one
+    read_acked: bool,
+
+    /// the SDA line driven by the EEPROM, `true` when released
+    output: bool,
+}
+
+impl I2cEeprom {
+    pub fn new_24c01() -> Self {
+        Self::new(0x80, true)
+    }
+
+    pub fn new_24c02() -> Self {
+        Self::new(0x100, false)
+    }
+
+    fn new(size: usize, is_24c01: bool) -> Self {
+        Self {
+            data: vec![0xFF; size],
+            is_24c01,
+            state: EepromState::Idle,
+            next_state: EepromState::Idle,
+            scl: false,
+            sda: false,
+            shift: 0,
+            clocks: 0,
+            address: 0,
+            read_acked: false,
+            output: true,
+        }
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    pub fn load_data(&mut self, data: &[u8]) {
+        self.data.copy_from_slice(data);
+    }
+
+    /// The SDA line driven by the EEPROM
+    pub fn output(&self) -> bool {
+        self.output
+    }
+
+    /// Update the SCL and SDA lines
+    pub fn write_lines(&mut self, scl: bool, sda: bool) {
+        if self.scl && scl && self.sda != sda {
+            // SDA changing while SCL is high are start and stop conditions
+            if sda {
+                self.stop();
+            } else {
+                self.start();
+            }
+        } else if !self.scl && scl {
+            self.clock_rise(sda);
+        } else if self.scl && !scl {
+            self.clock_fall();
+        }
+
+        self.scl = scl;
+        self.sda = sda;
+    }
+
+    fn start(&mut self) {
+        self.state = EepromState::Address;
+        self.clocks = 0;
+        self.output = true;
+    }
+
+    fn stop(&mut self) {
+        self.state = EepromState::Idle;
+        self.output = true;
+    }
+
+    fn clock_rise(&mut self, sda: bool) {
+        match self.state {
+            EepromState::Idle => return,
+            EepromState::Read => {
+                if self.clocks == 8 {
+                    // the master pulls SDA low to ask for the next byte
+                    self.read_acked = !sda;
+                }
+            }
+            _ => {
+                if self.clocks < 8 {
+                    self.shift = if self.is_24c01 {
+                        (self.shift >> 1) | ((sda as u8) << 7)
+                    } else {
+                        (self.shift << 1) | sda as u8
+                    };
+                }
+            }
+        }
+
+        self.clocks += 1;
+    }
+
+    fn clock_fall(&mut self) {
+        // the clock going low after a start condition
+        if self.state == EepromState::Idle || self.clocks == 0 {
+            return;
+        }
+
+        match self.clocks {
+            1..=7 if self.state == EepromState::Read => self.output = self.output_bit(),
+            1..=7 => {}
+            // release SDA for the master to acknowledge
+            8 if self.state == EepromState::Read => self.output = true,
+            8 => {
+                self.next_state = self.receive_byte();
+                // acknowledge the byte if it was for us
+                self.output = self.next_state == EepromState::Idle;
+            }
+            _ => {
+                self.clocks = 0;
+
+                if self.state == EepromState::Read {
+                    if self.read_acked {
+                        self.address = self.address.wrapping_add(1);
+                        self.start_sending();
+                    } else {
+                        self.stop();
+                    }
+                } else {
+                    self.state = self.next_state;
+                    self.output = true;
+
+                    if self.state == EepromState::Read {
+                        self.start_sending();
+                    }
+                }
+            }
+        }
+    }
+
+    /// Handle the received byte, and return the next state
+    fn receive_byte(&mut self) -> EepromState {
+        match self.state {
+            EepromState::Address if self.is_24c01 => {
+                self.address = self.shift & 0x7F;
+
+                if self.shift & 0x80 != 0 {
+                    EepromState::Read
+                } else {
+                    EepromState::Write
+                }
+            }
+            EepromState::Address => {
+                if self.shift & 0xF0 != 0xA0 {
+                    // not the EEPROM device address
+                    EepromState::Idle
+                } else if self.shift & 1 != 0 {
+                    EepromState::Read
+                } else {
+                    EepromState::WordAddress
+                }
+            }
+            EepromState::WordAddress => {
+                self.address = self.shift;
+                EepromState::Write
+            }
+            EepromState::Write => {
+                let size = self.data.len();
+                self.data[self.address as usize % size] = self.shift;
+                self.address = self.address.wrapping_add(1);
+                EepromState::Write
+            }
+            EepromState::Idle | EepromState::Read => unreachable!(),
+        }
+    }
+
+    /// Load the byte at the current address and output its first bit
+    fn start_sending(&mut self) {
+        self.shift = self.data[self.address as usize % self.data.len()];
+        self.output = self.output_bit();
+    }
+
+    /// The bit of `shift` to send after `clocks` clocks
+    fn output_bit(&self) -> bool {
+        let bit = if self.is_24c01 {
+            self.clocks
+        } else {
+            7 - self.clocks
+        };
+
+        (self.shift >> bit) & 1 != 0
+    }
+}
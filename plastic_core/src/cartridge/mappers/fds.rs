@@ -0,0 +1,451 @@
+use super::super::mapper::{Mapper, MappingResult};
+use crate::apu2a03::expansion::{APUChannel, ExpansionAudioChip, FdsAudio, TimedAPUChannel};
+use crate::common::{Device, MirroringMode};
+use serde::{Deserialize, Serialize};
+use std::cell::Cell;
+
+/// the number of CPU cycles the drive takes to move the head back to the start
+/// of the disk
+const DRIVE_RESTART_CYCLES: u32 = 50000;
+/// the number of CPU cycles to transfer one byte (~96.4kbit/s)
+const DRIVE_BYTE_CYCLES: u32 = 149;
+
+/// Famicom Disk System RAM adaptor and disk drive.
+///
+/// The BIOS (`disksys.rom`) is mapped at `$E000-$FFFF` as PRG ROM, and the
+/// 32kb RAM at `$6000-$DFFF` as PRG RAM.
+///
+/// The disk sides are stored in the raw format, with the gaps and block start marks,
+/// so that the drive can be emulated byte by byte like the real one. The CRC of
+/// the blocks is not checked.
+#[derive(Serialize, Deserialize)]
+pub struct Fds {
+    disk_sides: Vec<Vec<u8>>,
+    inserted_side: Option<usize>,
+
+    /// ($4020/$4021)
+    timer_reload: u16,
+    timer_counter: u16,
+    /// ($4022)
+    /// 7  bit  0
+    /// ---------
+    /// .... ..ER
+    ///        ||
+    ///        |+- Timer IRQ Repeat Flag
+    ///        +-- Timer IRQ Enabled
+    timer_repeat: bool,
+    timer_enabled: bool,
+
+    /// ($4023)
+    /// 7  bit  0
+    /// ---------
+    /// .... ..SD
+    ///        ||
+    ///        |+- Enable disk I/O registers
+    ///        +-- Enable sound I/O registers
+    disk_registers_enabled: bool,
+    sound_registers_enabled: bool,
+
+    /// ($4024)
+    write_data: u8,
+    /// ($4031)
+    read_data: u8,
+
+    /// ($4025)
+    /// 7  bit  0
+    /// ---------
+    /// IS1B MRTD
+    /// |||| ||||
+    /// |||| |||+- Drive Motor Control (0: stop motor; 1: turn on motor)
+    /// |||| ||+-- Transfer Reset (1: reset transfer timing to the initial state)
+    /// |||| |+--- Read / Write mode (0: write; 1: read)
+    /// |||| +---- Mirroring (0: vertical; 1: horizontal)
+    /// |||+------ CRC Transfer Control (1: transfer CRC value)
+    /// ||+------- Unknown, always set to '1'
+    /// |+-------- CRC Enabled, start looking for the end of the gap when set
+    /// +--------- Interrupt Enabled (1: Generate an IRQ every time the byte transfer flag is raised)
+    motor_on: bool,
+    transfer_reset: bool,
+    read_mode: bool,
+    horizontal_mirroring: bool,
+    crc_control: bool,
+    disk_ready: bool,
+    disk_irq_enabled: bool,
+
+    /// ($4026) the value written to the external connector
+    external_output: u8,
+
+    disk_position: usize,
+    drive_delay: u32,
+    end_of_head: bool,
+    scanning_disk: bool,
+    gap_ended: bool,
+
+    /// (`$4030` bit 1) a byte was transferred
+    transfer_complete: Cell<bool>,
+    /// (`$4030` bit 0)
+    timer_irq: Cell<bool>,
+    disk_irq: Cell<bool>,
+
+    irq_pin: Cell<bool>,
+    is_irq_pin_changed: Cell<bool>,
+
+    audio: FdsAudio,
+}
+
+impl Fds {
+    /// `disk_sides` must be in the raw format (with gaps)
+    pub fn new(disk_sides: Vec<Vec<u8>>) -> Self {
+        Self {
+            inserted_side: if disk_sides.is_empty() { None } else { Some(0) },
+            disk_sides,
+
+            timer_reload: 0,
+            timer_counter: 0,
+            timer_repeat: false,
+            timer_enabled: false,
+
+            disk_registers_enabled: false,
+            sound_registers_enabled: false,
+
+            write_data: 0,
+            read_data: 0,
+
+            motor_on: false,
+            transfer_reset: false,
+            read_mode: true,
+            horizontal_mirroring: false,
+            crc_control: false,
+            disk_ready: false,
+            disk_irq_enabled: false,
+
+            external_output: 0,
+
+            disk_position: 0,
+            drive_delay: 0,
+            end_of_head: true,
+            scanning_disk: false,
+            gap_ended: false,
+
+            transfer_complete: Cell::new(false),
+            timer_irq: Cell::new(false),
+            disk_irq: Cell::new(false),
+
+            irq_pin: Cell::new(false),
+            is_irq_pin_changed: Cell::new(false),
+
+            audio: FdsAudio::new(),
+        }
+    }
+
+    fn update_irq_pin(&self) {
+        let state = self.timer_irq.get() || self.disk_irq.get();
+
+        if self.irq_pin.get() != state {
+            self.irq_pin.set(state);
+            self.is_irq_pin_changed.set(true);
+        }
+    }
+
+    fn read_register(&self, address: u16) -> Option<u8> {
+        match address {
+            0x4030 => {
+                let result = self.timer_irq.get() as u8
+                    | (self.transfer_complete.get() as u8) << 1
+                    | (self.end_of_head as u8) << 6;
+
+                self.timer_irq.set(false);
+                self.transfer_complete.set(false);
+                self.disk_irq.set(false);
+                self.update_irq_pin();
+
+                Some(result)
+            }
+            0x4031 => {
+                self.transfer_complete.set(false);
+                self.disk_irq.set(false);
+                self.update_irq_pin();
+
+                Some(self.read_data)
+            }
+            0x4032 => {
+                let not_inserted = self.inserted_side.is_none();
+
+                // the upper bits are open bus
+                Some(
+                    not_inserted as u8
+                        | ((not_inserted || !self.scanning_disk) as u8) << 1
+                        | (not_inserted as u8) << 2
+                        | 0x40,
+                )
+            }
+            // battery is good
+            0x4033 => Some(0x80),
+            0x4040..=0x4092 if self.sound_registers_enabled => self.audio.read(address),
+            _ => None,
+        }
+    }
+
+    fn write_register(&mut self, address: u16, data: u8) {
+        match address {
+            0x4020 => self.timer_reload = (self.timer_reload & 0xFF00) | data as u16,
+            0x4021 => self.timer_reload = (self.timer_reload & 0x00FF) | (data as u16) << 8,
+            0x4022 => {
+                self.timer_repeat = data & 1 != 0;
+                self.timer_enabled = data & 2 != 0 && self.disk_registers_enabled;
+
+                if self.timer_enabled {
+                    self.timer_counter = self.timer_reload;
+                } else {
+                    self.timer_irq.set(false);
+                    self.update_irq_pin();
+                }
+            }
+            0x4023 => {
+                self.disk_registers_enabled = data & 1 != 0;
+                self.sound_registers_enabled = data & 2 != 0;
+
+                if !self.disk_registers_enabled {
+                    self.timer_enabled = false;
+                    self.timer_irq.set(false);
+                    self.disk_irq.set(false);
+                    self.update_irq_pin();
+                }
+            }
+            0x4024 if self.disk_registers_enabled => {
+                self.write_data = data;
+                self.transfer_complete.set(false);
+                self.disk_irq.set(false);
+                self.update_irq_pin();
+            }
+            0x4025 if self.disk_registers_enabled => {
+                self.motor_on = data & 0x01 != 0;
+                self.transfer_reset = data & 0x02 != 0;
+                self.read_mode = data & 0x04 != 0;
+                self.horizontal_mirroring = data & 0x08 != 0;
+                self.crc_control = data & 0x10 != 0;
+                self.disk_ready = data & 0x40 != 0;
+                self.disk_irq_enabled = data & 0x80 != 0;
+
+                self.disk_irq.set(false);
+                self.update_irq_pin();
+            }
+            0x4026 if self.disk_registers_enabled => self.external_output = data,
+            0x4040..=0x408A if self.sound_registers_enabled => self.audio.write(address, data),
+            _ => {}
+        }
+    }
+
+    fn clock_timer(&mut self) {
+        if !self.timer_enabled {
+            return;
+        }
+
+        if self.timer_counter == 0 {
+            self.timer_irq.set(true);
+            self.update_irq_pin();
+
+            self.timer_counter = self.timer_reload;
+            if !self.timer_repeat {
+                self.timer_enabled = false;
+            }
+        } else {
+            self.timer_counter -= 1;
+        }
+    }
+
+    fn clock_drive(&mut self) {
+        let side = match self.inserted_side {
+            Some(side) if self.motor_on => side,
+            _ => {
+                self.end_of_head = true;
+                self.scanning_disk = false;
+                return;
+            }
+        };
+
+        if self.transfer_reset && !self.scanning_disk {
+            return;
+        }
+
+        if self.end_of_head {
+            self.drive_delay = DRIVE_RESTART_CYCLES;
+            self.end_of_head = false;
+            self.disk_position = 0;
+            self.gap_ended = false;
+            return;
+        }
+
+        if self.drive_delay > 0 {
+            self.drive_delay -= 1;
+            return;
+        }
+
+        self.scanning_disk = true;
+
+        let mut need_irq = self.disk_irq_enabled;
+
+        if self.read_mode {
+            let disk_byte = self.disk_sides[side][self.disk_position];
+
+            if !self.disk_ready {
+                self.gap_ended = false;
+            } else if disk_byte != 0 && !self.gap_ended {
+                // the block start mark, not transferred to the CPU
+                self.gap_ended = true;
+                need_irq = false;
+            }
+
+            if self.gap_ended {
+                self.transfer_complete.set(true);
+                self.read_data = disk_byte;
+
+                if need_irq {
+                    self.disk_irq.set(true);
+                    self.update_irq_pin();
+                }
+            }
+        } else {
+            if !self.crc_control {
+                self.transfer_complete.set(true);
+
+                if need_irq {
+                    self.disk_irq.set(true);
+                    self.update_irq_pin();
+                }
+            }
+
+            // write the gap until the data is ready, and the CRC is not computed
+            self.disk_sides[side][self.disk_position] = if !self.disk_ready || self.crc_control {
+                0
+            } else {
+                self.write_data
+            };
+
+            self.gap_ended = false;
+        }
+
+        self.disk_position += 1;
+        if self.disk_position >= self.disk_sides[side].len() {
+            self.end_of_head = true;
+            self.scanning_disk = false;
+        } else {
+            self.drive_delay = DRIVE_BYTE_CYCLES;
+        }
+    }
+}
+
+impl Mapper for Fds {
+    fn init(&mut self, _prg_count: u16, _is_chr_ram: bool, _chr_count: u16, _prg_ram_size: u32) {
+        // the memory sizes are fixed
+    }
+
+    fn map_read(&self, address: u16, device: Device) -> MappingResult {
+        match device {
+            Device::Cpu => match address {
+                0x4020..=0x5FFF => match self.read_register(address) {
+                    Some(data) => MappingResult::Data(data),
+                    None => MappingResult::Denied,
+                },
+                0x6000..=0xDFFF => MappingResult::PrgRam(address as usize - 0x6000),
+                0xE000..=0xFFFF => MappingResult::PrgRom(address as usize - 0xE000),
+                _ => unreachable!(),
+            },
+            Device::Ppu => {
+                if address < 0x2000 {
+                    MappingResult::Allowed(address as usize)
+                } else {
+                    unreachable!();
+                }
+            }
+        }
+    }
+
+    fn map_write(&mut self, address: u16, data: u8, device: Device) -> MappingResult {
+        match device {
+            Device::Cpu => match address {
+                0x4020..=0x5FFF => {
+                    self.write_register(address, data);
+                    MappingResult::Denied
+                }
+                0x6000..=0xDFFF => MappingResult::PrgRam(address as usize - 0x6000),
+                0xE000..=0xFFFF => MappingResult::Denied,
+                _ => unreachable!(),
+            },
+            Device::Ppu => {
+                // CHR RAM
+                if address <= 0x1FFF {
+                    MappingResult::Allowed(address as usize)
+                } else {
+                    MappingResult::Denied
+                }
+            }
+        }
+    }
+
+    fn clock(&mut self) {
+        if self.disk_registers_enabled {
+            self.clock_timer();
+        }
+        self.clock_drive();
+
+        self.audio.timer_clock();
+    }
+
+    fn expansion_audio_output(&mut self) -> f32 {
+        ExpansionAudioChip::Fds.mix(self.audio.get_output())
+    }
+
+    fn is_hardwired_mirrored(&self) -> bool {
+        false
+    }
+
+    fn nametable_mirroring(&self) -> MirroringMode {
+        if self.horizontal_mirroring {
+            MirroringMode::Horizontal
+        } else {
+            MirroringMode::Vertical
+        }
+    }
+
+    fn is_irq_pin_state_changed_requested(&self) -> bool {
+        self.is_irq_pin_changed.get()
+    }
+
+    fn irq_pin_state(&self) -> bool {
+        self.irq_pin.get()
+    }
+
+    fn clear_irq_request_pin(&mut self) {
+        self.is_irq_pin_changed.set(false);
+    }
+
+    fn disk_sides(&self) -> &[Vec<u8>] {
+        &self.disk_sides
+    }
+
+    fn inserted_disk_side(&self) -> Option<usize> {
+        self.inserted_side
+    }
+
+    fn set_inserted_disk_side(&mut self, side: Option<usize>) {
+        self.inserted_side = side.filter(|&side| side < self.disk_sides.len());
+        // the new disk starts from the beginning
+        self.end_of_head = true;
+        self.scanning_disk = false;
+    }
+
+    fn save_state_size(&self) -> usize {
+        bincode::serialized_size(self).unwrap() as usize
+    }
+
+    fn save_state(&self) -> Vec<u8> {
+        bincode::serialize(self).unwrap()
+    }
+
+    fn load_state(&mut self, data: Vec<u8>) {
+        let state = bincode::deserialize(&data).unwrap();
+
+        let _ = std::mem::replace(self, state);
+    }
+}
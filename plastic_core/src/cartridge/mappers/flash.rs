@@ -0,0 +1,106 @@
+use super::super::mapper::MappingResult;
+use serde::{Deserialize, Serialize};
+
+const MANUFACTURER_ID: u8 = 0xBF;
+const DEVICE_ID: u8 = 0xB7;
+
+/// the smallest erasable unit
+const SECTOR_SIZE: usize = 0x1000;
+/// 512kb
+const CHIP_SIZE: usize = 0x80000;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+enum FlashState {
+    /// waiting for the first unlock cycle (`$AA` to `$5555`)
+    Ready,
+    /// got `$AA`, waiting for `$55` to `$2AAA`
+    Unlock1,
+    /// got the unlock sequence, waiting for the command at `$5555`
+    Command,
+    /// the next write is programmed to the flash
+    ByteProgram,
+    /// got the erase command (`$80`), waiting for the second unlock sequence
+    EraseUnlock0,
+    EraseUnlock1,
+    /// waiting for sector erase (`$30` to the sector) or chip erase (`$10` to `$5555`)
+    EraseCommand,
+}
+
+/// SST39SF040 flash chip, used as PRG ROM in self-flashable boards to save the game.
+///
+/// Only the commands are emulated here, the result of the commands are
+/// applied to PRG ROM by the cartridge. The operations finish immediately,
+/// so the status polling of the chip is not needed, and programmed bytes are
+/// written as they are, while the real chip can only clear bits.
+///
+/// Addresses are in the flash chip, that is the PRG ROM address,
+/// and only the lower 15 bits are used in command addresses.
+#[derive(Serialize, Deserialize)]
+pub struct Sst39sf040 {
+    state: FlashState,
+
+    /// reads return the manufacturer and device IDs instead of the data
+    software_id_mode: bool,
+}
+
+impl Sst39sf040 {
+    pub fn new() -> Self {
+        Self {
+            state: FlashState::Ready,
+            software_id_mode: false,
+        }
+    }
+
+    /// The result of reading `address` if the chip is in software ID mode
+    pub fn read(&self, address: usize) -> Option<u8> {
+        if self.software_id_mode {
+            Some(if address & 1 == 0 {
+                MANUFACTURER_ID
+            } else {
+                DEVICE_ID
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Handle a write to the chip, returns the change to PRG ROM if the write
+    /// completed a program or erase command
+    pub fn write(&mut self, address: usize, data: u8) -> MappingResult {
+        let mut result = MappingResult::Denied;
+
+        self.state = match (self.state, address & 0x7FFF, data) {
+            (FlashState::ByteProgram, _, _) => {
+                result = MappingResult::PrgRom(address);
+                FlashState::Ready
+            }
+            // exit software ID mode, also resets any command sequence
+            (_, _, 0xF0) => {
+                self.software_id_mode = false;
+                FlashState::Ready
+            }
+            (FlashState::Ready, 0x5555, 0xAA) => FlashState::Unlock1,
+            (FlashState::Unlock1, 0x2AAA, 0x55) => FlashState::Command,
+            (FlashState::Command, 0x5555, 0xA0) => FlashState::ByteProgram,
+            (FlashState::Command, 0x5555, 0x80) => FlashState::EraseUnlock0,
+            (FlashState::Command, 0x5555, 0x90) => {
+                self.software_id_mode = true;
+                FlashState::Ready
+            }
+            (FlashState::EraseUnlock0, 0x5555, 0xAA) => FlashState::EraseUnlock1,
+            (FlashState::EraseUnlock1, 0x2AAA, 0x55) => FlashState::EraseCommand,
+            (FlashState::EraseCommand, _, 0x30) => {
+                result = MappingResult::PrgRomErase(address & !(SECTOR_SIZE - 1), SECTOR_SIZE);
+                FlashState::Ready
+            }
+            (FlashState::EraseCommand, 0x5555, 0x10) => {
+                result = MappingResult::PrgRomErase(0, CHIP_SIZE);
+                FlashState::Ready
+            }
+            // invalid sequence
+            _ => FlashState::Ready,
+        };
+
+        result
+    }
+}
@@ -16,7 +16,7 @@ impl Mapper0 {
 }
 
 impl Mapper for Mapper0 {
-    fn init(&mut self, prg_count: u8, is_chr_ram: bool, _chr_count: u8, _sram_count: u8) {
+    fn init(&mut self, prg_count: u16, is_chr_ram: bool, _chr_count: u16, _prg_ram_size: u32) {
         // the only allowed options
         assert!(prg_count == 1 || prg_count == 2);
 
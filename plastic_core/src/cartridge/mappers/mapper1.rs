@@ -86,10 +86,10 @@ pub struct Mapper1 {
     is_chr_ram: bool,
 
     /// in 4kb units
-    chr_count: u8,
+    chr_count: u16,
 
     /// in 16kb units
-    prg_count: u8,
+    prg_count: u16,
 
     /// in 8kb units
     prg_ram_count: u8,
@@ -207,15 +207,15 @@ impl Mapper1 {
 }
 
 impl Mapper for Mapper1 {
-    fn init(&mut self, prg_count: u8, is_chr_ram: bool, chr_count: u8, sram_count: u8) {
+    fn init(&mut self, prg_count: u16, is_chr_ram: bool, chr_count: u16, prg_ram_size: u32) {
         self.prg_count = prg_count;
         self.chr_count = chr_count * 2; // since this passed as the number of 8kb banks
         self.is_chr_ram = is_chr_ram;
 
-        self.prg_bank = prg_count - 1; // power-up, should be all set?
+        self.prg_bank = (prg_count - 1) as u8; // power-up, should be all set?
         self.control_register = 0b11100; // power-up state
 
-        self.prg_ram_count = sram_count;
+        self.prg_ram_count = u8::try_from(prg_ram_size / 0x2000).unwrap_or(u8::MAX);
 
         self.reset_shift_register();
     }
@@ -228,23 +228,23 @@ impl Mapper for Mapper1 {
                     0x8000..=0xFFFF => {
                         let mut bank = if self.is_prg_32kb_mode() {
                             // ignore last bit
-                            self.get_prg_bank() & 0b11110
+                            (self.get_prg_bank() & 0b11110) as usize
                         } else if (0x8000..=0xBFFF).contains(&address) {
                             if self.is_first_prg_chunk_fixed() {
                                 0
                             } else {
-                                self.get_prg_bank()
+                                self.get_prg_bank() as usize
                             }
                         } else if address >= 0xC000 {
                             if self.is_first_prg_chunk_fixed() {
-                                self.get_prg_bank()
+                                self.get_prg_bank() as usize
                             } else {
                                 // last bank
-                                self.prg_count - 1
+                                self.prg_count as usize - 1
                             }
                         } else {
                             unreachable!();
-                        } as usize;
+                        };
 
                         if self.prg_count > 16 && self.chr_count == 2 {
                             let prg_high_bit_512_mode = if self.is_chr_8kb_mode() {
@@ -353,7 +353,7 @@ impl Mapper for Mapper1 {
     }
 
     fn save_state_size(&self) -> usize {
-        10
+        12
     }
 
     fn save_state(&self) -> Vec<u8> {
@@ -363,8 +363,10 @@ impl Mapper for Mapper1 {
             self.chr_0_bank,
             self.chr_1_bank,
             self.prg_bank,
-            self.chr_count,
-            self.prg_count,
+            self.chr_count as u8,
+            (self.chr_count >> 8) as u8,
+            self.prg_count as u8,
+            (self.prg_count >> 8) as u8,
             self.prg_ram_count,
             self.prg_ram_enable as u8,
             self.is_chr_ram as u8,
@@ -377,10 +379,10 @@ impl Mapper for Mapper1 {
         self.chr_0_bank = data[2];
         self.chr_1_bank = data[3];
         self.prg_bank = data[4];
-        self.chr_count = data[5];
-        self.prg_count = data[6];
-        self.prg_ram_count = data[7];
-        self.prg_ram_enable = data[8] != 0;
-        self.is_chr_ram = data[9] != 0;
+        self.chr_count = u16::from_le_bytes([data[5], data[6]]);
+        self.prg_count = u16::from_le_bytes([data[7], data[8]]);
+        self.prg_ram_count = data[9];
+        self.prg_ram_enable = data[10] != 0;
+        self.is_chr_ram = data[11] != 0;
     }
 }
@@ -69,10 +69,10 @@ pub struct Mapper10 {
     is_chr_ram: bool,
 
     /// in 4kb units
-    chr_count: u8,
+    chr_count: u16,
 
     /// in 16kb units
-    prg_count: u8,
+    prg_count: u16,
 }
 
 impl Mapper10 {
@@ -93,19 +93,13 @@ impl Mapper10 {
     }
 
     fn map_ppu(&self, address: u16) -> MappingResult {
-        let mut bank = if address & 0x1000 == 0 {
+        if address & 0x1000 == 0 {
             // set latch 0
             if address == 0x0FD8 {
                 self.latch_0.set(0xFD);
             } else if address == 0x0FE8 {
                 self.latch_0.set(0xFE);
             }
-
-            match self.latch_0.get() {
-                0xFD => self.chr_fd_0000_bank,
-                0xFE => self.chr_fe_0000_bank,
-                _ => unreachable!(),
-            }
         } else {
             // set latch 1
             if address & 0x8 != 0 {
@@ -114,7 +108,20 @@ impl Mapper10 {
                     self.latch_1.set(middle_byte as u8);
                 }
             }
+        }
+
+        self.map_chr(address)
+    }
 
+    /// map the CHR `address` with the current latches, without updating them
+    fn map_chr(&self, address: u16) -> MappingResult {
+        let mut bank = if address & 0x1000 == 0 {
+            match self.latch_0.get() {
+                0xFD => self.chr_fd_0000_bank,
+                0xFE => self.chr_fe_0000_bank,
+                _ => unreachable!(),
+            }
+        } else {
             match self.latch_1.get() {
                 0xFD => self.chr_fd_1000_bank,
                 0xFE => self.chr_fe_1000_bank,
@@ -131,7 +138,7 @@ impl Mapper10 {
 }
 
 impl Mapper for Mapper10 {
-    fn init(&mut self, prg_count: u8, is_chr_ram: bool, chr_count: u8, _sram_count: u8) {
+    fn init(&mut self, prg_count: u16, is_chr_ram: bool, chr_count: u16, _prg_ram_size: u32) {
         self.prg_count = prg_count;
 
         // because 0xC000-0xFFFF holds the last 2 banks (fixed)
@@ -148,7 +155,7 @@ impl Mapper for Mapper10 {
                 0x6000..=0x7FFF => MappingResult::Allowed(address as usize & 0x1FFF),
                 0x8000..=0xFFFF => {
                     let mut bank = match address {
-                        0x8000..=0xBFFF => self.prg_bank,
+                        0x8000..=0xBFFF => self.prg_bank as u16,
                         0xC000..=0xFFFF => self.prg_count - 1, // last bank
                         _ => unreachable!(),
                     } as usize;
@@ -203,6 +210,18 @@ impl Mapper for Mapper10 {
         }
     }
 
+    fn peek_ppu(&self, address: u16) -> MappingResult {
+        self.map_chr(address)
+    }
+
+    fn poke_ppu(&mut self, address: u16, _data: u8) -> MappingResult {
+        if self.is_chr_ram {
+            self.map_chr(address)
+        } else {
+            MappingResult::Denied
+        }
+    }
+
     fn is_hardwired_mirrored(&self) -> bool {
         false
     }
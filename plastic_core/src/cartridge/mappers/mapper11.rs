@@ -6,13 +6,13 @@ pub struct Mapper11 {
     prg_bank: u8,
 
     /// in 32kb units
-    prg_count: u8,
+    prg_count: u16,
 
     /// select the 8kb bank
     chr_bank: u8,
 
     /// in 8kb units
-    chr_count: u8,
+    chr_count: u16,
 
     /// is using CHR RAM?
     is_chr_ram: bool,
@@ -30,16 +30,16 @@ impl Mapper11 {
     }
 
     fn map_ppu(&self, address: u16) -> MappingResult {
-        let bank = self.chr_bank % self.chr_count;
+        let bank = self.chr_bank as usize % self.chr_count as usize;
 
-        let start_of_bank = 0x2000 * bank as usize;
+        let start_of_bank = 0x2000 * bank;
 
         MappingResult::Allowed(start_of_bank + (address & 0x1FFF) as usize)
     }
 }
 
 impl Mapper for Mapper11 {
-    fn init(&mut self, prg_count: u8, is_chr_ram: bool, chr_count: u8, _sram_count: u8) {
+    fn init(&mut self, prg_count: u16, is_chr_ram: bool, chr_count: u16, _prg_ram_size: u32) {
         // even and positive
         assert!(prg_count % 2 == 0 && prg_count > 0);
 
@@ -53,9 +53,9 @@ impl Mapper for Mapper11 {
             Device::Cpu => match address {
                 0x6000..=0x7FFF => MappingResult::Denied,
                 0x8000..=0xFFFF => {
-                    let bank = self.prg_bank % self.prg_count;
+                    let bank = self.prg_bank as usize % self.prg_count as usize;
 
-                    let start_of_bank = 0x8000 * bank as usize;
+                    let start_of_bank = 0x8000 * bank;
 
                     MappingResult::Allowed(start_of_bank + (address & 0x7FFF) as usize)
                 }
@@ -97,24 +97,26 @@ impl Mapper for Mapper11 {
     }
 
     fn save_state_size(&self) -> usize {
-        5
+        7
     }
 
     fn save_state(&self) -> Vec<u8> {
         vec![
             self.prg_bank,
-            self.prg_count,
+            self.prg_count as u8,
+            (self.prg_count >> 8) as u8,
             self.chr_bank,
-            self.chr_count,
+            self.chr_count as u8,
+            (self.chr_count >> 8) as u8,
             self.is_chr_ram as u8,
         ]
     }
 
     fn load_state(&mut self, data: Vec<u8>) {
         self.prg_bank = data[0];
-        self.prg_count = data[1];
-        self.chr_bank = data[2];
-        self.chr_count = data[3];
-        self.is_chr_ram = data[4] != 0;
+        self.prg_count = u16::from_le_bytes([data[1], data[2]]);
+        self.chr_bank = data[3];
+        self.chr_count = u16::from_le_bytes([data[4], data[5]]);
+        self.is_chr_ram = data[6] != 0;
     }
 }
@@ -0,0 +1,124 @@
+use super::super::mapper::{Mapper, MappingResult};
+use super::flash::Sst39sf040;
+use crate::common::{Device, NametableMapping};
+use serde::{Deserialize, Serialize};
+
+/// GTROM (Cheapocabra), with 512kb of PRG flash that the game writes to save,
+/// and 32kb of RAM, the first 16kb are CHR RAM and the rest are four screen nametables
+#[derive(Serialize, Deserialize)]
+pub struct Mapper111 {
+    /// ($5000-$5FFF and $7000-$7FFF)
+    /// 7  bit  0
+    /// ---- ----
+    /// GRNC PPPP
+    /// |||| ||||
+    /// |||| ++++- Select 32 KB PRG ROM bank for CPU $8000-$FFFF
+    /// |||+------ Select 8 KB CHR RAM bank for PPU $0000-$1FFF
+    /// ||+------- Select 8 KB nametable RAM bank for PPU $2000-$3EFF
+    /// ++-------- Red and green LEDs (not emulated)
+    prg_bank: u8,
+    chr_bank: u8,
+    nametable_bank: u8,
+
+    flash: Sst39sf040,
+
+    /// in 32kb units
+    prg_count: u16,
+}
+
+impl Mapper111 {
+    pub fn new() -> Self {
+        Self {
+            prg_bank: 0,
+            chr_bank: 0,
+            nametable_bank: 0,
+            flash: Sst39sf040::new(),
+            prg_count: 0,
+        }
+    }
+
+    fn map_prg(&self, address: u16) -> usize {
+        let bank = self.prg_bank as usize % self.prg_count as usize;
+
+        bank * 0x8000 + (address & 0x7FFF) as usize
+    }
+
+    fn map_ppu(&self, address: u16) -> MappingResult {
+        match address {
+            0x0000..=0x1FFF => {
+                MappingResult::Allowed(self.chr_bank as usize * 0x2000 + address as usize)
+            }
+            // only reached when `map_nametable` selects the cartridge
+            0x2000..=0x2FFF => MappingResult::Allowed(
+                0x4000 + self.nametable_bank as usize * 0x2000 + (address & 0xFFF) as usize,
+            ),
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl Mapper for Mapper111 {
+    fn init(&mut self, prg_count: u16, _is_chr_ram: bool, _chr_count: u16, _prg_ram_size: u32) {
+        self.prg_count = prg_count.div_ceil(2);
+    }
+
+    fn map_read(&self, address: u16, device: Device) -> MappingResult {
+        match device {
+            Device::Cpu => match address {
+                0x6000..=0x7FFF => MappingResult::Denied,
+                0x8000..=0xFFFF => {
+                    let address = self.map_prg(address);
+
+                    match self.flash.read(address) {
+                        Some(data) => MappingResult::Data(data),
+                        None => MappingResult::Allowed(address),
+                    }
+                }
+                0x4020..=0x5FFF => MappingResult::Denied,
+                _ => unreachable!(),
+            },
+            Device::Ppu => self.map_ppu(address),
+        }
+    }
+
+    fn map_write(&mut self, address: u16, data: u8, device: Device) -> MappingResult {
+        match device {
+            Device::Cpu => match address {
+                0x5000..=0x5FFF | 0x7000..=0x7FFF => {
+                    self.prg_bank = data & 0xF;
+                    self.chr_bank = (data >> 4) & 1;
+                    self.nametable_bank = (data >> 5) & 1;
+
+                    MappingResult::Denied
+                }
+                0x8000..=0xFFFF => self.flash.write(self.map_prg(address), data),
+                0x4020..=0x4FFF | 0x6000..=0x6FFF => MappingResult::Denied,
+                _ => unreachable!(),
+            },
+            // CHR and nametable RAM
+            Device::Ppu => self.map_ppu(address),
+        }
+    }
+
+    fn map_nametable(&self, _address: u16) -> Option<NametableMapping> {
+        Some(NametableMapping::Cartridge)
+    }
+
+    fn has_prg_flash(&self) -> bool {
+        true
+    }
+
+    fn save_state_size(&self) -> usize {
+        bincode::serialized_size(self).unwrap() as usize
+    }
+
+    fn save_state(&self) -> Vec<u8> {
+        bincode::serialize(self).unwrap()
+    }
+
+    fn load_state(&mut self, data: Vec<u8>) {
+        let state = bincode::deserialize(&data).unwrap();
+
+        let _ = std::mem::replace(self, state);
+    }
+}
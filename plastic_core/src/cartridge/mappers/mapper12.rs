@@ -133,7 +133,7 @@ pub struct Mapper12 {
     chr_count: u16,
 
     /// in 8kb units
-    prg_count: u8,
+    prg_count: u16,
 
     /// false if the last accessed pattern table address is $0000
     /// true  if the last accessed pattern table address is $1000
@@ -202,6 +202,11 @@ impl Mapper12 {
     fn map_ppu(&self, address: u16) -> MappingResult {
         self.handle_irq_counter(address);
 
+        self.map_chr(address)
+    }
+
+    /// map the CHR `address` without clocking the IRQ counter
+    fn map_chr(&self, address: u16) -> MappingResult {
         let is_2k = (address & 0x1000 == 0) ^ self.chr_bank_2k_1000;
 
         let mut bank = if is_2k {
@@ -239,13 +244,13 @@ impl Mapper12 {
 }
 
 impl Mapper for Mapper12 {
-    fn init(&mut self, prg_count: u8, is_chr_ram: bool, chr_count: u8, sram_count: u8) {
+    fn init(&mut self, prg_count: u16, is_chr_ram: bool, chr_count: u16, prg_ram_size: u32) {
         self.prg_count = prg_count * 2;
-        self.chr_count = chr_count as u16 * 8;
+        self.chr_count = chr_count * 8;
 
         self.is_chr_ram = is_chr_ram;
 
-        self.has_prg_ram = sram_count != 0;
+        self.has_prg_ram = prg_ram_size != 0;
     }
 
     fn map_read(&self, address: u16, device: Device) -> MappingResult {
@@ -266,16 +271,16 @@ impl Mapper for Mapper12 {
                                     // second to last
                                     self.prg_count - 2
                                 } else {
-                                    self.prg_bank_8000_c000
+                                    self.prg_bank_8000_c000 as u16
                                 }
                             }
-                            0xA000..=0xBFFF => self.prg_bank_a000,
+                            0xA000..=0xBFFF => self.prg_bank_a000 as u16,
                             0xC000..=0xDFFF => {
                                 if !self.prg_rom_bank_fix_8000 {
                                     // second to last
                                     self.prg_count - 2
                                 } else {
-                                    self.prg_bank_8000_c000
+                                    self.prg_bank_8000_c000 as u16
                                 }
                             }
                             0xE000..=0xFFFF => self.prg_count - 1,
@@ -395,6 +400,18 @@ impl Mapper for Mapper12 {
         }
     }
 
+    fn peek_ppu(&self, address: u16) -> MappingResult {
+        self.map_chr(address)
+    }
+
+    fn poke_ppu(&mut self, address: u16, _data: u8) -> MappingResult {
+        if self.is_chr_ram {
+            self.map_chr(address)
+        } else {
+            MappingResult::Denied
+        }
+    }
+
     fn is_hardwired_mirrored(&self) -> bool {
         false
     }
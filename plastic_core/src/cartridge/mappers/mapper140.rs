@@ -0,0 +1,114 @@
+use super::super::mapper::{Mapper, MappingResult};
+use crate::common::Device;
+use serde::{Deserialize, Serialize};
+
+/// Jaleco JF-11/JF-14, like GxROM but with the bank register at $6000-$7FFF
+#[derive(Serialize, Deserialize)]
+pub struct Mapper140 {
+    /// ($6000-$7FFF)
+    /// 7  bit  0
+    /// ---- ----
+    /// xxPP CCCC
+    ///   || ||||
+    ///   || ++++- Select 8 KB CHR ROM bank for PPU $0000-$1FFF
+    ///   ++------ Select 32 KB PRG ROM bank for CPU $8000-$FFFF
+    prg_bank: u8,
+    chr_bank: u8,
+
+    /// in 16kb units
+    prg_count: u16,
+
+    /// in 8kb units
+    chr_count: u16,
+
+    is_chr_ram: bool,
+}
+
+impl Mapper140 {
+    pub fn new() -> Self {
+        Self {
+            prg_bank: 0,
+            chr_bank: 0,
+            prg_count: 0,
+            chr_count: 0,
+            is_chr_ram: false,
+        }
+    }
+
+    fn map_ppu(&self, address: u16) -> MappingResult {
+        let bank = self.chr_bank as usize % self.chr_count as usize;
+
+        MappingResult::Allowed(bank * 0x2000 + (address & 0x1FFF) as usize)
+    }
+}
+
+impl Mapper for Mapper140 {
+    fn init(&mut self, prg_count: u16, is_chr_ram: bool, chr_count: u16, _prg_ram_size: u32) {
+        self.prg_count = prg_count;
+        self.chr_count = chr_count;
+        self.is_chr_ram = is_chr_ram;
+    }
+
+    fn map_read(&self, address: u16, device: Device) -> MappingResult {
+        match device {
+            Device::Cpu => match address {
+                0x6000..=0x7FFF => MappingResult::Denied,
+                0x8000..=0xFFFF => {
+                    // in 16kb banks, as 16kb games are mirrored
+                    let bank = (self.prg_bank as usize * 2 + ((address >> 14) & 1) as usize)
+                        % self.prg_count as usize;
+
+                    MappingResult::Allowed(bank * 0x4000 + (address & 0x3FFF) as usize)
+                }
+                0x4020..=0x5FFF => MappingResult::Denied,
+                _ => unreachable!(),
+            },
+            Device::Ppu => {
+                if address < 0x2000 {
+                    self.map_ppu(address)
+                } else {
+                    unreachable!()
+                }
+            }
+        }
+    }
+
+    fn map_write(&mut self, address: u16, data: u8, device: Device) -> MappingResult {
+        match device {
+            Device::Cpu => {
+                match address {
+                    0x6000..=0x7FFF => {
+                        self.prg_bank = (data >> 4) & 0b11;
+                        self.chr_bank = data & 0xF;
+                    }
+                    0x4020..=0x5FFF | 0x8000..=0xFFFF => {}
+                    _ => unreachable!(),
+                }
+
+                MappingResult::Denied
+            }
+            Device::Ppu => {
+                // CHR RAM
+                if self.is_chr_ram && address <= 0x1FFF {
+                    self.map_ppu(address)
+                } else {
+                    MappingResult::Denied
+                }
+            }
+        }
+    }
+
+    fn save_state_size(&self) -> usize {
+        bincode::serialized_size(self).unwrap() as usize
+    }
+
+    fn save_state(&self) -> Vec<u8> {
+        bincode::serialize(self).unwrap()
+    }
+
+    fn load_state(&mut self, data: Vec<u8>) {
+        let state = bincode::deserialize(&data).unwrap();
+
+        let _ = std::mem::replace(self, state);
+    }
+}
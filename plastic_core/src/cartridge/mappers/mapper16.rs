@@ -0,0 +1,335 @@
+use super::super::mapper::{Mapper, MappingResult};
+use super::eeprom::I2cEeprom;
+use crate::common::{Device, MirroringMode};
+use serde::{Deserialize, Serialize};
+
+/// The Datach Joint ROM System barcode reader (mapper 157).
+///
+/// Scanning barcodes is not supported yet, so the reader never outputs any data,
+/// this is where the barcode bit stream should be generated.
+#[derive(Serialize, Deserialize)]
+pub struct DatachBarcodeReader {}
+
+impl DatachBarcodeReader {
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    /// The barcode data line, read in bit 3 of $6000-$7FFF
+    pub fn output(&self) -> bool {
+        false
+    }
+}
+
+/// Bandai FCG-1/FCG-2 and LZ93D50 boards, some of them save to a serial
+/// EEPROM instead of PRG RAM.
+///
+/// - mapper 16: FCG-1/2 (submapper 4, registers at $6000-$7FFF) or LZ93D50 with
+///   a 24C02 EEPROM (submapper 5, registers at $8000-$FFFF), both for submapper 0
+/// - mapper 153: LZ93D50 with 8kb of PRG RAM and 512kb of PRG ROM
+/// - mapper 157: LZ93D50 with a 24C02 EEPROM and the Datach barcode reader
+/// - mapper 159: LZ93D50 with a 24C01 EEPROM
+#[derive(Serialize, Deserialize)]
+pub struct Mapper16 {
+    mapper_id: u16,
+    submapper_id: u8,
+
+    /// (registers $0-$7) 1kb CHR ROM banks, for mapper 153, bit 0 of
+    /// any of them selects the 256kb outer PRG ROM bank
+    chr_banks: [u8; 8],
+    prg_outer_bank: u8,
+
+    /// (register $8)
+    /// select 16 KB PRG ROM bank for CPU $8000-$BFFF
+    prg_bank: u8,
+
+    /// (register $9)
+    /// 7  bit  0
+    /// ---- ----
+    /// xxxx xxMM
+    ///        ||
+    ///        ++- Nametable mirroring (0: vertical; 1: horizontal;
+    ///                                 2: single screen low; 3: single screen high)
+    mirroring: u8,
+
+    /// (register $A) bit 0
+    irq_enabled: bool,
+    /// (registers $B and $C) the value loaded to `irq_counter` when $A is written,
+    /// the FCG boards write to the counter directly
+    irq_latch: u16,
+    /// decremented on every CPU cycle
+    irq_counter: u16,
+
+    irq_pin: bool,
+    is_irq_pin_changed: bool,
+
+    /// (register $D)
+    /// 7  bit  0
+    /// ---- ----
+    /// RDCx xxxx
+    /// |||
+    /// ||+------- EEPROM clock (SCL), or PRG RAM enable for mapper 153
+    /// |+-------- EEPROM data (SDA)
+    /// +--------- EEPROM read enable
+    prg_ram_enabled: bool,
+
+    eeprom: Option<I2cEeprom>,
+    barcode_reader: Option<DatachBarcodeReader>,
+
+    /// in 16kb units
+    prg_count: u16,
+    /// in 1kb units
+    chr_count: u16,
+
+    is_chr_ram: bool,
+    has_prg_ram: bool,
+}
+
+impl Mapper16 {
+    pub fn new(mapper_id: u16, submapper_id: u8) -> Self {
+        let eeprom = match (mapper_id, submapper_id) {
+            (16, 4) | (153, _) => None,
+            (159, _) => Some(I2cEeprom::new_24c01()),
+            (16, _) | (157, _) => Some(I2cEeprom::new_24c02()),
+            _ => unreachable!("mapper {} is not Bandai FCG", mapper_id),
+        };
+
+        Self {
+            mapper_id,
+            submapper_id,
+            chr_banks: [0; 8],
+            prg_outer_bank: 0,
+            prg_bank: 0,
+            mirroring: 0,
+            irq_enabled: false,
+            irq_latch: 0,
+            irq_counter: 0,
+            irq_pin: false,
+            is_irq_pin_changed: false,
+            prg_ram_enabled: false,
+            eeprom,
+            barcode_reader: (mapper_id == 157).then(DatachBarcodeReader::new),
+            prg_count: 0,
+            chr_count: 0,
+            is_chr_ram: false,
+            has_prg_ram: false,
+        }
+    }
+
+    fn set_irq_pin(&mut self, state: bool) {
+        if self.irq_pin != state {
+            self.irq_pin = state;
+            self.is_irq_pin_changed = true;
+        }
+    }
+
+    /// The registers are at $6000-$7FFF for FCG boards, and at $8000-$FFFF
+    /// for LZ93D50, mapper 16 submapper 0 has both
+    fn is_register_address(&self, address: u16) -> bool {
+        match (self.mapper_id, self.submapper_id) {
+            (16, 0) => true,
+            (16, 4) => address < 0x8000,
+            _ => address >= 0x8000,
+        }
+    }
+
+    fn write_register(&mut self, address: u16, data: u8) {
+        match address & 0xF {
+            register @ 0x0..=0x7 => {
+                self.chr_banks[register as usize] = data;
+                self.prg_outer_bank = data & 1;
+            }
+            0x8 => self.prg_bank = data & 0xF,
+            0x9 => self.mirroring = data & 0b11,
+            0xA => {
+                self.irq_enabled = data & 1 != 0;
+                // LZ93D50 loads the counter from the latch
+                if address >= 0x8000 {
+                    self.irq_counter = self.irq_latch;
+                }
+                self.set_irq_pin(false);
+            }
+            0xB | 0xC => {
+                let shift = if address & 0xF == 0xB { 0 } else { 8 };
+                self.irq_latch = (self.irq_latch & !(0xFF << shift)) | ((data as u16) << shift);
+
+                // FCG boards write to the counter directly
+                if address < 0x8000 {
+                    self.irq_counter = self.irq_latch;
+                }
+            }
+            0xD => {
+                if let Some(eeprom) = &mut self.eeprom {
+                    eeprom.write_lines(data & 0x20 != 0, data & 0x40 != 0);
+                }
+                self.prg_ram_enabled = data & 0x20 != 0;
+            }
+            _ => {}
+        }
+    }
+
+    fn map_ppu(&self, address: u16) -> MappingResult {
+        if self.is_chr_ram {
+            MappingResult::Allowed(address as usize)
+        } else {
+            let bank = self.chr_banks[(address >> 10) as usize] as usize % self.chr_count as usize;
+
+            MappingResult::Allowed(bank * 0x400 + (address & 0x3FF) as usize)
+        }
+    }
+}
+
+impl Mapper for Mapper16 {
+    fn init(&mut self, prg_count: u16, is_chr_ram: bool, chr_count: u16, prg_ram_size: u32) {
+        self.prg_count = prg_count;
+        self.chr_count = chr_count * 8;
+        self.is_chr_ram = is_chr_ram;
+        // only mapper 153 has PRG RAM, in the others the header size is the EEPROM
+        self.has_prg_ram = self.mapper_id == 153 && prg_ram_size != 0;
+    }
+
+    fn map_read(&self, address: u16, device: Device) -> MappingResult {
+        match device {
+            Device::Cpu => match address {
+                0x6000..=0x7FFF if self.has_prg_ram => {
+                    if self.prg_ram_enabled {
+                        MappingResult::Allowed(address as usize & 0x1FFF)
+                    } else {
+                        MappingResult::Denied
+                    }
+                }
+                0x6000..=0x7FFF => {
+                    let eeprom_bit = self
+                        .eeprom
+                        .as_ref()
+                        .map(|eeprom| eeprom.output())
+                        .unwrap_or(false);
+                    let barcode_bit = self
+                        .barcode_reader
+                        .as_ref()
+                        .map(|reader| reader.output())
+                        .unwrap_or(false);
+
+                    MappingResult::Data(((eeprom_bit as u8) << 4) | ((barcode_bit as u8) << 3))
+                }
+                0x8000..=0xFFFF => {
+                    let bank = if address < 0xC000 { self.prg_bank } else { 0xF };
+                    // only mapper 153 has the outer bank, other mappers have up to 256kb
+                    let outer_bank = if self.mapper_id == 153 {
+                        self.prg_outer_bank
+                    } else {
+                        0
+                    };
+
+                    let bank =
+                        ((outer_bank as usize) << 4 | bank as usize) % self.prg_count as usize;
+
+                    MappingResult::Allowed(bank * 0x4000 + (address & 0x3FFF) as usize)
+                }
+                0x4020..=0x5FFF => MappingResult::Denied,
+                _ => unreachable!(),
+            },
+            Device::Ppu => {
+                if address < 0x2000 {
+                    self.map_ppu(address)
+                } else {
+                    unreachable!()
+                }
+            }
+        }
+    }
+
+    fn map_write(&mut self, address: u16, data: u8, device: Device) -> MappingResult {
+        match device {
+            Device::Cpu => match address {
+                0x6000..=0x7FFF if self.has_prg_ram => {
+                    if self.prg_ram_enabled {
+                        MappingResult::Allowed(address as usize & 0x1FFF)
+                    } else {
+                        MappingResult::Denied
+                    }
+                }
+                0x6000..=0xFFFF => {
+                    if self.is_register_address(address) {
+                        self.write_register(address, data);
+                    }
+
+                    MappingResult::Denied
+                }
+                0x4020..=0x5FFF => MappingResult::Denied,
+                _ => unreachable!(),
+            },
+            Device::Ppu => {
+                // CHR RAM
+                if self.is_chr_ram && address <= 0x1FFF {
+                    self.map_ppu(address)
+                } else {
+                    MappingResult::Denied
+                }
+            }
+        }
+    }
+
+    fn is_hardwired_mirrored(&self) -> bool {
+        false
+    }
+
+    fn nametable_mirroring(&self) -> MirroringMode {
+        match self.mirroring {
+            0 => MirroringMode::Vertical,
+            1 => MirroringMode::Horizontal,
+            2 => MirroringMode::SingleScreenLowBank,
+            3 => MirroringMode::SingleScreenHighBank,
+            _ => unreachable!(),
+        }
+    }
+
+    fn is_irq_pin_state_changed_requested(&self) -> bool {
+        self.is_irq_pin_changed
+    }
+
+    fn irq_pin_state(&self) -> bool {
+        self.irq_pin
+    }
+
+    fn clear_irq_request_pin(&mut self) {
+        self.is_irq_pin_changed = false;
+    }
+
+    fn clock(&mut self) {
+        if self.irq_enabled {
+            // the counter is checked before decrementing, which some games rely on
+            if self.irq_counter == 0 {
+                self.set_irq_pin(true);
+            }
+            self.irq_counter = self.irq_counter.wrapping_sub(1);
+        }
+    }
+
+    fn battery_backed_ram(&self) -> &[u8] {
+        self.eeprom
+            .as_ref()
+            .map(|eeprom| eeprom.data())
+            .unwrap_or(&[])
+    }
+
+    fn load_battery_backed_ram(&mut self, data: &[u8]) {
+        if let Some(eeprom) = &mut self.eeprom {
+            eeprom.load_data(data);
+        }
+    }
+
+    fn save_state_size(&self) -> usize {
+        bincode::serialized_size(self).unwrap() as usize
+    }
+
+    fn save_state(&self) -> Vec<u8> {
+        bincode::serialize(self).unwrap()
+    }
+
+    fn load_state(&mut self, data: Vec<u8>) {
+        let state = bincode::deserialize(&data).unwrap();
+
+        let _ = std::mem::replace(self, state);
+    }
+}
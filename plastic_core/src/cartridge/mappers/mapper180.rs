@@ -0,0 +1,101 @@
+use super::super::mapper::{Mapper, MappingResult};
+use crate::common::Device;
+use serde::{Deserialize, Serialize};
+
+/// UNROM with a 74HC08 instead of the 74HC32, so the first bank is fixed
+/// at $8000-$BFFF and the switchable bank is at $C000-$FFFF (Crazy Climber)
+#[derive(Serialize, Deserialize)]
+pub struct Mapper180 {
+    /// ($8000-$FFFF)
+    /// select 16 KB PRG ROM bank for CPU $C000-$FFFF
+    prg_bank: u8,
+
+    /// in 16kb units
+    prg_count: u16,
+
+    is_chr_ram: bool,
+}
+
+impl Mapper180 {
+    pub fn new() -> Self {
+        Self {
+            prg_bank: 0,
+            prg_count: 0,
+            is_chr_ram: false,
+        }
+    }
+}
+
+impl Mapper for Mapper180 {
+    fn init(&mut self, prg_count: u16, is_chr_ram: bool, _chr_count: u16, _prg_ram_size: u32) {
+        self.prg_count = prg_count;
+        self.is_chr_ram = is_chr_ram;
+    }
+
+    fn map_read(&self, address: u16, device: Device) -> MappingResult {
+        match device {
+            Device::Cpu => match address {
+                0x6000..=0x7FFF => MappingResult::Denied,
+                0x8000..=0xFFFF => {
+                    let bank = if address < 0xC000 {
+                        0
+                    } else {
+                        self.prg_bank as usize % self.prg_count as usize
+                    };
+
+                    MappingResult::Allowed(bank * 0x4000 + (address & 0x3FFF) as usize)
+                }
+                0x4020..=0x5FFF => MappingResult::Denied,
+                _ => unreachable!(),
+            },
+            Device::Ppu => {
+                // only one fixed memory
+                if address < 0x2000 {
+                    MappingResult::Allowed(address as usize)
+                } else {
+                    unreachable!()
+                }
+            }
+        }
+    }
+
+    fn map_write(&mut self, address: u16, data: u8, device: Device) -> MappingResult {
+        match device {
+            Device::Cpu => {
+                match address {
+                    0x8000..=0xFFFF => self.prg_bank = data,
+                    0x4020..=0x7FFF => {}
+                    _ => unreachable!(),
+                }
+
+                MappingResult::Denied
+            }
+            Device::Ppu => {
+                // CHR RAM
+                if self.is_chr_ram && address <= 0x1FFF {
+                    MappingResult::Allowed(address as usize)
+                } else {
+                    MappingResult::Denied
+                }
+            }
+        }
+    }
+
+    fn has_bus_conflicts(&self) -> bool {
+        true
+    }
+
+    fn save_state_size(&self) -> usize {
+        bincode::serialized_size(self).unwrap() as usize
+    }
+
+    fn save_state(&self) -> Vec<u8> {
+        bincode::serialize(self).unwrap()
+    }
+
+    fn load_state(&mut self, data: Vec<u8>) {
+        let state = bincode::deserialize(&data).unwrap();
+
+        let _ = std::mem::replace(self, state);
+    }
+}
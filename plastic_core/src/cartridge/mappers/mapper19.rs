@@ -0,0 +1,297 @@
+use super::super::mapper::{Mapper, MappingResult};
+use crate::apu2a03::expansion::{APUChannel, ExpansionAudioChip, Namco163Audio, TimedAPUChannel};
+use crate::common::{Device, MirroringMode, NametableMapping};
+use serde::{Deserialize, Serialize};
+
+/// Namco 129 and 163, with the Namco 163 audio.
+///
+/// The 128 bytes of internal RAM are owned by the audio chip, as it is used
+/// for the waveforms and the channels registers.
+///
+/// Selecting the nametable RAM (CIRAM) as pattern tables (CHR banks `$E0-$FF`
+/// with `$E800` bits 6,7 clear) is not supported, these are mapped to CHR ROM.
+#[derive(Serialize, Deserialize)]
+pub struct Mapper19 {
+    /// ($8000-$BFFF) 1kb CHR banks, one register for every `$800` bytes
+    chr_banks: [u8; 8],
+
+    /// ($C000-$DFFF) nametables for `$2000`, `$2400`, `$2800` and `$2C00`,
+    /// values `$E0-$FF` select the nametable RAM (CIRAM) page using bit 0,
+    /// and the rest select 1kb CHR ROM banks
+    nametable_banks: [u8; 4],
+
+    /// ($E000-$F7FF) 8kb PRG ROM banks at `$8000`, `$A000` and `$C000`
+    /// 7  bit  0
+    /// ---- ----
+    /// .SPP PPPP
+    ///  ||| ||||
+    ///  |++-++++- Select 8 KB page of PRG-ROM
+    ///  +-------- ($E000 only) Disable sound if set
+    prg_banks: [u8; 3],
+
+    /// ($F800-$FFFF), shared with the internal RAM address port
+    /// 7  bit  0
+    /// ---- ----
+    /// KKKK DCBA
+    /// |||| ||||
+    /// |||| |||+- 1: Write-protect 2kB window of PRG RAM from $6000-$67FF
+    /// |||| ||+-- 1: Write-protect 2kB window of PRG RAM from $6800-$6FFF
+    /// |||| |+--- 1: Write-protect 2kB window of PRG RAM from $7000-$77FF
+    /// |||| +---- 1: Write-protect 2kB window of PRG RAM from $7800-$7FFF
+    /// ++++------ PRG RAM writes are enabled only if this is 0100
+    write_protect: u8,
+
+    /// ($5000-$5FFF) 15 bits, incremented on every CPU cycle
+    /// until it reaches `$7FFF`
+    irq_counter: u16,
+    /// (`$5800` bit 7)
+    irq_enabled: bool,
+
+    irq_pin: bool,
+    is_irq_pin_changed: bool,
+
+    audio: Namco163Audio,
+
+    /// in 8kb units
+    prg_count: u16,
+    /// in 1kb units
+    chr_count: u16,
+
+    is_chr_ram: bool,
+    has_prg_ram: bool,
+}
+
+impl Mapper19 {
+    pub fn new() -> Self {
+        Self {
+            chr_banks: [0; 8],
+            nametable_banks: [0; 4],
+            prg_banks: [0; 3],
+            write_protect: 0,
+
+            irq_counter: 0,
+            irq_enabled: false,
+
+            irq_pin: false,
+            is_irq_pin_changed: false,
+
+            audio: Namco163Audio::new(),
+
+            prg_count: 0,
+            chr_count: 0,
+
+            is_chr_ram: false,
+            has_prg_ram: false,
+        }
+    }
+
+    fn set_irq_pin(&mut self, state: bool) {
+        if self.irq_pin != state {
+            self.irq_pin = state;
+            self.is_irq_pin_changed = true;
+        }
+    }
+
+    fn is_prg_ram_writable(&self, address: u16) -> bool {
+        let window = (address - 0x6000) >> 11;
+
+        self.write_protect & 0xF0 == 0x40 && self.write_protect & (1 << window) == 0
+    }
+
+    fn map_chr_bank(&self, bank: u8, address: u16) -> MappingResult {
+        let bank = bank as usize % self.chr_count as usize;
+
+        MappingResult::Allowed(bank * 0x400 + (address & 0x3FF) as usize)
+    }
+
+    fn map_ppu(&self, address: u16) -> MappingResult {
+        match address {
+            0x0000..=0x1FFF => self.map_chr_bank(self.chr_banks[(address >> 10) as usize], address),
+            // only reached when `map_nametable` selects the cartridge
+            0x2000..=0x3EFF => {
+                self.map_chr_bank(self.nametable_banks[(address >> 10) as usize & 3], address)
+            }
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl Mapper for Mapper19 {
+    fn init(&mut self, prg_count: u16, is_chr_ram: bool, chr_count: u16, prg_ram_size: u32) {
+        self.prg_count = prg_count * 2;
+        self.chr_count = chr_count * 8;
+
+        self.is_chr_ram = is_chr_ram;
+        self.has_prg_ram = prg_ram_size != 0;
+    }
+
+    fn map_read(&self, address: u16, device: Device) -> MappingResult {
+        match device {
+            Device::Cpu => match address {
+                0x4800..=0x4FFF => MappingResult::Data(self.audio.read_data()),
+                0x5000..=0x57FF => MappingResult::Data(self.irq_counter as u8),
+                0x5800..=0x5FFF => MappingResult::Data(
+                    (self.irq_counter >> 8) as u8 | (self.irq_enabled as u8) << 7,
+                ),
+                0x6000..=0x7FFF => {
+                    if self.has_prg_ram {
+                        MappingResult::Allowed(address as usize & 0x1FFF)
+                    } else {
+                        MappingResult::Denied
+                    }
+                }
+                0x8000..=0xFFFF => {
+                    let bank = match address {
+                        0x8000..=0xDFFF => {
+                            (self.prg_banks[(address as usize - 0x8000) >> 13] & 0x3F) as u16
+                        }
+                        0xE000..=0xFFFF => self.prg_count - 1,
+                        _ => unreachable!(),
+                    } as usize
+                        % self.prg_count as usize;
+
+                    MappingResult::Allowed(bank * 0x2000 + (address & 0x1FFF) as usize)
+                }
+                0x4020..=0x47FF => MappingResult::Denied,
+                _ => unreachable!(),
+            },
+            Device::Ppu => self.map_ppu(address),
+        }
+    }
+
+    fn map_write(&mut self, address: u16, data: u8, device: Device) -> MappingResult {
+        match device {
+            Device::Cpu => match address {
+                0x4800..=0x4FFF => {
+                    self.audio.write_data(data);
+                    MappingResult::Denied
+                }
+                0x5000..=0x57FF => {
+                    self.irq_counter = (self.irq_counter & 0x7F00) | data as u16;
+                    self.set_irq_pin(false);
+                    MappingResult::Denied
+                }
+                0x5800..=0x5FFF => {
+                    self.irq_counter = (self.irq_counter & 0xFF) | (data as u16 & 0x7F) << 8;
+                    self.irq_enabled = data & 0x80 != 0;
+                    self.set_irq_pin(false);
+                    MappingResult::Denied
+                }
+                0x6000..=0x7FFF => {
+                    if self.has_prg_ram && self.is_prg_ram_writable(address) {
+                        MappingResult::Allowed(address as usize & 0x1FFF)
+                    } else {
+                        MappingResult::Denied
+                    }
+                }
+                0x8000..=0xFFFF => {
+                    match address {
+                        0x8000..=0xBFFF => {
+                            self.chr_banks[(address as usize - 0x8000) >> 11] = data;
+                        }
+                        0xC000..=0xDFFF => {
+                            self.nametable_banks[(address as usize - 0xC000) >> 11] = data;
+                        }
+                        0xE000..=0xE7FF => {
+                            self.prg_banks[0] = data;
+                            self.audio.set_sound_disabled(data & 0x40 != 0);
+                        }
+                        0xE800..=0xEFFF => self.prg_banks[1] = data,
+                        0xF000..=0xF7FF => self.prg_banks[2] = data,
+                        0xF800..=0xFFFF => {
+                            self.write_protect = data;
+                            self.audio.write_address(data);
+                        }
+                        _ => unreachable!(),
+                    }
+
+                    MappingResult::Denied
+                }
+                0x4020..=0x47FF => MappingResult::Denied,
+                _ => unreachable!(),
+            },
+            Device::Ppu => {
+                // CHR RAM
+                if self.is_chr_ram && address <= 0x1FFF {
+                    self.map_ppu(address)
+                } else {
+                    MappingResult::Denied
+                }
+            }
+        }
+    }
+
+    fn map_nametable(&self, address: u16) -> Option<NametableMapping> {
+        let bank = self.nametable_banks[(address >> 10) as usize & 3];
+
+        if bank >= 0xE0 {
+            Some(NametableMapping::Vram(bank & 1))
+        } else {
+            Some(NametableMapping::Cartridge)
+        }
+    }
+
+    fn clock(&mut self) {
+        if self.irq_enabled && self.irq_counter < 0x7FFF {
+            self.irq_counter += 1;
+
+            if self.irq_counter == 0x7FFF {
+                self.set_irq_pin(true);
+            }
+        }
+
+        self.audio.timer_clock();
+    }
+
+    fn expansion_audio_output(&mut self) -> f32 {
+        ExpansionAudioChip::Namco163.mix(self.audio.get_output())
+    }
+
+    fn is_hardwired_mirrored(&self) -> bool {
+        false
+    }
+
+    fn nametable_mirroring(&self) -> MirroringMode {
+        // only an approximation, as the nametables are mapped by `map_nametable`
+        match self.nametable_banks.map(|bank| bank | 0xFE) {
+            [0xFE, 0xFE, 0xFF, 0xFF] => MirroringMode::Horizontal,
+            [0xFE, 0xFE, 0xFE, 0xFE] => MirroringMode::SingleScreenLowBank,
+            [0xFF, 0xFF, 0xFF, 0xFF] => MirroringMode::SingleScreenHighBank,
+            _ => MirroringMode::Vertical,
+        }
+    }
+
+    fn is_irq_pin_state_changed_requested(&self) -> bool {
+        self.is_irq_pin_changed
+    }
+
+    fn irq_pin_state(&self) -> bool {
+        self.irq_pin
+    }
+
+    fn clear_irq_request_pin(&mut self) {
+        self.is_irq_pin_changed = false;
+    }
+
+    fn battery_backed_ram(&self) -> &[u8] {
+        self.audio.ram()
+    }
+
+    fn load_battery_backed_ram(&mut self, data: &[u8]) {
+        self.audio.load_ram(data);
+    }
+
+    fn save_state_size(&self) -> usize {
+        bincode::serialized_size(self).unwrap() as usize
+    }
+
+    fn save_state(&self) -> Vec<u8> {
+        bincode::serialize(self).unwrap()
+    }
+
+    fn load_state(&mut self, data: Vec<u8>) {
+        let state = bincode::deserialize(&data).unwrap();
+
+        let _ = std::mem::replace(self, state);
+    }
+}
@@ -5,7 +5,7 @@ pub struct Mapper2 {
     prg_top_bank: u8,
 
     /// in 16kb units
-    prg_count: u8,
+    prg_count: u16,
 
     is_chr_ram: bool,
 }
@@ -21,7 +21,7 @@ impl Mapper2 {
 }
 
 impl Mapper for Mapper2 {
-    fn init(&mut self, prg_count: u8, is_chr_ram: bool, _chr_count: u8, _sram_count: u8) {
+    fn init(&mut self, prg_count: u16, is_chr_ram: bool, _chr_count: u16, _prg_ram_size: u32) {
         self.prg_count = prg_count;
         self.is_chr_ram = is_chr_ram;
     }
@@ -33,12 +33,12 @@ impl Mapper for Mapper2 {
                     0x6000..=0x7FFF => MappingResult::Denied,
                     0x8000..=0xFFFF => {
                         let mut bank = if (0x8000..=0xBFFF).contains(&address) {
-                            self.prg_top_bank & 0xF
+                            (self.prg_top_bank & 0xF) as usize
                         } else if address >= 0xC000 {
-                            self.prg_count - 1
+                            self.prg_count as usize - 1
                         } else {
                             unreachable!();
-                        } as usize;
+                        };
 
                         bank %= self.prg_count as usize;
 
@@ -86,16 +86,21 @@ impl Mapper for Mapper2 {
     }
 
     fn save_state_size(&self) -> usize {
-        3
+        4
     }
 
     fn save_state(&self) -> Vec<u8> {
-        vec![self.prg_top_bank, self.prg_count, self.is_chr_ram as u8]
+        vec![
+            self.prg_top_bank,
+            self.prg_count as u8,
+            (self.prg_count >> 8) as u8,
+            self.is_chr_ram as u8,
+        ]
     }
 
     fn load_state(&mut self, data: Vec<u8>) {
         self.prg_top_bank = data[0];
-        self.prg_count = data[1];
-        self.is_chr_ram = data[2] != 0;
+        self.prg_count = u16::from_le_bytes([data[1], data[2]]);
+        self.is_chr_ram = data[3] != 0;
     }
 }
@@ -0,0 +1,290 @@
+use super::super::mapper::{Mapper, MappingResult};
+use super::vrc_irq::VrcIrq;
+use crate::common::{Device, MirroringMode};
+use serde::{Deserialize, Serialize};
+
+const A0: u16 = 1 << 0;
+const A1: u16 = 1 << 1;
+const A2: u16 = 1 << 2;
+const A3: u16 = 1 << 3;
+const A6: u16 = 1 << 6;
+const A7: u16 = 1 << 7;
+
+/// Konami VRC2 and VRC4, this handles mappers 21, 22, 23 and 25.
+///
+/// The boards differ in which CPU address lines are connected to the
+/// two register select pins of the chip, it is selected using the
+/// submapper id, and for iNES 1.0 files (submapper 0) the lines of all possible
+/// boards are ORed together, which works for almost all games.
+#[derive(Serialize, Deserialize)]
+pub struct Mapper21 {
+    /// ($8000-$8003)
+    /// 7  bit  0
+    /// ---------
+    /// ...P PPPP
+    ///    | ||||
+    ///    +-++++- Select 8 KB PRG bank at $8000 or $C000
+    prg_bank_0: u8,
+
+    /// ($A000-$A003)
+    /// 7  bit  0
+    /// ---------
+    /// ...P PPPP
+    ///    | ||||
+    ///    +-++++- Select 8 KB PRG bank at $A000
+    prg_bank_1: u8,
+
+    /// ($9002, VRC4 only)
+    /// 7  bit  0
+    /// ---------
+    /// .... ..MW
+    ///        |+- WRAM control
+    ///        +-- Swap Mode (0: $8000 swappable, $C000 fixed to second-last bank;
+    ///                       1: $C000 swappable, $8000 fixed to second-last bank)
+    prg_swap_mode: bool,
+
+    /// ($9000)
+    /// 7  bit  0
+    /// ---------
+    /// .... ..MM
+    ///        ||
+    ///        ++- Mirroring (0: vertical; 1: horizontal;
+    ///                       2: one-screen lower bank; 3: one-screen upper bank)
+    ///            (VRC2 has only the low bit)
+    mirroring: u8,
+
+    /// ($B000-$E003) 1kb CHR banks, each one is written in two parts,
+    /// the low 4 bits, and the high bits
+    chr_banks: [u16; 8],
+
+    irq: VrcIrq,
+
+    is_vrc2: bool,
+    /// VRC2a ignores the lowest bit of the CHR banks
+    chr_bank_shift: u8,
+    /// the address lines connected to the register select pins (bit0, bit1)
+    register_lines: (u16, u16),
+
+    /// in 8kb units
+    prg_count: u16,
+    /// in 1kb units
+    chr_count: u16,
+
+    is_chr_ram: bool,
+    has_prg_ram: bool,
+}
+
+impl Mapper21 {
+    pub fn new(mapper_id: u16, submapper_id: u8) -> Self {
+        // (is_vrc2, (bit0, bit1))
+        let (is_vrc2, register_lines) = match (mapper_id, submapper_id) {
+            // VRC4a
+            (21, 1) => (false, (A1, A2)),
+            // VRC4c
+            (21, 2) => (false, (A6, A7)),
+            (21, _) => (false, (A1 | A6, A2 | A7)),
+            // VRC2a
+            (22, _) => (true, (A1, A0)),
+            // VRC4f
+            (23, 1) => (false, (A0, A1)),
+            // VRC4e
+            (23, 2) => (false, (A2, A3)),
+            // VRC2b
+            (23, 3) => (true, (A0, A1)),
+            (23, _) => (false, (A0 | A2, A1 | A3)),
+            // VRC4b
+            (25, 1) => (false, (A1, A0)),
+            // VRC4d
+            (25, 2) => (false, (A3, A2)),
+            // VRC2c
+            (25, 3) => (true, (A1, A0)),
+            (25, _) => (false, (A1 | A3, A0 | A2)),
+            _ => unreachable!("mapper {} is not VRC2/VRC4", mapper_id),
+        };
+
+        Self {
+            prg_bank_0: 0,
+            prg_bank_1: 0,
+            prg_swap_mode: false,
+            mirroring: 0,
+            chr_banks: [0; 8],
+
+            irq: VrcIrq::new(),
+
+            is_vrc2,
+            chr_bank_shift: (mapper_id == 22) as u8,
+            register_lines,
+
+            prg_count: 0,
+            chr_count: 0,
+
+            is_chr_ram: false,
+            has_prg_ram: false,
+        }
+    }
+
+    /// returns the register address in the form `$x000-$x003`
+    fn register_address(&self, address: u16) -> u16 {
+        let bit0 = (address & self.register_lines.0 != 0) as u16;
+        let bit1 = (address & self.register_lines.1 != 0) as u16;
+
+        (address & 0xF000) | bit1 << 1 | bit0
+    }
+
+    fn map_ppu(&self, address: u16) -> MappingResult {
+        let bank = (self.chr_banks[(address >> 10) as usize & 7] >> self.chr_bank_shift) as usize
+            % self.chr_count as usize;
+
+        MappingResult::Allowed(bank * 0x400 + (address & 0x3FF) as usize)
+    }
+}
+
+impl Mapper for Mapper21 {
+    fn init(&mut self, prg_count: u16, is_chr_ram: bool, chr_count: u16, prg_ram_size: u32) {
+        self.prg_count = prg_count * 2;
+        self.chr_count = chr_count * 8;
+
+        self.is_chr_ram = is_chr_ram;
+        self.has_prg_ram = prg_ram_size != 0;
+    }
+
+    fn map_read(&self, address: u16, device: Device) -> MappingResult {
+        match device {
+            Device::Cpu => match address {
+                0x6000..=0x7FFF => {
+                    if self.has_prg_ram {
+                        MappingResult::Allowed(address as usize & 0x1FFF)
+                    } else {
+                        MappingResult::Denied
+                    }
+                }
+                0x8000..=0xFFFF => {
+                    let second_last = self.prg_count - 2;
+
+                    let bank = match address {
+                        0x8000..=0x9FFF if self.prg_swap_mode => second_last,
+                        0x8000..=0x9FFF => self.prg_bank_0 as u16,
+                        0xA000..=0xBFFF => self.prg_bank_1 as u16,
+                        0xC000..=0xDFFF if self.prg_swap_mode => self.prg_bank_0 as u16,
+                        0xC000..=0xDFFF => second_last,
+                        0xE000..=0xFFFF => self.prg_count - 1,
+                        _ => unreachable!(),
+                    } as usize
+                        % self.prg_count as usize;
+
+                    MappingResult::Allowed(bank * 0x2000 + (address & 0x1FFF) as usize)
+                }
+                0x4020..=0x5FFF => MappingResult::Denied,
+                _ => unreachable!(),
+            },
+            Device::Ppu => {
+                if address < 0x2000 {
+                    self.map_ppu(address)
+                } else {
+                    unreachable!();
+                }
+            }
+        }
+    }
+
+    fn map_write(&mut self, address: u16, data: u8, device: Device) -> MappingResult {
+        match device {
+            Device::Cpu => match address {
+                0x6000..=0x7FFF => {
+                    if self.has_prg_ram {
+                        MappingResult::Allowed(address as usize & 0x1FFF)
+                    } else {
+                        MappingResult::Denied
+                    }
+                }
+                0x8000..=0xFFFF => {
+                    let address = self.register_address(address);
+
+                    match address {
+                        0x8000..=0x8003 => self.prg_bank_0 = data & 0x1F,
+                        0x9000..=0x9003 if self.is_vrc2 => self.mirroring = data & 1,
+                        0x9000..=0x9001 => self.mirroring = data & 3,
+                        0x9002..=0x9003 => self.prg_swap_mode = data & 2 != 0,
+                        0xA000..=0xA003 => self.prg_bank_1 = data & 0x1F,
+                        0xB000..=0xEFFF => {
+                            let register = address & 3;
+                            let bank_index =
+                                ((address - 0xB000) >> 12) as usize * 2 + (register >> 1) as usize;
+                            let bank = &mut self.chr_banks[bank_index];
+
+                            if register & 1 == 0 {
+                                *bank = (*bank & 0x1F0) | (data as u16 & 0xF);
+                            } else {
+                                *bank = (*bank & 0xF) | (data as u16 & 0x1F) << 4;
+                            }
+                        }
+                        0xF000..=0xFFFF if self.is_vrc2 => {}
+                        0xF000 => self.irq.write_latch_low(data),
+                        0xF001 => self.irq.write_latch_high(data),
+                        0xF002 => self.irq.write_control(data),
+                        0xF003 => self.irq.acknowledge(),
+                        _ => unreachable!(),
+                    }
+
+                    MappingResult::Denied
+                }
+                0x4020..=0x5FFF => MappingResult::Denied,
+                _ => unreachable!(),
+            },
+            Device::Ppu => {
+                // CHR RAM
+                if self.is_chr_ram && address <= 0x1FFF {
+                    self.map_ppu(address)
+                } else {
+                    MappingResult::Denied
+                }
+            }
+        }
+    }
+
+    fn clock(&mut self) {
+        if !self.is_vrc2 {
+            self.irq.clock();
+        }
+    }
+
+    fn is_hardwired_mirrored(&self) -> bool {
+        false
+    }
+
+    fn nametable_mirroring(&self) -> MirroringMode {
+        match self.mirroring {
+            0 => MirroringMode::Vertical,
+            1 => MirroringMode::Horizontal,
+            2 => MirroringMode::SingleScreenLowBank,
+            3 => MirroringMode::SingleScreenHighBank,
+            _ => unreachable!(),
+        }
+    }
+
+    fn is_irq_pin_state_changed_requested(&self) -> bool {
+        self.irq.is_irq_pin_state_changed_requested()
+    }
+
+    fn irq_pin_state(&self) -> bool {
+        self.irq.irq_pin_state()
+    }
+
+    fn clear_irq_request_pin(&mut self) {
+        self.irq.clear_irq_request_pin();
+    }
+
+    fn save_state_size(&self) -> usize {
+        bincode::serialized_size(self).unwrap() as usize
+    }
+
+    fn save_state(&self) -> Vec<u8> {
+        bincode::serialize(self).unwrap()
+    }
+
+    fn load_state(&mut self, data: Vec<u8>) {
+        let state = bincode::deserialize(&data).unwrap();
+
+        let _ = std::mem::replace(self, state);
+    }
+}
@@ -0,0 +1,119 @@
+use super::super::mapper::{Mapper, MappingResult};
+use crate::common::Device;
+use serde::{Deserialize, Serialize};
+
+/// Camerica/Codemasters BF9096 (Quattro multicarts), UNROM like inside
+/// 64 KB blocks
+#[derive(Serialize, Deserialize)]
+pub struct Mapper232 {
+    /// the Aladdin Deck Enhancer (submapper 1) has the block bits swapped
+    is_aladdin: bool,
+
+    /// ($8000-$BFFF)
+    /// 7  bit  0
+    /// ---- ----
+    /// xxxB Bxxx
+    ///    | |
+    ///    +-+---- Select 64 KB PRG ROM block
+    prg_block: u8,
+
+    /// ($C000-$FFFF)
+    /// 7  bit  0
+    /// ---- ----
+    /// xxxx xxPP
+    ///        ||
+    ///        ++- Select 16 KB PRG ROM bank at CPU $8000-$BFFF inside the block,
+    ///            CPU $C000-$FFFF is fixed to the last bank of the block
+    prg_bank: u8,
+
+    /// in 16kb units
+    prg_count: u16,
+
+    is_chr_ram: bool,
+}
+
+impl Mapper232 {
+    pub fn new(submapper_id: u8) -> Self {
+        Self {
+            is_aladdin: submapper_id == 1,
+            prg_block: 0,
+            prg_bank: 0,
+            prg_count: 0,
+            is_chr_ram: false,
+        }
+    }
+}
+
+impl Mapper for Mapper232 {
+    fn init(&mut self, prg_count: u16, is_chr_ram: bool, _chr_count: u16, _prg_ram_size: u32) {
+        self.prg_count = prg_count;
+        self.is_chr_ram = is_chr_ram;
+    }
+
+    fn map_read(&self, address: u16, device: Device) -> MappingResult {
+        match device {
+            Device::Cpu => match address {
+                0x6000..=0x7FFF => MappingResult::Denied,
+                0x8000..=0xFFFF => {
+                    let bank = if address < 0xC000 { self.prg_bank } else { 3 };
+                    let bank = ((self.prg_block << 2) | bank) as usize % self.prg_count as usize;
+
+                    MappingResult::Allowed(bank * 0x4000 + (address & 0x3FFF) as usize)
+                }
+                0x4020..=0x5FFF => MappingResult::Denied,
+                _ => unreachable!(),
+            },
+            Device::Ppu => {
+                // only one fixed memory
+                if address < 0x2000 {
+                    MappingResult::Allowed(address as usize)
+                } else {
+                    unreachable!()
+                }
+            }
+        }
+    }
+
+    fn map_write(&mut self, address: u16, data: u8, device: Device) -> MappingResult {
+        match device {
+            Device::Cpu => {
+                match address {
+                    0x8000..=0xBFFF => {
+                        self.prg_block = if self.is_aladdin {
+                            ((data >> 4) & 1) | ((data >> 2) & 0b10)
+                        } else {
+                            (data >> 3) & 0b11
+                        };
+                    }
+                    0xC000..=0xFFFF => self.prg_bank = data & 0b11,
+                    0x4020..=0x7FFF => {}
+                    _ => unreachable!(),
+                }
+
+                MappingResult::Denied
+            }
+            Device::Ppu => {
+                // CHR RAM
+                if self.is_chr_ram && address <= 0x1FFF {
+                    MappingResult::Allowed(address as usize)
+                } else {
+                    MappingResult::Denied
+                }
+            }
+        }
+    }
+
+    fn save_state_size(&self) -> usize {
+        bincode::serialized_size(self).unwrap() as usize
+    }
+
+    fn save_state(&self) -> Vec<u8> {
+        bincode::serialize(self).unwrap()
+    }
+
+    fn load_state(&mut self, data: Vec<u8>) {
+        let state = bincode::deserialize(&data).unwrap();
+
+        let _ = std::mem::replace(self, state);
+    }
+}
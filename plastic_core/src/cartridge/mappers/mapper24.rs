@@ -0,0 +1,239 @@
+use super::super::mapper::{Mapper, MappingResult};
+use super::vrc_irq::VrcIrq;
+use crate::apu2a03::expansion::{APUChannel, ExpansionAudioChip, TimedAPUChannel, Vrc6Audio};
+use crate::common::{Device, MirroringMode};
+use serde::{Deserialize, Serialize};
+
+/// Konami VRC6, this handles both mapper 24 (VRC6a) and mapper 26 (VRC6b),
+/// mapper 26 has the `A0` and `A1` address lines swapped
+#[derive(Serialize, Deserialize)]
+pub struct Mapper24 {
+    /// ($8000-$8003)
+    /// 7  bit  0
+    /// ---- ----
+    /// .... PPPP
+    ///      ||||
+    ///      ++++- Select 16 KB PRG ROM at $8000-$BFFF
+    prg_bank_16k: u8,
+
+    /// ($C000-$C003)
+    /// 7  bit  0
+    /// ---- ----
+    /// ...P PPPP
+    ///    | ||||
+    ///    +-++++- Select 8 KB PRG ROM at $C000-$DFFF
+    prg_bank_8k: u8,
+
+    /// ($D000-$E003) R0-R7, 1kb CHR banks
+    chr_banks: [u8; 8],
+
+    /// ($B003)
+    /// 7  bit  0
+    /// ---- ----
+    /// W.PN MMDD
+    /// | || ||||
+    /// | || ||++- PPU banking mode
+    /// | || ++--- Mirroring
+    /// | |+------ Nametable source (0: CIRAM, 1: CHR ROM)
+    /// | +------- CHR A10 (1KB CHR banks)
+    /// +--------- PRG RAM enable
+    ///
+    /// Only the common configuration used by all games is implemented, i.e.
+    /// 1kb CHR banks, and `MM` selecting the mirroring from CIRAM
+    /// (0: vertical; 1: horizontal; 2: one-screen lower bank; 3: one-screen upper bank)
+    ppu_control: u8,
+
+    irq: VrcIrq,
+    audio: Vrc6Audio,
+
+    /// mapper 26 swaps `A0` and `A1`
+    swap_address_lines: bool,
+
+    /// in 8kb units
+    prg_count: u16,
+    /// in 1kb units
+    chr_count: u16,
+
+    is_chr_ram: bool,
+    has_prg_ram: bool,
+}
+
+impl Mapper24 {
+    pub fn new(swap_address_lines: bool) -> Self {
+        Self {
+            prg_bank_16k: 0,
+            prg_bank_8k: 0,
+            chr_banks: [0; 8],
+            ppu_control: 0,
+
+            irq: VrcIrq::new(),
+            audio: Vrc6Audio::new(),
+
+            swap_address_lines,
+
+            prg_count: 0,
+            chr_count: 0,
+
+            is_chr_ram: false,
+            has_prg_ram: false,
+        }
+    }
+
+    /// returns the register address in the form `$x000-$x003`
+    fn register_address(&self, address: u16) -> u16 {
+        let lines = if self.swap_address_lines {
+            (address & 1) << 1 | (address >> 1) & 1
+        } else {
+            address & 3
+        };
+
+        (address & 0xF000) | lines
+    }
+
+    fn is_prg_ram_enabled(&self) -> bool {
+        self.has_prg_ram && self.ppu_control & 0x80 != 0
+    }
+
+    fn map_ppu(&self, address: u16) -> MappingResult {
+        let bank = self.chr_banks[(address >> 10) as usize & 7] as usize % self.chr_count as usize;
+
+        MappingResult::Allowed(bank * 0x400 + (address & 0x3FF) as usize)
+    }
+}
+
+impl Mapper for Mapper24 {
+    fn init(&mut self, prg_count: u16, is_chr_ram: bool, chr_count: u16, prg_ram_size: u32) {
+        self.prg_count = prg_count * 2;
+        self.chr_count = chr_count * 8;
+
+        self.is_chr_ram = is_chr_ram;
+        self.has_prg_ram = prg_ram_size != 0;
+    }
+
+    fn map_read(&self, address: u16, device: Device) -> MappingResult {
+        match device {
+            Device::Cpu => match address {
+                0x6000..=0x7FFF => {
+                    if self.is_prg_ram_enabled() {
+                        MappingResult::Allowed(address as usize & 0x1FFF)
+                    } else {
+                        MappingResult::Denied
+                    }
+                }
+                0x8000..=0xFFFF => {
+                    let bank = match address {
+                        0x8000..=0xBFFF => self.prg_bank_16k as u16 * 2 + ((address >> 13) & 1),
+                        0xC000..=0xDFFF => self.prg_bank_8k as u16,
+                        0xE000..=0xFFFF => self.prg_count - 1,
+                        _ => unreachable!(),
+                    } as usize
+                        % self.prg_count as usize;
+
+                    MappingResult::Allowed(bank * 0x2000 + (address & 0x1FFF) as usize)
+                }
+                0x4020..=0x5FFF => MappingResult::Denied,
+                _ => unreachable!(),
+            },
+            Device::Ppu => {
+                if address < 0x2000 {
+                    self.map_ppu(address)
+                } else {
+                    unreachable!();
+                }
+            }
+        }
+    }
+
+    fn map_write(&mut self, address: u16, data: u8, device: Device) -> MappingResult {
+        match device {
+            Device::Cpu => match address {
+                0x6000..=0x7FFF => {
+                    if self.is_prg_ram_enabled() {
+                        MappingResult::Allowed(address as usize & 0x1FFF)
+                    } else {
+                        MappingResult::Denied
+                    }
+                }
+                0x8000..=0xFFFF => {
+                    let address = self.register_address(address);
+
+                    match address {
+                        0x8000..=0x8003 => self.prg_bank_16k = data & 0xF,
+                        0x9000..=0x9003 | 0xA000..=0xA002 | 0xB000..=0xB002 => {
+                            self.audio.write(address, data)
+                        }
+                        0xB003 => self.ppu_control = data,
+                        0xC000..=0xC003 => self.prg_bank_8k = data & 0x1F,
+                        0xD000..=0xD003 => self.chr_banks[address as usize & 3] = data,
+                        0xE000..=0xE003 => self.chr_banks[4 + (address as usize & 3)] = data,
+                        0xF000 => self.irq.write_latch(data),
+                        0xF001 => self.irq.write_control(data),
+                        0xF002 => self.irq.acknowledge(),
+                        _ => {}
+                    }
+
+                    MappingResult::Denied
+                }
+                0x4020..=0x5FFF => MappingResult::Denied,
+                _ => unreachable!(),
+            },
+            Device::Ppu => {
+                // CHR RAM
+                if self.is_chr_ram && address <= 0x1FFF {
+                    self.map_ppu(address)
+                } else {
+                    MappingResult::Denied
+                }
+            }
+        }
+    }
+
+    fn clock(&mut self) {
+        self.irq.clock();
+        self.audio.timer_clock();
+    }
+
+    fn expansion_audio_output(&mut self) -> f32 {
+        ExpansionAudioChip::Vrc6.mix(self.audio.get_output())
+    }
+
+    fn is_hardwired_mirrored(&self) -> bool {
+        false
+    }
+
+    fn nametable_mirroring(&self) -> MirroringMode {
+        match (self.ppu_control >> 2) & 3 {
+            0 => MirroringMode::Vertical,
+            1 => MirroringMode::Horizontal,
+            2 => MirroringMode::SingleScreenLowBank,
+            3 => MirroringMode::SingleScreenHighBank,
+            _ => unreachable!(),
+        }
+    }
+
+    fn is_irq_pin_state_changed_requested(&self) -> bool {
+        self.irq.is_irq_pin_state_changed_requested()
+    }
+
+    fn irq_pin_state(&self) -> bool {
+        self.irq.irq_pin_state()
+    }
+
+    fn clear_irq_request_pin(&mut self) {
+        self.irq.clear_irq_request_pin();
+    }
+
+    fn save_state_size(&self) -> usize {
+        bincode::serialized_size(self).unwrap() as usize
+    }
+
+    fn save_state(&self) -> Vec<u8> {
+        bincode::serialize(self).unwrap()
+    }
+
+    fn load_state(&mut self, data: Vec<u8>) {
+        let state = bincode::deserialize(&data).unwrap();
+
+        let _ = std::mem::replace(self, state);
+    }
+}
@@ -0,0 +1,205 @@
+use super::super::mapper::{Mapper, MappingResult};
+use crate::common::{Device, MirroringMode};
+use serde::{Deserialize, Serialize};
+
+/// Action 53, a multicart mapper that can emulate NROM, CNROM (CHR RAM only),
+/// UNROM, UOROM and AxROM games inside an outer PRG bank
+#[derive(Serialize, Deserialize)]
+pub struct Mapper28 {
+    /// ($5000-$5FFF)
+    /// 7  bit  0
+    /// ---- ----
+    /// Sxxx xxxR
+    /// |       |
+    /// +-------+- Select the register written at $8000-$FFFF ($00, $01, $80 or $81)
+    register_select: u8,
+
+    /// (register $00)
+    /// 7  bit  0
+    /// ---- ----
+    /// xxxM xxCC
+    ///    |   ||
+    ///    |   ++- Select 8 KB CHR RAM bank for PPU $0000-$1FFF
+    ///    +------ Single screen mirroring bank, if the mirroring mode is single screen
+    chr_bank: u8,
+
+    /// (register $01)
+    /// 7  bit  0
+    /// ---- ----
+    /// xxxM PPPP
+    ///    | ||||
+    ///    | ++++- Select the inner PRG ROM bank, 32 KB or 16 KB depending on the PRG bank mode
+    ///    +------ Single screen mirroring bank, if the mirroring mode is single screen
+    inner_prg_bank: u8,
+
+    /// (register $80)
+    /// 7  bit  0
+    /// ---- ----
+    /// xxSS PPMM
+    ///   || ||||
+    ///   || ||++- Nametable mirroring (0: single screen low; 1: single screen high;
+    ///   || ||                         2: vertical; 3: horizontal)
+    ///   || ++--- PRG bank mode (0, 1: 32 KB; 2: $8000 fixed to the first bank
+    ///   ||                      of the outer bank; 3: $C000 fixed to the last bank)
+    ///   ++------ Outer PRG bank size (0: 32 KB; 1: 64 KB; 2: 128 KB; 3: 256 KB)
+    mode: u8,
+
+    /// (register $81)
+    /// Select the outer PRG ROM bank, in 32 KB units
+    outer_prg_bank: u8,
+
+    /// in 16kb units
+    prg_count: u16,
+
+    /// in 8kb units
+    chr_count: u16,
+
+    is_chr_ram: bool,
+}
+
+impl Mapper28 {
+    pub fn new() -> Self {
+        Self {
+            register_select: 0,
+            chr_bank: 0,
+            inner_prg_bank: 0,
+            mode: 0,
+            // the menu is in the last bank
+            outer_prg_bank: 0xFF,
+            prg_count: 0,
+            chr_count: 0,
+            is_chr_ram: false,
+        }
+    }
+
+    /// the 16kb bank mapped at `address`
+    fn prg_bank(&self, address: u16) -> usize {
+        // 0: $8000-$BFFF, 1: $C000-$FFFF
+        let slot = ((address >> 14) & 1) as usize;
+        let bank_mode = ((self.mode >> 2) & 0b11) as usize;
+        let outer_bank = (self.outer_prg_bank as usize) << 1;
+
+        let bank = if bank_mode ^ slot == 2 {
+            // the fixed bank, first of the outer bank at $8000, last at $C000
+            outer_bank | slot
+        } else {
+            let inner_bank = if bank_mode < 2 {
+                // 32kb banks
+                ((self.inner_prg_bank as usize) << 1) | slot
+            } else {
+                self.inner_prg_bank as usize
+            };
+
+            // the bits of the bank that come from the inner bank
+            let inner_mask = (2 << ((self.mode >> 4) & 0b11)) - 1;
+
+            (inner_bank & inner_mask) | (outer_bank & !inner_mask)
+        };
+
+        bank % self.prg_count as usize
+    }
+
+    fn map_ppu(&self, address: u16) -> MappingResult {
+        let bank = self.chr_bank as usize % self.chr_count as usize;
+
+        MappingResult::Allowed(bank * 0x2000 + (address & 0x1FFF) as usize)
+    }
+
+    /// Writes to the CHR and inner PRG registers control the bank of single screen mirroring
+    fn set_single_screen_bank(&mut self, data: u8) {
+        if self.mode & 0b10 == 0 {
+            self.mode = (self.mode & !1) | ((data >> 4) & 1);
+        }
+    }
+}
+
+impl Mapper for Mapper28 {
+    fn init(&mut self, prg_count: u16, is_chr_ram: bool, chr_count: u16, _prg_ram_size: u32) {
+        self.prg_count = prg_count;
+        self.chr_count = chr_count;
+        self.is_chr_ram = is_chr_ram;
+    }
+
+    fn map_read(&self, address: u16, device: Device) -> MappingResult {
+        match device {
+            Device::Cpu => match address {
+                0x6000..=0x7FFF => MappingResult::Denied,
+                0x8000..=0xFFFF => MappingResult::Allowed(
+                    self.prg_bank(address) * 0x4000 + (address & 0x3FFF) as usize,
+                ),
+                0x4020..=0x5FFF => MappingResult::Denied,
+                _ => unreachable!(),
+            },
+            Device::Ppu => {
+                if address < 0x2000 {
+                    self.map_ppu(address)
+                } else {
+                    unreachable!()
+                }
+            }
+        }
+    }
+
+    fn map_write(&mut self, address: u16, data: u8, device: Device) -> MappingResult {
+        match device {
+            Device::Cpu => {
+                match address {
+                    0x5000..=0x5FFF => self.register_select = data & 0x81,
+                    0x8000..=0xFFFF => match self.register_select {
+                        0x00 => {
+                            self.chr_bank = data & 0b11;
+                            self.set_single_screen_bank(data);
+                        }
+                        0x01 => {
+                            self.inner_prg_bank = data & 0xF;
+                            self.set_single_screen_bank(data);
+                        }
+                        0x80 => self.mode = data & 0x3F,
+                        0x81 => self.outer_prg_bank = data,
+                        _ => unreachable!(),
+                    },
+                    0x4020..=0x4FFF | 0x6000..=0x7FFF => {}
+                    _ => unreachable!(),
+                }
+
+                MappingResult::Denied
+            }
+            Device::Ppu => {
+                // CHR RAM
+                if self.is_chr_ram && address <= 0x1FFF {
+                    self.map_ppu(address)
+                } else {
+                    MappingResult::Denied
+                }
+            }
+        }
+    }
+
+    fn is_hardwired_mirrored(&self) -> bool {
+        false
+    }
+
+    fn nametable_mirroring(&self) -> MirroringMode {
+        match self.mode & 0b11 {
+            0 => MirroringMode::SingleScreenLowBank,
+            1 => MirroringMode::SingleScreenHighBank,
+            2 => MirroringMode::Vertical,
+            3 => MirroringMode::Horizontal,
+            _ => unreachable!(),
+        }
+    }
+
+    fn save_state_size(&self) -> usize {
+        bincode::serialized_size(self).unwrap() as usize
+    }
+
+    fn save_state(&self) -> Vec<u8> {
+        bincode::serialize(self).unwrap()
+    }
+
+    fn load_state(&mut self, data: Vec<u8>) {
+        let state = bincode::deserialize(&data).unwrap();
+
+        let _ = std::mem::replace(self, state);
+    }
+}
@@ -6,7 +6,7 @@ pub struct Mapper3 {
 
     chr_bank: u8,
 
-    chr_count: u8,
+    chr_count: u16,
 
     is_chr_ram: bool,
 }
@@ -22,16 +22,16 @@ impl Mapper3 {
     }
 
     fn map_ppu(&self, address: u16) -> MappingResult {
-        let bank = self.chr_bank % self.chr_count;
+        let bank = self.chr_bank as usize % self.chr_count as usize;
 
-        let start_of_bank = 0x2000 * bank as usize;
+        let start_of_bank = 0x2000 * bank;
 
         MappingResult::Allowed(start_of_bank + (address & 0x1FFF) as usize)
     }
 }
 
 impl Mapper for Mapper3 {
-    fn init(&mut self, prg_count: u8, is_chr_ram: bool, chr_count: u8, _sram_count: u8) {
+    fn init(&mut self, prg_count: u16, is_chr_ram: bool, chr_count: u16, _prg_ram_size: u32) {
         assert!(prg_count == 1 || prg_count == 2);
 
         self.has_32kb_prg_rom = prg_count == 2;
@@ -104,13 +104,14 @@ impl Mapper for Mapper3 {
     }
 
     fn save_state_size(&self) -> usize {
-        4
+        5
     }
 
     fn save_state(&self) -> Vec<u8> {
         vec![
             self.chr_bank,
-            self.chr_count,
+            self.chr_count as u8,
+            (self.chr_count >> 8) as u8,
             self.has_32kb_prg_rom as u8,
             self.is_chr_ram as u8,
         ]
@@ -118,8 +119,8 @@ impl Mapper for Mapper3 {
 
     fn load_state(&mut self, data: Vec<u8>) {
         self.chr_bank = data[0];
-        self.chr_count = data[1];
-        self.has_32kb_prg_rom = data[2] != 0;
-        self.is_chr_ram = data[3] != 0;
+        self.chr_count = u16::from_le_bytes([data[1], data[2]]);
+        self.has_32kb_prg_rom = data[3] != 0;
+        self.is_chr_ram = data[4] != 0;
     }
 }
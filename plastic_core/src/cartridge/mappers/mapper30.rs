@@ -0,0 +1,162 @@
+use super::super::mapper::{Mapper, MappingResult};
+use super::flash::Sst39sf040;
+use crate::common::{Device, NametableMapping};
+use serde::{Deserialize, Serialize};
+
+/// UNROM-512, UNROM with up to 512kb PRG ROM and 32kb CHR RAM.
+///
+/// The self-flashable version (battery flag in the header) has the PRG ROM in
+/// a flash chip that the game writes to at $8000-$BFFF to save, and the bank
+/// register is moved to $C000-$FFFF.
+#[derive(Serialize, Deserialize)]
+pub struct Mapper30 {
+    /// ($C000-$FFFF, or $8000-$FFFF if not flashable)
+    /// 7  bit  0
+    /// ---- ----
+    /// MCCP PPPP
+    /// |||| ||||
+    /// |||+-++++- Select 16 KB PRG ROM bank for CPU $8000-$BFFF
+    /// |++------- Select 8 KB CHR RAM bank for PPU $0000-$1FFF
+    /// +--------- Select single screen mirroring bank (if the board has it)
+    prg_bank: u8,
+    chr_bank: u8,
+    single_screen_high_bank: bool,
+
+    is_flashable: bool,
+
+    /// the header has four screen mirroring and horizontal mirroring
+    has_single_screen_mirroring: bool,
+
+    flash: Sst39sf040,
+
+    /// in 16kb units
+    prg_count: u16,
+
+    /// in 8kb units
+    chr_count: u16,
+}
+
+impl Mapper30 {
+    pub fn new(is_flashable: bool, has_single_screen_mirroring: bool) -> Self {
+        Self {
+            prg_bank: 0,
+            chr_bank: 0,
+            single_screen_high_bank: false,
+            is_flashable,
+            has_single_screen_mirroring,
+            flash: Sst39sf040::new(),
+            prg_count: 0,
+            chr_count: 0,
+        }
+    }
+
+    /// the PRG ROM address of `address` in CPU $8000-$FFFF
+    fn map_prg(&self, address: u16) -> usize {
+        let bank = if address < 0xC000 {
+            self.prg_bank as usize
+        } else {
+            self.prg_count as usize - 1
+        } % self.prg_count as usize;
+
+        bank * 0x4000 + (address & 0x3FFF) as usize
+    }
+
+    fn map_ppu(&self, address: u16) -> MappingResult {
+        let bank = self.chr_bank as usize % self.chr_count as usize;
+
+        MappingResult::Allowed(bank * 0x2000 + (address & 0x1FFF) as usize)
+    }
+
+    fn write_register(&mut self, data: u8) {
+        self.prg_bank = data & 0x1F;
+        self.chr_bank = (data >> 5) & 0b11;
+        self.single_screen_high_bank = data & 0x80 != 0;
+    }
+}
+
+impl Mapper for Mapper30 {
+    fn init(&mut self, prg_count: u16, _is_chr_ram: bool, chr_count: u16, _prg_ram_size: u32) {
+        self.prg_count = prg_count;
+        self.chr_count = chr_count;
+    }
+
+    fn map_read(&self, address: u16, device: Device) -> MappingResult {
+        match device {
+            Device::Cpu => match address {
+                0x6000..=0x7FFF => MappingResult::Denied,
+                0x8000..=0xFFFF => {
+                    let address = self.map_prg(address);
+
+                    match self.flash.read(address) {
+                        Some(data) => MappingResult::Data(data),
+                        None => MappingResult::Allowed(address),
+                    }
+                }
+                0x4020..=0x5FFF => MappingResult::Denied,
+                _ => unreachable!(),
+            },
+            Device::Ppu => {
+                if address < 0x2000 {
+                    self.map_ppu(address)
+                } else {
+                    unreachable!()
+                }
+            }
+        }
+    }
+
+    fn map_write(&mut self, address: u16, data: u8, device: Device) -> MappingResult {
+        match device {
+            Device::Cpu => match address {
+                0x8000..=0xBFFF if self.is_flashable => {
+                    self.flash.write(self.map_prg(address), data)
+                }
+                0x8000..=0xFFFF => {
+                    self.write_register(data);
+
+                    MappingResult::Denied
+                }
+                0x4020..=0x7FFF => MappingResult::Denied,
+                _ => unreachable!(),
+            },
+            // CHR RAM
+            Device::Ppu => {
+                if address <= 0x1FFF {
+                    self.map_ppu(address)
+                } else {
+                    MappingResult::Denied
+                }
+            }
+        }
+    }
+
+    fn map_nametable(&self, _address: u16) -> Option<NametableMapping> {
+        if self.has_single_screen_mirroring {
+            Some(NametableMapping::Vram(self.single_screen_high_bank as u8))
+        } else {
+            None
+        }
+    }
+
+    fn has_bus_conflicts(&self) -> bool {
+        !self.is_flashable
+    }
+
+    fn has_prg_flash(&self) -> bool {
+        self.is_flashable
+    }
+
+    fn save_state_size(&self) -> usize {
+        bincode::serialized_size(self).unwrap() as usize
+    }
+
+    fn save_state(&self) -> Vec<u8> {
+        bincode::serialize(self).unwrap()
+    }
+
+    fn load_state(&mut self, data: Vec<u8>) {
+        let state = bincode::deserialize(&data).unwrap();
+
+        let _ = std::mem::replace(self, state);
+    }
+}
@@ -0,0 +1,172 @@
+use super::super::mapper::{Mapper, MappingResult};
+use crate::common::Device;
+use serde::{Deserialize, Serialize};
+
+/// Two different boards share this mapper number, BNROM (submapper 2) has
+/// a PRG bank register at $8000-$FFFF, and NINA-001 (submapper 1) has
+/// PRG and CHR bank registers at the end of PRG RAM
+#[derive(Serialize, Deserialize)]
+pub struct Mapper34 {
+    submapper_id: u8,
+
+    is_nina001: bool,
+
+    /// BNROM ($8000-$FFFF) or NINA-001 ($7FFD)
+    /// select 32 KB PRG ROM bank for CPU $8000-$FFFF
+    prg_bank: u8,
+
+    /// NINA-001 ($7FFE)
+    /// select 4 KB CHR ROM bank for PPU $0000-$0FFF
+    chr_bank_0000: u8,
+
+    /// NINA-001 ($7FFF)
+    /// select 4 KB CHR ROM bank for PPU $1000-$1FFF
+    chr_bank_1000: u8,
+
+    /// in 16kb units
+    prg_count: u16,
+
+    /// in 4kb units
+    chr_count: u16,
+
+    is_chr_ram: bool,
+
+    has_prg_ram: bool,
+}
+
+impl Mapper34 {
+    pub fn new(submapper_id: u8) -> Self {
+        Self {
+            submapper_id,
+            is_nina001: false,
+            prg_bank: 0,
+            chr_bank_0000: 0,
+            chr_bank_1000: 0,
+            prg_count: 0,
+            chr_count: 0,
+            is_chr_ram: false,
+            has_prg_ram: false,
+        }
+    }
+
+    fn map_ppu(&self, address: u16) -> MappingResult {
+        if self.is_nina001 {
+            let bank = if address < 0x1000 {
+                self.chr_bank_0000
+            } else {
+                self.chr_bank_1000
+            } as usize
+                % self.chr_count as usize;
+
+            MappingResult::Allowed(bank * 0x1000 + (address & 0xFFF) as usize)
+        } else {
+            // BNROM has only 8kb of CHR RAM
+            MappingResult::Allowed(address as usize)
+        }
+    }
+}
+
+impl Mapper for Mapper34 {
+    fn init(&mut self, prg_count: u16, is_chr_ram: bool, chr_count: u16, prg_ram_size: u32) {
+        self.prg_count = prg_count;
+        self.chr_count = chr_count * 2;
+        self.is_chr_ram = is_chr_ram;
+        self.has_prg_ram = prg_ram_size != 0;
+
+        self.is_nina001 = match self.submapper_id {
+            1 => true,
+            2 => false,
+            // only NINA-001 has banked CHR
+            _ => chr_count > 1,
+        };
+    }
+
+    fn map_read(&self, address: u16, device: Device) -> MappingResult {
+        match device {
+            Device::Cpu => match address {
+                0x6000..=0x7FFF => {
+                    if self.has_prg_ram {
+                        MappingResult::Allowed(address as usize & 0x1FFF)
+                    } else {
+                        MappingResult::Denied
+                    }
+                }
+                0x8000..=0xFFFF => {
+                    // in 16kb banks, as 16kb games are mirrored
+                    let bank = (self.prg_bank as usize * 2 + ((address >> 14) & 1) as usize)
+                        % self.prg_count as usize;
+
+                    MappingResult::Allowed(bank * 0x4000 + (address & 0x3FFF) as usize)
+                }
+                0x4020..=0x5FFF => MappingResult::Denied,
+                _ => unreachable!(),
+            },
+            Device::Ppu => {
+                if address < 0x2000 {
+                    self.map_ppu(address)
+                } else {
+                    unreachable!()
+                }
+            }
+        }
+    }
+
+    fn map_write(&mut self, address: u16, data: u8, device: Device) -> MappingResult {
+        match device {
+            Device::Cpu => match address {
+                0x6000..=0x7FFF => {
+                    if self.is_nina001 {
+                        match address {
+                            0x7FFD => self.prg_bank = data & 1,
+                            0x7FFE => self.chr_bank_0000 = data & 0xF,
+                            0x7FFF => self.chr_bank_1000 = data & 0xF,
+                            _ => {}
+                        }
+                    }
+
+                    // the registers are written to PRG RAM as well
+                    if self.has_prg_ram {
+                        MappingResult::Allowed(address as usize & 0x1FFF)
+                    } else {
+                        MappingResult::Denied
+                    }
+                }
+                0x8000..=0xFFFF => {
+                    if !self.is_nina001 {
+                        self.prg_bank = data;
+                    }
+
+                    MappingResult::Denied
+                }
+                0x4020..=0x5FFF => MappingResult::Denied,
+                _ => unreachable!(),
+            },
+            Device::Ppu => {
+                // CHR RAM
+                if self.is_chr_ram && address <= 0x1FFF {
+                    self.map_ppu(address)
+                } else {
+                    MappingResult::Denied
+                }
+            }
+        }
+    }
+
+    fn has_bus_conflicts(&self) -> bool {
+        !self.is_nina001
+    }
+
+    fn save_state_size(&self) -> usize {
+        bincode::serialized_size(self).unwrap() as usize
+    }
+
+    fn save_state(&self) -> Vec<u8> {
+        bincode::serialize(self).unwrap()
+    }
+
+    fn load_state(&mut self, data: Vec<u8>) {
+        let state = bincode::deserialize(&data).unwrap();
+
+        let _ = std::mem::replace(self, state);
+    }
+}
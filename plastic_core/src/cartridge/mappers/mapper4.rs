@@ -1,10 +1,27 @@
 use super::super::mapper::{Mapper, MappingResult};
-use crate::common::{Device, MirroringMode};
+use crate::common::{Device, MirroringMode, NametableMapping};
 use serde::{Deserialize, Serialize};
 use std::cell::Cell;
 
+/// The MMC3 boards emulated by this mapper, they only differ in what the
+/// CHR bank registers control
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+enum Mmc3Board {
+    /// mapper 4
+    Standard,
+    /// mapper 118, bit 7 of the CHR bank register of each 1kb of PPU $0000-$0FFF
+    /// selects the CIRAM page of the nametable in the same position, so
+    /// the register at $A000 is not connected
+    TxSrom,
+    /// mapper 119, bit 6 of the CHR bank register selects the 8kb CHR RAM
+    /// instead of the CHR ROM, the CHR RAM is placed after the CHR ROM
+    Tqrom,
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct Mapper4 {
+    board: Mmc3Board,
+
     /// ($8000-$9FFE, even)
     /// 7  bit  0
     /// ---- ----
@@ -124,7 +141,7 @@ pub struct Mapper4 {
     chr_count: u16,
 
     /// in 8kb units
-    prg_count: u8,
+    prg_count: u16,
 
     /// false if the last accessed pattern table address is $0000
     /// true  if the last accessed pattern table address is $1000
@@ -135,8 +152,16 @@ pub struct Mapper4 {
 }
 
 impl Mapper4 {
-    pub fn new() -> Self {
+    pub fn new(mapper_id: u16) -> Self {
+        let board = match mapper_id {
+            4 => Mmc3Board::Standard,
+            118 => Mmc3Board::TxSrom,
+            119 => Mmc3Board::Tqrom,
+            _ => unreachable!("mapper {} is not MMC3", mapper_id),
+        };
+
         Self {
+            board,
             bank_select: 0,
             prg_rom_bank_fix_8000: false,
             prg_bank_8000_c000: 0,
@@ -188,12 +213,11 @@ impl Mapper4 {
         self.last_pattern_table.set(current_pattern_table);
     }
 
-    fn map_ppu(&self, address: u16) -> MappingResult {
-        self.handle_irq_counter(address);
-
+    /// The value of the CHR bank register mapping the pattern table `address`
+    fn chr_bank(&self, address: u16) -> u8 {
         let is_2k = (address & 0x1000 == 0) ^ self.chr_bank_2k_1000;
 
-        let mut bank = if is_2k {
+        if is_2k {
             if address & 0x0800 == 0 {
                 self.chr_bank_r0
             } else {
@@ -207,26 +231,51 @@ impl Mapper4 {
                 3 => self.chr_bank_r5,
                 _ => unreachable!(),
             }
-        } as usize;
+        }
+    }
 
-        bank %= self.chr_count as usize;
+    fn map_ppu(&self, address: u16) -> MappingResult {
+        self.handle_irq_counter(address);
 
+        self.map_chr(address)
+    }
+
+    /// map the CHR `address` without clocking the IRQ counter
+    fn map_chr(&self, address: u16) -> MappingResult {
+        let is_2k = (address & 0x1000 == 0) ^ self.chr_bank_2k_1000;
         let mask = if is_2k { 0x7FF } else { 0x3FF };
 
-        let start_of_bank = bank * 0x400;
+        let bank = self.chr_bank(address) as usize;
+
+        let start_of_bank = match self.board {
+            Mmc3Board::Standard => (bank % self.chr_count as usize) * 0x400,
+            // the bit 7 is used for the nametables
+            Mmc3Board::TxSrom => ((bank & 0x7F) % self.chr_count as usize) * 0x400,
+            Mmc3Board::Tqrom if bank & 0x40 != 0 => {
+                // 8kb of CHR RAM after the CHR ROM
+                (self.chr_count as usize + (bank & 0x7)) * 0x400
+            }
+            Mmc3Board::Tqrom => ((bank & 0x3F) % self.chr_count as usize) * 0x400,
+        };
 
         MappingResult::Allowed(start_of_bank + (address & mask) as usize)
     }
+
+    /// Only CHR RAM can be written to, which is either all CHR memory,
+    /// or the CHR RAM banks in TQROM
+    fn is_chr_ram_bank(&self, address: u16) -> bool {
+        self.is_chr_ram || (self.board == Mmc3Board::Tqrom && self.chr_bank(address) & 0x40 != 0)
+    }
 }
 
 impl Mapper for Mapper4 {
-    fn init(&mut self, prg_count: u8, is_chr_ram: bool, chr_count: u8, sram_count: u8) {
+    fn init(&mut self, prg_count: u16, is_chr_ram: bool, chr_count: u16, prg_ram_size: u32) {
         self.prg_count = prg_count * 2;
-        self.chr_count = chr_count as u16 * 8;
+        self.chr_count = chr_count * 8;
 
         self.is_chr_ram = is_chr_ram;
 
-        self.has_prg_ram = sram_count != 0;
+        self.has_prg_ram = prg_ram_size != 0;
     }
 
     fn map_read(&self, address: u16, device: Device) -> MappingResult {
@@ -247,16 +296,16 @@ impl Mapper for Mapper4 {
                                     // second to last
                                     self.prg_count - 2
                                 } else {
-                                    self.prg_bank_8000_c000
+                                    self.prg_bank_8000_c000 as u16
                                 }
                             }
-                            0xA000..=0xBFFF => self.prg_bank_a000,
+                            0xA000..=0xBFFF => self.prg_bank_a000 as u16,
                             0xC000..=0xDFFF => {
                                 if !self.prg_rom_bank_fix_8000 {
                                     // second to last
                                     self.prg_count - 2
                                 } else {
-                                    self.prg_bank_8000_c000
+                                    self.prg_bank_8000_c000 as u16
                                 }
                             }
                             0xE000..=0xFFFF => self.prg_count - 1,
@@ -319,7 +368,7 @@ impl Mapper for Mapper4 {
                             }
                             0xA000..=0xBFFF => {
                                 if address & 1 == 0 {
-                                    // even
+                                    // even (not used in TxSROM)
                                     self.mirroring_vertical = data & 1 == 0;
                                 } else {
                                     // odd
@@ -359,7 +408,7 @@ impl Mapper for Mapper4 {
             }
             Device::Ppu => {
                 // CHR RAM
-                if self.is_chr_ram && address <= 0x1FFF {
+                if address <= 0x1FFF && self.is_chr_ram_bank(address) {
                     self.map_ppu(address)
                 } else {
                     MappingResult::Denied
@@ -368,6 +417,18 @@ impl Mapper for Mapper4 {
         }
     }
 
+    fn peek_ppu(&self, address: u16) -> MappingResult {
+        self.map_chr(address)
+    }
+
+    fn poke_ppu(&mut self, address: u16, _data: u8) -> MappingResult {
+        if self.is_chr_ram_bank(address) {
+            self.map_chr(address)
+        } else {
+            MappingResult::Denied
+        }
+    }
+
     fn is_hardwired_mirrored(&self) -> bool {
         false
     }
@@ -380,6 +441,15 @@ impl Mapper for Mapper4 {
         }
     }
 
+    fn map_nametable(&self, address: u16) -> Option<NametableMapping> {
+        if self.board == Mmc3Board::TxSrom {
+            // the nametable `n` uses the same register as the CHR 1kb bank `n`
+            Some(NametableMapping::Vram(self.chr_bank(address & 0xC00) >> 7))
+        } else {
+            None
+        }
+    }
+
     fn is_irq_pin_state_changed_requested(&self) -> bool {
         self.is_irq_pin_changed.get()
     }
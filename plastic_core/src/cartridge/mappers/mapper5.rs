@@ -0,0 +1,774 @@
+use super::super::mapper::{Mapper, MappingResult};
+use crate::apu2a03::expansion::{APUChannel, ExpansionAudioChip, Mmc5Audio, TimedAPUChannel};
+use crate::common::{Device, MirroringMode, NametableMapping};
+use serde::{Deserialize, Serialize};
+use std::cell::Cell;
+
+/// number of CPU cycles without PPU reads before the mapper considers that
+/// the PPU stopped rendering, the hardware uses 3 cycles, but the PPU here
+/// fetches some data in one go, so there are larger gaps between the reads
+const PPU_IDLE_CPU_CYCLES: u8 = 32;
+
+#[derive(Serialize, Deserialize)]
+pub struct Mapper5 {
+    /// ($5100)
+    /// 7  bit  0
+    /// ---- ----
+    /// xxxx xxPP
+    ///        ||
+    ///        ++- Select PRG banking mode
+    ///              0: one 32 KB bank
+    ///              1: two 16 KB banks
+    ///              2: one 16 KB bank ($8000-$BFFF) and two 8 KB banks ($C000-$DFFF and $E000-$FFFF)
+    ///              3: four 8 KB banks
+    prg_mode: u8,
+
+    /// ($5101)
+    /// 7  bit  0
+    /// ---- ----
+    /// xxxx xxCC
+    ///        ||
+    ///        ++- Select CHR banking mode
+    ///              0: 8 KB CHR pages
+    ///              1: 4 KB CHR pages
+    ///              2: 2 KB CHR pages
+    ///              3: 1 KB CHR pages
+    chr_mode: u8,
+
+    /// ($5102) PRG RAM is writable only when this is `0b10`
+    prg_ram_protect_1: u8,
+
+    /// ($5103) PRG RAM is writable only when this is `0b01`
+    prg_ram_protect_2: u8,
+
+    /// ($5104)
+    /// 7  bit  0
+    /// ---- ----
+    /// xxxx xxXX
+    ///        ||
+    ///        ++- Specify extended RAM usage
+    ///              0: Use as extra nametable (possibly for split mode)
+    ///              1: Use as extended attribute data (can also be used as extended nametable)
+    ///              2: Use as ordinary RAM
+    ///              3: Use as ordinary RAM, write protected
+    exram_mode: u8,
+
+    /// ($5105)
+    /// 7  bit  0
+    /// ---- ----
+    /// DDCC BBAA
+    /// |||| ||||
+    /// |||| ||++- Select nametable at PPU $2000-$23FF
+    /// |||| ++--- Select nametable at PPU $2400-$27FF
+    /// ||++------ Select nametable at PPU $2800-$2BFF
+    /// ++-------- Select nametable at PPU $2C00-$2FFF
+    ///
+    /// Nametable values:
+    ///   0 - CIRAM page 0
+    ///   1 - CIRAM page 1
+    ///   2 - Expansion RAM
+    ///   3 - Fill-mode data
+    nametable_mapping: u8,
+
+    /// ($5106) tile used by all nametable slots in fill-mode
+    fill_tile: u8,
+
+    /// ($5107) palette (2 bits) used by all nametable slots in fill-mode
+    fill_attribute: u8,
+
+    /// ($5113)
+    /// 7  bit  0
+    /// ---- ----
+    /// xxxx xCBB
+    ///       |||
+    ///       +++- Select 8 KB PRG RAM bank at $6000-$7FFF
+    prg_ram_bank: u8,
+
+    /// ($5114-$5117)
+    /// 7  bit  0
+    /// ---- ----
+    /// RBBB BBBB
+    /// |||| ||||
+    /// |+++-++++- Bank number
+    /// +--------- RAM/ROM toggle (0: RAM; 1: ROM) (ignored in $5117, always ROM)
+    prg_banks: [u8; 4],
+
+    /// ($5120-$5127) 1kb bank values, including the upper bits from `$5130`.
+    /// Used for sprites in 8x16 mode, and for everything when the last
+    /// written set is this one
+    chr_banks_sprites: [u16; 8],
+
+    /// ($5128-$512B) 1kb bank values, including the upper bits from `$5130`.
+    /// Used for the background in 8x16 mode, and for everything when the last
+    /// written set is this one
+    chr_banks_background: [u16; 4],
+
+    /// ($5130) the upper 2 bits of the CHR bank registers, applied on write
+    chr_upper_bits: u8,
+
+    /// which CHR bank set was written last
+    last_chr_write_background: bool,
+
+    /// ($5200)
+    /// 7  bit  0
+    /// ---- ----
+    /// ESxW WWWW
+    /// || | ||||
+    /// || +-++++- Specify vertical split start/stop tile
+    /// |+-------- Specify vertical split screen side (0:left; 1:right)
+    /// +--------- Enable vertical split mode
+    split_control: u8,
+
+    /// ($5201) vertical scroll of the split region
+    split_scroll: u8,
+
+    /// ($5202) 4kb CHR page used for the split region
+    split_chr_page: u8,
+
+    /// ($5203) the scanline number that should trigger the IRQ
+    irq_scanline_compare: u8,
+
+    /// ($5204) write
+    /// 7  bit  0
+    /// ---- ----
+    /// Exxx xxxx
+    /// |
+    /// +--------- Scanline IRQ Enable flag (1=enabled)
+    irq_enabled: bool,
+
+    /// ($5205)
+    multiplicand: u8,
+    /// ($5206)
+    multiplier: u8,
+
+    /// ($5C00-$5FFF) 1kb of internal RAM
+    exram: Vec<u8>,
+
+    /// ($5000-$5015)
+    audio: Mmc5Audio,
+
+    /// snooped from PPUCTRL ($2000)
+    sprites_8x16: bool,
+    /// snooped from PPUMASK ($2001)
+    rendering_enabled: bool,
+
+    /// scanline detection, three consecutive reads from the same nametable
+    /// address mark the start of a new scanline
+    last_nametable_address: Cell<u16>,
+    same_nametable_address_count: Cell<u8>,
+    in_frame: Cell<bool>,
+    scanline_counter: Cell<u8>,
+    ppu_idle_counter: Cell<u8>,
+
+    irq_pending: Cell<bool>,
+    irq_pin: Cell<bool>,
+    is_irq_pin_changed: Cell<bool>,
+
+    /// number of tile fetches since the start of the scanline
+    tile_fetch_counter: Cell<u8>,
+    /// the number of pattern reads remaining for the current background tile
+    background_pattern_fetches: Cell<u8>,
+    /// the current background tile is in the split region
+    fetch_in_split: Cell<bool>,
+    /// the tile column of the current split tile
+    split_tile_x: Cell<u8>,
+    /// the scrolled y position of the current split tile
+    split_tile_y: Cell<u8>,
+    /// the ExRAM byte of the current tile in extended attribute mode
+    extended_attribute: Cell<u8>,
+    /// the value to use when the nametable read is handled by the mapper
+    nametable_data: Cell<u8>,
+
+    /// in 8kb units
+    prg_count: u16,
+    /// in 1kb units
+    chr_count: u32,
+    /// in 8kb units
+    prg_ram_count: u8,
+
+    is_chr_ram: bool,
+}
+
+impl Mapper5 {
+    pub fn new() -> Self {
+        Self {
+            prg_mode: 3,
+            chr_mode: 3,
+            prg_ram_protect_1: 0,
+            prg_ram_protect_2: 0,
+            exram_mode: 0,
+            nametable_mapping: 0,
+            fill_tile: 0,
+            fill_attribute: 0,
+            prg_ram_bank: 0,
+            prg_banks: [0, 0, 0, 0xFF],
+            chr_banks_sprites: [0; 8],
+            chr_banks_background: [0; 4],
+            chr_upper_bits: 0,
+            last_chr_write_background: false,
+            split_control: 0,
+            split_scroll: 0,
+            split_chr_page: 0,
+            irq_scanline_compare: 0,
+            irq_enabled: false,
+            multiplicand: 0xFF,
+            multiplier: 0xFF,
+            exram: vec![0; 0x400],
+
+            audio: Mmc5Audio::new(),
+
+            sprites_8x16: false,
+            rendering_enabled: false,
+
+            last_nametable_address: Cell::new(0),
+            same_nametable_address_count: Cell::new(0),
+            in_frame: Cell::new(false),
+            scanline_counter: Cell::new(0),
+            ppu_idle_counter: Cell::new(0),
+
+            irq_pending: Cell::new(false),
+            irq_pin: Cell::new(false),
+            is_irq_pin_changed: Cell::new(false),
+
+            tile_fetch_counter: Cell::new(0),
+            background_pattern_fetches: Cell::new(0),
+            fetch_in_split: Cell::new(false),
+            split_tile_x: Cell::new(0),
+            split_tile_y: Cell::new(0),
+            extended_attribute: Cell::new(0),
+            nametable_data: Cell::new(0),
+
+            prg_count: 0,
+            chr_count: 0,
+            prg_ram_count: 0,
+
+            is_chr_ram: false,
+        }
+    }
+
+    fn is_prg_ram_writable(&self) -> bool {
+        self.prg_ram_protect_1 & 0b11 == 0b10 && self.prg_ram_protect_2 & 0b11 == 0b01
+    }
+
+    fn update_irq_pin(&self) {
+        let state = self.irq_pending.get() && self.irq_enabled;
+
+        if state != self.irq_pin.get() {
+            self.irq_pin.set(state);
+            self.is_irq_pin_changed.set(true);
+        }
+    }
+
+    /// the `$5204` status, the IRQ pending and in frame flags
+    fn irq_status(&self) -> u8 {
+        (self.irq_pending.get() as u8) << 7 | (self.in_frame.get() as u8) << 6
+    }
+
+    fn leave_frame(&self) {
+        self.in_frame.set(false);
+        self.same_nametable_address_count.set(0);
+    }
+
+    fn is_rendering_in_frame(&self) -> bool {
+        self.in_frame.get() && self.rendering_enabled
+    }
+
+    fn map_prg_ram(&self, bank: u8, address: u16) -> MappingResult {
+        if self.prg_ram_count == 0 {
+            return MappingResult::Denied;
+        }
+
+        let bank = (bank & 0x7) as usize % self.prg_ram_count as usize;
+
+        MappingResult::PrgRam(bank * 0x2000 + (address & 0x1FFF) as usize)
+    }
+
+    /// map `$8000-$FFFF`, returns the mapping and if its mapped to RAM
+    fn map_prg(&self, address: u16) -> (MappingResult, bool) {
+        let slot = ((address - 0x8000) >> 13) as usize;
+
+        // (register, bank size as a power of 2 of 8kb units)
+        let (register, size_shift) = match self.prg_mode & 3 {
+            0 => (3, 2),
+            1 => (if slot < 2 { 1 } else { 3 }, 1),
+            2 => match slot {
+                0 | 1 => (1, 1),
+                2 => (2, 0),
+                _ => (3, 0),
+            },
+            3 => (slot, 0),
+            _ => unreachable!(),
+        };
+
+        let value = self.prg_banks[register];
+        let is_rom = register == 3 || value & 0x80 != 0;
+
+        // in larger banks, the low bits of the bank come from the address
+        let mask = (1 << size_shift) - 1;
+        let bank = (value as usize & 0x7F & !mask) | (slot & mask);
+
+        if is_rom {
+            let bank = bank % self.prg_count as usize;
+
+            (
+                MappingResult::PrgRom(bank * 0x2000 + (address & 0x1FFF) as usize),
+                false,
+            )
+        } else {
+            (self.map_prg_ram(bank as u8, address), true)
+        }
+    }
+
+    fn map_chr(&self, address: u16) -> MappingResult {
+        self.ppu_idle_counter.set(PPU_IDLE_CPU_CYCLES);
+
+        let remaining_background_fetches = self.background_pattern_fetches.get();
+        let is_background = remaining_background_fetches > 0;
+        if is_background {
+            self.background_pattern_fetches
+                .set(remaining_background_fetches - 1);
+        }
+
+        let in_frame = self.is_rendering_in_frame();
+        let chr_size = self.chr_count as usize * 0x400;
+
+        if is_background && in_frame {
+            if self.fetch_in_split.get() {
+                // use the split page, with the fine y from the split scroll
+                let fine_y = (self.split_tile_y.get() & 7) as usize;
+                let address =
+                    self.split_chr_page as usize * 0x1000 + (address as usize & 0xFF8) + fine_y;
+
+                return MappingResult::Allowed(address % chr_size);
+            }
+
+            if self.exram_mode == 1 {
+                let bank = (self.extended_attribute.get() & 0x3F) as usize
+                    | (self.chr_upper_bits as usize) << 6;
+
+                return MappingResult::Allowed(
+                    (bank * 0x1000 + (address & 0xFFF) as usize) % chr_size,
+                );
+            }
+        }
+
+        let use_background_set = if self.sprites_8x16 && in_frame {
+            is_background
+        } else {
+            self.last_chr_write_background
+        };
+
+        self.map_chr_bank(address, use_background_set)
+    }
+
+    /// map the CHR `address` using the background or the sprites bank registers
+    fn map_chr_bank(&self, address: u16, use_background_set: bool) -> MappingResult {
+        let slot_1k = (address >> 10) as usize & 7;
+
+        let bank = if use_background_set {
+            let banks = &self.chr_banks_background;
+            match self.chr_mode & 3 {
+                0 => banks[3] as usize * 8 + slot_1k,
+                1 => banks[3] as usize * 4 + (slot_1k & 3),
+                2 => banks[if address & 0x800 == 0 { 1 } else { 3 }] as usize * 2 + (slot_1k & 1),
+                3 => banks[slot_1k & 3] as usize,
+                _ => unreachable!(),
+            }
+        } else {
+            let banks = &self.chr_banks_sprites;
+            match self.chr_mode & 3 {
+                0 => banks[7] as usize * 8 + slot_1k,
+                1 => banks[if address < 0x1000 { 3 } else { 7 }] as usize * 4 + (slot_1k & 3),
+                2 => banks[(slot_1k >> 1) * 2 + 1] as usize * 2 + (slot_1k & 1),
+                3 => banks[slot_1k] as usize,
+                _ => unreachable!(),
+            }
+        } % self.chr_count as usize;
+
+        MappingResult::Allowed(bank * 0x400 + (address & 0x3FF) as usize)
+    }
+
+    fn scanline_detected(&self) {
+        if !self.in_frame.get() {
+            self.in_frame.set(true);
+            self.scanline_counter.set(0);
+            self.irq_pending.set(false);
+        } else {
+            let counter = self.scanline_counter.get().wrapping_add(1);
+            self.scanline_counter.set(counter);
+
+            if counter == self.irq_scanline_compare && self.irq_scanline_compare != 0 {
+                self.irq_pending.set(true);
+            }
+        }
+        self.update_irq_pin();
+
+        self.tile_fetch_counter.set(0);
+    }
+
+    /// track the nametable fetches, to detect scanlines and the current
+    /// rendered tile
+    fn track_nametable_fetch(&self, address: u16) {
+        self.ppu_idle_counter.set(PPU_IDLE_CPU_CYCLES);
+
+        if address == self.last_nametable_address.get() {
+            let count = self.same_nametable_address_count.get() + 1;
+            self.same_nametable_address_count.set(count);
+
+            if count == 2 && self.rendering_enabled {
+                self.scanline_detected();
+            }
+        } else {
+            self.same_nametable_address_count.set(0);
+        }
+        self.last_nametable_address.set(address);
+
+        // only tile fetches, not attributes
+        if address & 0x3FF >= 0x3C0 {
+            return;
+        }
+
+        self.background_pattern_fetches.set(2);
+        self.extended_attribute
+            .set(self.exram[address as usize & 0x3FF]);
+
+        // the first 32 fetches are for tiles 2-33, then the next 2 are tiles 0-1
+        // of the next scanline
+        let fetch = self.tile_fetch_counter.get();
+        self.tile_fetch_counter.set(fetch.saturating_add(1));
+        let (column, line) = if fetch < 32 {
+            (fetch + 2, self.scanline_counter.get())
+        } else {
+            (
+                fetch.wrapping_sub(32),
+                self.scanline_counter.get().wrapping_add(1),
+            )
+        };
+
+        let threshold = self.split_control & 0x1F;
+        let in_split = self.split_control & 0x80 != 0
+            && self.exram_mode <= 1
+            && self.is_rendering_in_frame()
+            && column < 32
+            && if self.split_control & 0x40 != 0 {
+                column >= threshold
+            } else {
+                column < threshold
+            };
+
+        self.fetch_in_split.set(in_split);
+        if in_split {
+            let y = ((self.split_scroll as u16 + line as u16) % 240) as u8;
+            self.split_tile_x.set(column);
+            self.split_tile_y.set(y);
+        }
+    }
+
+    /// map the nametable `address` with the `$5105` quadrants mapping, with the
+    /// data for the quadrants handled by the cartridge (ExRAM and fill-mode)
+    fn map_quadrant(&self, address: u16) -> (NametableMapping, u8) {
+        let quadrant = (address >> 10) & 3;
+        match (self.nametable_mapping >> (quadrant * 2)) & 3 {
+            0 => (NametableMapping::Vram(0), 0),
+            1 => (NametableMapping::Vram(1), 0),
+            2 => {
+                let data = if self.exram_mode <= 1 {
+                    self.exram[address as usize & 0x3FF]
+                } else {
+                    0
+                };
+                (NametableMapping::Cartridge, data)
+            }
+            3 => {
+                let data = if address & 0x3FF >= 0x3C0 {
+                    self.fill_attribute * 0x55
+                } else {
+                    self.fill_tile
+                };
+                (NametableMapping::Cartridge, data)
+            }
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl Mapper for Mapper5 {
+    fn init(&mut self, prg_count: u16, is_chr_ram: bool, chr_count: u16, prg_ram_size: u32) {
+        self.prg_count = prg_count * 2;
+        // large NES 2.0 CHR sizes overflow `u16` in 1kb units
+        self.chr_count = (chr_count as u32 * 8).max(1);
+        self.prg_ram_count = u8::try_from(prg_ram_size / 0x2000).unwrap_or(u8::MAX);
+
+        self.is_chr_ram = is_chr_ram;
+    }
+
+    fn map_read(&self, address: u16, device: Device) -> MappingResult {
+        match device {
+            Device::Cpu => match address {
+                0x5000..=0x5015 => match self.audio.read(address) {
+                    Some(data) => MappingResult::Data(data),
+                    None => MappingResult::Denied,
+                },
+                0x5204 => {
+                    let result = self.irq_status();
+
+                    // acknowledge
+                    self.irq_pending.set(false);
+                    self.update_irq_pin();
+
+                    MappingResult::Data(result)
+                }
+                0x5205 => {
+                    MappingResult::Data((self.multiplicand as u16 * self.multiplier as u16) as u8)
+                }
+                0x5206 => MappingResult::Data(
+                    ((self.multiplicand as u16 * self.multiplier as u16) >> 8) as u8,
+                ),
+                0x5C00..=0x5FFF => {
+                    if self.exram_mode >= 2 {
+                        MappingResult::Data(self.exram[address as usize & 0x3FF])
+                    } else {
+                        MappingResult::Denied
+                    }
+                }
+                0x6000..=0x7FFF => self.map_prg_ram(self.prg_ram_bank, address),
+                0x8000..=0xFFFF => {
+                    // the CPU reads the NMI vector at the start of vblank
+                    if address == 0xFFFA || address == 0xFFFB {
+                        self.leave_frame();
+                    }
+
+                    self.map_prg(address).0
+                }
+                0x4020..=0x5FFF => MappingResult::Denied,
+                _ => unreachable!(),
+            },
+            Device::Ppu => match address {
+                0x0000..=0x1FFF => self.map_chr(address),
+                0x2000..=0x2FFF => MappingResult::Data(self.nametable_data.get()),
+                _ => unreachable!(),
+            },
+        }
+    }
+
+    fn map_write(&mut self, address: u16, data: u8, device: Device) -> MappingResult {
+        match device {
+            Device::Cpu => {
+                match address {
+                    0x5000..=0x5015 => self.audio.write(address, data),
+                    0x5100 => self.prg_mode = data & 3,
+                    0x5101 => self.chr_mode = data & 3,
+                    0x5102 => self.prg_ram_protect_1 = data,
+                    0x5103 => self.prg_ram_protect_2 = data,
+                    0x5104 => self.exram_mode = data & 3,
+                    0x5105 => self.nametable_mapping = data,
+                    0x5106 => self.fill_tile = data,
+                    0x5107 => self.fill_attribute = data & 3,
+                    0x5113 => self.prg_ram_bank = data,
+                    0x5114..=0x5117 => self.prg_banks[(address - 0x5114) as usize] = data,
+                    0x5120..=0x5127 => {
+                        self.chr_banks_sprites[(address - 0x5120) as usize] =
+                            data as u16 | (self.chr_upper_bits as u16) << 8;
+                        self.last_chr_write_background = false;
+                    }
+                    0x5128..=0x512B => {
+                        self.chr_banks_background[(address - 0x5128) as usize] =
+                            data as u16 | (self.chr_upper_bits as u16) << 8;
+                        self.last_chr_write_background = true;
+                    }
+                    0x5130 => self.chr_upper_bits = data & 3,
+                    0x5200 => self.split_control = data,
+                    0x5201 => self.split_scroll = data,
+                    0x5202 => self.split_chr_page = data,
+                    0x5203 => self.irq_scanline_compare = data,
+                    0x5204 => {
+                        self.irq_enabled = data & 0x80 != 0;
+                        self.update_irq_pin();
+                    }
+                    0x5205 => self.multiplicand = data,
+                    0x5206 => self.multiplier = data,
+                    0x5C00..=0x5FFF => {
+                        if self.exram_mode != 3 {
+                            self.exram[address as usize & 0x3FF] = data;
+                        }
+                    }
+                    0x6000..=0x7FFF => {
+                        if self.is_prg_ram_writable() {
+                            return self.map_prg_ram(self.prg_ram_bank, address);
+                        }
+                    }
+                    0x8000..=0xFFFF => {
+                        let (result, is_ram) = self.map_prg(address);
+                        if is_ram && self.is_prg_ram_writable() {
+                            return result;
+                        }
+                    }
+                    0x4020..=0x5FFF => {}
+                    _ => unreachable!(),
+                }
+
+                MappingResult::Denied
+            }
+            Device::Ppu => match address {
+                0x0000..=0x1FFF => {
+                    if self.is_chr_ram {
+                        self.map_chr(address)
+                    } else {
+                        MappingResult::Denied
+                    }
+                }
+                0x2000..=0x2FFF => {
+                    let quadrant = (address >> 10) & 3;
+                    let source = (self.nametable_mapping >> (quadrant * 2)) & 3;
+
+                    if source == 2 && self.exram_mode <= 1 {
+                        self.exram[address as usize & 0x3FF] = data;
+                    }
+
+                    MappingResult::Denied
+                }
+                _ => unreachable!(),
+            },
+        }
+    }
+
+    fn map_nametable(&self, address: u16) -> Option<NametableMapping> {
+        self.track_nametable_fetch(address);
+
+        let is_attribute = address & 0x3FF >= 0x3C0;
+
+        if self.is_rendering_in_frame() {
+            if self.fetch_in_split.get() {
+                let x = self.split_tile_x.get() as usize;
+                let y = (self.split_tile_y.get() >> 3) as usize;
+
+                let data = if is_attribute {
+                    let attribute = self.exram[0x3C0 + (y >> 2) * 8 + (x >> 2)];
+                    let shift = ((y >> 1) & 1) << 2 | ((x >> 1) & 1) << 1;
+                    // the PPU select the palette from its own scroll, so put it
+                    // in all positions
+                    ((attribute >> shift) & 3) * 0x55
+                } else {
+                    self.exram[y * 32 + x]
+                };
+                self.nametable_data.set(data);
+
+                return Some(NametableMapping::Cartridge);
+            }
+
+            if self.exram_mode == 1 && is_attribute {
+                self.nametable_data
+                    .set((self.extended_attribute.get() >> 6) * 0x55);
+
+                return Some(NametableMapping::Cartridge);
+            }
+        }
+
+        let (mapping, data) = self.map_quadrant(address);
+        if mapping == NametableMapping::Cartridge {
+            self.nametable_data.set(data);
+        }
+
+        Some(mapping)
+    }
+
+    fn peek_cpu(&self, address: u16) -> MappingResult {
+        match address {
+            0x5204 => MappingResult::Data(self.irq_status()),
+            0x8000..=0xFFFF => self.map_prg(address).0,
+            _ => self.map_read(address, Device::Cpu),
+        }
+    }
+
+    fn peek_nametable(&self, address: u16) -> Option<NametableMapping> {
+        Some(self.map_quadrant(address).0)
+    }
+
+    fn peek_ppu(&self, address: u16) -> MappingResult {
+        match address {
+            0x0000..=0x1FFF => self.map_chr_bank(address, self.last_chr_write_background),
+            0x2000..=0x2FFF => MappingResult::Data(self.map_quadrant(address).1),
+            _ => unreachable!(),
+        }
+    }
+
+    fn poke_ppu(&mut self, address: u16, data: u8) -> MappingResult {
+        match address {
+            0x0000..=0x1FFF if self.is_chr_ram => {
+                self.map_chr_bank(address, self.last_chr_write_background)
+            }
+            0x0000..=0x1FFF => MappingResult::Denied,
+            _ => self.map_write(address, data, Device::Ppu),
+        }
+    }
+
+    fn clock(&mut self) {
+        self.audio.timer_clock();
+
+        if self.in_frame.get() {
+            let idle = self.ppu_idle_counter.get();
+            if idle == 0 {
+                self.leave_frame();
+            } else {
+                self.ppu_idle_counter.set(idle - 1);
+            }
+        }
+    }
+
+    fn snoop_ppu_register_write(&mut self, address: u16, data: u8) {
+        match address {
+            0x2000 => self.sprites_8x16 = data & 0x20 != 0,
+            0x2001 => {
+                self.rendering_enabled = data & 0x18 != 0;
+                if !self.rendering_enabled {
+                    self.leave_frame();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn expansion_audio_output(&mut self) -> f32 {
+        ExpansionAudioChip::Mmc5.mix(self.audio.get_output())
+    }
+
+    fn is_hardwired_mirrored(&self) -> bool {
+        false
+    }
+
+    fn nametable_mirroring(&self) -> MirroringMode {
+        // only an approximation, as the nametables are mapped by `map_nametable`
+        match self.nametable_mapping {
+            0x50 => MirroringMode::Horizontal,
+            0x00 => MirroringMode::SingleScreenLowBank,
+            0x55 => MirroringMode::SingleScreenHighBank,
+            _ => MirroringMode::Vertical,
+        }
+    }
+
+    fn is_irq_pin_state_changed_requested(&self) -> bool {
+        self.is_irq_pin_changed.get()
+    }
+
+    fn irq_pin_state(&self) -> bool {
+        self.irq_pin.get()
+    }
+
+    fn clear_irq_request_pin(&mut self) {
+        self.is_irq_pin_changed.set(false);
+    }
+
+    fn save_state_size(&self) -> usize {
+        bincode::serialized_size(self).unwrap() as usize
+    }
+
+    fn save_state(&self) -> Vec<u8> {
+        bincode::serialize(self).unwrap()
+    }
+
+    fn load_state(&mut self, data: Vec<u8>) {
+        let state = bincode::deserialize(&data).unwrap();
+
+        let _ = std::mem::replace(self, state);
+    }
+}
@@ -3,7 +3,7 @@ use crate::common::Device;
 
 pub struct Mapper66 {
     /// in 8kb units
-    chr_count: u8,
+    chr_count: u16,
 
     /// ($8000-$FFFF)
     /// 7  bit  0
@@ -14,7 +14,7 @@ pub struct Mapper66 {
     chr_bank: u8,
 
     /// in 32kb units
-    prg_count: u8,
+    prg_count: u16,
 
     /// ($8000-$FFFF)
     /// 7  bit  0
@@ -41,16 +41,16 @@ impl Mapper66 {
     }
 
     fn map_ppu(&self, address: u16) -> MappingResult {
-        let bank = self.chr_bank % self.chr_count;
+        let bank = self.chr_bank as usize % self.chr_count as usize;
 
-        let start_of_bank = 0x2000 * bank as usize;
+        let start_of_bank = 0x2000 * bank;
 
         MappingResult::Allowed(start_of_bank + (address & 0x1FFF) as usize)
     }
 }
 
 impl Mapper for Mapper66 {
-    fn init(&mut self, prg_count: u8, is_chr_ram: bool, chr_count: u8, _sram_count: u8) {
+    fn init(&mut self, prg_count: u16, is_chr_ram: bool, chr_count: u16, _prg_ram_size: u32) {
         // even and more than 0
         assert!(prg_count % 2 == 0 && prg_count > 0);
 
@@ -64,9 +64,9 @@ impl Mapper for Mapper66 {
             Device::Cpu => match address {
                 0x6000..=0x7FFF => MappingResult::Denied,
                 0x8000..=0xFFFF => {
-                    let bank = self.prg_bank % self.prg_count;
+                    let bank = self.prg_bank as usize % self.prg_count as usize;
 
-                    let start_of_bank = 0x8000 * bank as usize;
+                    let start_of_bank = 0x8000 * bank;
 
                     MappingResult::Allowed(start_of_bank + (address & 0x7FFF) as usize)
                 }
@@ -107,24 +107,26 @@ impl Mapper for Mapper66 {
     }
 
     fn save_state_size(&self) -> usize {
-        5
+        7
     }
 
     fn save_state(&self) -> Vec<u8> {
         vec![
-            self.chr_count,
+            self.chr_count as u8,
+            (self.chr_count >> 8) as u8,
             self.chr_bank,
-            self.prg_count,
+            self.prg_count as u8,
+            (self.prg_count >> 8) as u8,
             self.prg_bank,
             self.is_chr_ram as u8,
         ]
     }
 
     fn load_state(&mut self, data: Vec<u8>) {
-        self.chr_count = data[0];
-        self.chr_bank = data[1];
-        self.prg_count = data[2];
-        self.prg_bank = data[3];
-        self.is_chr_ram = data[4] != 0;
+        self.chr_count = u16::from_le_bytes([data[0], data[1]]);
+        self.chr_bank = data[2];
+        self.prg_count = u16::from_le_bytes([data[3], data[4]]);
+        self.prg_bank = data[5];
+        self.is_chr_ram = data[6] != 0;
     }
 }
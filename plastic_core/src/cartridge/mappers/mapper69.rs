@@ -0,0 +1,288 @@
+use super::super::mapper::{Mapper, MappingResult};
+use crate::apu2a03::expansion::{APUChannel, ExpansionAudioChip, Sunsoft5BAudio, TimedAPUChannel};
+use crate::common::{Device, MirroringMode};
+use serde::{Deserialize, Serialize};
+
+/// Sunsoft FME-7, 5A and 5B, the 5B has an additional audio chip.
+///
+/// All the registers are accessed through a command register ($8000-$9FFF)
+/// that selects which register will be written using the
+/// parameter register ($A000-$BFFF)
+#[derive(Serialize, Deserialize)]
+pub struct Mapper69 {
+    /// ($8000-$9FFF)
+    /// 7  bit  0
+    /// ---- ----
+    /// .... CCCC
+    ///      ||||
+    ///      ++++- The command number to invoke when writing to the Parameter Register
+    command: u8,
+
+    /// (commands $0-$7) 1kb CHR banks
+    chr_banks: [u8; 8],
+
+    /// (command $8)
+    /// 7  bit  0
+    /// ---- ----
+    /// ERbB BBBB
+    /// |||| ||||
+    /// ||++-++++- The bank number to select at CPU $6000 - $7FFF
+    /// |+------- RAM / ROM Select Bit
+    /// |         0 = PRG ROM
+    /// |         1 = PRG RAM
+    /// +-------- RAM Enable Bit (6264 +CE line)
+    ///           0 = PRG RAM Disabled
+    ///           1 = PRG RAM Enabled
+    prg_bank_6000: u8,
+
+    /// (commands $9-$B) 8kb PRG ROM banks at $8000, $A000 and $C000
+    prg_banks: [u8; 3],
+
+    /// (command $C)
+    /// 7  bit  0
+    /// ---- ----
+    /// .... ..MM
+    ///        ||
+    ///        ++- Mirroring Mode
+    ///             0 = Vertical
+    ///             1 = Horizontal
+    ///             2 = One Screen Mirroring from $2000 ("1ScA")
+    ///             3 = One Screen Mirroring from $2400 ("1ScB")
+    mirroring: u8,
+
+    /// (command $D)
+    /// 7  bit  0
+    /// ---- ----
+    /// C... ...T
+    /// |       |
+    /// |       +- IRQ Enable
+    /// |           0 = Do not generate IRQs
+    /// |           1 = Do generate IRQs
+    /// +-------- IRQ Counter Enable
+    ///             0 = Disable Counter Decrement
+    ///             1 = Enable Counter Decrement
+    irq_enabled: bool,
+    irq_counter_enabled: bool,
+
+    /// (commands $E and $F) decremented on every CPU cycle
+    irq_counter: u16,
+
+    irq_pin: bool,
+    is_irq_pin_changed: bool,
+
+    audio: Sunsoft5BAudio,
+
+    /// in 8kb units
+    prg_count: u16,
+    /// in 1kb units
+    chr_count: u16,
+    /// in 8kb units
+    prg_ram_count: u8,
+
+    is_chr_ram: bool,
+}
+
+impl Mapper69 {
+    pub fn new() -> Self {
+        Self {
+            command: 0,
+            chr_banks: [0; 8],
+            prg_bank_6000: 0,
+            prg_banks: [0; 3],
+            mirroring: 0,
+
+            irq_enabled: false,
+            irq_counter_enabled: false,
+            irq_counter: 0,
+
+            irq_pin: false,
+            is_irq_pin_changed: false,
+
+            audio: Sunsoft5BAudio::new(),
+
+            prg_count: 0,
+            chr_count: 0,
+            prg_ram_count: 0,
+
+            is_chr_ram: false,
+        }
+    }
+
+    fn set_irq_pin(&mut self, state: bool) {
+        if self.irq_pin != state {
+            self.irq_pin = state;
+            self.is_irq_pin_changed = true;
+        }
+    }
+
+    fn map_6000(&self, address: u16) -> MappingResult {
+        let bank = self.prg_bank_6000 & 0x3F;
+        let offset = (address & 0x1FFF) as usize;
+
+        if self.prg_bank_6000 & 0x40 == 0 {
+            let bank = bank as usize % self.prg_count as usize;
+
+            MappingResult::PrgRom(bank * 0x2000 + offset)
+        } else if self.prg_bank_6000 & 0x80 != 0 && self.prg_ram_count != 0 {
+            let bank = bank as usize % self.prg_ram_count as usize;
+
+            MappingResult::PrgRam(bank * 0x2000 + offset)
+        } else {
+            // open bus, which usually holds the high byte of the address,
+            // from the last fetched operand
+            MappingResult::Data((address >> 8) as u8)
+        }
+    }
+
+    fn map_ppu(&self, address: u16) -> MappingResult {
+        let bank = self.chr_banks[(address >> 10) as usize & 7] as usize % self.chr_count as usize;
+
+        MappingResult::Allowed(bank * 0x400 + (address & 0x3FF) as usize)
+    }
+
+    fn write_parameter(&mut self, data: u8) {
+        match self.command {
+            0..=7 => self.chr_banks[self.command as usize] = data,
+            8 => self.prg_bank_6000 = data,
+            9..=0xB => self.prg_banks[self.command as usize - 9] = data & 0x3F,
+            0xC => self.mirroring = data & 3,
+            0xD => {
+                self.irq_enabled = data & 1 != 0;
+                self.irq_counter_enabled = data & 0x80 != 0;
+
+                // any write acknowledges the IRQ
+                self.set_irq_pin(false);
+            }
+            0xE => self.irq_counter = (self.irq_counter & 0xFF00) | data as u16,
+            0xF => self.irq_counter = (self.irq_counter & 0x00FF) | (data as u16) << 8,
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl Mapper for Mapper69 {
+    fn init(&mut self, prg_count: u16, is_chr_ram: bool, chr_count: u16, prg_ram_size: u32) {
+        self.prg_count = prg_count * 2;
+        self.chr_count = chr_count * 8;
+        self.prg_ram_count = u8::try_from(prg_ram_size / 0x2000).unwrap_or(u8::MAX);
+
+        self.is_chr_ram = is_chr_ram;
+    }
+
+    fn map_read(&self, address: u16, device: Device) -> MappingResult {
+        match device {
+            Device::Cpu => match address {
+                0x6000..=0x7FFF => self.map_6000(address),
+                0x8000..=0xFFFF => {
+                    let bank = match address {
+                        0x8000..=0xDFFF => self.prg_banks[(address as usize - 0x8000) >> 13] as u16,
+                        0xE000..=0xFFFF => self.prg_count - 1,
+                        _ => unreachable!(),
+                    } as usize
+                        % self.prg_count as usize;
+
+                    MappingResult::Allowed(bank * 0x2000 + (address & 0x1FFF) as usize)
+                }
+                0x4020..=0x5FFF => MappingResult::Denied,
+                _ => unreachable!(),
+            },
+            Device::Ppu => {
+                if address < 0x2000 {
+                    self.map_ppu(address)
+                } else {
+                    unreachable!();
+                }
+            }
+        }
+    }
+
+    fn map_write(&mut self, address: u16, data: u8, device: Device) -> MappingResult {
+        match device {
+            Device::Cpu => match address {
+                0x6000..=0x7FFF => match self.map_6000(address) {
+                    result @ MappingResult::PrgRam(_) => result,
+                    // cannot write to ROM or disabled RAM
+                    _ => MappingResult::Denied,
+                },
+                0x8000..=0xFFFF => {
+                    match address {
+                        0x8000..=0x9FFF => self.command = data & 0xF,
+                        0xA000..=0xBFFF => self.write_parameter(data),
+                        0xC000..=0xDFFF => self.audio.write_register_select(data),
+                        0xE000..=0xFFFF => self.audio.write_register(data),
+                        _ => unreachable!(),
+                    }
+
+                    MappingResult::Denied
+                }
+                0x4020..=0x5FFF => MappingResult::Denied,
+                _ => unreachable!(),
+            },
+            Device::Ppu => {
+                // CHR RAM
+                if self.is_chr_ram && address <= 0x1FFF {
+                    self.map_ppu(address)
+                } else {
+                    MappingResult::Denied
+                }
+            }
+        }
+    }
+
+    fn clock(&mut self) {
+        if self.irq_counter_enabled {
+            let (counter, underflow) = self.irq_counter.overflowing_sub(1);
+            self.irq_counter = counter;
+
+            if underflow && self.irq_enabled {
+                self.set_irq_pin(true);
+            }
+        }
+
+        self.audio.timer_clock();
+    }
+
+    fn expansion_audio_output(&mut self) -> f32 {
+        ExpansionAudioChip::Sunsoft5B.mix(self.audio.get_output())
+    }
+
+    fn is_hardwired_mirrored(&self) -> bool {
+        false
+    }
+
+    fn nametable_mirroring(&self) -> MirroringMode {
+        match self.mirroring {
+            0 => MirroringMode::Vertical,
+            1 => MirroringMode::Horizontal,
+            2 => MirroringMode::SingleScreenLowBank,
+            3 => MirroringMode::SingleScreenHighBank,
+            _ => unreachable!(),
+        }
+    }
+
+    fn is_irq_pin_state_changed_requested(&self) -> bool {
+        self.is_irq_pin_changed
+    }
+
+    fn irq_pin_state(&self) -> bool {
+        self.irq_pin
+    }
+
+    fn clear_irq_request_pin(&mut self) {
+        self.is_irq_pin_changed = false;
+    }
+
+    fn save_state_size(&self) -> usize {
+        bincode::serialized_size(self).unwrap() as usize
+    }
+
+    fn save_state(&self) -> Vec<u8> {
+        bincode::serialize(self).unwrap()
+    }
+
+    fn load_state(&mut self, data: Vec<u8>) {
+        let state = bincode::deserialize(&data).unwrap();
+
+        let _ = std::mem::replace(self, state);
+    }
+}
@@ -6,7 +6,7 @@ pub struct Mapper7 {
     prg_bank: u8,
 
     /// in 32kb units
-    prg_count: u8,
+    prg_count: u16,
 
     /// this mapper support only one screen mirroring and able to switch between
     /// low and high banks
@@ -29,7 +29,7 @@ impl Mapper7 {
 }
 
 impl Mapper for Mapper7 {
-    fn init(&mut self, prg_count: u8, is_chr_ram: bool, _chr_count: u8, _sram_count: u8) {
+    fn init(&mut self, prg_count: u16, is_chr_ram: bool, _chr_count: u16, _prg_ram_size: u32) {
         // even and positive
         assert!(prg_count % 2 == 0 && prg_count > 0);
 
@@ -43,9 +43,9 @@ impl Mapper for Mapper7 {
                 match address {
                     0x6000..=0x7FFF => MappingResult::Denied,
                     0x8000..=0xFFFF => {
-                        let bank = self.prg_bank % self.prg_count;
+                        let bank = self.prg_bank as usize % self.prg_count as usize;
 
-                        let start_of_bank = 0x8000 * bank as usize;
+                        let start_of_bank = 0x8000 * bank;
 
                         // add the offset
                         MappingResult::Allowed(start_of_bank + (address & 0x7FFF) as usize)
@@ -103,13 +103,14 @@ impl Mapper for Mapper7 {
     }
 
     fn save_state_size(&self) -> usize {
-        4
+        5
     }
 
     fn save_state(&self) -> Vec<u8> {
         vec![
             self.prg_bank,
-            self.prg_count,
+            self.prg_count as u8,
+            (self.prg_count >> 8) as u8,
             self.is_mirroring_screen_high_bank as u8,
             self.is_chr_ram as u8,
         ]
@@ -117,8 +118,8 @@ impl Mapper for Mapper7 {
 
     fn load_state(&mut self, data: Vec<u8>) {
         self.prg_bank = data[0];
-        self.prg_count = data[1];
-        self.is_mirroring_screen_high_bank = data[2] != 0;
-        self.is_chr_ram = data[3] != 0;
+        self.prg_count = u16::from_le_bytes([data[1], data[2]]);
+        self.is_mirroring_screen_high_bank = data[3] != 0;
+        self.is_chr_ram = data[4] != 0;
     }
 }
@@ -0,0 +1,128 @@
+use super::super::mapper::{Mapper, MappingResult};
+use crate::common::{Device, MirroringMode};
+use serde::{Deserialize, Serialize};
+
+/// Camerica/Codemasters BF909x, UNROM like with the bank register at $C000-$FFFF
+#[derive(Serialize, Deserialize)]
+pub struct Mapper71 {
+    /// ($C000-$FFFF)
+    /// select 16 KB PRG ROM bank for CPU $8000-$BFFF
+    prg_bank: u8,
+
+    /// ($9000-$9FFF, or $8000-$9FFF for submapper 1)
+    /// 7  bit  0
+    /// ---- ----
+    /// xxxM xxxx
+    ///    |
+    ///    +---- Select single screen mirroring bank
+    single_screen_high_bank: bool,
+
+    /// only the BF9097 board (Fire Hawk) controls the mirroring, other boards
+    /// have hardwired mirroring until the game writes to $9000-$9FFF
+    has_mirroring_control: bool,
+
+    /// in 16kb units
+    prg_count: u16,
+
+    is_chr_ram: bool,
+}
+
+impl Mapper71 {
+    pub fn new(submapper_id: u8) -> Self {
+        Self {
+            prg_bank: 0,
+            single_screen_high_bank: false,
+            has_mirroring_control: submapper_id == 1,
+            prg_count: 0,
+            is_chr_ram: false,
+        }
+    }
+}
+
+impl Mapper for Mapper71 {
+    fn init(&mut self, prg_count: u16, is_chr_ram: bool, _chr_count: u16, _prg_ram_size: u32) {
+        self.prg_count = prg_count;
+        self.is_chr_ram = is_chr_ram;
+    }
+
+    fn map_read(&self, address: u16, device: Device) -> MappingResult {
+        match device {
+            Device::Cpu => match address {
+                0x6000..=0x7FFF => MappingResult::Denied,
+                0x8000..=0xFFFF => {
+                    let bank = if address < 0xC000 {
+                        self.prg_bank as usize
+                    } else {
+                        self.prg_count as usize - 1
+                    } % self.prg_count as usize;
+
+                    MappingResult::Allowed(bank * 0x4000 + (address & 0x3FFF) as usize)
+                }
+                0x4020..=0x5FFF => MappingResult::Denied,
+                _ => unreachable!(),
+            },
+            Device::Ppu => {
+                // only one fixed memory
+                if address < 0x2000 {
+                    MappingResult::Allowed(address as usize)
+                } else {
+                    unreachable!()
+                }
+            }
+        }
+    }
+
+    fn map_write(&mut self, address: u16, data: u8, device: Device) -> MappingResult {
+        match device {
+            Device::Cpu => {
+                match address {
+                    0x8000..=0x9FFF => {
+                        if address >= 0x9000 || self.has_mirroring_control {
+                            self.has_mirroring_control = true;
+                            self.single_screen_high_bank = data & 0x10 != 0;
+                        }
+                    }
+                    0xC000..=0xFFFF => self.prg_bank = data,
+                    0x4020..=0x7FFF | 0xA000..=0xBFFF => {}
+                    _ => unreachable!(),
+                }
+
+                MappingResult::Denied
+            }
+            Device::Ppu => {
+                // CHR RAM
+                if self.is_chr_ram && address <= 0x1FFF {
+                    MappingResult::Allowed(address as usize)
+                } else {
+                    MappingResult::Denied
+                }
+            }
+        }
+    }
+
+    fn is_hardwired_mirrored(&self) -> bool {
+        !self.has_mirroring_control
+    }
+
+    fn nametable_mirroring(&self) -> MirroringMode {
+        if self.single_screen_high_bank {
+            MirroringMode::SingleScreenHighBank
+        } else {
+            MirroringMode::SingleScreenLowBank
+        }
+    }
+
+    fn save_state_size(&self) -> usize {
+        bincode::serialized_size(self).unwrap() as usize
+    }
+
+    fn save_state(&self) -> Vec<u8> {
+        bincode::serialize(self).unwrap()
+    }
+
+    fn load_state(&mut self, data: Vec<u8>) {
+        let state = bincode::deserialize(&data).unwrap();
+
+        let _ = std::mem::replace(self, state);
+    }
+}
@@ -0,0 +1,148 @@
+use super::super::mapper::{Mapper, MappingResult};
+use crate::common::{Device, MirroringMode};
+use serde::{Deserialize, Serialize};
+
+/// AVE NINA-03/NINA-06 (mapper 79), and the multicart version of it (mapper 113)
+/// with more bank bits and mirroring control in the same register
+#[derive(Serialize, Deserialize)]
+pub struct Mapper79 {
+    is_mapper113: bool,
+
+    /// ($4100-$5FFF, when A8 is set)
+    /// mapper 79:
+    /// 7  bit  0
+    /// ---- ----
+    /// xxxx PCCC
+    ///      ||||
+    ///      |+++- Select 8 KB CHR ROM bank for PPU $0000-$1FFF
+    ///      +---- Select 32 KB PRG ROM bank for CPU $8000-$FFFF
+    ///
+    /// mapper 113:
+    /// 7  bit  0
+    /// ---- ----
+    /// MCPP PCCC
+    /// |||| ||||
+    /// |+|| |+++- Select 8 KB CHR ROM bank for PPU $0000-$1FFF
+    /// | ++-+---- Select 32 KB PRG ROM bank for CPU $8000-$FFFF
+    /// +--------- Nametable mirroring (0: horizontal; 1: vertical)
+    prg_bank: u8,
+    chr_bank: u8,
+    mirroring_vertical: bool,
+
+    /// in 16kb units
+    prg_count: u16,
+
+    /// in 8kb units
+    chr_count: u16,
+
+    is_chr_ram: bool,
+}
+
+impl Mapper79 {
+    pub fn new(mapper_id: u16) -> Self {
+        Self {
+            is_mapper113: mapper_id == 113,
+            prg_bank: 0,
+            chr_bank: 0,
+            mirroring_vertical: false,
+            prg_count: 0,
+            chr_count: 0,
+            is_chr_ram: false,
+        }
+    }
+
+    fn map_ppu(&self, address: u16) -> MappingResult {
+        let bank = self.chr_bank as usize % self.chr_count as usize;
+
+        MappingResult::Allowed(bank * 0x2000 + (address & 0x1FFF) as usize)
+    }
+}
+
+impl Mapper for Mapper79 {
+    fn init(&mut self, prg_count: u16, is_chr_ram: bool, chr_count: u16, _prg_ram_size: u32) {
+        self.prg_count = prg_count;
+        self.chr_count = chr_count;
+        self.is_chr_ram = is_chr_ram;
+    }
+
+    fn map_read(&self, address: u16, device: Device) -> MappingResult {
+        match device {
+            Device::Cpu => match address {
+                0x6000..=0x7FFF => MappingResult::Denied,
+                0x8000..=0xFFFF => {
+                    // in 16kb banks, as 16kb games are mirrored
+                    let bank = (self.prg_bank as usize * 2 + ((address >> 14) & 1) as usize)
+                        % self.prg_count as usize;
+
+                    MappingResult::Allowed(bank * 0x4000 + (address & 0x3FFF) as usize)
+                }
+                0x4020..=0x5FFF => MappingResult::Denied,
+                _ => unreachable!(),
+            },
+            Device::Ppu => {
+                if address < 0x2000 {
+                    self.map_ppu(address)
+                } else {
+                    unreachable!()
+                }
+            }
+        }
+    }
+
+    fn map_write(&mut self, address: u16, data: u8, device: Device) -> MappingResult {
+        match device {
+            Device::Cpu => {
+                match address {
+                    0x4100..=0x5FFF if address & 0x100 != 0 => {
+                        if self.is_mapper113 {
+                            self.prg_bank = (data >> 3) & 0b111;
+                            self.chr_bank = ((data >> 3) & 0b1000) | (data & 0b111);
+                            self.mirroring_vertical = data & 0x80 != 0;
+                        } else {
+                            self.prg_bank = (data >> 3) & 1;
+                            self.chr_bank = data & 0b111;
+                        }
+                    }
+                    0x4020..=0xFFFF => {}
+                    _ => unreachable!(),
+                }
+
+                MappingResult::Denied
+            }
+            Device::Ppu => {
+                // CHR RAM
+                if self.is_chr_ram && address <= 0x1FFF {
+                    self.map_ppu(address)
+                } else {
+                    MappingResult::Denied
+                }
+            }
+        }
+    }
+
+    fn is_hardwired_mirrored(&self) -> bool {
+        !self.is_mapper113
+    }
+
+    fn nametable_mirroring(&self) -> MirroringMode {
+        if self.mirroring_vertical {
+            MirroringMode::Vertical
+        } else {
+            MirroringMode::Horizontal
+        }
+    }
+
+    fn save_state_size(&self) -> usize {
+        bincode::serialized_size(self).unwrap() as usize
+    }
+
+    fn save_state(&self) -> Vec<u8> {
+        bincode::serialize(self).unwrap()
+    }
+
+    fn load_state(&mut self, data: Vec<u8>) {
+        let state = bincode::deserialize(&data).unwrap();
+
+        let _ = std::mem::replace(self, state);
+    }
+}
@@ -0,0 +1,240 @@
+use super::super::mapper::{Mapper, MappingResult};
+use super::vrc_irq::VrcIrq;
+use crate::apu2a03::expansion::{APUChannel, ExpansionAudioChip, TimedAPUChannel, Vrc7Audio};
+use crate::common::{Device, MirroringMode};
+use serde::{Deserialize, Serialize};
+
+const A3: u16 = 1 << 3;
+const A4: u16 = 1 << 4;
+
+/// Konami VRC7, with the VRC7 FM audio.
+///
+/// VRC7a (Lagrange Point) selects the second register of each pair with `A4`,
+/// and VRC7b (Tiny Toon Adventures 2) uses `A3`, it is selected with the
+/// submapper id, and for iNES 1.0 files (submapper 0) both lines are used.
+#[derive(Serialize, Deserialize)]
+pub struct Mapper85 {
+    /// ($8000, $8010, $9000) 8kb PRG ROM banks at `$8000`, `$A000` and `$C000`
+    prg_banks: [u8; 3],
+
+    /// ($A000-$D010) 1kb CHR banks
+    chr_banks: [u8; 8],
+
+    /// ($E000)
+    /// 7  bit  0
+    /// ---------
+    /// RS.. ..MM
+    /// ||     ||
+    /// ||     ++- Mirroring (0: vertical; 1: horizontal;
+    /// ||                        2: one-screen lower bank; 3: one-screen upper bank)
+    /// |+-------- Silence expansion sound if set
+    /// +--------- WRAM enable (1: enable WRAM, 0: protect)
+    control: u8,
+
+    irq: VrcIrq,
+    audio: Vrc7Audio,
+
+    /// the address line used to select the second register of each pair
+    register_line: u16,
+
+    /// in 8kb units
+    prg_count: u16,
+    /// in 1kb units
+    chr_count: u16,
+
+    is_chr_ram: bool,
+    has_prg_ram: bool,
+}
+
+impl Mapper85 {
+    pub fn new(submapper_id: u8) -> Self {
+        let register_line = match submapper_id {
+            // VRC7b
+            1 => A3,
+            // VRC7a
+            2 => A4,
+            _ => A3 | A4,
+        };
+
+        Self {
+            prg_banks: [0; 3],
+            chr_banks: [0; 8],
+            control: 0,
+
+            irq: VrcIrq::new(),
+            audio: Vrc7Audio::new(),
+
+            register_line,
+
+            prg_count: 0,
+            chr_count: 0,
+
+            is_chr_ram: false,
+            has_prg_ram: false,
+        }
+    }
+
+    /// returns the register address in the form `$x000` or `$x010`
+    fn register_address(&self, address: u16) -> u16 {
+        (address & 0xF000) | ((address & self.register_line != 0) as u16) << 4
+    }
+
+    fn is_prg_ram_enabled(&self) -> bool {
+        self.has_prg_ram && self.control & 0x80 != 0
+    }
+
+    fn map_ppu(&self, address: u16) -> MappingResult {
+        let bank = self.chr_banks[(address >> 10) as usize & 7] as usize % self.chr_count as usize;
+
+        MappingResult::Allowed(bank * 0x400 + (address & 0x3FF) as usize)
+    }
+}
+
+impl Mapper for Mapper85 {
+    fn init(&mut self, prg_count: u16, is_chr_ram: bool, chr_count: u16, prg_ram_size: u32) {
+        self.prg_count = prg_count * 2;
+        self.chr_count = chr_count * 8;
+
+        self.is_chr_ram = is_chr_ram;
+        self.has_prg_ram = prg_ram_size != 0;
+    }
+
+    fn map_read(&self, address: u16, device: Device) -> MappingResult {
+        match device {
+            Device::Cpu => match address {
+                0x6000..=0x7FFF => {
+                    if self.is_prg_ram_enabled() {
+                        MappingResult::Allowed(address as usize & 0x1FFF)
+                    } else {
+                        MappingResult::Denied
+                    }
+                }
+                0x8000..=0xFFFF => {
+                    let bank = match address {
+                        0x8000..=0xDFFF => self.prg_banks[(address as usize - 0x8000) >> 13] as u16,
+                        0xE000..=0xFFFF => self.prg_count - 1,
+                        _ => unreachable!(),
+                    } as usize
+                        % self.prg_count as usize;
+
+                    MappingResult::Allowed(bank * 0x2000 + (address & 0x1FFF) as usize)
+                }
+                0x4020..=0x5FFF => MappingResult::Denied,
+                _ => unreachable!(),
+            },
+            Device::Ppu => {
+                if address < 0x2000 {
+                    self.map_ppu(address)
+                } else {
+                    unreachable!();
+                }
+            }
+        }
+    }
+
+    fn map_write(&mut self, address: u16, data: u8, device: Device) -> MappingResult {
+        match device {
+            Device::Cpu => match address {
+                0x6000..=0x7FFF => {
+                    if self.is_prg_ram_enabled() {
+                        MappingResult::Allowed(address as usize & 0x1FFF)
+                    } else {
+                        MappingResult::Denied
+                    }
+                }
+                // the audio ports use `A4` and `A5` on both boards
+                0x9010..=0x9FFF if address & 0x30 == 0x10 => {
+                    self.audio.write_register_select(data);
+                    MappingResult::Denied
+                }
+                0x9030..=0x9FFF if address & 0x30 == 0x30 => {
+                    self.audio.write_register(data);
+                    MappingResult::Denied
+                }
+                0x8000..=0xFFFF => {
+                    let address = self.register_address(address);
+
+                    match address {
+                        0x8000 => self.prg_banks[0] = data & 0x3F,
+                        0x8010 => self.prg_banks[1] = data & 0x3F,
+                        0x9000 => self.prg_banks[2] = data & 0x3F,
+                        0xA000..=0xD010 => {
+                            let bank_index = ((address - 0xA000) >> 12) as usize * 2
+                                + ((address >> 4) & 1) as usize;
+                            self.chr_banks[bank_index] = data;
+                        }
+                        0xE000 => {
+                            self.control = data;
+                            self.audio.set_silenced(data & 0x40 != 0);
+                        }
+                        0xE010 => self.irq.write_latch(data),
+                        0xF000 => self.irq.write_control(data),
+                        0xF010 => self.irq.acknowledge(),
+                        _ => {}
+                    }
+
+                    MappingResult::Denied
+                }
+                0x4020..=0x5FFF => MappingResult::Denied,
+                _ => unreachable!(),
+            },
+            Device::Ppu => {
+                // CHR RAM
+                if self.is_chr_ram && address <= 0x1FFF {
+                    self.map_ppu(address)
+                } else {
+                    MappingResult::Denied
+                }
+            }
+        }
+    }
+
+    fn clock(&mut self) {
+        self.irq.clock();
+        self.audio.timer_clock();
+    }
+
+    fn expansion_audio_output(&mut self) -> f32 {
+        ExpansionAudioChip::Vrc7.mix(self.audio.get_output())
+    }
+
+    fn is_hardwired_mirrored(&self) -> bool {
+        false
+    }
+
+    fn nametable_mirroring(&self) -> MirroringMode {
+        match self.control & 3 {
+            0 => MirroringMode::Vertical,
+            1 => MirroringMode::Horizontal,
+            2 => MirroringMode::SingleScreenLowBank,
+            3 => MirroringMode::SingleScreenHighBank,
+            _ => unreachable!(),
+        }
+    }
+
+    fn is_irq_pin_state_changed_requested(&self) -> bool {
+        self.irq.is_irq_pin_state_changed_requested()
+    }
+
+    fn irq_pin_state(&self) -> bool {
+        self.irq.irq_pin_state()
+    }
+
+    fn clear_irq_request_pin(&mut self) {
+        self.irq.clear_irq_request_pin();
+    }
+
+    fn save_state_size(&self) -> usize {
+        bincode::serialized_size(self).unwrap() as usize
+    }
+
+    fn save_state(&self) -> Vec<u8> {
+        bincode::serialize(self).unwrap()
+    }
+
+    fn load_state(&mut self, data: Vec<u8>) {
+        let state = bincode::deserialize(&data).unwrap();
+
+        let _ = std::mem::replace(self, state);
+    }
+}
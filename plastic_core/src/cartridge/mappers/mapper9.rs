@@ -69,10 +69,10 @@ pub struct Mapper9 {
     is_chr_ram: bool,
 
     /// in 4kb units
-    chr_count: u8,
+    chr_count: u16,
 
     /// in 8kb units
-    prg_count: u8,
+    prg_count: u16,
 
     /// does it have prg ram
     has_prg_ram: bool,
@@ -97,19 +97,13 @@ impl Mapper9 {
     }
 
     fn map_ppu(&self, address: u16) -> MappingResult {
-        let mut bank = if address & 0x1000 == 0 {
+        if address & 0x1000 == 0 {
             // set latch 0
             if address == 0x0FD8 {
                 self.latch_0.set(0xFD);
             } else if address == 0x0FE8 {
                 self.latch_0.set(0xFE);
             }
-
-            match self.latch_0.get() {
-                0xFD => self.chr_fd_0000_bank,
-                0xFE => self.chr_fe_0000_bank,
-                _ => unreachable!(),
-            }
         } else {
             // set latch 1
             if address & 0x8 != 0 {
@@ -118,7 +112,20 @@ impl Mapper9 {
                     self.latch_1.set(middle_byte as u8);
                 }
             }
+        }
+
+        self.map_chr(address)
+    }
 
+    /// map the CHR `address` with the current latches, without updating them
+    fn map_chr(&self, address: u16) -> MappingResult {
+        let mut bank = if address & 0x1000 == 0 {
+            match self.latch_0.get() {
+                0xFD => self.chr_fd_0000_bank,
+                0xFE => self.chr_fe_0000_bank,
+                _ => unreachable!(),
+            }
+        } else {
             match self.latch_1.get() {
                 0xFD => self.chr_fd_1000_bank,
                 0xFE => self.chr_fe_1000_bank,
@@ -135,7 +142,7 @@ impl Mapper9 {
 }
 
 impl Mapper for Mapper9 {
-    fn init(&mut self, prg_count: u8, is_chr_ram: bool, chr_count: u8, sram_count: u8) {
+    fn init(&mut self, prg_count: u16, is_chr_ram: bool, chr_count: u16, prg_ram_size: u32) {
         self.prg_count = prg_count * 2;
 
         // because 0xA000-0xFFFF holds the last 3 banks (fixed)
@@ -144,7 +151,7 @@ impl Mapper for Mapper9 {
         self.chr_count = chr_count * 2;
 
         self.is_chr_ram = is_chr_ram;
-        self.has_prg_ram = sram_count != 0;
+        self.has_prg_ram = prg_ram_size != 0;
     }
 
     fn map_read(&self, address: u16, device: Device) -> MappingResult {
@@ -159,11 +166,11 @@ impl Mapper for Mapper9 {
                 }
                 0x8000..=0xFFFF => {
                     let mut bank = match address {
-                        0x8000..=0x9FFF => self.prg_bank,
+                        0x8000..=0x9FFF => self.prg_bank as u16,
                         0xA000..=0xFFFF => {
                             // last 3 banks
                             // 0: third last bank, 1: second last bank, 2: last bank
-                            let bank = ((address >> 13) & 0b11) as u8 - 1;
+                            let bank = ((address >> 13) & 0b11) - 1;
 
                             self.prg_count - 3 + bank
                         }
@@ -226,6 +233,18 @@ impl Mapper for Mapper9 {
         }
     }
 
+    fn peek_ppu(&self, address: u16) -> MappingResult {
+        self.map_chr(address)
+    }
+
+    fn poke_ppu(&mut self, address: u16, _data: u8) -> MappingResult {
+        if self.is_chr_ram {
+            self.map_chr(address)
+        } else {
+            MappingResult::Denied
+        }
+    }
+
     fn is_hardwired_mirrored(&self) -> bool {
         false
     }
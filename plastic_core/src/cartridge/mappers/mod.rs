@@ -3,14 +3,39 @@ mod mapper1;
 mod mapper2;
 mod mapper3;
 mod mapper4;
+mod mapper5;
 mod mapper7;
 mod mapper9;
 
 mod mapper10;
 mod mapper11;
 mod mapper12;
+mod mapper16;
+mod mapper19;
+
+mod mapper21;
+mod mapper24;
+mod mapper28;
+mod mapper30;
+mod mapper34;
 
 mod mapper66;
+mod mapper69;
+mod mapper71;
+mod mapper79;
+
+mod mapper85;
+
+mod mapper111;
+mod mapper140;
+mod mapper180;
+mod mapper232;
+
+mod eeprom;
+mod fds;
+mod flash;
+mod nsf;
+mod vrc_irq;
 
 mod tests;
 
@@ -19,11 +44,33 @@ pub use mapper1::Mapper1;
 pub use mapper2::Mapper2;
 pub use mapper3::Mapper3;
 pub use mapper4::Mapper4;
+pub use mapper5::Mapper5;
 pub use mapper7::Mapper7;
 pub use mapper9::Mapper9;
 
 pub use mapper10::Mapper10;
 pub use mapper11::Mapper11;
 pub use mapper12::Mapper12;
+pub use mapper16::Mapper16;
+pub use mapper19::Mapper19;
+
+pub use mapper21::Mapper21;
+pub use mapper24::Mapper24;
+pub use mapper28::Mapper28;
+pub use mapper30::Mapper30;
+pub use mapper34::Mapper34;
 
 pub use mapper66::Mapper66;
+pub use mapper69::Mapper69;
+pub use mapper71::Mapper71;
+pub use mapper79::Mapper79;
+
+pub use mapper85::Mapper85;
+
+pub use mapper111::Mapper111;
+pub use mapper140::Mapper140;
+pub use mapper180::Mapper180;
+pub use mapper232::Mapper232;
+
+pub use fds::Fds;
+pub use nsf::Nsf;
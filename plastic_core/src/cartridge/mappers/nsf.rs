@@ -0,0 +1,533 @@
+use super::super::mapper::{Mapper, MappingResult};
+use super::super::nsf_file::{NsfFile, NsfInfo};
+use crate::apu2a03::expansion::{
+    APUChannel, ExpansionAudioChip, FdsAudio, Mmc5Audio, Namco163Audio, Sunsoft5BAudio,
+    TimedAPUChannel, Vrc6Audio, Vrc7Audio,
+};
+use crate::common::{Device, Region};
+use serde::{Deserialize, Serialize};
+use std::cell::Cell;
+use std::time::Duration;
+
+const VRC6: u8 = 1 << 0;
+const VRC7: u8 = 1 << 1;
+const FDS: u8 = 1 << 2;
+const MMC5: u8 = 1 << 3;
+const NAMCO163: u8 = 1 << 4;
+const SUNSOFT5B: u8 = 1 << 5;
+
+/// the fade out length of tracks that have a length but no fade
+const DEFAULT_FADE: Duration = Duration::from_secs(8);
+
+/// where the driver program is mapped, unused by the NSF specification
+const DRIVER_ADDRESS: u16 = 0x4100;
+/// reading it returns non-zero if the play routine should be called,
+/// and acknowledges it
+const PLAY_FLAG_ADDRESS: u16 = 0x41FF;
+
+const DRIVER_TRACK_OFFSET: usize = 0x38;
+const DRIVER_REGION_OFFSET: usize = 0x3A;
+const DRIVER_INIT_OFFSET: usize = 0x3C;
+const DRIVER_PLAY_OFFSET: usize = 0x44;
+
+/// The program that runs the NSF routines, the reset vector points to it.
+///
+/// It initializes the RAM and the APU, then calls the init routine with the track
+/// number in `A` and the region in `X`, then calls the play routine every time
+/// [`PLAY_FLAG_ADDRESS`] is set.
+#[rustfmt::skip]
+const DRIVER: [u8; 0x49] = [
+    0x78,                   // $4100: SEI
+    0xD8,                   // $4101: CLD
+    0xA2, 0xFF,             // $4102: LDX #$FF
+    0x9A,                   // $4104: TXS
+    0xA9, 0x00,             // $4105: LDA #$00
+    0xAA,                   // $4107: TAX
+    0x95, 0x00,             // $4108: STA $00,X       ; clear RAM
+    0x9D, 0x00, 0x01,       // $410A: STA $0100,X
+    0x9D, 0x00, 0x02,       // $410D: STA $0200,X
+    0x9D, 0x00, 0x03,       // $4110: STA $0300,X
+    0x9D, 0x00, 0x04,       // $4113: STA $0400,X
+    0x9D, 0x00, 0x05,       // $4116: STA $0500,X
+    0x9D, 0x00, 0x06,       // $4119: STA $0600,X
+    0x9D, 0x00, 0x07,       // $411C: STA $0700,X
+    0xE8,                   // $411F: INX
+    0xD0, 0xE6,             // $4120: BNE $4108
+    0xA2, 0x13,             // $4122: LDX #$13
+    0x9D, 0x00, 0x40,       // $4124: STA $4000,X     ; clear APU registers
+    0xCA,                   // $4127: DEX
+    0x10, 0xFA,             // $4128: BPL $4124
+    0x8D, 0x15, 0x40,       // $412A: STA $4015
+    0xA9, 0x0F,             // $412D: LDA #$0F
+    0x8D, 0x15, 0x40,       // $412F: STA $4015
+    0xA9, 0x40,             // $4132: LDA #$40
+    0x8D, 0x17, 0x40,       // $4134: STA $4017
+    0xA9, 0x00,             // $4137: LDA #track
+    0xA2, 0x00,             // $4139: LDX #region
+    0x20, 0x00, 0x00,       // $413B: JSR init
+    0xAD, 0xFF, 0x41,       // $413E: LDA PLAY_FLAG   ; wait for the next play call
+    0xF0, 0xFB,             // $4141: BEQ $413E
+    0x20, 0x00, 0x00,       // $4143: JSR play
+    0x4C, 0x3E, 0x41,       // $4146: JMP $413E
+];
+
+/// NSF player, runs the NSF program with [`DRIVER`], and has all the expansion
+/// audio chips used by the file.
+///
+/// The program and the RAM are stored in the mapper and returned with
+/// [`MappingResult::Data`], so the cartridge PRG data is not used.
+#[derive(Serialize, Deserialize)]
+pub struct Nsf {
+    info: NsfInfo,
+
+    load_address: u16,
+    init_address: u16,
+    play_address: u16,
+    bank_init: Option<[u8; 8]>,
+    expansion_chips: u8,
+
+    /// the program data in 4kb banks, if the file does not use bank switching,
+    /// this is an image of `$8000-$FFFF`
+    prg: Vec<u8>,
+    /// ($5FF8-$5FFF) 4kb banks at `$8000-$FFFF`
+    banks: [u8; 8],
+    /// `$6000-$7FFF`, or `$6000-$FFFF` if the FDS is used, where
+    /// the program is loaded into RAM
+    ram: Vec<u8>,
+
+    driver: Vec<u8>,
+    /// the play routine rates in microseconds
+    ntsc_play_speed: u16,
+    pal_play_speed: u16,
+    dendy_play_speed: u16,
+    region: Region,
+    /// in CPU cycles
+    play_period: u32,
+    play_timer: u32,
+    play_requested: Cell<bool>,
+
+    current_track: usize,
+    elapsed_cycles: u64,
+
+    vrc6: Option<Vrc6Audio>,
+    vrc7: Option<Vrc7Audio>,
+    fds: Option<FdsAudio>,
+    mmc5: Option<Mmc5Audio>,
+    /// ($5C00-$5FF5)
+    mmc5_exram: Vec<u8>,
+    /// ($5205/$5206)
+    mmc5_multiplicand: u8,
+    mmc5_multiplier: u8,
+    namco163: Option<Namco163Audio>,
+    sunsoft5b: Option<Sunsoft5BAudio>,
+}
+
+impl Nsf {
+    pub fn new(nsf: NsfFile) -> Self {
+        let is_fds = nsf.expansion_chips & FDS != 0;
+
+        let prg = match nsf.bank_init {
+            Some(_) => {
+                // the data is aligned with the 4kb banks using the load address
+                let padding = nsf.load_address as usize & 0xFFF;
+                let mut prg = vec![0; padding];
+                prg.extend_from_slice(&nsf.data);
+                prg.resize(prg.len().next_multiple_of(0x1000), 0);
+                prg
+            }
+            // loaded into the RAM when the track starts
+            None if is_fds => nsf.data,
+            None => {
+                let mut prg = vec![0; 0x8000];
+                let start = nsf.load_address as usize - 0x8000;
+                let len = nsf.data.len().min(prg.len() - start);
+                prg[start..start + len].copy_from_slice(&nsf.data[..len]);
+                prg
+            }
+        };
+
+        let mut mapper = Self {
+            load_address: nsf.load_address,
+            init_address: nsf.init_address,
+            play_address: nsf.play_address,
+            bank_init: nsf.bank_init,
+            expansion_chips: nsf.expansion_chips,
+
+            prg,
+            banks: [0; 8],
+            ram: vec![0; if is_fds { 0xA000 } else { 0x2000 }],
+
+            driver: Vec::new(),
+            ntsc_play_speed: nsf.ntsc_play_speed,
+            pal_play_speed: nsf.pal_play_speed,
+            dendy_play_speed: nsf.dendy_play_speed,
+            region: Region::Ntsc,
+            play_period: 0,
+            play_timer: 0,
+            play_requested: Cell::new(false),
+
+            current_track: nsf.info.starting_track,
+            elapsed_cycles: 0,
+
+            vrc6: None,
+            vrc7: None,
+            fds: None,
+            mmc5: None,
+            mmc5_exram: vec![0; 0x400],
+            mmc5_multiplicand: 0xFF,
+            mmc5_multiplier: 0xFF,
+            namco163: None,
+            sunsoft5b: None,
+
+            info: nsf.info,
+        };
+        mapper.set_region(Region::Ntsc);
+        mapper
+    }
+
+    fn has_chip(&self, chip: u8) -> bool {
+        self.expansion_chips & chip != 0
+    }
+
+    pub fn info(&self) -> &NsfInfo {
+        &self.info
+    }
+
+    pub fn current_track(&self) -> usize {
+        self.current_track
+    }
+
+    /// Prepare the mapper to play `track`, the CPU must be reset after this
+    /// to run the init routine
+    pub fn set_track(&mut self, track: usize) {
+        self.current_track = track % self.info.tracks.len();
+        self.elapsed_cycles = 0;
+        self.play_timer = 0;
+        self.play_requested.set(false);
+
+        self.vrc6 = self.has_chip(VRC6).then(Vrc6Audio::new);
+        self.vrc7 = self.has_chip(VRC7).then(Vrc7Audio::new);
+        self.fds = self.has_chip(FDS).then(|| {
+            let mut fds = FdsAudio::new();
+            // disable the volume envelope and set the envelopes speed like the BIOS
+            fds.write(0x4080, 0x80);
+            fds.write(0x408A, 0xE8);
+            fds
+        });
+        self.mmc5 = self.has_chip(MMC5).then(Mmc5Audio::new);
+        self.mmc5_exram.fill(0);
+        self.namco163 = self.has_chip(NAMCO163).then(Namco163Audio::new);
+        self.sunsoft5b = self.has_chip(SUNSOFT5B).then(Sunsoft5BAudio::new);
+
+        self.ram.fill(0);
+        match self.bank_init {
+            Some(bank_init) => {
+                if self.has_chip(FDS) {
+                    // `$5FF6/$5FF7` use the same initial banks as `$5FFE/$5FFF`
+                    self.switch_bank(0x5FF6, bank_init[6]);
+                    self.switch_bank(0x5FF7, bank_init[7]);
+                }
+                for (i, &bank) in bank_init.iter().enumerate() {
+                    self.switch_bank(0x5FF8 + i as u16, bank);
+                }
+            }
+            None => {
+                self.banks = [0, 1, 2, 3, 4, 5, 6, 7];
+                if self.has_chip(FDS) {
+                    let start = self.load_address as usize - 0x6000;
+                    let len = self.prg.len().min(self.ram.len() - start);
+                    self.ram[start..start + len].copy_from_slice(&self.prg[..len]);
+                }
+            }
+        }
+
+        self.driver = DRIVER.to_vec();
+        self.driver[DRIVER_TRACK_OFFSET] = self.current_track as u8;
+        self.driver[DRIVER_REGION_OFFSET] = match self.region {
+            Region::Ntsc => 0,
+            Region::Pal => 1,
+            Region::Dendy => 2,
+        };
+        self.driver[DRIVER_INIT_OFFSET..DRIVER_INIT_OFFSET + 2]
+            .copy_from_slice(&self.init_address.to_le_bytes());
+        self.driver[DRIVER_PLAY_OFFSET..DRIVER_PLAY_OFFSET + 2]
+            .copy_from_slice(&self.play_address.to_le_bytes());
+    }
+
+    /// The time since the start of the current track
+    pub fn elapsed(&self) -> Duration {
+        Duration::from_secs_f64(self.elapsed_cycles as f64 / self.region.cpu_frequency())
+    }
+
+    fn track_length_and_fade(&self) -> Option<(Duration, Duration)> {
+        let track = &self.info.tracks[self.current_track];
+
+        track
+            .length
+            .map(|length| (length, track.fade.unwrap_or(DEFAULT_FADE)))
+    }
+
+    /// The volume of the output, used to fade out the track after its length
+    pub fn volume(&self) -> f32 {
+        match self.track_length_and_fade() {
+            Some((length, fade)) => {
+                let elapsed = self.elapsed();
+                if elapsed <= length {
+                    1.
+                } else if fade.is_zero() {
+                    0.
+                } else {
+                    (1. - (elapsed - length).as_secs_f32() / fade.as_secs_f32()).max(0.)
+                }
+            }
+            None => 1.,
+        }
+    }
+
+    /// The track ended and faded out, tracks without length never finish
+    pub fn is_track_finished(&self) -> bool {
+        self.track_length_and_fade()
+            .map(|(length, fade)| self.elapsed() >= length + fade)
+            .unwrap_or(false)
+    }
+
+    fn bank_count(&self) -> usize {
+        self.prg.len() / 0x1000
+    }
+
+    fn prg_bank_data(&self, bank: u8) -> &[u8] {
+        let start = (bank as usize % self.bank_count()) * 0x1000;
+
+        &self.prg[start..start + 0x1000]
+    }
+
+    /// ($5FF6-$5FFF)
+    fn switch_bank(&mut self, address: u16, bank: u8) {
+        if self.has_chip(FDS) {
+            // the bank is copied into the RAM
+            let start = (address as usize - 0x5FF6) * 0x1000;
+            let data = self.prg_bank_data(bank).to_vec();
+            self.ram[start..start + 0x1000].copy_from_slice(&data);
+        } else if address >= 0x5FF8 {
+            self.banks[(address - 0x5FF8) as usize] = bank;
+        }
+    }
+
+    fn write_expansion_registers(&mut self, address: u16, data: u8) {
+        if let Some(vrc6) = &mut self.vrc6 {
+            if matches!(address, 0x9000..=0x9003 | 0xA000..=0xA002 | 0xB000..=0xB002) {
+                vrc6.write(address, data);
+            }
+        }
+        if let Some(vrc7) = &mut self.vrc7 {
+            match address {
+                0x9010 => vrc7.write_register_select(data),
+                0x9030 => vrc7.write_register(data),
+                _ => {}
+            }
+        }
+        if let Some(sunsoft5b) = &mut self.sunsoft5b {
+            match address {
+                0xC000 => sunsoft5b.write_register_select(data),
+                0xE000 => sunsoft5b.write_register(data),
+                _ => {}
+            }
+        }
+        if let Some(namco163) = &mut self.namco163 {
+            if address == 0xF800 {
+                namco163.write_address(data);
+            }
+        }
+    }
+}
+
+impl Mapper for Nsf {
+    fn init(&mut self, _prg_count: u16, _is_chr_ram: bool, _chr_count: u16, _prg_ram_size: u32) {
+        // the memory is created from the NSF file
+    }
+
+    fn map_read(&self, address: u16, device: Device) -> MappingResult {
+        match device {
+            Device::Cpu => match address {
+                0x4040..=0x4092 if self.fds.is_some() => {
+                    match self.fds.as_ref().and_then(|fds| fds.read(address)) {
+                        Some(data) => MappingResult::Data(data),
+                        None => MappingResult::Denied,
+                    }
+                }
+                PLAY_FLAG_ADDRESS => MappingResult::Data(self.play_requested.take() as u8),
+                DRIVER_ADDRESS..=PLAY_FLAG_ADDRESS => self
+                    .driver
+                    .get((address - DRIVER_ADDRESS) as usize)
+                    .map(|&data| MappingResult::Data(data))
+                    .unwrap_or(MappingResult::Denied),
+                0x4800..=0x4FFF if self.namco163.is_some() => {
+                    MappingResult::Data(self.namco163.as_ref().unwrap().read_data())
+                }
+                0x5000..=0x5015 if self.mmc5.is_some() => {
+                    match self.mmc5.as_ref().and_then(|mmc5| mmc5.read(address)) {
+                        Some(data) => MappingResult::Data(data),
+                        None => MappingResult::Denied,
+                    }
+                }
+                0x5205 if self.mmc5.is_some() => MappingResult::Data(
+                    (self.mmc5_multiplicand as u16 * self.mmc5_multiplier as u16) as u8,
+                ),
+                0x5206 if self.mmc5.is_some() => MappingResult::Data(
+                    ((self.mmc5_multiplicand as u16 * self.mmc5_multiplier as u16) >> 8) as u8,
+                ),
+                0x5C00..=0x5FF5 if self.mmc5.is_some() => {
+                    MappingResult::Data(self.mmc5_exram[address as usize - 0x5C00])
+                }
+                // the reset vector always points to the driver
+                0xFFFC => MappingResult::Data(DRIVER_ADDRESS as u8),
+                0xFFFD => MappingResult::Data((DRIVER_ADDRESS >> 8) as u8),
+                0x6000..=0xFFFF if self.has_chip(FDS) => {
+                    MappingResult::Data(self.ram[address as usize - 0x6000])
+                }
+                0x6000..=0x7FFF => MappingResult::Data(self.ram[address as usize - 0x6000]),
+                0x8000..=0xFFFF => {
+                    let bank = self.banks[(address as usize - 0x8000) >> 12];
+
+                    MappingResult::Data(self.prg_bank_data(bank)[address as usize & 0xFFF])
+                }
+                0x4020..=0x5FFF => MappingResult::Denied,
+                _ => unreachable!(),
+            },
+            // CHR RAM
+            Device::Ppu => MappingResult::Allowed(address as usize & 0x1FFF),
+        }
+    }
+
+    fn map_write(&mut self, address: u16, data: u8, device: Device) -> MappingResult {
+        match device {
+            Device::Cpu => {
+                match address {
+                    0x4040..=0x408A if self.fds.is_some() => {
+                        self.fds.as_mut().unwrap().write(address, data);
+                    }
+                    0x4800..=0x4FFF if self.namco163.is_some() => {
+                        self.namco163.as_mut().unwrap().write_data(data);
+                    }
+                    0x5000..=0x5015 if self.mmc5.is_some() => {
+                        self.mmc5.as_mut().unwrap().write(address, data);
+                    }
+                    0x5205 if self.mmc5.is_some() => self.mmc5_multiplicand = data,
+                    0x5206 if self.mmc5.is_some() => self.mmc5_multiplier = data,
+                    0x5C00..=0x5FF5 if self.mmc5.is_some() => {
+                        self.mmc5_exram[address as usize - 0x5C00] = data
+                    }
+                    0x5FF6..=0x5FFF if self.bank_init.is_some() => {
+                        self.switch_bank(address, data);
+                    }
+                    0x6000..=0xFFFF if self.has_chip(FDS) => {
+                        self.ram[address as usize - 0x6000] = data;
+                    }
+                    0x6000..=0x7FFF => self.ram[address as usize - 0x6000] = data,
+                    _ => {}
+                }
+
+                if address >= 0x8000 {
+                    self.write_expansion_registers(address, data);
+                }
+
+                MappingResult::Denied
+            }
+            Device::Ppu => MappingResult::Allowed(address as usize & 0x1FFF),
+        }
+    }
+
+    fn clock(&mut self) {
+        self.elapsed_cycles += 1;
+
+        self.play_timer += 1;
+        if self.play_timer >= self.play_period {
+            self.play_timer = 0;
+            self.play_requested.set(true);
+        }
+
+        if let Some(vrc6) = &mut self.vrc6 {
+            vrc6.timer_clock();
+        }
+        if let Some(vrc7) = &mut self.vrc7 {
+            vrc7.timer_clock();
+        }
+        if let Some(fds) = &mut self.fds {
+            fds.timer_clock();
+        }
+        if let Some(mmc5) = &mut self.mmc5 {
+            mmc5.timer_clock();
+        }
+        if let Some(namco163) = &mut self.namco163 {
+            namco163.timer_clock();
+        }
+        if let Some(sunsoft5b) = &mut self.sunsoft5b {
+            sunsoft5b.timer_clock();
+        }
+    }
+
+    fn expansion_audio_output(&mut self) -> f32 {
+        let mut output = 0.;
+
+        if let Some(vrc6) = &mut self.vrc6 {
+            output += ExpansionAudioChip::Vrc6.mix(vrc6.get_output());
+        }
+        if let Some(vrc7) = &mut self.vrc7 {
+            output += ExpansionAudioChip::Vrc7.mix(vrc7.get_output());
+        }
+        if let Some(fds) = &mut self.fds {
+            output += ExpansionAudioChip::Fds.mix(fds.get_output());
+        }
+        if let Some(mmc5) = &mut self.mmc5 {
+            output += ExpansionAudioChip::Mmc5.mix(mmc5.get_output());
+        }
+        if let Some(namco163) = &mut self.namco163 {
+            output += ExpansionAudioChip::Namco163.mix(namco163.get_output());
+        }
+        if let Some(sunsoft5b) = &mut self.sunsoft5b {
+            output += ExpansionAudioChip::Sunsoft5B.mix(sunsoft5b.get_output());
+        }
+
+        output
+    }
+
+    fn nsf(&self) -> Option<&Nsf> {
+        Some(self)
+    }
+
+    fn nsf_mut(&mut self) -> Option<&mut Nsf> {
+        Some(self)
+    }
+
+    fn save_state_size(&self) -> usize {
+        bincode::serialized_size(self).unwrap() as usize
+    }
+
+    fn save_state(&self) -> Vec<u8> {
+        bincode::serialize(self).unwrap()
+    }
+
+    fn load_state(&mut self, data: Vec<u8>) {
+        let mut state: Self = bincode::deserialize(&data).unwrap();
+
+        // the region depends on the emulator and not the state
+        state.region = self.region;
+        state.play_period = self.play_period;
+
+        let _ = std::mem::replace(self, state);
+    }
+
+    fn set_region(&mut self, region: Region) {
+        self.region = region;
+
+        let play_speed = match region {
+            Region::Ntsc => self.ntsc_play_speed,
+            Region::Pal => self.pal_play_speed,
+            Region::Dendy => self.dendy_play_speed,
+        };
+        self.play_period = (play_speed as f64 * region.cpu_frequency() / 1E6) as u32;
+
+        // the init routine gets the region, so restart the track
+        self.set_track(self.current_track);
+    }
+}
@@ -269,8 +269,7 @@ mod mappers_tests {
         )
     }
 
-    // FIXME: this test is still failing
-    // #[test]
+    #[test]
     fn holy_mapperel_m28_p512k_test() -> Result<(), TestError> {
         run_holy_mapperel_test(
             "../test_roms/holy-mapperel-bin-0.02/testroms/M28_P512K.nes",
@@ -278,8 +277,7 @@ mod mappers_tests {
         )
     }
 
-    // FIXME: this test is still failing
-    // #[test]
+    #[test]
     fn holy_mapperel_m28_p512k_cr32k_test() -> Result<(), TestError> {
         run_holy_mapperel_test(
             "../test_roms/holy-mapperel-bin-0.02/testroms/M28_P512K_CR32K.nes",
@@ -287,8 +285,7 @@ mod mappers_tests {
         )
     }
 
-    // FIXME: this test is still failing
-    // #[test]
+    #[test]
     fn holy_mapperel_m34_p128k_cr8k_h_test() -> Result<(), TestError> {
         run_holy_mapperel_test(
             "../test_roms/holy-mapperel-bin-0.02/testroms/M34_P128K_CR8K_H.nes",
@@ -296,8 +293,7 @@ mod mappers_tests {
         )
     }
 
-    // FIXME: this test is still failing
-    // #[test]
+    #[test]
     fn holy_mapperel_m34_p128k_h_test() -> Result<(), TestError> {
         run_holy_mapperel_test(
             "../test_roms/holy-mapperel-bin-0.02/testroms/M34_P128K_H.nes",
@@ -313,8 +309,7 @@ mod mappers_tests {
         )
     }
 
-    // FIXME: this test is still failing
-    // #[test]
+    #[test]
     fn holy_mapperel_m69_p128k_c64k_s8k_test() -> Result<(), TestError> {
         run_holy_mapperel_test(
             "../test_roms/holy-mapperel-bin-0.02/testroms/M69_P128K_C64K_S8K.nes",
@@ -322,8 +317,7 @@ mod mappers_tests {
         )
     }
 
-    // FIXME: this test is still failing
-    // #[test]
+    #[test]
     fn holy_mapperel_m69_p128k_c64k_w8k_test() -> Result<(), TestError> {
         run_holy_mapperel_test(
             "../test_roms/holy-mapperel-bin-0.02/testroms/M69_P128K_C64K_W8K.nes",
@@ -340,8 +334,7 @@ mod mappers_tests {
         )
     }
 
-    // FIXME: this test is still failing
-    // #[test]
+    #[test]
     fn holy_mapperel_m118_p128k_c64k_test() -> Result<(), TestError> {
         run_holy_mapperel_test(
             "../test_roms/holy-mapperel-bin-0.02/testroms/M118_P128K_C64K.nes",
@@ -349,8 +342,7 @@ mod mappers_tests {
         )
     }
 
-    // FIXME: this test is still failing
-    // #[test]
+    #[test]
     fn holy_mapperel_m180_p128k_cr8k_h_test() -> Result<(), TestError> {
         run_holy_mapperel_test(
             "../test_roms/holy-mapperel-bin-0.02/testroms/M180_P128K_CR8K_H.nes",
@@ -358,8 +350,7 @@ mod mappers_tests {
         )
     }
 
-    // FIXME: this test is still failing
-    // #[test]
+    #[test]
     fn holy_mapperel_m180_p128k_h_test() -> Result<(), TestError> {
         run_holy_mapperel_test(
             "../test_roms/holy-mapperel-bin-0.02/testroms/M180_P128K_H.nes",
@@ -0,0 +1,122 @@
+use serde::{Deserialize, Serialize};
+
+/// The IRQ counter used in Konami VRC4, VRC6 and VRC7 mappers.
+///
+/// It is clocked on every CPU cycle, in scanline mode, a prescaler divides
+/// the CPU clock by `113.667` (341 PPU dots / 3) to approximate scanlines
+#[derive(Serialize, Deserialize)]
+pub struct VrcIrq {
+    latch: u8,
+    counter: u8,
+    prescaler: i16,
+
+    /// 7  bit  0
+    /// ---- ----
+    /// xxxx xMEA
+    ///       |||
+    ///       ||+- IRQ Enable after acknowledgement (see IRQ Acknowledge)
+    ///       |+-- IRQ Enable (1 = enabled)
+    ///       +--- IRQ Mode (1 = cycle mode, 0 = scanline mode)
+    enable_after_ack: bool,
+    enabled: bool,
+    cycle_mode: bool,
+
+    irq_pin: bool,
+    is_irq_pin_changed: bool,
+}
+
+impl VrcIrq {
+    pub fn new() -> Self {
+        Self {
+            latch: 0,
+            counter: 0,
+            prescaler: 341,
+
+            enable_after_ack: false,
+            enabled: false,
+            cycle_mode: false,
+
+            irq_pin: false,
+            is_irq_pin_changed: false,
+        }
+    }
+
+    fn set_irq_pin(&mut self, state: bool) {
+        if self.irq_pin != state {
+            self.irq_pin = state;
+            self.is_irq_pin_changed = true;
+        }
+    }
+
+    pub fn write_latch(&mut self, data: u8) {
+        self.latch = data;
+    }
+
+    /// VRC4 writes the latch in two 4bit parts
+    pub fn write_latch_low(&mut self, data: u8) {
+        self.latch = (self.latch & 0xF0) | (data & 0xF);
+    }
+
+    /// VRC4 writes the latch in two 4bit parts
+    pub fn write_latch_high(&mut self, data: u8) {
+        self.latch = (self.latch & 0x0F) | (data & 0xF) << 4;
+    }
+
+    pub fn write_control(&mut self, data: u8) {
+        self.enable_after_ack = data & 1 != 0;
+        self.enabled = data & 2 != 0;
+        self.cycle_mode = data & 4 != 0;
+
+        if self.enabled {
+            self.counter = self.latch;
+            self.prescaler = 341;
+        }
+
+        self.set_irq_pin(false);
+    }
+
+    pub fn acknowledge(&mut self) {
+        self.enabled = self.enable_after_ack;
+
+        self.set_irq_pin(false);
+    }
+
+    fn clock_counter(&mut self) {
+        if self.counter == 0xFF {
+            self.counter = self.latch;
+            self.set_irq_pin(true);
+        } else {
+            self.counter += 1;
+        }
+    }
+
+    /// clock on every CPU cycle
+    pub fn clock(&mut self) {
+        if !self.enabled {
+            return;
+        }
+
+        if self.cycle_mode {
+            self.clock_counter();
+        } else {
+            self.prescaler -= 3;
+
+            if self.prescaler <= 0 {
+                self.prescaler += 341;
+                self.clock_counter();
+            }
+        }
+    }
+
+    pub fn is_irq_pin_state_changed_requested(&self) -> bool {
+        self.is_irq_pin_changed
+    }
+
+    pub fn irq_pin_state(&self) -> bool {
+        self.irq_pin
+    }
+
+    pub fn clear_irq_request_pin(&mut self) {
+        self.is_irq_pin_changed = false;
+    }
+}
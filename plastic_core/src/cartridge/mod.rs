@@ -1,45 +1,107 @@
+#[cfg(feature = "archives")]
+mod archive;
 mod error;
+mod fds_image;
+mod game_db;
 mod mapper;
 mod mappers;
+mod nsf_file;
+mod patch;
+mod unif_file;
 
 mod tests;
 
 pub use error::CartridgeError;
 use error::SramError;
+pub use game_db::GameInfo;
 use mapper::{Mapper, MappingResult};
 use mappers::{
-    Mapper0, Mapper1, Mapper10, Mapper11, Mapper12, Mapper2, Mapper3, Mapper4, Mapper66, Mapper7,
-    Mapper9,
+    Fds, Mapper0, Mapper1, Mapper10, Mapper11, Mapper111, Mapper12, Mapper140, Mapper16, Mapper180,
+    Mapper19, Mapper2, Mapper21, Mapper232, Mapper24, Mapper28, Mapper3, Mapper30, Mapper34,
+    Mapper4, Mapper5, Mapper66, Mapper69, Mapper7, Mapper71, Mapper79, Mapper85, Mapper9, Nsf,
 };
+pub use nsf_file::{NsfInfo, NsfTrack};
 
 use crate::common::{
-    interconnection::CPUIrqProvider,
+    interconnection::{CPUIrqProvider, ExpansionAudioProvider},
     save_state::{Savable, SaveError},
-    Bus, Device, MirroringMode, MirroringProvider,
+    Bus, Device, MirroringMode, MirroringProvider, NametableMapping, Region,
 };
+use nsf_file::NsfFile;
 use std::{
     fs::File,
-    io::{Read, Seek, SeekFrom, Write},
-    path::Path,
+    io::{Read, Write},
+    path::{Path, PathBuf},
 };
+use unif_file::UnifFile;
+
+/// the name of the Famicom Disk System BIOS file
+const FDS_BIOS_FILE_NAME: &str = "disksys.rom";
+const FDS_BIOS_SIZE: usize = 0x2000;
+
+/// The console the game is made for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConsoleType {
+    Nes,
+    /// Nintendo Vs. System, with the PPU and hardware types from the NES 2.0 header
+    VsSystem {
+        ppu_type: u8,
+        hardware_type: u8,
+    },
+    Playchoice10,
+    /// Extended console type from the NES 2.0 header (famiclones, VT0x, etc.)
+    Extended(u8),
+}
+
+/// The CPU/PPU timing the game is made for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimingMode {
+    Ntsc,
+    Pal,
+    /// The game works on all regions
+    MultipleRegion,
+    Dendy,
+}
+
+impl TimingMode {
+    /// The region to run the game in, games that work on all regions run in NTSC
+    fn region(&self) -> Region {
+        match self {
+            TimingMode::Pal => Region::Pal,
+            TimingMode::Dendy => Region::Dendy,
+            TimingMode::Ntsc | TimingMode::MultipleRegion => Region::Ntsc,
+        }
+    }
+}
+
+/// Sizes larger than what the NES 2.0 header can express without the
+/// exponent-multiplier notation are not supported
+const MAX_PRG_ROM_SIZE: usize = 0xFFF * 0x4000;
+const MAX_CHR_ROM_SIZE: usize = 0xFFF * 0x2000;
 
 #[allow(dead_code)]
 struct INesHeader {
-    // in 16kb units
-    prg_rom_size: u16,
-    // in 8kb units
-    chr_rom_size: u16,
+    // in bytes
+    prg_rom_size: usize,
+    // in bytes
+    chr_rom_size: usize,
     is_chr_ram: bool,
     hardwired_mirroring_vertical: bool,
     has_prg_ram_battery: bool,
     contain_trainer_data: bool,
     use_hardwaired_4_screen_mirroring: bool,
+    console_type: ConsoleType,
+    timing_mode: TimingMode,
     mapper_id: u16,
     submapper_id: u8,
     prg_wram_size: u32,
     prg_sram_size: u32,
     chr_wram_size: u32,
     chr_sram_size: u32,
+    is_nes2: bool,
+    /// number of miscellaneous ROMs after the CHR data
+    misc_rom_count: u8,
+    default_expansion_device: u8,
 }
 
 impl INesHeader {
@@ -47,9 +109,8 @@ impl INesHeader {
         // decode header
         Self::check_magic(&header[0..4])?;
 
-        let prg_size_low = header[4] as u16;
-        let chr_size_low = header[5] as u16;
-        let is_chr_ram = chr_size_low == 0;
+        let prg_size_low = header[4];
+        let chr_size_low = header[5];
 
         let hardwired_mirroring_vertical = header[6] & 1 != 0;
         header[6] >>= 1;
@@ -61,7 +122,7 @@ impl INesHeader {
         header[6] >>= 1;
         let mapper_id_low = (header[6] & 0xF) as u16;
 
-        // let mut console_type = header[7] & 0x3;
+        let mut console_type = header[7] & 0x3;
         header[7] >>= 2;
         let ines_2_ident = header[7] & 0x3;
         header[7] >>= 2;
@@ -76,10 +137,13 @@ impl INesHeader {
             }
 
             let prg_ram_size;
+            let mut timing_mode = TimingMode::Ntsc;
 
             if !is_archaic_ines {
                 prg_ram_size = if header[8] == 0 { 1 } else { header[8] };
-                // let ntcs_tv_system = header[9] & 1 == 0;
+                if header[9] & 1 != 0 {
+                    timing_mode = TimingMode::Pal;
+                }
 
                 if header[9] >> 1 != 0 {
                     return Err(CartridgeError::HeaderError);
@@ -89,67 +153,244 @@ impl INesHeader {
                 // let board_has_bus_conflict = (header[10] >> 5) & 1 != 0;
             } else {
                 // ignore `header[7]` data
-                // console_type = 0;
+                console_type = 0;
                 mapper_id_middle = 0;
 
                 prg_ram_size = 1;
             }
 
+            // iNES does not specify if the PRG RAM is battery backed or not,
+            // so it is all battery backed if the cartridge has a battery
+            let prg_ram_size = prg_ram_size as u32 * 0x2000;
+            let (prg_wram_size, prg_sram_size) = if has_prg_ram_battery {
+                (0, prg_ram_size)
+            } else {
+                (prg_ram_size, 0)
+            };
+
             Ok(Self {
-                prg_rom_size: prg_size_low,
-                chr_rom_size: chr_size_low,
-                is_chr_ram,
+                prg_rom_size: prg_size_low as usize * 0x4000,
+                chr_rom_size: chr_size_low as usize * 0x2000,
+                is_chr_ram: chr_size_low == 0,
                 hardwired_mirroring_vertical,
                 has_prg_ram_battery,
                 contain_trainer_data,
                 use_hardwaired_4_screen_mirroring,
+                console_type: match console_type {
+                    1 => ConsoleType::VsSystem {
+                        ppu_type: 0,
+                        hardware_type: 0,
+                    },
+                    2 => ConsoleType::Playchoice10,
+                    _ => ConsoleType::Nes,
+                },
+                timing_mode,
                 mapper_id: mapper_id_middle << 4 | mapper_id_low,
                 submapper_id: 0,
-                prg_wram_size: prg_ram_size as u32 * 0x2000,
-                prg_sram_size: prg_ram_size as u32 * 0x2000,
+                prg_wram_size,
+                prg_sram_size,
                 chr_wram_size: 0x2000, // can only use 8kb
-                chr_sram_size: 0x2000,
+                chr_sram_size: 0,
+                is_nes2: false,
+                misc_rom_count: 0,
+                default_expansion_device: 0,
             })
         } else {
             let mapper_id_high = (header[8] & 0xF) as u16;
             header[8] >>= 4;
             let submapper_id = header[8] & 0xF;
 
-            let prg_size_high = (header[9] & 0xF) as u16;
-            let chr_size_high = ((header[9] >> 4) & 0xF) as u16;
+            let prg_size_high = header[9] & 0xF;
+            let chr_size_high = (header[9] >> 4) & 0xF;
+
+            let prg_rom_size = Self::nes2_rom_size(prg_size_low, prg_size_high, 0x4000)?;
+            let chr_rom_size = Self::nes2_rom_size(chr_size_low, chr_size_high, 0x2000)?;
+            if prg_rom_size > MAX_PRG_ROM_SIZE || chr_rom_size > MAX_CHR_ROM_SIZE {
+                return Err(CartridgeError::HeaderError);
+            }
 
-            let shift_size = (header[10] & 0xF) as u32;
-            let prg_wram_size_bytes = if shift_size != 0 { 64 << shift_size } else { 0 };
-            header[10] >>= 4;
-            let shift_size = (header[10] & 0xF) as u32;
-            let prg_sram_size_bytes = if shift_size != 0 { 64 << shift_size } else { 0 };
+            let prg_wram_size_bytes = Self::nes2_ram_size(header[10] & 0xF);
+            let prg_sram_size_bytes = Self::nes2_ram_size(header[10] >> 4);
+            let chr_wram_size_bytes = Self::nes2_ram_size(header[11] & 0xF);
+            let chr_sram_size_bytes = Self::nes2_ram_size(header[11] >> 4);
 
-            let shift_size = (header[11] & 0xF) as u32;
-            let chr_wram_size_bytes = if shift_size != 0 { 64 << shift_size } else { 0 };
-            header[11] >>= 4;
-            let shift_size = (header[11] & 0xF) as u32;
-            let chr_sram_size_bytes = if shift_size != 0 { 64 << shift_size } else { 0 };
+            let console_type = match console_type {
+                0 => ConsoleType::Nes,
+                1 => ConsoleType::VsSystem {
+                    ppu_type: header[13] & 0xF,
+                    hardware_type: header[13] >> 4,
+                },
+                2 => ConsoleType::Playchoice10,
+                _ => ConsoleType::Extended(header[13] & 0xF),
+            };
 
-            // TODO: implement the rest
+            let timing_mode = match header[12] & 0x3 {
+                0 => TimingMode::Ntsc,
+                1 => TimingMode::Pal,
+                2 => TimingMode::MultipleRegion,
+                _ => TimingMode::Dendy,
+            };
 
             Ok(Self {
-                prg_rom_size: prg_size_high << 8 | prg_size_low,
-                chr_rom_size: chr_size_high << 8 | chr_size_low,
-                is_chr_ram,
+                prg_rom_size,
+                chr_rom_size,
+                is_chr_ram: chr_rom_size == 0,
                 hardwired_mirroring_vertical,
                 has_prg_ram_battery,
                 contain_trainer_data,
                 use_hardwaired_4_screen_mirroring,
+                console_type,
+                timing_mode,
                 mapper_id: mapper_id_high << 8 | mapper_id_middle << 4 | mapper_id_low,
                 submapper_id,
                 prg_wram_size: prg_wram_size_bytes,
                 prg_sram_size: prg_sram_size_bytes,
                 chr_wram_size: chr_wram_size_bytes,
                 chr_sram_size: chr_sram_size_bytes,
+                is_nes2: true,
+                misc_rom_count: header[14] & 0x3,
+                default_expansion_device: header[15] & 0x3F,
             })
         }
     }
 
+    /// The size in bytes of a ROM from the LSB byte and MSB nibble of its size,
+    /// if the MSB nibble is `0xF`, the size is `2^E * (MM * 2 + 1)` where the LSB
+    /// byte is `EEEEEEMM`, otherwise it is a count of `unit` sized banks
+    fn nes2_rom_size(lsb: u8, msb: u8, unit: usize) -> Result<usize, CartridgeError> {
+        if msb == 0xF {
+            let exponent = (lsb >> 2) as u32;
+            let multiplier = (lsb & 0x3) as usize * 2 + 1;
+
+            1usize
+                .checked_shl(exponent)
+                .and_then(|size| size.checked_mul(multiplier))
+                .ok_or(CartridgeError::HeaderError)
+        } else {
+            Ok(((msb as usize) << 8 | lsb as usize) * unit)
+        }
+    }
+
+    /// The size in bytes of a RAM from its shift count, `0` means no RAM
+    fn nes2_ram_size(shift_count: u8) -> u32 {
+        if shift_count != 0 {
+            64 << shift_count
+        } else {
+            0
+        }
+    }
+
+    /// The number of 16kb PRG ROM banks, the last bank may not be full
+    fn prg_rom_count(&self) -> u16 {
+        self.prg_rom_size.div_ceil(0x4000) as u16
+    }
+
+    /// The number of 8kb CHR ROM or RAM banks, the last bank may not be full
+    fn chr_count(&self) -> u16 {
+        if self.is_chr_ram {
+            self.chr_ram_size().div_ceil(0x2000) as u16
+        } else {
+            self.chr_rom_size.div_ceil(0x2000) as u16
+        }
+    }
+
+    /// The size of all PRG RAM in bytes, the battery backed part (if any) comes first.
+    ///
+    /// Rounded up to 8kb as mappers map PRG RAM in 8kb banks.
+    fn prg_ram_size(&self) -> usize {
+        ((self.prg_sram_size + self.prg_wram_size) as usize).next_multiple_of(0x2000)
+    }
+
+    /// The size of all CHR RAM in bytes, the battery backed part (if any) comes first.
+    ///
+    /// Some NES 2.0 files do not specify the CHR RAM size, so use 8kb in that case.
+    fn chr_ram_size(&self) -> usize {
+        let size = (self.chr_sram_size + self.chr_wram_size) as usize;
+
+        // GTROM uses 32kb for CHR and nametables, and UNROM-512 has 32kb
+        // if not specified (iNES 1.0)
+        let size = match self.mapper_id {
+            30 if size == 0 => 0x8000,
+            111 => size.max(0x8000),
+            _ => size,
+        };
+
+        if size == 0 {
+            0x2000
+        } else {
+            size.next_multiple_of(0x2000)
+        }
+    }
+
+    /// The size of the CHR RAM placed after the CHR ROM, for boards that have both
+    fn mixed_chr_ram_size(&self) -> usize {
+        // TQROM has 8kb, most files do not specify the CHR RAM size
+        if !self.is_chr_ram && self.mapper_id == 119 {
+            0x2000
+        } else {
+            0
+        }
+    }
+
+    /// The size of the CHR RAM part that is battery backed
+    fn chr_nvram_size(&self) -> usize {
+        if self.is_chr_ram && self.has_prg_ram_battery {
+            self.chr_sram_size as usize
+        } else {
+            0
+        }
+    }
+
+    /// The Famicom Disk System is not an iNES file, but it is handled like a
+    /// cartridge with mapper 20 (reserved for it), 32kb of PRG RAM and 8kb of CHR RAM
+    fn fds() -> Self {
+        Self {
+            is_chr_ram: true,
+            mapper_id: 20,
+            prg_wram_size: 0x8000,
+            prg_sram_size: 0,
+            chr_wram_size: 0x2000,
+            ..Self::empty()
+        }
+    }
+
+    /// NSF files are not iNES files, the player only needs 8kb of CHR RAM for the PPU,
+    /// the program and RAM are stored in the [`Nsf`] mapper
+    fn nsf(timing_mode: TimingMode) -> Self {
+        Self {
+            chr_wram_size: 0x2000,
+            is_chr_ram: true,
+            timing_mode,
+            ..Self::empty()
+        }
+    }
+
+    /// UNIF files are handled with the iNES header of the mapper emulating the board,
+    /// with 8kb of PRG RAM like iNES 1.0 files
+    fn unif(unif: &UnifFile) -> Self {
+        let (prg_wram_size, prg_sram_size) = if unif.has_battery {
+            (0, 0x2000)
+        } else {
+            (0x2000, 0)
+        };
+
+        Self {
+            prg_rom_size: unif.prg_data.len(),
+            chr_rom_size: unif.chr_data.len(),
+            is_chr_ram: unif.chr_data.is_empty(),
+            hardwired_mirroring_vertical: unif.hardwired_mirroring_vertical.unwrap_or(false),
+            has_prg_ram_battery: unif.has_battery,
+            use_hardwaired_4_screen_mirroring: unif.four_screen_mirroring,
+            timing_mode: unif.timing_mode,
+            mapper_id: unif.mapper_id,
+            submapper_id: unif.submapper_id,
+            prg_wram_size,
+            prg_sram_size,
+            chr_wram_size: 0x2000,
+            ..Self::empty()
+        }
+    }
+
     fn empty() -> Self {
         Self::from_bytes([0x4E, 0x45, 0x53, 0x1A, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]).unwrap()
     }
@@ -170,108 +411,386 @@ pub struct Cartridge {
     header: INesHeader,
 
     _trainer_data: Vec<u8>,
+    _misc_rom_data: Vec<u8>,
     pub(crate) prg_data: Vec<u8>,
     pub(crate) chr_data: Vec<u8>,
     prg_ram_data: Vec<u8>,
+    /// the disk sides of the Famicom Disk System image before any writes,
+    /// used to save the changes
+    original_disk_sides: Vec<Vec<u8>>,
 
     mapper: Box<dyn Mapper>,
+    /// the game database entry of the game, if found
+    game_info: Option<GameInfo>,
 
     is_empty: bool,
 }
 
 impl Cartridge {
-    // TODO: not sure if it should consume the file or not
+    /// Load a cartridge from a `.nes`, `.unf`, `.fds`, `.nsf` or `.nsfe` file, or from the first
+    /// of these files inside a `.zip`, `.7z` or `.gz` archive.
+    ///
+    /// The save files are stored next to `file_path`, and if there is a patch file with
+    /// the same name and the `.ips`, `.bps` or `.ups` extension, it is applied to the ROM.
     pub fn from_file<P: AsRef<Path>>(file_path: P) -> Result<Self, CartridgeError> {
-        if let Some(extension) = file_path.as_ref().extension() {
+        let patch_path = Self::find_patch_file(file_path.as_ref());
+
+        Self::from_file_with_patches(file_path, patch_path.as_slice())
+    }
+
+    /// Same as [`Cartridge::from_file`], but applies the IPS/BPS/UPS patches at
+    /// `patch_paths` in order, instead of the patch file next to the ROM.
+    pub fn from_file_with_patches<P: AsRef<Path>, Q: AsRef<Path>>(
+        file_path: P,
+        patch_paths: &[Q],
+    ) -> Result<Self, CartridgeError> {
+        let file_path = file_path.as_ref();
+        let patches = patch_paths
+            .iter()
+            .map(|path| {
+                println!("Applying patch {:?}", path.as_ref());
+                Self::read_file(path.as_ref())
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let read_patched_file = || patch::apply_patches(Self::read_file(file_path)?, &patches);
+
+        if let Some(extension) = file_path.extension() {
             if extension == "nes" {
-                let mut file = File::open(file_path.as_ref())?;
-
-                let mut header = [0; 16];
-                file.read_exact(&mut header)?;
-
-                // decode header
-                let header = INesHeader::from_bytes(header)?;
-
-                let sram_data = if header.has_prg_ram_battery {
-                    // try to load old save data
-                    if let Ok(data) =
-                        Self::load_sram_file(file_path.as_ref(), header.prg_sram_size as usize)
-                    {
-                        data
-                    } else {
-                        vec![0; header.prg_sram_size as usize]
-                    }
-                } else {
-                    vec![0; header.prg_wram_size as usize]
-                };
+                Self::from_ines_bytes(&read_patched_file()?, file_path)
+            } else if extension == "unf" || extension == "unif" {
+                Self::from_unif_bytes(&read_patched_file()?, file_path)
+            } else if extension == "nsf" || extension == "nsfe" {
+                Self::from_nsf_bytes(&read_patched_file()?, file_path)
+            } else if extension == "fds" {
+                let bios = Self::read_fds_bios(Self::find_fds_bios(file_path)?)?;
+
+                Self::from_fds_bytes(&read_patched_file()?, bios, file_path)
+            } else if cfg!(feature = "archives")
+                && (extension == "zip" || extension == "7z" || extension == "gz")
+            {
+                Self::from_bytes_with_patches(&Self::read_file(file_path)?, file_path, &patches)
+            } else {
+                Err(CartridgeError::ExtensionError)
+            }
+        } else {
+            Err(CartridgeError::ExtensionError)
+        }
+    }
 
-                println!("mapper {}", header.mapper_id);
+    /// Load a cartridge from the content of a file, the format is detected from the data.
+    ///
+    /// `identity` is the path used for the save files, as if the data was loaded
+    /// from that file, the Famicom Disk System BIOS is also searched for next to it.
+    pub fn from_bytes<P: AsRef<Path>>(data: &[u8], identity: P) -> Result<Self, CartridgeError> {
+        Self::from_bytes_with_patches(data, identity.as_ref(), &[])
+    }
 
-                // initialize the mapper first, so that if it is not supported yet,
-                // panic
-                let mapper = Self::get_mapper(&header)?;
+    fn from_bytes_with_patches(
+        data: &[u8],
+        identity: &Path,
+        patches: &[Vec<u8>],
+    ) -> Result<Self, CartridgeError> {
+        #[cfg(feature = "archives")]
+        if archive::is_archive(data) {
+            let rom = archive::extract_rom(data)?;
+            // name the save files after the archive, `game.zip` and `game.nes.gz`
+            // both become `game.nes.sav`
+            return Self::from_bytes_with_patches(&rom, &identity.with_extension(""), patches);
+        }
 
-                let mut trainer_data = Vec::new();
+        let data = patch::apply_patches(data.to_vec(), patches)?;
 
-                // read training data if present
-                if header.contain_trainer_data {
-                    trainer_data.resize(512, 0);
-                    file.read_exact(&mut trainer_data)?;
-                }
+        if UnifFile::is_unif(&data) {
+            Self::from_unif_bytes(&data, identity)
+        } else if NsfFile::is_nsf(&data) {
+            Self::from_nsf_bytes(&data, identity)
+        } else if fds_image::is_fds_image(&data) {
+            let bios = Self::read_fds_bios(Self::find_fds_bios(identity)?)?;
 
-                // read PRG data
-                let mut prg_data = vec![0; (header.prg_rom_size as usize) * 16 * 1024];
-                file.read_exact(&mut prg_data)?;
-
-                // read CHR data
-                let chr_data = if !header.is_chr_ram {
-                    let mut data = vec![0; (header.chr_rom_size as usize) * 8 * 1024];
-                    file.read_exact(&mut data)?;
-
-                    data
-                } else {
-                    // TODO: there is no way of knowing if we are using CHR WRAM or SRAM
-                    let ram_size = header.chr_wram_size;
-
-                    vec![0; ram_size as usize]
-                };
-
-                // there are missing parts
-                let current = file.stream_position()?;
-                let end = file.seek(SeekFrom::End(0))?;
-                if current != end {
-                    Err(CartridgeError::TooLargeFile(end - current))
-                } else {
-                    Ok(Self {
-                        file_path: file_path.as_ref().to_path_buf().into_boxed_path(),
-                        header,
-                        _trainer_data: trainer_data,
-                        prg_data,
-                        chr_data,
-                        prg_ram_data: sram_data,
-                        mapper,
-
-                        is_empty: false,
-                    })
-                }
+            Self::from_fds_bytes(&data, bios, identity)
+        } else {
+            Self::from_ines_bytes(&data, identity)
+        }
+    }
+
+    /// The patch file with the same name as the ROM, if any
+    fn find_patch_file(file_path: &Path) -> Option<PathBuf> {
+        ["ips", "bps", "ups"]
+            .iter()
+            .map(|extension| file_path.with_extension(extension))
+            .find(|path| path.is_file())
+    }
+
+    fn read_file(file_path: &Path) -> Result<Vec<u8>, CartridgeError> {
+        let mut data = Vec::new();
+        File::open(file_path)?.read_to_end(&mut data)?;
+
+        Ok(data)
+    }
+
+    fn from_ines_bytes(data: &[u8], file_path: &Path) -> Result<Self, CartridgeError> {
+        let mut file = data;
+
+        let mut header = [0; 16];
+        file.read_exact(&mut header)?;
+
+        // decode header
+        let mut header = INesHeader::from_bytes(header)?;
+
+        let mut trainer_data = Vec::new();
+
+        // read training data if present
+        if header.contain_trainer_data {
+            trainer_data.resize(512, 0);
+            file.read_exact(&mut trainer_data)?;
+        }
+
+        // read PRG data
+        let mut prg_data = vec![0; header.prg_rom_size];
+        file.read_exact(&mut prg_data)?;
+
+        // read CHR data
+        let mut chr_rom_data = vec![
+            0;
+            if header.is_chr_ram {
+                0
             } else {
-                Err(CartridgeError::ExtensionError)
+                header.chr_rom_size
             }
+        ];
+        file.read_exact(&mut chr_rom_data)?;
+
+        // iNES 1.0 headers are not reliable, use the game database if the game is there
+        let game_info = if !header.is_nes2 {
+            game_db::lookup(file_path, &[prg_data.as_slice(), &chr_rom_data].concat()).map(
+                |entry| {
+                    println!("found {:?} in the game database", entry.info.title);
+                    entry.apply(&mut header);
+                    entry.info
+                },
+            )
         } else {
-            Err(CartridgeError::ExtensionError)
+            None
+        };
+
+        println!("mapper {}", header.mapper_id);
+
+        let mapper = Self::get_mapper(&header)?;
+
+        Self::fill_last_bank(&mut prg_data, 0x4000);
+
+        let chr_data = if !header.is_chr_ram {
+            Self::fill_last_bank(&mut chr_rom_data, 0x2000);
+
+            chr_rom_data
+        } else {
+            vec![0; header.chr_ram_size()]
+        };
+
+        // the miscellaneous ROMs take the rest of the file
+        let mut misc_rom_data = Vec::new();
+        if header.misc_rom_count != 0 {
+            file.read_to_end(&mut misc_rom_data)?;
+        }
+
+        // there are missing parts
+        if !file.is_empty() {
+            Err(CartridgeError::TooLargeFile(file.len() as u64))
+        } else {
+            let mut cartridge = Self::new_rom_cartridge(
+                file_path,
+                header,
+                mapper,
+                trainer_data,
+                misc_rom_data,
+                prg_data,
+                chr_data,
+            );
+            cartridge.game_info = game_info;
+
+            Ok(cartridge)
         }
     }
 
+    /// Load a UNIF file, the board is emulated with the iNES mapper for it
+    fn from_unif_bytes(data: &[u8], file_path: &Path) -> Result<Self, CartridgeError> {
+        let unif = UnifFile::from_bytes(data)?;
+        let header = INesHeader::unif(&unif);
+
+        println!("mapper {}", header.mapper_id);
+
+        let mapper = Self::get_mapper(&header)?;
+
+        let mut prg_data = unif.prg_data;
+        Self::fill_last_bank(&mut prg_data, 0x4000);
+
+        let chr_data = if !header.is_chr_ram {
+            let mut data = unif.chr_data;
+            Self::fill_last_bank(&mut data, 0x2000);
+
+            data
+        } else {
+            vec![0; header.chr_ram_size()]
+        };
+
+        Ok(Self::new_rom_cartridge(
+            file_path,
+            header,
+            mapper,
+            Vec::new(),
+            Vec::new(),
+            prg_data,
+            chr_data,
+        ))
+    }
+
+    /// Create a cartridge from the content of an iNES or UNIF file,
+    /// and load the battery backed data from previous runs
+    fn new_rom_cartridge(
+        file_path: &Path,
+        header: INesHeader,
+        mapper: Box<dyn Mapper>,
+        trainer_data: Vec<u8>,
+        misc_rom_data: Vec<u8>,
+        prg_data: Vec<u8>,
+        mut chr_data: Vec<u8>,
+    ) -> Self {
+        chr_data.resize(chr_data.len() + header.mixed_chr_ram_size(), 0);
+
+        let mut cartridge = Self {
+            file_path: file_path.to_path_buf().into_boxed_path(),
+            _trainer_data: trainer_data,
+            _misc_rom_data: misc_rom_data,
+            prg_data,
+            chr_data,
+            prg_ram_data: vec![0; header.prg_ram_size()],
+            header,
+            original_disk_sides: Vec::new(),
+            mapper,
+            game_info: None,
+
+            is_empty: false,
+        };
+
+        if cartridge.has_battery_backed_data() {
+            cartridge.load_battery_backed_data();
+        }
+
+        cartridge
+    }
+
+    /// Load a Famicom Disk System image (with or without the `.fds` header),
+    /// using the BIOS at `bios_path`.
+    ///
+    /// Writes to the disk are saved in a side-car file (`.fds.sav`) next to the image,
+    /// and the image itself is never modified.
+    pub fn from_fds_file<P: AsRef<Path>, B: AsRef<Path>>(
+        file_path: P,
+        bios_path: B,
+    ) -> Result<Self, CartridgeError> {
+        let bios = Self::read_fds_bios(bios_path)?;
+        let image = Self::read_file(file_path.as_ref())?;
+
+        Self::from_fds_bytes(&image, bios, file_path.as_ref())
+    }
+
+    fn read_fds_bios<B: AsRef<Path>>(bios_path: B) -> Result<Vec<u8>, CartridgeError> {
+        let mut bios = Vec::new();
+        File::open(bios_path)
+            .and_then(|mut file| file.read_to_end(&mut bios))
+            .map_err(|_| CartridgeError::FdsBiosError)?;
+        if bios.len() != FDS_BIOS_SIZE {
+            return Err(CartridgeError::FdsBiosError);
+        }
+
+        Ok(bios)
+    }
+
+    fn from_fds_bytes(
+        image: &[u8],
+        bios: Vec<u8>,
+        file_path: &Path,
+    ) -> Result<Self, CartridgeError> {
+        let original_disk_sides = fds_image::raw_disk_sides(image)?;
+        let mut disk_sides = original_disk_sides.clone();
+
+        // load the changes from previous runs
+        let diff_path = file_path.with_extension("fds.sav");
+        if let Ok(file) = File::open(&diff_path) {
+            println!("Loading disk save data from {:?}", diff_path);
+
+            if let Err(err) = fds_image::apply_disk_diff(&mut disk_sides, file) {
+                println!("[ERROR] {}, ignoring it", err);
+                disk_sides = original_disk_sides.clone();
+            }
+        }
+
+        let header = INesHeader::fds();
+        println!("mapper {}", header.mapper_id);
+
+        Ok(Self {
+            file_path: file_path.to_path_buf().into_boxed_path(),
+            _trainer_data: Vec::new(),
+            _misc_rom_data: Vec::new(),
+            prg_data: bios,
+            chr_data: vec![0; header.chr_wram_size as usize],
+            prg_ram_data: vec![0; header.prg_wram_size as usize],
+            header,
+            original_disk_sides,
+            mapper: Box::new(Fds::new(disk_sides)),
+            game_info: None,
+
+            is_empty: false,
+        })
+    }
+
+    /// Load an NSF/NSFe music file, which is played with the [`Nsf`] mapper
+    fn from_nsf_bytes(data: &[u8], file_path: &Path) -> Result<Self, CartridgeError> {
+        let nsf = NsfFile::from_bytes(data)?;
+        let header = INesHeader::nsf(nsf.timing_mode);
+
+        Ok(Self {
+            file_path: file_path.to_path_buf().into_boxed_path(),
+            _trainer_data: Vec::new(),
+            _misc_rom_data: Vec::new(),
+            prg_data: Vec::new(),
+            chr_data: vec![0; header.chr_wram_size as usize],
+            prg_ram_data: Vec::new(),
+            header,
+            original_disk_sides: Vec::new(),
+            mapper: Box::new(Nsf::new(nsf)),
+            game_info: None,
+
+            is_empty: false,
+        })
+    }
+
+    /// The BIOS is searched for next to the disk image, and then in the current directory
+    fn find_fds_bios(file_path: &Path) -> Result<PathBuf, CartridgeError> {
+        let next_to_image = file_path
+            .parent()
+            .map(|parent| parent.join(FDS_BIOS_FILE_NAME));
+
+        next_to_image
+            .into_iter()
+            .chain(std::iter::once(PathBuf::from(FDS_BIOS_FILE_NAME)))
+            .find(|path| path.is_file())
+            .ok_or(CartridgeError::FdsBiosError)
+    }
+
     pub fn new_without_file() -> Self {
         Self {
             // should not be used
             file_path: Path::new("").to_path_buf().into_boxed_path(),
             header: INesHeader::empty(),
             _trainer_data: Vec::new(),
+            _misc_rom_data: Vec::new(),
             prg_data: Vec::new(),
             chr_data: Vec::new(),
             prg_ram_data: Vec::new(),
+            original_disk_sides: Vec::new(),
             mapper: Box::new(Mapper0::new()),
+            game_info: None,
 
             is_empty: true,
         }
@@ -283,39 +802,108 @@ impl Cartridge {
             1 => Box::new(Mapper1::new()),
             2 => Box::new(Mapper2::new()),
             3 => Box::new(Mapper3::new()),
-            4 => Box::new(Mapper4::new()),
+            4 | 118 | 119 => Box::new(Mapper4::new(header.mapper_id)),
+            5 => Box::new(Mapper5::new()),
             7 => Box::new(Mapper7::new()),
             9 => Box::new(Mapper9::new()),
             10 => Box::new(Mapper10::new()),
             11 => Box::new(Mapper11::new()),
             12 => Box::new(Mapper12::new()),
+            16 | 153 | 157 | 159 => Box::new(Mapper16::new(header.mapper_id, header.submapper_id)),
+            19 => Box::new(Mapper19::new()),
+            21 | 22 | 23 | 25 => Box::new(Mapper21::new(header.mapper_id, header.submapper_id)),
+            24 => Box::new(Mapper24::new(false)),
+            26 => Box::new(Mapper24::new(true)),
+            28 => Box::new(Mapper28::new()),
+            30 => Box::new(Mapper30::new(
+                header.has_prg_ram_battery,
+                header.use_hardwaired_4_screen_mirroring && !header.hardwired_mirroring_vertical,
+            )),
+            34 => Box::new(Mapper34::new(header.submapper_id)),
             66 => Box::new(Mapper66::new()),
+            69 => Box::new(Mapper69::new()),
+            71 => Box::new(Mapper71::new(header.submapper_id)),
+            79 | 113 => Box::new(Mapper79::new(header.mapper_id)),
+            85 => Box::new(Mapper85::new(header.submapper_id)),
+            111 => Box::new(Mapper111::new()),
+            140 => Box::new(Mapper140::new()),
+            180 => Box::new(Mapper180::new()),
+            232 => Box::new(Mapper232::new(header.submapper_id)),
             _ => {
                 return Err(CartridgeError::MapperNotImplemented(header.mapper_id));
             }
         };
 
-        // FIXME: fix parameters types to support INES2.0
         // should always call init in a new mapper, as it is the only way
         // they share a constructor
         mapper.init(
-            header.prg_rom_size as u8,
+            header.prg_rom_count(),
             header.is_chr_ram,
-            if !header.is_chr_ram {
-                header.chr_rom_size as u8
-            } else {
-                (header.chr_wram_size / 0x2000) as u8
-            },
-            if header.has_prg_ram_battery {
-                header.prg_sram_size / 0x2000
-            } else {
-                header.prg_wram_size / 0x2000
-            } as u8,
+            header.chr_count(),
+            header.prg_ram_size() as u32,
         );
 
         Ok(mapper)
     }
 
+    /// ROM sizes in the exponent-multiplier notation may not be a multiple
+    /// of the bank size, so fill the last bank by mirroring the data
+    fn fill_last_bank(data: &mut Vec<u8>, bank_size: usize) {
+        let size = data.len().next_multiple_of(bank_size);
+
+        for i in data.len()..size {
+            data.push(data[i % data.len()]);
+        }
+    }
+
+    /// The start of the CHR RAM in `chr_data`, which is all of it for CHR RAM
+    /// cartridges, and after the CHR ROM for boards with both
+    fn chr_ram_start(&self) -> usize {
+        if self.header.is_chr_ram {
+            0
+        } else {
+            self.chr_data.len() - self.header.mixed_chr_ram_size()
+        }
+    }
+
+    /// Load the PRG SRAM, the mapper battery backed RAM, and the CHR NVRAM
+    /// from the `.sav` file, in that order
+    fn load_battery_backed_data(&mut self) {
+        let prg_sram_size = self.header.prg_sram_size as usize;
+        let mapper_ram_size = self.mapper.battery_backed_ram().len();
+        let chr_nvram_size = self.header.chr_nvram_size();
+        let prg_flash_size = self.prg_flash().len();
+
+        // try to load old save data
+        if let Ok(data) = Self::load_sram_file(
+            &self.file_path,
+            prg_sram_size + mapper_ram_size + chr_nvram_size + prg_flash_size,
+        ) {
+            let (prg_sram, rest) = data.split_at(prg_sram_size);
+            let (mapper_ram, rest) = rest.split_at(mapper_ram_size);
+            let (chr_nvram, prg_flash) = rest.split_at(chr_nvram_size);
+
+            self.prg_ram_data[..prg_sram_size].copy_from_slice(prg_sram);
+            self.mapper.load_battery_backed_ram(mapper_ram);
+            self.chr_data[..chr_nvram_size].copy_from_slice(chr_nvram);
+            self.prg_data[..prg_flash_size].copy_from_slice(prg_flash);
+        }
+    }
+
+    /// The PRG ROM if it is a flash chip written by the game, empty otherwise
+    fn prg_flash(&self) -> &[u8] {
+        if self.mapper.has_prg_flash() {
+            &self.prg_data
+        } else {
+            &[]
+        }
+    }
+
+    /// Whether there is any data to save in the `.sav` file
+    fn has_battery_backed_data(&self) -> bool {
+        self.header.has_prg_ram_battery || self.mapper.has_prg_flash()
+    }
+
     fn load_sram_file<P: AsRef<Path>>(path: P, sram_size: usize) -> Result<Vec<u8>, SramError> {
         let path = path.as_ref().with_extension("nes.sav");
         println!("Loading SRAM file data from {:?}", path);
@@ -335,9 +923,17 @@ impl Cartridge {
 
         let mut file = File::create(&path)?;
 
-        let size = file.write(&self.prg_ram_data)?;
+        let prg_sram = &self.prg_ram_data[..self.header.prg_sram_size as usize];
+        let mapper_ram = self.mapper.battery_backed_ram();
+        let chr_nvram = &self.chr_data[..self.header.chr_nvram_size()];
+        let prg_flash = self.prg_flash();
 
-        if size != self.header.prg_sram_size as usize {
+        let size = file.write(prg_sram)?
+            + file.write(mapper_ram)?
+            + file.write(chr_nvram)?
+            + file.write(prg_flash)?;
+
+        if size != prg_sram.len() + mapper_ram.len() + chr_nvram.len() + prg_flash.len() {
             file.sync_all()?;
             // remove the file so it will not be loaded next time the game is run
             std::fs::remove_file(path).expect("Could not remove `nes.sav` file");
@@ -347,28 +943,131 @@ impl Cartridge {
         }
     }
 
+    fn save_disk_diff_file(&self) -> Result<(), SramError> {
+        let diff = fds_image::create_disk_diff(&self.original_disk_sides, self.mapper.disk_sides());
+        let path = self.file_path.with_extension("fds.sav");
+
+        if diff.is_empty() && !path.exists() {
+            return Ok(());
+        }
+
+        println!("Writing disk save data to {:?}", path);
+
+        let mut file = File::create(path)?;
+        file.write_all(&diff)?;
+
+        Ok(())
+    }
+
     pub fn is_empty(&self) -> bool {
         self.is_empty
     }
 
+    /// The NSF player if this is an NSF/NSFe file
+    pub(crate) fn nsf(&self) -> Option<&Nsf> {
+        self.mapper.nsf()
+    }
+
+    pub(crate) fn nsf_mut(&mut self) -> Option<&mut Nsf> {
+        self.mapper.nsf_mut()
+    }
+
+    /// The region the game is made for, from the header
+    pub fn region(&self) -> Region {
+        self.header.timing_mode.region()
+    }
+
+    /// The offset in PRG ROM mapped to the CPU `address` with the current banking
+    pub fn prg_rom_offset(&self, address: u16) -> Option<usize> {
+        if self.is_empty || address < 0x6000 {
+            return None;
+        }
+
+        match self.mapper.peek_cpu(address) {
+            MappingResult::Allowed(offset) if address >= 0x8000 => Some(offset),
+            MappingResult::PrgRom(offset) => Some(offset),
+            _ => None,
+        }
+    }
+
+    /// The offset in PRG RAM mapped to the CPU `address` with the current banking
+    pub fn prg_ram_offset(&self, address: u16) -> Option<usize> {
+        if self.is_empty || address < 0x6000 {
+            return None;
+        }
+
+        match self.mapper.peek_cpu(address) {
+            MappingResult::Allowed(offset) if address < 0x8000 => Some(offset),
+            MappingResult::PrgRam(offset) => Some(offset),
+            _ => None,
+        }
+        .filter(|&offset| offset < self.prg_ram_data.len())
+    }
+
+    /// All the PRG RAM, the battery backed part (if any) comes first
+    pub fn prg_ram(&self) -> &[u8] {
+        &self.prg_ram_data
+    }
+
+    pub fn prg_ram_mut(&mut self) -> &mut [u8] {
+        &mut self.prg_ram_data
+    }
+
+    /// The console the game is made for, from the header
+    pub fn console_type(&self) -> ConsoleType {
+        self.header.console_type
+    }
+
+    /// The game database entry of the loaded game, only iNES 1.0 files are searched for
+    pub fn game_info(&self) -> Option<&GameInfo> {
+        self.game_info.as_ref()
+    }
+
+    /// Set the region the emulator is running in
+    pub fn set_region(&mut self, region: Region) {
+        self.mapper.set_region(region);
+    }
+
+    /// The number of disk sides in a Famicom Disk System image, `0` for normal cartridges
+    pub fn disk_sides_count(&self) -> usize {
+        self.mapper.disk_sides().len()
+    }
+
+    /// The disk side inserted in the Famicom Disk System drive, if any
+    pub fn inserted_disk_side(&self) -> Option<usize> {
+        self.mapper.inserted_disk_side()
+    }
+
+    /// Insert a disk side into the Famicom Disk System drive, or eject the disk
+    /// if `side` is `None`
+    pub fn set_inserted_disk_side(&mut self, side: Option<usize>) {
+        self.mapper.set_inserted_disk_side(side);
+    }
+
     pub fn cartridge_path(&self) -> &Path {
         &self.file_path
     }
-}
 
-impl Bus for Cartridge {
-    fn read(&self, address: u16, device: Device) -> u8 {
-        if self.is_empty {
-            return match device {
-                Device::Cpu => 0xEA, // NOP instruction just in case, this
-                Device::Ppu => 0x00, // should not be called
-            };
+    /// Clock the cartridge for one CPU cycle
+    pub fn clock(&mut self) {
+        if !self.is_empty {
+            self.mapper.clock();
         }
+    }
 
-        let result = self.mapper.map_read(address, device);
+    /// Forward writes to the PPU registers `$2000-$2007` to the mapper
+    pub fn snoop_ppu_register_write(&mut self, address: u16, data: u8) {
+        if !self.is_empty {
+            self.mapper.snoop_ppu_register_write(address, data);
+        }
+    }
+}
 
-        if let MappingResult::Allowed(new_address) = result {
-            match device {
+impl Cartridge {
+    /// Read from the memory `address` is mapped to by the mapper
+    fn read_mapped(&self, address: u16, device: Device, result: MappingResult) -> u8 {
+        match result {
+            MappingResult::Allowed(new_address) => match device {
                 Device::Cpu => match address {
                     0x6000..=0x7FFF => *self
                         .prg_ram_data
@@ -379,28 +1078,24 @@ impl Bus for Cartridge {
                         unreachable!();
                     }
                 },
-                Device::Ppu => {
-                    if address <= 0x1FFF {
-                        *self.chr_data.get(new_address).expect("CHR out of bounds")
-                    } else {
-                        unreachable!();
-                    }
-                }
+                Device::Ppu => *self.chr_data.get(new_address).expect("CHR out of bounds"),
+            },
+            MappingResult::PrgRam(new_address) => *self
+                .prg_ram_data
+                .get(new_address)
+                .expect("SRAM out of bounds"),
+            MappingResult::PrgRom(new_address) => {
+                *self.prg_data.get(new_address).expect("PRG out of bounds")
             }
-        } else {
-            0
+            MappingResult::Data(data) => data,
+            MappingResult::PrgRomErase(..) | MappingResult::Denied => 0,
         }
     }
-    fn write(&mut self, address: u16, data: u8, device: Device) {
-        if self.is_empty {
-            return;
-        }
 
-        // send the write signal, this might trigger bank change
-        let result = self.mapper.map_write(address, data, device);
-
-        if let MappingResult::Allowed(new_address) = result {
-            match device {
+    /// Write `data` to the memory `address` is mapped to by the mapper
+    fn write_mapped(&mut self, address: u16, data: u8, device: Device, result: MappingResult) {
+        match result {
+            MappingResult::Allowed(new_address) => match device {
                 Device::Cpu => match address {
                     0x6000..=0x7FFF => {
                         *self
@@ -419,17 +1114,82 @@ impl Bus for Cartridge {
                     }
                 },
                 Device::Ppu => {
-                    if address <= 0x1FFF {
-                        *self
-                            .chr_data
-                            .get_mut(new_address)
-                            .expect("CHR out of bounds") = data;
-                    } else {
-                        unreachable!();
-                    }
+                    *self
+                        .chr_data
+                        .get_mut(new_address)
+                        .expect("CHR out of bounds") = data;
                 }
+            },
+            MappingResult::PrgRam(new_address) => {
+                *self
+                    .prg_ram_data
+                    .get_mut(new_address)
+                    .expect("SRAM out of bounds") = data;
             }
+            MappingResult::PrgRom(new_address) => {
+                *self
+                    .prg_data
+                    .get_mut(new_address)
+                    .expect("PRG out of bounds") = data;
+            }
+            MappingResult::PrgRomErase(start, size) => {
+                let end = (start + size).min(self.prg_data.len());
+                self.prg_data[start.min(end)..end].fill(0xFF);
+            }
+            MappingResult::Data(_) | MappingResult::Denied => {}
+        }
+    }
+}
+
+impl Bus for Cartridge {
+    fn read(&self, address: u16, device: Device) -> u8 {
+        if self.is_empty {
+            return match device {
+                Device::Cpu => 0xEA, // NOP instruction just in case, this
+                Device::Ppu => 0x00, // should not be called
+            };
+        }
+
+        self.read_mapped(address, device, self.mapper.map_read(address, device))
+    }
+    fn write(&mut self, address: u16, data: u8, device: Device) {
+        if self.is_empty {
+            return;
         }
+
+        let data = if device == Device::Cpu && address >= 0x8000 && self.mapper.has_bus_conflicts()
+        {
+            data & self.read(address, device)
+        } else {
+            data
+        };
+
+        // send the write signal, this might trigger bank change
+        let result = self.mapper.map_write(address, data, device);
+        self.write_mapped(address, data, device, result);
+    }
+
+    fn peek(&self, address: u16, device: Device) -> u8 {
+        if self.is_empty {
+            return self.read(address, device);
+        }
+
+        let result = match device {
+            Device::Cpu => self.mapper.peek_cpu(address),
+            Device::Ppu => self.mapper.peek_ppu(address),
+        };
+        self.read_mapped(address, device, result)
+    }
+
+    // only the PPU writes have mappings without side effects, the CPU writes
+    // to the mapper registers are the side effects
+    fn poke(&mut self, address: u16, data: u8, device: Device) {
+        if self.is_empty || device == Device::Cpu {
+            return self.write(address, data, device);
+        }
+
+        let result = self.mapper.poke_ppu(address, data);
+        self.write_mapped(address, data, device, result);
     }
 }
 
@@ -452,12 +1212,40 @@ impl MirroringProvider for Cartridge {
             self.mapper.nametable_mirroring()
         }
     }
+
+    fn nametable_mapping(&self, address: u16) -> NametableMapping {
+        if !self.is_empty {
+            if let Some(mapping) = self.mapper.map_nametable(address) {
+                return mapping;
+            }
+        }
+
+        NametableMapping::Vram(self.mirroring_mode().nametable_block(address))
+    }
+
+    fn peek_nametable_mapping(&self, address: u16) -> NametableMapping {
+        if !self.is_empty {
+            if let Some(mapping) = self.mapper.peek_nametable(address) {
+                return mapping;
+            }
+        }
+
+        NametableMapping::Vram(self.mirroring_mode().nametable_block(address))
+    }
 }
 
 impl Drop for Cartridge {
     fn drop(&mut self) {
-        if !self.is_empty && self.header.has_prg_ram_battery {
-            self.save_sram_file().unwrap();
+        // errors are not fatal here, the emulator is closing or loading another game
+        if !self.is_empty && self.has_battery_backed_data() {
+            if let Err(err) = self.save_sram_file() {
+                println!("[ERROR] {}, ignoring it", err);
+            }
+        }
+        if !self.is_empty && !self.original_disk_sides.is_empty() {
+            if let Err(err) = self.save_disk_diff_file() {
+                println!("[ERROR] {}, ignoring it", err);
+            }
         }
     }
 }
@@ -480,17 +1268,27 @@ impl CPUIrqProvider for Cartridge {
     }
 }
 
+impl ExpansionAudioProvider for Cartridge {
+    fn expansion_audio_output(&mut self) -> f32 {
+        if self.is_empty {
+            return 0.;
+        }
+
+        self.mapper.expansion_audio_output()
+    }
+}
+
 impl Savable for Cartridge {
     fn save<W: Write>(&self, writer: &mut W) -> Result<(), SaveError> {
         let mapper_saved_state = self.mapper.save_state();
         writer.write_all(&mapper_saved_state)?;
 
         writer.write_all(&self.prg_ram_data)?;
+        writer.write_all(self.prg_flash())?;
 
-        writer.write_all(&[self.header.is_chr_ram as u8])?;
-        if self.header.is_chr_ram {
-            writer.write_all(&self.chr_data)?;
-        }
+        let chr_ram_data = &self.chr_data[self.chr_ram_start()..];
+        writer.write_all(&[!chr_ram_data.is_empty() as u8])?;
+        writer.write_all(chr_ram_data)?;
 
         Ok(())
     }
@@ -501,11 +1299,15 @@ impl Savable for Cartridge {
         self.mapper.load_state(mapper_load_data);
 
         reader.read_exact(&mut self.prg_ram_data)?;
+        if self.mapper.has_prg_flash() {
+            reader.read_exact(&mut self.prg_data)?;
+        }
 
         let mut is_chr_ram = [0u8; 1];
         reader.read_exact(&mut is_chr_ram)?;
         if is_chr_ram[0] != 0 {
-            reader.read_exact(&mut self.chr_data)?;
+            let chr_ram_start = self.chr_ram_start();
+            reader.read_exact(&mut self.chr_data[chr_ram_start..])?;
         }
 
         Ok(())
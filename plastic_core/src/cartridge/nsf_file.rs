@@ -0,0 +1,335 @@
+//! NSF and NSFe music files.
+//!
+//! NSF files have a fixed `$80` bytes header followed by the program data, and
+//! NSFe files are a list of chunks containing the same information, in addition
+//! to metadata like the tracks names and lengths.
+//!
+//! NSF2 files can also have NSFe metadata chunks after the program data.
+
+use super::error::CartridgeError;
+use super::TimingMode;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+const NSF_MAGIC: &[u8] = b"NESM\x1A";
+const NSFE_MAGIC: &[u8] = b"NSFE";
+const NSF_HEADER_SIZE: usize = 0x80;
+
+/// the play routine rate (in microseconds) of NSFe files without a `RATE` chunk
+const DEFAULT_NTSC_PLAY_SPEED: u16 = 16639;
+const DEFAULT_PAL_PLAY_SPEED: u16 = 19997;
+
+/// Information about an NSF/NSFe music file and its tracks.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NsfInfo {
+    pub title: String,
+    pub artist: String,
+    pub copyright: String,
+    /// only available in NSFe files
+    pub ripper: String,
+    pub tracks: Vec<NsfTrack>,
+    /// the track played first (0 based)
+    pub starting_track: usize,
+}
+
+/// Information about one track in an NSF/NSFe file, only available in NSFe files
+/// (and NSF2 files with metadata).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NsfTrack {
+    pub label: Option<String>,
+    /// the length of the track before fading out, `None` if it is unknown,
+    /// in which case the track plays forever
+    pub length: Option<Duration>,
+    /// the length of the fade out after [`length`][NsfTrack::length]
+    pub fade: Option<Duration>,
+}
+
+/// The program and the player configuration of an NSF/NSFe file
+pub(crate) struct NsfFile {
+    pub(crate) info: NsfInfo,
+
+    pub(crate) load_address: u16,
+    pub(crate) init_address: u16,
+    pub(crate) play_address: u16,
+    /// the play routine rate in microseconds
+    pub(crate) ntsc_play_speed: u16,
+    pub(crate) pal_play_speed: u16,
+    pub(crate) dendy_play_speed: u16,
+    /// the region the tune is made for
+    pub(crate) timing_mode: TimingMode,
+    /// the initial banks of `$8000-$FFFF`, `None` if the file does not use bank switching
+    pub(crate) bank_init: Option<[u8; 8]>,
+    /// 7  bit  0
+    /// ---- ----
+    /// xxFN MFV6
+    ///   || ||||
+    ///   || |||+- VRC6
+    ///   || ||+-- VRC7
+    ///   || |+--- FDS
+    ///   || +---- MMC5
+    ///   |+------ Namco 163
+    ///   +------- Sunsoft 5B
+    pub(crate) expansion_chips: u8,
+
+    pub(crate) data: Vec<u8>,
+}
+
+impl NsfFile {
+    pub(super) fn is_nsf(data: &[u8]) -> bool {
+        data.starts_with(NSF_MAGIC) || data.starts_with(NSFE_MAGIC)
+    }
+
+    pub(super) fn from_bytes(data: &[u8]) -> Result<Self, CartridgeError> {
+        if data.starts_with(NSF_MAGIC) {
+            Self::from_nsf(data)
+        } else if data.starts_with(NSFE_MAGIC) {
+            Self::from_nsfe(&data[NSFE_MAGIC.len()..])
+        } else {
+            Err(CartridgeError::NsfFileError)
+        }
+    }
+
+    fn from_nsf(data: &[u8]) -> Result<Self, CartridgeError> {
+        if data.len() <= NSF_HEADER_SIZE {
+            return Err(CartridgeError::NsfFileError);
+        }
+
+        let version = data[5];
+        let tracks_count = data[6] as usize;
+        // 1 based in the header
+        let starting_track = (data[7] as usize).max(1) - 1;
+
+        let bank_init: [u8; 8] = data[0x70..0x78].try_into().unwrap();
+        // NSF2 program length, zero means the program is until the end of the file
+        let program_length = u32::from_le_bytes([data[0x7D], data[0x7E], data[0x7F], 0]) as usize;
+
+        let (program, metadata) = if version >= 2 && program_length != 0 {
+            let program_end = NSF_HEADER_SIZE + program_length;
+            if program_end > data.len() {
+                return Err(CartridgeError::NsfFileError);
+            }
+            (&data[NSF_HEADER_SIZE..program_end], &data[program_end..])
+        } else {
+            (&data[NSF_HEADER_SIZE..], &[][..])
+        };
+
+        let mut nsf = Self {
+            info: NsfInfo {
+                title: read_string(&data[0x0E..0x2E]),
+                artist: read_string(&data[0x2E..0x4E]),
+                copyright: read_string(&data[0x4E..0x6E]),
+                ripper: String::new(),
+                tracks: vec![NsfTrack::default(); tracks_count],
+                starting_track,
+            },
+            load_address: read_u16(&data[0x08..]),
+            init_address: read_u16(&data[0x0A..]),
+            play_address: read_u16(&data[0x0C..]),
+            ntsc_play_speed: read_u16(&data[0x6E..]),
+            pal_play_speed: read_u16(&data[0x78..]),
+            dendy_play_speed: 0,
+            timing_mode: read_timing_mode(data[0x7A]),
+            bank_init: bank_init.iter().any(|&b| b != 0).then_some(bank_init),
+            expansion_chips: data[0x7B],
+            data: program.to_vec(),
+        };
+
+        for chunk in Chunks(metadata) {
+            let (id, chunk) = chunk?;
+            nsf.read_metadata_chunk(id, chunk)?;
+        }
+
+        nsf.validate()
+    }
+
+    fn from_nsfe(data: &[u8]) -> Result<Self, CartridgeError> {
+        let mut nsf = Self {
+            info: NsfInfo::default(),
+            load_address: 0,
+            init_address: 0,
+            play_address: 0,
+            ntsc_play_speed: DEFAULT_NTSC_PLAY_SPEED,
+            pal_play_speed: DEFAULT_PAL_PLAY_SPEED,
+            dendy_play_speed: 0,
+            timing_mode: TimingMode::Ntsc,
+            bank_init: None,
+            expansion_chips: 0,
+            data: Vec::new(),
+        };
+        let mut has_info = false;
+
+        for chunk in Chunks(data) {
+            let (id, chunk) = chunk?;
+
+            match id {
+                b"INFO" => {
+                    if chunk.len() < 8 {
+                        return Err(CartridgeError::NsfFileError);
+                    }
+                    nsf.load_address = read_u16(&chunk[0..]);
+                    nsf.init_address = read_u16(&chunk[2..]);
+                    nsf.play_address = read_u16(&chunk[4..]);
+                    nsf.timing_mode = read_timing_mode(chunk[6]);
+                    nsf.expansion_chips = chunk[7];
+
+                    let tracks_count = chunk.get(8).copied().unwrap_or(1) as usize;
+                    nsf.info.tracks.resize(tracks_count, NsfTrack::default());
+                    // 0 based here
+                    nsf.info.starting_track = chunk.get(9).copied().unwrap_or(0) as usize;
+
+                    has_info = true;
+                }
+                b"DATA" => nsf.data = chunk.to_vec(),
+                b"BANK" => {
+                    let mut bank_init = [0; 8];
+                    let len = chunk.len().min(8);
+                    bank_init[..len].copy_from_slice(&chunk[..len]);
+                    nsf.bank_init = Some(bank_init);
+                }
+                b"RATE" => {
+                    if chunk.len() < 2 {
+                        return Err(CartridgeError::NsfFileError);
+                    }
+                    nsf.ntsc_play_speed = read_u16(chunk);
+                    if chunk.len() >= 4 {
+                        nsf.pal_play_speed = read_u16(&chunk[2..]);
+                    }
+                    if chunk.len() >= 6 {
+                        nsf.dendy_play_speed = read_u16(&chunk[4..]);
+                    }
+                }
+                b"NEND" => break,
+                _ => nsf.read_metadata_chunk(id, chunk)?,
+            }
+        }
+
+        if !has_info {
+            return Err(CartridgeError::NsfFileError);
+        }
+
+        nsf.validate()
+    }
+
+    /// Read the NSFe chunks that are also allowed in NSF2 files
+    fn read_metadata_chunk(&mut self, id: &[u8; 4], chunk: &[u8]) -> Result<(), CartridgeError> {
+        match id {
+            b"auth" => {
+                let mut strings = chunk.split(|&c| c == 0).map(read_string);
+
+                self.info.title = strings.next().unwrap_or_default();
+                self.info.artist = strings.next().unwrap_or_default();
+                self.info.copyright = strings.next().unwrap_or_default();
+                self.info.ripper = strings.next().unwrap_or_default();
+            }
+            b"time" => {
+                for (track, time) in self.info.tracks.iter_mut().zip(chunk.chunks_exact(4)) {
+                    track.length = read_duration(time);
+                }
+            }
+            b"fade" => {
+                for (track, fade) in self.info.tracks.iter_mut().zip(chunk.chunks_exact(4)) {
+                    track.fade = read_duration(fade);
+                }
+            }
+            b"tlbl" => {
+                for (track, label) in self.info.tracks.iter_mut().zip(chunk.split(|&c| c == 0)) {
+                    track.label = Some(read_string(label));
+                }
+            }
+            // chunks starting with an uppercase letter must be understood by the player
+            _ if id[0].is_ascii_uppercase() => return Err(CartridgeError::NsfFileError),
+            // unknown optional chunk
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    fn validate(mut self) -> Result<Self, CartridgeError> {
+        if self.data.is_empty() || self.info.tracks.is_empty() {
+            return Err(CartridgeError::NsfFileError);
+        }
+
+        // NSF files without the FDS chip can only be loaded in `$8000-$FFFF`
+        let is_fds = self.expansion_chips & 0x4 != 0;
+        if self.load_address < if is_fds { 0x6000 } else { 0x8000 } {
+            return Err(CartridgeError::NsfFileError);
+        }
+
+        if self.ntsc_play_speed == 0 {
+            self.ntsc_play_speed = DEFAULT_NTSC_PLAY_SPEED;
+        }
+        if self.pal_play_speed == 0 {
+            self.pal_play_speed = DEFAULT_PAL_PLAY_SPEED;
+        }
+        // Dendy runs at 50Hz like PAL
+        if self.dendy_play_speed == 0 {
+            self.dendy_play_speed = self.pal_play_speed;
+        }
+        if self.info.starting_track >= self.info.tracks.len() {
+            self.info.starting_track = 0;
+        }
+
+        Ok(self)
+    }
+}
+
+/// Iterator over the NSFe chunks of `data`, each chunk is
+/// length (u32 LE), id (4 bytes) and `length` bytes of data
+struct Chunks<'a>(&'a [u8]);
+
+impl<'a> Iterator for Chunks<'a> {
+    type Item = Result<(&'a [u8; 4], &'a [u8]), CartridgeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.0.is_empty() {
+            return None;
+        }
+        if self.0.len() < 8 {
+            self.0 = &[];
+            return Some(Err(CartridgeError::NsfFileError));
+        }
+
+        let length = u32::from_le_bytes(self.0[0..4].try_into().unwrap()) as usize;
+        let id = self.0[4..8].try_into().unwrap();
+        let rest = &self.0[8..];
+
+        if length > rest.len() {
+            self.0 = &[];
+            return Some(Err(CartridgeError::NsfFileError));
+        }
+
+        self.0 = &rest[length..];
+        Some(Ok((id, &rest[..length])))
+    }
+}
+
+/// Read the region byte, bit 0 is set for PAL tunes and bit 1 for tunes
+/// that support both NTSC and PAL
+fn read_timing_mode(region: u8) -> TimingMode {
+    if region & 0x2 != 0 {
+        TimingMode::MultipleRegion
+    } else if region & 0x1 != 0 {
+        TimingMode::Pal
+    } else {
+        TimingMode::Ntsc
+    }
+}
+
+fn read_u16(data: &[u8]) -> u16 {
+    u16::from_le_bytes([data[0], data[1]])
+}
+
+/// Read a string until the first null byte
+fn read_string(data: &[u8]) -> String {
+    let end = data.iter().position(|&c| c == 0).unwrap_or(data.len());
+
+    String::from_utf8_lossy(&data[..end]).into_owned()
+}
+
+/// Read a duration in milliseconds (i32 LE), negative values mean the default/unknown
+fn read_duration(data: &[u8]) -> Option<Duration> {
+    let milliseconds = i32::from_le_bytes(data.try_into().unwrap());
+
+    (milliseconds >= 0).then(|| Duration::from_millis(milliseconds as u64))
+}
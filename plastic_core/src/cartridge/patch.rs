@@ -0,0 +1,242 @@
+//! IPS, BPS and UPS patches, applied to the ROM file before it is loaded.
+//!
+//! IPS patches are a list of records to write at some offset, without any checksums.
+//! BPS and UPS patches contain the CRC32 of the source and target files, so a patch
+//! made for a different ROM is detected.
+
+use super::error::CartridgeError;
+
+const IPS_MAGIC: &[u8] = b"PATCH";
+const IPS_EOF: &[u8] = b"EOF";
+const BPS_MAGIC: &[u8] = b"BPS1";
+const UPS_MAGIC: &[u8] = b"UPS1";
+/// source CRC32, target CRC32 and patch CRC32 at the end of BPS and UPS patches
+const FOOTER_SIZE: usize = 12;
+
+/// Apply `patches` to `rom` in order
+pub(super) fn apply_patches(rom: Vec<u8>, patches: &[Vec<u8>]) -> Result<Vec<u8>, CartridgeError> {
+    patches
+        .iter()
+        .try_fold(rom, |rom, patch| apply_patch(&rom, patch))
+}
+
+pub(super) fn apply_patch(rom: &[u8], patch: &[u8]) -> Result<Vec<u8>, CartridgeError> {
+    if patch.starts_with(IPS_MAGIC) {
+        apply_ips(rom, &patch[IPS_MAGIC.len()..])
+    } else if patch.starts_with(BPS_MAGIC) {
+        apply_bps(rom, patch)
+    } else if patch.starts_with(UPS_MAGIC) {
+        apply_ups(rom, patch)
+    } else {
+        Err(CartridgeError::PatchError)
+    }
+}
+
+fn apply_ips(rom: &[u8], mut patch: &[u8]) -> Result<Vec<u8>, CartridgeError> {
+    let mut output = rom.to_vec();
+
+    loop {
+        if patch.starts_with(IPS_EOF) {
+            patch = &patch[IPS_EOF.len()..];
+            break;
+        }
+
+        let record = take(&mut patch, 5)?;
+        let offset = u32::from_be_bytes([0, record[0], record[1], record[2]]) as usize;
+        let size = u16::from_be_bytes([record[3], record[4]]) as usize;
+
+        // RLE record
+        let (size, data) = if size == 0 {
+            let rle = take(&mut patch, 3)?;
+            let size = u16::from_be_bytes([rle[0], rle[1]]) as usize;
+            (size, std::iter::repeat_n(rle[2], size).collect())
+        } else {
+            (size, take(&mut patch, size)?.to_vec())
+        };
+
+        if output.len() < offset + size {
+            output.resize(offset + size, 0);
+        }
+        output[offset..offset + size].copy_from_slice(&data);
+    }
+
+    // some patches truncate the file after the EOF marker
+    if patch.len() >= 3 {
+        let size = u32::from_be_bytes([0, patch[0], patch[1], patch[2]]) as usize;
+        output.truncate(size);
+    }
+
+    Ok(output)
+}
+
+fn apply_bps(rom: &[u8], patch: &[u8]) -> Result<Vec<u8>, CartridgeError> {
+    let (mut actions, (source_crc, target_crc)) = check_footer(patch, BPS_MAGIC.len())?;
+
+    let source_size = read_number(&mut actions)?;
+    let target_size = read_number(&mut actions)?;
+    let metadata_size = read_number(&mut actions)?;
+    take(&mut actions, metadata_size)?;
+
+    check_source(rom, source_size, source_crc)?;
+
+    let mut output = Vec::with_capacity(target_size);
+    let mut source_offset = 0usize;
+    let mut target_offset = 0usize;
+
+    while !actions.is_empty() {
+        let data = read_number(&mut actions)?;
+        let length = (data >> 2) + 1;
+
+        match data & 3 {
+            // source read
+            0 => {
+                let start = output.len();
+                let source = rom
+                    .get(start..start + length)
+                    .ok_or(CartridgeError::PatchError)?;
+                output.extend_from_slice(source);
+            }
+            // target read
+            1 => output.extend_from_slice(take(&mut actions, length)?),
+            // source copy
+            2 => {
+                source_offset = relative_offset(source_offset, read_number(&mut actions)?)?;
+                let source = rom
+                    .get(source_offset..source_offset + length)
+                    .ok_or(CartridgeError::PatchError)?;
+                output.extend_from_slice(source);
+                source_offset += length;
+            }
+            // target copy, may overlap with the bytes being written
+            _ => {
+                target_offset = relative_offset(target_offset, read_number(&mut actions)?)?;
+                for _ in 0..length {
+                    let byte = *output
+                        .get(target_offset)
+                        .ok_or(CartridgeError::PatchError)?;
+                    output.push(byte);
+                    target_offset += 1;
+                }
+            }
+        }
+
+        if output.len() > target_size {
+            return Err(CartridgeError::PatchError);
+        }
+    }
+
+    check_target(output, target_size, target_crc)
+}
+
+fn apply_ups(rom: &[u8], patch: &[u8]) -> Result<Vec<u8>, CartridgeError> {
+    let (mut hunks, (source_crc, target_crc)) = check_footer(patch, UPS_MAGIC.len())?;
+
+    let source_size = read_number(&mut hunks)?;
+    let target_size = read_number(&mut hunks)?;
+
+    check_source(rom, source_size, source_crc)?;
+
+    let mut output = rom.to_vec();
+    output.resize(target_size, 0);
+    let mut offset = 0;
+
+    while !hunks.is_empty() {
+        offset += read_number(&mut hunks)?;
+
+        // XOR the bytes until (and including) the zero byte
+        loop {
+            let xor = take(&mut hunks, 1)?[0];
+            if offset < target_size {
+                output[offset] = rom.get(offset).copied().unwrap_or(0) ^ xor;
+            }
+            offset += 1;
+
+            if xor == 0 {
+                break;
+            }
+        }
+    }
+
+    check_target(output, target_size, target_crc)
+}
+
+/// Verify the patch CRC32, and return the patch content without
+/// the magic and the footer, and the source and target CRC32s
+fn check_footer(patch: &[u8], magic_size: usize) -> Result<(&[u8], (u32, u32)), CartridgeError> {
+    if patch.len() < magic_size + FOOTER_SIZE {
+        return Err(CartridgeError::PatchError);
+    }
+
+    let (content, footer) = patch.split_at(patch.len() - FOOTER_SIZE);
+    let read_crc = |i: usize| u32::from_le_bytes(footer[i..i + 4].try_into().unwrap());
+
+    if crc32fast::hash(&patch[..patch.len() - 4]) != read_crc(8) {
+        return Err(CartridgeError::PatchError);
+    }
+
+    Ok((&content[magic_size..], (read_crc(0), read_crc(4))))
+}
+
+fn check_source(rom: &[u8], source_size: usize, source_crc: u32) -> Result<(), CartridgeError> {
+    if rom.len() != source_size || crc32fast::hash(rom) != source_crc {
+        Err(CartridgeError::PatchMismatch)
+    } else {
+        Ok(())
+    }
+}
+
+fn check_target(
+    output: Vec<u8>,
+    target_size: usize,
+    target_crc: u32,
+) -> Result<Vec<u8>, CartridgeError> {
+    if output.len() != target_size || crc32fast::hash(&output) != target_crc {
+        Err(CartridgeError::PatchError)
+    } else {
+        Ok(output)
+    }
+}
+
+/// Read a variable length number, 7 bits per byte with the last byte having the top bit set
+fn read_number(data: &mut &[u8]) -> Result<usize, CartridgeError> {
+    let mut number = 0usize;
+    let mut shift = 1usize;
+
+    loop {
+        let byte = take(data, 1)?[0];
+        number = (byte as usize & 0x7F)
+            .checked_mul(shift)
+            .and_then(|n| n.checked_add(number))
+            .ok_or(CartridgeError::PatchError)?;
+
+        if byte & 0x80 != 0 {
+            return Ok(number);
+        }
+
+        shift = shift.checked_mul(0x80).ok_or(CartridgeError::PatchError)?;
+        number = number
+            .checked_add(shift)
+            .ok_or(CartridgeError::PatchError)?;
+    }
+}
+
+/// BPS copy offsets are relative to the last copy, the lowest bit is the sign
+fn relative_offset(offset: usize, data: usize) -> Result<usize, CartridgeError> {
+    if data & 1 != 0 {
+        offset.checked_sub(data >> 1)
+    } else {
+        offset.checked_add(data >> 1)
+    }
+    .ok_or(CartridgeError::PatchError)
+}
+
+fn take<'a>(data: &mut &'a [u8], size: usize) -> Result<&'a [u8], CartridgeError> {
+    if data.len() < size {
+        return Err(CartridgeError::PatchError);
+    }
+
+    let (taken, rest) = data.split_at(size);
+    *data = rest;
+
+    Ok(taken)
+}
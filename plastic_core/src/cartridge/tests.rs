@@ -1,6 +1,14 @@
 #[cfg(test)]
 mod cartridge_tests {
-    use super::super::{Cartridge, CartridgeError};
+    use super::super::{
+        game_db::GameDb, nsf_file::NsfFile, patch, unif_file::UnifFile, Cartridge, CartridgeError,
+        ConsoleType, INesHeader, TimingMode,
+    };
+    use crate::common::{
+        interconnection::CPUIrqProvider, Bus, Device, MirroringProvider, NametableMapping, Region,
+    };
+    use crate::tests::nsf_file;
+    use std::time::Duration;
 
     #[test]
     fn cartridge_file_not_found() {
@@ -71,4 +79,1300 @@ mod cartridge_tests {
         // test passed
         Ok(())
     }
+
+    #[test]
+    fn test_ines2_header() -> Result<(), CartridgeError> {
+        let header = INesHeader::from_bytes([
+            0x4E, 0x45, 0x53, 0x1A, // magic
+            0x01, // PRG ROM size LSB
+            0x00, // CHR ROM size LSB
+            0x12, // battery, mapper low
+            0x09, // NES 2.0, Vs. System, mapper middle
+            0x21, // submapper 2, mapper high 1 (mapper 0x101)
+            0x01, // PRG ROM size MSB 1 (257 * 16kb)
+            0x70, // 8kb PRG NVRAM
+            0x57, // 8kb CHR RAM, 2kb CHR NVRAM
+            0x03, // Dendy
+            0x34, // PPU type 4, hardware type 3
+            0x01, // 1 misc ROM
+            0x2A, // default expansion device
+        ])?;
+
+        assert_eq!(header.prg_rom_size, 257 * 0x4000);
+        assert_eq!(header.prg_rom_count(), 257);
+        assert!(header.is_chr_ram);
+        assert_eq!(header.mapper_id, 0x101);
+        assert_eq!(header.submapper_id, 2);
+        assert_eq!(header.prg_sram_size, 0x2000);
+        assert_eq!(header.prg_wram_size, 0);
+        assert_eq!(header.prg_ram_size(), 0x2000);
+        // rounded up to 8kb banks
+        assert_eq!(header.chr_ram_size(), 0x4000);
+        assert_eq!(header.chr_count(), 2);
+        assert_eq!(header.chr_nvram_size(), 0x800);
+        assert_eq!(
+            header.console_type,
+            ConsoleType::VsSystem {
+                ppu_type: 4,
+                hardware_type: 3
+            }
+        );
+        assert_eq!(header.timing_mode, TimingMode::Dendy);
+        assert_eq!(header.misc_rom_count, 1);
+        assert_eq!(header.default_expansion_device, 0x2A);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_ines2_exponent_multiplier_rom_size() -> Result<(), CartridgeError> {
+        // PRG: 2^10 * 3, CHR: 2^13 * 1
+        let header = INesHeader::from_bytes([
+            0x4E, 0x45, 0x53, 0x1A, 0x29, 0x34, 0x00, 0x08, 0x00, 0xFF, 0, 0, 0, 0, 0, 0,
+        ])?;
+
+        assert_eq!(header.prg_rom_size, 3 * 1024);
+        assert_eq!(header.prg_rom_count(), 1);
+        assert_eq!(header.chr_rom_size, 8 * 1024);
+        assert!(!header.is_chr_ram);
+
+        // too large
+        let header = INesHeader::from_bytes([
+            0x4E, 0x45, 0x53, 0x1A, 0xFF, 0x00, 0x00, 0x08, 0x00, 0x0F, 0, 0, 0, 0, 0, 0,
+        ]);
+        assert!(matches!(header, Err(CartridgeError::HeaderError)));
+
+        Ok(())
+    }
+
+    const TEST_CREATION_ROM: &[u8] =
+        include_bytes!("../../../test_roms/cartridge_tests/test_creation.nes");
+
+    fn assert_test_creation_data(cartridge: &Cartridge) {
+        assert!(cartridge.prg_data.iter().all(|&c| c == 0xFF));
+        assert!(cartridge.chr_data.iter().all(|&c| c == 0xEE));
+    }
+
+    #[test]
+    fn test_cartridge_from_bytes() -> Result<(), CartridgeError> {
+        let cartridge = Cartridge::from_bytes(TEST_CREATION_ROM, "roms/test_creation.nes")?;
+
+        assert_test_creation_data(&cartridge);
+        assert_eq!(
+            cartridge.cartridge_path(),
+            std::path::Path::new("roms/test_creation.nes")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "archives")]
+    fn test_cartridge_from_gzip() -> Result<(), CartridgeError> {
+        use std::io::Write;
+
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), Default::default());
+        encoder.write_all(TEST_CREATION_ROM)?;
+        let data = encoder.finish()?;
+
+        let cartridge = Cartridge::from_bytes(&data, "roms/test_creation.nes.gz")?;
+
+        assert_test_creation_data(&cartridge);
+        // the save files are named after the file inside the archive
+        assert_eq!(
+            cartridge.cartridge_path(),
+            std::path::Path::new("roms/test_creation.nes")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "archives")]
+    fn test_cartridge_from_zip() -> Result<(), CartridgeError> {
+        use std::io::Write;
+        use zip::write::SimpleFileOptions;
+
+        let mut writer = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        writer
+            .start_file("readme.txt", SimpleFileOptions::default())
+            .unwrap();
+        writer.write_all(b"not a ROM")?;
+        writer
+            .start_file("Test (U).NES", SimpleFileOptions::default())
+            .unwrap();
+        writer.write_all(TEST_CREATION_ROM)?;
+        let data = writer.finish().unwrap().into_inner();
+
+        let cartridge = Cartridge::from_bytes(&data, "roms/test.zip")?;
+
+        assert_test_creation_data(&cartridge);
+        assert_eq!(
+            cartridge.cartridge_path(),
+            std::path::Path::new("roms/test")
+        );
+
+        // no ROM inside
+        let mut writer = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        writer
+            .start_file("readme.txt", SimpleFileOptions::default())
+            .unwrap();
+        writer.write_all(b"not a ROM")?;
+        let data = writer.finish().unwrap().into_inner();
+
+        assert!(matches!(
+            Cartridge::from_bytes(&data, "roms/test.zip"),
+            Err(CartridgeError::ArchiveError)
+        ));
+
+        Ok(())
+    }
+
+    /// Encode a BPS/UPS variable length number
+    fn encode_number(mut number: usize, output: &mut Vec<u8>) {
+        loop {
+            let byte = (number & 0x7F) as u8;
+            number >>= 7;
+            if number == 0 {
+                output.push(byte | 0x80);
+                break;
+            }
+            output.push(byte);
+            number -= 1;
+        }
+    }
+
+    /// Add the source, target and patch CRC32s
+    fn add_patch_footer(patch: &mut Vec<u8>, source: &[u8], target: &[u8]) {
+        patch.extend_from_slice(&crc32fast::hash(source).to_le_bytes());
+        patch.extend_from_slice(&crc32fast::hash(target).to_le_bytes());
+        patch.extend_from_slice(&crc32fast::hash(patch).to_le_bytes());
+    }
+
+    #[test]
+    fn test_ips_patch() -> Result<(), CartridgeError> {
+        let mut patch = b"PATCH".to_vec();
+        // 2 bytes at the start of PRG
+        patch.extend_from_slice(&[0x00, 0x00, 0x10, 0x00, 0x02, 0x12, 0x34]);
+        // RLE, 3 bytes after them
+        patch.extend_from_slice(&[0x00, 0x00, 0x12, 0x00, 0x00, 0x00, 0x03, 0x56]);
+        patch.extend_from_slice(b"EOF");
+
+        let cartridge = Cartridge::from_bytes_with_patches(
+            TEST_CREATION_ROM,
+            std::path::Path::new("test.nes"),
+            &[patch],
+        )?;
+
+        assert_eq!(
+            cartridge.prg_data[..6],
+            [0x12, 0x34, 0x56, 0x56, 0x56, 0xFF]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_bps_patch() -> Result<(), CartridgeError> {
+        let mut target = TEST_CREATION_ROM.to_vec();
+        target[0x10] = 0x00;
+
+        let mut patch = b"BPS1".to_vec();
+        encode_number(TEST_CREATION_ROM.len(), &mut patch);
+        encode_number(target.len(), &mut patch);
+        // no metadata
+        encode_number(0, &mut patch);
+        // source read the header
+        encode_number((0x10 - 1) << 2, &mut patch);
+        // target read one byte
+        encode_number(1, &mut patch);
+        patch.push(0x00);
+        // source read the rest
+        encode_number((target.len() - 0x11 - 1) << 2, &mut patch);
+        add_patch_footer(&mut patch, TEST_CREATION_ROM, &target);
+
+        assert_eq!(patch::apply_patch(TEST_CREATION_ROM, &patch)?, target);
+
+        // applying it on another ROM
+        assert!(matches!(
+            patch::apply_patch(&target, &patch),
+            Err(CartridgeError::PatchMismatch)
+        ));
+
+        // corrupted patch
+        patch[6] ^= 0xFF;
+        assert!(matches!(
+            patch::apply_patch(TEST_CREATION_ROM, &patch),
+            Err(CartridgeError::PatchError)
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_ups_patch() -> Result<(), CartridgeError> {
+        let mut target = TEST_CREATION_ROM.to_vec();
+        target[0x10] = 0x00;
+        target.push(0x42);
+
+        let mut patch = b"UPS1".to_vec();
+        encode_number(TEST_CREATION_ROM.len(), &mut patch);
+        encode_number(target.len(), &mut patch);
+        // 0xFF ^ 0xFF at the start of PRG
+        encode_number(0x10, &mut patch);
+        patch.extend_from_slice(&[0xFF, 0x00]);
+        // the new byte at the end
+        encode_number(TEST_CREATION_ROM.len() - 0x12, &mut patch);
+        patch.extend_from_slice(&[0x42, 0x00]);
+        add_patch_footer(&mut patch, TEST_CREATION_ROM, &target);
+
+        assert_eq!(patch::apply_patch(TEST_CREATION_ROM, &patch)?, target);
+
+        assert!(matches!(
+            patch::apply_patch(&target, &patch),
+            Err(CartridgeError::PatchMismatch)
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_patch_file_next_to_rom() -> Result<(), CartridgeError> {
+        let dir = std::env::temp_dir().join("plastic_patch_test");
+        std::fs::create_dir_all(&dir)?;
+        std::fs::write(dir.join("game.nes"), TEST_CREATION_ROM)?;
+
+        let mut patch = b"PATCH".to_vec();
+        patch.extend_from_slice(&[0x00, 0x00, 0x10, 0x00, 0x01, 0x12]);
+        patch.extend_from_slice(b"EOF");
+        std::fs::write(dir.join("game.ips"), patch)?;
+
+        let cartridge = Cartridge::from_file(dir.join("game.nes"))?;
+        assert_eq!(cartridge.prg_data[..2], [0x12, 0xFF]);
+
+        // explicit patches replace the one next to the ROM
+        let no_patches: &[&str] = &[];
+        let cartridge = Cartridge::from_file_with_patches(dir.join("game.nes"), no_patches)?;
+        assert_eq!(cartridge.prg_data[..2], [0xFF, 0xFF]);
+
+        std::fs::remove_dir_all(dir)?;
+
+        Ok(())
+    }
+
+    fn add_unif_chunk(data: &mut Vec<u8>, id: &[u8], chunk: &[u8]) {
+        data.extend_from_slice(id);
+        data.extend_from_slice(&(chunk.len() as u32).to_le_bytes());
+        data.extend_from_slice(chunk);
+    }
+
+    /// A UNIF file without battery, dropping a cartridge with a battery
+    /// would write a save file
+    fn unif_file(board: &str) -> Vec<u8> {
+        let mut data = b"UNIF".to_vec();
+        // revision
+        data.extend_from_slice(&7u32.to_le_bytes());
+        data.resize(0x20, 0);
+
+        add_unif_chunk(&mut data, b"MAPR", format!("{}\0", board).as_bytes());
+        add_unif_chunk(&mut data, b"NAME", b"test\0");
+        add_unif_chunk(&mut data, b"PRG0", &[0xFF; 0x4000]);
+        add_unif_chunk(&mut data, b"PRG1", &[0xFE; 0x4000]);
+        add_unif_chunk(&mut data, b"CHR0", &[0xEE; 0x2000]);
+        // vertical
+        add_unif_chunk(&mut data, b"MIRR", &[1]);
+
+        data
+    }
+
+    #[test]
+    fn test_unif_cartridge_read() -> Result<(), CartridgeError> {
+        let cartridge = Cartridge::from_bytes(&unif_file("NES-NROM-256"), "test.unf")?;
+
+        assert_eq!(cartridge.header.mapper_id, 0);
+        assert!(cartridge.header.hardwired_mirroring_vertical);
+        assert!(!cartridge.header.has_prg_ram_battery);
+        assert_eq!(cartridge.prg_data.len(), 0x8000);
+        assert!(cartridge.prg_data[..0x4000].iter().all(|&c| c == 0xFF));
+        assert!(cartridge.prg_data[0x4000..].iter().all(|&c| c == 0xFE));
+        assert_eq!(cartridge.chr_data.len(), 0x2000);
+        assert!(cartridge.chr_data.iter().all(|&c| c == 0xEE));
+
+        let cartridge = Cartridge::from_bytes(&unif_file("NES-SNROM"), "test.unf")?;
+        assert_eq!(cartridge.header.mapper_id, 1);
+
+        let mut data = unif_file("NES-NROM-256");
+        add_unif_chunk(&mut data, b"BATR", &[1]);
+        assert!(UnifFile::from_bytes(&data)?.has_battery);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_unif_unknown_board() {
+        let err = Cartridge::from_bytes(&unif_file("UNL-SOME-BOARD"), "test.unf")
+            .err()
+            .expect("Should get an error as the board is not known");
+
+        if let CartridgeError::UnknownUnifBoard(board) = err {
+            assert_eq!(board, "UNL-SOME-BOARD");
+        } else {
+            panic!("Should get unknown UNIF board error");
+        }
+    }
+
+    fn game_db_entry(rom_crc32: u32, rom_sha1: &str) -> String {
+        format!(
+            r#"<game>
+                <!-- 'Test Game (Europe).nes' -->
+                <rom size="40960" crc32="{:08X}" sha1="{}"/>
+                <prgnvram size="8192"/>
+                <pcb mapper="3" submapper="0" mirroring="V" battery="1"/>
+                <console type="0" region="1"/>
+            </game>"#,
+            rom_crc32, rom_sha1
+        )
+    }
+
+    #[test]
+    fn test_game_db_lookup() {
+        // PRG and CHR without the header
+        let rom = &TEST_CREATION_ROM[16..];
+        let crc32 = crc32fast::hash(rom);
+        let sha1 = sha1_smol::Sha1::from(rom).digest().to_string();
+
+        let db = GameDb::from_xml(&format!(
+            "<nes20db>{}</nes20db>",
+            game_db_entry(crc32, &sha1.to_ascii_uppercase())
+        ))
+        .unwrap();
+
+        let entry = db.find(rom).expect("Should find the game");
+        assert_eq!(entry.info.title, "Test Game (Europe)");
+        assert_eq!(entry.info.region, Region::Pal);
+        assert_eq!(entry.info.mapper_id, 3);
+
+        // same CRC32 but different SHA-1
+        let db = GameDb::from_xml(&format!(
+            "<nes20db>{}</nes20db>",
+            game_db_entry(crc32, &"0".repeat(40))
+        ))
+        .unwrap();
+        assert!(db.find(rom).is_none());
+
+        assert!(GameDb::from_xml(include_str!("game_db.xml")).is_ok());
+    }
+
+    #[test]
+    fn test_game_db_header_override() -> Result<(), CartridgeError> {
+        let rom = &TEST_CREATION_ROM[16..];
+        let sha1 = sha1_smol::Sha1::from(rom).digest().to_string();
+
+        let dir = std::env::temp_dir().join("plastic_game_db_test");
+        std::fs::create_dir_all(&dir)?;
+        std::fs::write(dir.join("game.nes"), TEST_CREATION_ROM)?;
+        std::fs::write(
+            dir.join("nes20db.xml"),
+            format!(
+                "<nes20db>{}</nes20db>",
+                game_db_entry(crc32fast::hash(rom), &sha1)
+            ),
+        )?;
+
+        let cartridge = Cartridge::from_file(dir.join("game.nes"))?;
+
+        assert_eq!(cartridge.header.mapper_id, 3);
+        assert!(cartridge.header.hardwired_mirroring_vertical);
+        assert!(cartridge.header.has_prg_ram_battery);
+        assert_eq!(cartridge.header.prg_sram_size, 0x2000);
+        assert_eq!(cartridge.region(), Region::Pal);
+        assert_eq!(
+            cartridge.game_info().map(|info| info.title.as_str()),
+            Some("Test Game (Europe)")
+        );
+
+        // the SRAM is saved when the cartridge is dropped
+        drop(cartridge);
+        std::fs::remove_dir_all(dir)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_builtin_game_db_fixes_bad_header() -> Result<(), CartridgeError> {
+        let mut rom = include_bytes!("../../../test_roms/instr_test-v5/official_only.nes").to_vec();
+        // mapper 0 with horizontal mirroring and a battery, instead of MMC1 with
+        // vertical mirroring and 8kb of PRG RAM without battery
+        rom[6] = 0x02;
+        rom[7] = 0x00;
+
+        let cartridge = Cartridge::from_bytes(&rom, "bad_header.nes")?;
+
+        assert_eq!(cartridge.header.mapper_id, 1);
+        assert!(cartridge.header.hardwired_mirroring_vertical);
+        assert!(!cartridge.header.has_prg_ram_battery);
+        assert_eq!(cartridge.header.prg_wram_size, 0x2000);
+        assert_eq!(cartridge.header.prg_sram_size, 0);
+        assert_eq!(cartridge.header.chr_wram_size, 0x2000);
+        assert_eq!(
+            cartridge.game_info().map(|info| info.title.as_str()),
+            Some("instr_test-v5 official_only")
+        );
+
+        Ok(())
+    }
+
+    /// Send the flash unlock sequence to the UNROM-512 `cartridge`, the command addresses
+    /// `$5555` and `$2AAA` are at `$9555` in bank 1 and `$AAAA` in bank 0
+    fn unrom512_flash_unlock(cartridge: &mut Cartridge) {
+        for (bank, address, data) in [(1, 0x9555, 0xAA), (0, 0xAAAA, 0x55)] {
+            cartridge.write(0xC000, bank, Device::Cpu);
+            cartridge.write(address, data, Device::Cpu);
+        }
+    }
+
+    fn unrom512_flash_command(cartridge: &mut Cartridge, command: u8) {
+        unrom512_flash_unlock(cartridge);
+        cartridge.write(0xC000, 1, Device::Cpu);
+        cartridge.write(0x9555, command, Device::Cpu);
+    }
+
+    #[test]
+    fn test_unrom512_flash_save() -> Result<(), CartridgeError> {
+        // mapper 30, self-flashable (battery), 32kb PRG ROM and CHR RAM
+        let mut rom = b"NES\x1A\x02\x00\xE2\x10".to_vec();
+        rom.resize(16, 0);
+        rom.resize(16 + 0x8000, 0xFF);
+
+        let dir = std::env::temp_dir().join("plastic_flash_test");
+        std::fs::create_dir_all(&dir)?;
+        std::fs::write(dir.join("game.nes"), rom)?;
+
+        let mut cartridge = Cartridge::from_file(dir.join("game.nes"))?;
+
+        unrom512_flash_command(&mut cartridge, 0x90);
+        assert_eq!(cartridge.read(0x8000, Device::Cpu), 0xBF);
+        assert_eq!(cartridge.read(0x8001, Device::Cpu), 0xB7);
+        // exit software ID mode
+        cartridge.write(0x8000, 0xF0, Device::Cpu);
+        assert_eq!(cartridge.read(0x8000, Device::Cpu), 0xFF);
+
+        for (address, data) in [(0x8010, 0x42), (0x9010, 0x43)] {
+            unrom512_flash_command(&mut cartridge, 0xA0);
+            cartridge.write(0xC000, 0, Device::Cpu);
+            cartridge.write(address, data, Device::Cpu);
+        }
+        assert_eq!(cartridge.prg_data[0x10], 0x42);
+        assert_eq!(cartridge.prg_data[0x1010], 0x43);
+
+        // erase the first sector
+        unrom512_flash_command(&mut cartridge, 0x80);
+        unrom512_flash_unlock(&mut cartridge);
+        cartridge.write(0x8000, 0x30, Device::Cpu);
+        assert!(cartridge.prg_data[0x0000..0x1000]
+            .iter()
+            .all(|&b| b == 0xFF));
+        assert_eq!(cartridge.prg_data[0x1010], 0x43);
+
+        // the flash is saved when the cartridge is dropped
+        drop(cartridge);
+        let cartridge = Cartridge::from_file(dir.join("game.nes"))?;
+        assert_eq!(cartridge.prg_data[0x1010], 0x43);
+
+        drop(cartridge);
+        std::fs::remove_dir_all(dir)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_namco163_internal_ram_save() -> Result<(), CartridgeError> {
+        // mapper 19 with a battery, 32kb PRG ROM and 8kb CHR ROM
+        let mut rom = b"NES\x1A\x02\x01\x32\x10".to_vec();
+        rom.resize(16 + 0x8000 + 0x2000, 0);
+
+        let dir = std::env::temp_dir().join("plastic_namco163_save_test");
+        std::fs::create_dir_all(&dir)?;
+        std::fs::write(dir.join("game.nes"), rom)?;
+
+        let mut cartridge = Cartridge::from_file(dir.join("game.nes"))?;
+
+        // enable PRG RAM writes
+        cartridge.write(0xF800, 0x40, Device::Cpu);
+        cartridge.write(0x6000, 0x55, Device::Cpu);
+
+        // internal RAM address $10 with auto increment
+        cartridge.write(0xF800, 0x90, Device::Cpu);
+        for data in [0x12, 0x34, 0x56] {
+            cartridge.write(0x4800, data, Device::Cpu);
+        }
+        cartridge.write(0xF800, 0xFF, Device::Cpu);
+        cartridge.write(0x4800, 0x78, Device::Cpu);
+
+        // the internal RAM is saved after the PRG RAM when the cartridge is dropped
+        drop(cartridge);
+        let save = std::fs::read(dir.join("game.nes.sav"))?;
+        assert_eq!(save.len(), 0x2000 + 0x80);
+        assert_eq!(save[0], 0x55);
+        assert_eq!(save[0x2000 + 0x10..0x2000 + 0x13], [0x12, 0x34, 0x56]);
+        assert_eq!(save[0x2000 + 0x7F], 0x78);
+
+        let mut cartridge = Cartridge::from_file(dir.join("game.nes"))?;
+        assert_eq!(cartridge.read(0x6000, Device::Cpu), 0x55);
+
+        cartridge.write(0xF800, 0x90, Device::Cpu);
+        for data in [0x12, 0x34, 0x56] {
+            assert_eq!(cartridge.read(0x4800, Device::Cpu), data);
+        }
+        cartridge.write(0xF800, 0x7F, Device::Cpu);
+        assert_eq!(cartridge.read(0x4800, Device::Cpu), 0x78);
+
+        drop(cartridge);
+        std::fs::remove_dir_all(dir)?;
+
+        Ok(())
+    }
+
+    /// A headerless `.fds` image with 2 empty sides, next to a blank BIOS in `dir`
+    fn write_fds_image(dir: &std::path::Path) -> std::io::Result<()> {
+        let mut side = b"\x01*NINTENDO-HVC*".to_vec();
+        side.resize(65500, 0);
+
+        std::fs::create_dir_all(dir)?;
+        std::fs::write(dir.join("game.fds"), [side.as_slice(), &side].concat())?;
+        std::fs::write(dir.join("disksys.rom"), [0; 0x2000])
+    }
+
+    #[test]
+    fn test_fds_insert_eject_disk() -> Result<(), CartridgeError> {
+        let dir = std::env::temp_dir().join("plastic_fds_insert_test");
+        write_fds_image(&dir)?;
+
+        let mut cartridge = Cartridge::from_file(dir.join("game.fds"))?;
+        // enable the disk registers
+        cartridge.write(0x4023, 0x01, Device::Cpu);
+
+        assert_eq!(cartridge.disk_sides_count(), 2);
+        assert_eq!(cartridge.inserted_disk_side(), Some(0));
+        assert_eq!(cartridge.read(0x4032, Device::Cpu) & 1, 0);
+
+        cartridge.set_inserted_disk_side(None);
+        assert_eq!(cartridge.inserted_disk_side(), None);
+        assert_eq!(cartridge.read(0x4032, Device::Cpu) & 1, 1);
+
+        cartridge.set_inserted_disk_side(Some(1));
+        assert_eq!(cartridge.inserted_disk_side(), Some(1));
+        assert_eq!(cartridge.read(0x4032, Device::Cpu) & 1, 0);
+
+        // inserting a side that does not exist ejects the disk
+        cartridge.set_inserted_disk_side(Some(2));
+        assert_eq!(cartridge.inserted_disk_side(), None);
+
+        drop(cartridge);
+        // nothing was written to the disk
+        assert!(!dir.join("game.fds.sav").exists());
+        std::fs::remove_dir_all(dir)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_fds_disk_diff_save() -> Result<(), CartridgeError> {
+        let dir = std::env::temp_dir().join("plastic_fds_save_test");
+        write_fds_image(&dir)?;
+
+        let mut cartridge = Cartridge::from_file(dir.join("game.fds"))?;
+        cartridge.set_inserted_disk_side(Some(1));
+
+        // write one byte at the start of the second side: motor on, write mode,
+        // and wait for the head to move back to the start
+        cartridge.write(0x4023, 0x01, Device::Cpu);
+        cartridge.write(0x4024, 0xAB, Device::Cpu);
+        cartridge.write(0x4025, 0x41, Device::Cpu);
+        for _ in 0..50002 {
+            cartridge.clock();
+        }
+        cartridge.write(0x4025, 0x40, Device::Cpu);
+        assert_eq!(cartridge.mapper.disk_sides()[1][0], 0xAB);
+        assert_eq!(cartridge.mapper.disk_sides()[1][1], 0x00);
+
+        // only the difference from the image is saved when the cartridge is dropped
+        drop(cartridge);
+        assert_eq!(
+            std::fs::read(dir.join("game.fds.sav"))?,
+            [1, 0, 0, 0, 0, 1, 0, 0, 0, 0xAB]
+        );
+
+        let cartridge = Cartridge::from_file(dir.join("game.fds"))?;
+        assert_eq!(cartridge.mapper.disk_sides()[0][0], 0x00);
+        assert_eq!(cartridge.mapper.disk_sides()[1][0], 0xAB);
+        drop(cartridge);
+
+        // a corrupted save file is ignored
+        std::fs::write(dir.join("game.fds.sav"), [5, 0, 0])?;
+        let cartridge = Cartridge::from_file(dir.join("game.fds"))?;
+        assert_eq!(cartridge.mapper.disk_sides()[1][0], 0x00);
+
+        drop(cartridge);
+        std::fs::remove_dir_all(dir)?;
+
+        Ok(())
+    }
+
+    /// Set the SCL and SDA lines of the EEPROM through register $D
+    fn eeprom_lines(cartridge: &mut Cartridge, scl: bool, sda: bool) {
+        cartridge.write(0x800D, ((sda as u8) << 6) | ((scl as u8) << 5), Device::Cpu);
+    }
+
+    fn eeprom_start(cartridge: &mut Cartridge) {
+        eeprom_lines(cartridge, true, true);
+        eeprom_lines(cartridge, true, false);
+        eeprom_lines(cartridge, false, false);
+    }
+
+    fn eeprom_stop(cartridge: &mut Cartridge) {
+        eeprom_lines(cartridge, false, false);
+        eeprom_lines(cartridge, true, false);
+        eeprom_lines(cartridge, true, true);
+    }
+
+    fn eeprom_send_bit(cartridge: &mut Cartridge, bit: bool) {
+        eeprom_lines(cartridge, false, bit);
+        eeprom_lines(cartridge, true, bit);
+        eeprom_lines(cartridge, false, bit);
+    }
+
+    fn eeprom_receive_bit(cartridge: &mut Cartridge) -> bool {
+        eeprom_lines(cartridge, false, true);
+        eeprom_lines(cartridge, true, true);
+        let bit = cartridge.read(0x6000, Device::Cpu) & 0x10 != 0;
+        eeprom_lines(cartridge, false, true);
+        bit
+    }
+
+    /// Send a byte LSB first (24C01) and return if it was acknowledged
+    fn eeprom_send_byte(cartridge: &mut Cartridge, data: u8) -> bool {
+        for i in 0..8 {
+            eeprom_send_bit(cartridge, (data >> i) & 1 != 0);
+        }
+        !eeprom_receive_bit(cartridge)
+    }
+
+    #[test]
+    fn test_bandai_24c01_eeprom_save() -> Result<(), CartridgeError> {
+        // mapper 159, battery, 32kb PRG ROM and 8kb CHR ROM
+        let mut rom = b"NES\x1A\x02\x01\xF2\x90".to_vec();
+        rom.resize(16 + 0x8000 + 0x2000, 0);
+
+        let dir = std::env::temp_dir().join("plastic_eeprom_test");
+        std::fs::create_dir_all(&dir)?;
+        std::fs::write(dir.join("game.nes"), rom)?;
+
+        let mut cartridge = Cartridge::from_file(dir.join("game.nes"))?;
+
+        // write 0x5A to address 0x12
+        eeprom_start(&mut cartridge);
+        assert!(eeprom_send_byte(&mut cartridge, 0x12));
+        assert!(eeprom_send_byte(&mut cartridge, 0x5A));
+        eeprom_stop(&mut cartridge);
+
+        // read it back, the direction bit is the last bit of the address
+        eeprom_start(&mut cartridge);
+        assert!(eeprom_send_byte(&mut cartridge, 0x80 | 0x12));
+        let mut data = 0;
+        for i in 0..8 {
+            data |= (eeprom_receive_bit(&mut cartridge) as u8) << i;
+        }
+        // no acknowledge to end the read
+        eeprom_send_bit(&mut cartridge, true);
+        eeprom_stop(&mut cartridge);
+        assert_eq!(data, 0x5A);
+
+        // the EEPROM is saved when the cartridge is dropped
+        drop(cartridge);
+        let cartridge = Cartridge::from_file(dir.join("game.nes"))?;
+        assert_eq!(cartridge.mapper.battery_backed_ram()[0x12], 0x5A);
+
+        drop(cartridge);
+        std::fs::remove_dir_all(dir)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_tqrom_chr_ram_banks() -> Result<(), CartridgeError> {
+        // mapper 119, 32kb PRG ROM and 8kb CHR ROM, the board has 8kb CHR RAM too
+        let mut rom = b"NES\x1A\x02\x01\x70\x70".to_vec();
+        rom.resize(16 + 0x8000, 0);
+        // every 1kb CHR ROM bank is filled with its number
+        for bank in 0..8 {
+            rom.extend_from_slice(&[bank; 0x400]);
+        }
+
+        let mut cartridge = Cartridge::from_bytes(&rom, "tqrom.nes")?;
+        let set_chr_bank = |cartridge: &mut Cartridge, register: u8, bank: u8| {
+            cartridge.write(0x8000, register, Device::Cpu);
+            cartridge.write(0x8001, bank, Device::Cpu);
+        };
+
+        // R0 (2kb at $0000) to CHR RAM, bit 6 set, and R2 (1kb at $1000) to CHR ROM
+        set_chr_bank(&mut cartridge, 0, 0x40);
+        set_chr_bank(&mut cartridge, 2, 0x01);
+
+        cartridge.write(0x0010, 0x42, Device::Ppu);
+        cartridge.write(0x0410, 0x43, Device::Ppu);
+        assert_eq!(cartridge.read(0x0010, Device::Ppu), 0x42);
+        assert_eq!(cartridge.read(0x0410, Device::Ppu), 0x43);
+        // the CHR RAM is after the CHR ROM
+        assert_eq!(cartridge.chr_data[0x2000 + 0x10], 0x42);
+        assert_eq!(cartridge.chr_data[0x2000 + 0x410], 0x43);
+
+        cartridge.write(0x1010, 0x44, Device::Ppu);
+        assert_eq!(cartridge.read(0x1010, Device::Ppu), 0x01);
+
+        // back to CHR ROM, which can't be written
+        set_chr_bank(&mut cartridge, 0, 0x02);
+        assert_eq!(cartridge.read(0x0010, Device::Ppu), 0x02);
+        cartridge.write(0x0010, 0x45, Device::Ppu);
+        assert_eq!(cartridge.read(0x0010, Device::Ppu), 0x02);
+        assert!(cartridge.chr_data[..0x2000]
+            .chunks(0x400)
+            .enumerate()
+            .all(|(bank, data)| data.iter().all(|&b| b == bank as u8)));
+
+        // the CHR RAM keeps its content
+        set_chr_bank(&mut cartridge, 0, 0x40);
+        assert_eq!(cartridge.read(0x0010, Device::Ppu), 0x42);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_mmc2_peek_keeps_latches() -> Result<(), CartridgeError> {
+        // mapper 9, 32kb PRG ROM and 16kb CHR ROM
+        let mut rom = b"NES\x1A\x02\x02\x90\x00".to_vec();
+        rom.resize(16 + 0x8000, 0);
+        // every 4kb CHR ROM bank is filled with its number
+        for bank in 0..4 {
+            rom.extend_from_slice(&[bank; 0x1000]);
+        }
+
+        let mut cartridge = Cartridge::from_bytes(&rom, "mmc2.nes")?;
+        // $FD bank 1 and $FE bank 2 at $0000
+        cartridge.write(0xB000, 1, Device::Cpu);
+        cartridge.write(0xC000, 2, Device::Cpu);
+        assert_eq!(cartridge.read(0x0000, Device::Ppu), 2);
+
+        // peeking the latch address doesn't switch the bank
+        cartridge.peek(0x0FD8, Device::Ppu);
+        assert_eq!(cartridge.peek(0x0000, Device::Ppu), 2);
+        assert_eq!(cartridge.read(0x0000, Device::Ppu), 2);
+
+        cartridge.read(0x0FD8, Device::Ppu);
+        assert_eq!(cartridge.peek(0x0000, Device::Ppu), 1);
+
+        // the CHR ROM is not written
+        cartridge.poke(0x0000, 0x42, Device::Ppu);
+        assert_eq!(cartridge.peek(0x0000, Device::Ppu), 1);
+
+        Ok(())
+    }
+
+    /// An MMC5 cartridge with 128kb PRG ROM, 64kb CHR ROM and 8kb PRG RAM, every
+    /// 8kb PRG bank and 1kb CHR bank is filled with its number
+    fn mmc5_cartridge() -> Result<Cartridge, CartridgeError> {
+        let mut rom = b"NES\x1A\x08\x08\x50\x00".to_vec();
+        rom.resize(16, 0);
+        for bank in 0..16 {
+            rom.extend_from_slice(&[bank; 0x2000]);
+        }
+        for bank in 0..64 {
+            rom.extend_from_slice(&[bank; 0x400]);
+        }
+
+        Cartridge::from_bytes(&rom, "mmc5.nes")
+    }
+
+    /// Make the MMC5 detect a scanline, by fetching the same nametable address 3 times
+    fn mmc5_scanline(cartridge: &Cartridge, address: u16) {
+        for _ in 0..3 {
+            cartridge.nametable_mapping(address);
+        }
+    }
+
+    #[test]
+    fn test_mmc5_peek_keeps_frame_state() -> Result<(), CartridgeError> {
+        let mut cartridge = mmc5_cartridge()?;
+        cartridge.snoop_ppu_register_write(0x2001, 0x18);
+        // IRQ at scanline 1
+        cartridge.write(0x5203, 1, Device::Cpu);
+        cartridge.write(0x5204, 0x80, Device::Cpu);
+
+        mmc5_scanline(&cartridge, 0x2000);
+        mmc5_scanline(&cartridge, 0x2001);
+        // IRQ pending and in frame
+        assert_eq!(cartridge.peek(0x5204, Device::Cpu), 0xC0);
+
+        // reading the NMI vector leaves the frame, but not peeking it
+        cartridge.peek(0xFFFA, Device::Cpu);
+        cartridge.peek(0xFFFB, Device::Cpu);
+        assert_eq!(cartridge.peek(0x5204, Device::Cpu), 0xC0);
+        assert!(cartridge.irq_pin_state());
+
+        cartridge.read(0xFFFA, Device::Cpu);
+        assert_eq!(cartridge.peek(0x5204, Device::Cpu), 0x80);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_mmc5_prg_modes() -> Result<(), CartridgeError> {
+        let mut cartridge = mmc5_cartridge()?;
+        let read_slots = |cartridge: &Cartridge| {
+            [0x8000, 0xA000, 0xC000, 0xE000].map(|address| cartridge.read(address, Device::Cpu))
+        };
+
+        // mode 0: one 32kb bank from $5117
+        cartridge.write(0x5100, 0, Device::Cpu);
+        cartridge.write(0x5117, 0x87, Device::Cpu);
+        assert_eq!(read_slots(&cartridge), [4, 5, 6, 7]);
+
+        // mode 1: two 16kb banks from $5115 and $5117
+        cartridge.write(0x5100, 1, Device::Cpu);
+        cartridge.write(0x5115, 0x82, Device::Cpu);
+        cartridge.write(0x5117, 0x8B, Device::Cpu);
+        assert_eq!(read_slots(&cartridge), [2, 3, 10, 11]);
+
+        // mode 2: one 16kb bank from $5115, and two 8kb banks from $5116 and $5117
+        cartridge.write(0x5100, 2, Device::Cpu);
+        cartridge.write(0x5115, 0x84, Device::Cpu);
+        cartridge.write(0x5116, 0x89, Device::Cpu);
+        cartridge.write(0x5117, 0x8F, Device::Cpu);
+        assert_eq!(read_slots(&cartridge), [4, 5, 9, 15]);
+
+        // mode 3: four 8kb banks from $5114-$5117
+        cartridge.write(0x5100, 3, Device::Cpu);
+        cartridge.write(0x5114, 0x81, Device::Cpu);
+        cartridge.write(0x5115, 0x83, Device::Cpu);
+        cartridge.write(0x5116, 0x85, Device::Cpu);
+        cartridge.write(0x5117, 0x8E, Device::Cpu);
+        assert_eq!(read_slots(&cartridge), [1, 3, 5, 14]);
+
+        // bit 7 clear maps PRG RAM, which is writable after the $5102/$5103 unlock
+        cartridge.write(0x5114, 0x00, Device::Cpu);
+        cartridge.write(0x8000, 0x42, Device::Cpu);
+        assert_eq!(cartridge.read(0x8000, Device::Cpu), 0x00);
+        cartridge.write(0x5102, 0x02, Device::Cpu);
+        cartridge.write(0x5103, 0x01, Device::Cpu);
+        cartridge.write(0x8000, 0x42, Device::Cpu);
+        assert_eq!(cartridge.read(0x8000, Device::Cpu), 0x42);
+        assert_eq!(cartridge.read(0x6000, Device::Cpu), 0x42);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_mmc5_multiplier() -> Result<(), CartridgeError> {
+        let mut cartridge = mmc5_cartridge()?;
+
+        cartridge.write(0x5205, 200, Device::Cpu);
+        cartridge.write(0x5206, 100, Device::Cpu);
+        // 20000 = $4E20
+        assert_eq!(cartridge.read(0x5205, Device::Cpu), 0x20);
+        assert_eq!(cartridge.read(0x5206, Device::Cpu), 0x4E);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_mmc5_exram_modes() -> Result<(), CartridgeError> {
+        let mut cartridge = mmc5_cartridge()?;
+
+        // mode 2: CPU RAM
+        cartridge.write(0x5104, 2, Device::Cpu);
+        cartridge.write(0x5C10, 0x42, Device::Cpu);
+        assert_eq!(cartridge.read(0x5C10, Device::Cpu), 0x42);
+
+        // mode 3: read only
+        cartridge.write(0x5104, 3, Device::Cpu);
+        cartridge.write(0x5C10, 0x43, Device::Cpu);
+        assert_eq!(cartridge.read(0x5C10, Device::Cpu), 0x42);
+
+        // mode 0: used as a nametable, not readable by the CPU
+        cartridge.write(0x5104, 0, Device::Cpu);
+        assert_eq!(cartridge.read(0x5C10, Device::Cpu), 0x00);
+        // all the nametables from ExRAM
+        cartridge.write(0x5105, 0xAA, Device::Cpu);
+        assert_eq!(
+            cartridge.nametable_mapping(0x2410),
+            NametableMapping::Cartridge
+        );
+        assert_eq!(cartridge.read(0x2410, Device::Ppu), 0x42);
+
+        // ExRAM is not a nametable in mode 2
+        cartridge.write(0x5104, 2, Device::Cpu);
+        assert_eq!(cartridge.peek(0x2410, Device::Ppu), 0x00);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_mmc5_scanline_irq() -> Result<(), CartridgeError> {
+        let mut cartridge = mmc5_cartridge()?;
+        cartridge.snoop_ppu_register_write(0x2001, 0x18);
+        cartridge.write(0x5203, 2, Device::Cpu);
+        cartridge.write(0x5204, 0x80, Device::Cpu);
+
+        // the first scanline starts the frame at scanline 0
+        mmc5_scanline(&cartridge, 0x2000);
+        mmc5_scanline(&cartridge, 0x2001);
+        assert_eq!(cartridge.peek(0x5204, Device::Cpu), 0x40);
+        assert!(!cartridge.irq_pin_state());
+
+        mmc5_scanline(&cartridge, 0x2002);
+        assert!(cartridge.irq_pin_state());
+
+        // reading $5204 acknowledges the IRQ
+        assert_eq!(cartridge.read(0x5204, Device::Cpu), 0xC0);
+        assert!(!cartridge.irq_pin_state());
+        assert_eq!(cartridge.read(0x5204, Device::Cpu), 0x40);
+
+        Ok(())
+    }
+
+    /// An NSFe chunk: length, `id` and `data`
+    fn nsfe_chunk(id: &[u8; 4], data: &[u8]) -> Vec<u8> {
+        [&(data.len() as u32).to_le_bytes(), id.as_slice(), data].concat()
+    }
+
+    fn milliseconds(values: &[i32]) -> Vec<u8> {
+        values
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect()
+    }
+
+    #[test]
+    fn test_nsf_header() -> Result<(), CartridgeError> {
+        let nsf = NsfFile::from_bytes(&nsf_file(&[0x60], 0x02, [0; 8]))?;
+
+        assert_eq!(nsf.info.title, "Test Song");
+        assert_eq!(nsf.info.artist, "Composer");
+        assert_eq!(nsf.info.copyright, "2024");
+        assert_eq!(nsf.info.tracks.len(), 3);
+        assert_eq!(nsf.info.starting_track, 1);
+        assert!(nsf.info.tracks.iter().all(|track| track.length.is_none()));
+        assert_eq!(nsf.load_address, 0x8000);
+        assert_eq!(nsf.init_address, 0x8000);
+        assert_eq!(nsf.play_address, 0x8010);
+        assert_eq!(nsf.ntsc_play_speed, 10000);
+        assert_eq!(nsf.pal_play_speed, 20000);
+        // Dendy uses the PAL rate
+        assert_eq!(nsf.dendy_play_speed, 20000);
+        assert_eq!(nsf.timing_mode, TimingMode::MultipleRegion);
+        assert_eq!(nsf.bank_init, None);
+        assert_eq!(nsf.data, [0x60]);
+
+        // NSF2 with metadata chunks after the program
+        let mut data = nsf_file(&[0x60], 0x01, [0, 1, 0, 0, 0, 0, 0, 0]);
+        data[5] = 2;
+        data[0x7D] = 1;
+        data.extend(nsfe_chunk(b"time", &milliseconds(&[90000, -1, 1500])));
+        data.extend(nsfe_chunk(b"fade", &milliseconds(&[2000])));
+        data.extend(nsfe_chunk(b"tlbl", b"Intro\0Stage\0Ending"));
+
+        let nsf = NsfFile::from_bytes(&data)?;
+        let tracks = &nsf.info.tracks;
+        assert_eq!(nsf.data, [0x60]);
+        assert_eq!(nsf.timing_mode, TimingMode::Pal);
+        assert_eq!(nsf.bank_init, Some([0, 1, 0, 0, 0, 0, 0, 0]));
+        assert_eq!(tracks[0].length, Some(Duration::from_secs(90)));
+        assert_eq!(tracks[0].fade, Some(Duration::from_secs(2)));
+        assert_eq!(tracks[1].length, None);
+        assert_eq!(tracks[1].fade, None);
+        assert_eq!(tracks[2].length, Some(Duration::from_millis(1500)));
+        assert_eq!(tracks[2].label.as_deref(), Some("Ending"));
+
+        // the program must be in `$8000-$FFFF` without the FDS
+        let mut data = nsf_file(&[0x60], 0x00, [0; 8]);
+        data[0x09] = 0x60;
+        assert!(matches!(
+            NsfFile::from_bytes(&data),
+            Err(CartridgeError::NsfFileError)
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_nsfe_chunks() -> Result<(), CartridgeError> {
+        let info = [0x00, 0x80, 0x03, 0x80, 0x06, 0x80, 0x01, 0x00, 0x02, 0x01];
+        let chunks = [
+            nsfe_chunk(b"INFO", &info),
+            nsfe_chunk(b"DATA", &[0x60; 8]),
+            nsfe_chunk(b"BANK", &[0, 1]),
+            nsfe_chunk(b"RATE", &0x1234u16.to_le_bytes()),
+            nsfe_chunk(b"auth", b"Title\0Artist\0Copyright\0Ripper"),
+            nsfe_chunk(b"time", &milliseconds(&[5000, -1])),
+            nsfe_chunk(b"fade", &milliseconds(&[1000])),
+            nsfe_chunk(b"tlbl", b"Intro\0Loop"),
+            // optional chunks are ignored
+            nsfe_chunk(b"xtra", &[1, 2, 3]),
+            nsfe_chunk(b"NEND", &[]),
+        ];
+        let mut data = [b"NSFE".as_slice(), &chunks.concat()].concat();
+        // nothing is read after `NEND`
+        data.extend_from_slice(b"garbage");
+
+        let nsf = NsfFile::from_bytes(&data)?;
+        let info = &nsf.info;
+        assert_eq!(nsf.load_address, 0x8000);
+        assert_eq!(nsf.init_address, 0x8003);
+        assert_eq!(nsf.play_address, 0x8006);
+        assert_eq!(nsf.timing_mode, TimingMode::Pal);
+        assert_eq!(nsf.data, [0x60; 8]);
+        assert_eq!(nsf.bank_init, Some([0, 1, 0, 0, 0, 0, 0, 0]));
+        assert_eq!(nsf.ntsc_play_speed, 0x1234);
+        // the default rates are used for the missing ones
+        assert_eq!(nsf.pal_play_speed, 19997);
+        assert_eq!(nsf.dendy_play_speed, 19997);
+
+        assert_eq!(info.title, "Title");
+        assert_eq!(info.artist, "Artist");
+        assert_eq!(info.copyright, "Copyright");
+        assert_eq!(info.ripper, "Ripper");
+        assert_eq!(info.tracks.len(), 2);
+        assert_eq!(info.starting_track, 1);
+        assert_eq!(info.tracks[0].length, Some(Duration::from_secs(5)));
+        assert_eq!(info.tracks[0].fade, Some(Duration::from_secs(1)));
+        assert_eq!(info.tracks[0].label.as_deref(), Some("Intro"));
+        assert_eq!(info.tracks[1].length, None);
+        assert_eq!(info.tracks[1].fade, None);
+        assert_eq!(info.tracks[1].label.as_deref(), Some("Loop"));
+
+        // unknown required chunk, missing `INFO` and truncated chunk
+        for data in [
+            [
+                b"NSFE".as_slice(),
+                &chunks[..2].concat(),
+                &nsfe_chunk(b"ABCD", &[]),
+            ]
+            .concat(),
+            [b"NSFE".as_slice(), &chunks[1]].concat(),
+            [b"NSFE".as_slice(), &chunks[0], &chunks[1][..10]].concat(),
+        ] {
+            assert!(matches!(
+                NsfFile::from_bytes(&data),
+                Err(CartridgeError::NsfFileError)
+            ));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_nsf_play_rate() -> Result<(), CartridgeError> {
+        let mut cartridge = Cartridge::from_bytes(&nsf_file(&[0x60], 0x02, [0; 8]), "test.nsf")?;
+
+        // 10ms on NTSC and 20ms on PAL, in CPU cycles
+        for (region, period) in [(Region::Ntsc, 17897), (Region::Pal, 33252)] {
+            cartridge.set_region(region);
+
+            for _ in 0..period - 1 {
+                cartridge.clock();
+            }
+            assert_eq!(cartridge.read(0x41FF, Device::Cpu), 0);
+            cartridge.clock();
+            assert_eq!(cartridge.read(0x41FF, Device::Cpu), 1);
+            // acknowledged by the read
+            assert_eq!(cartridge.read(0x41FF, Device::Cpu), 0);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_nsf_bankswitch_init() -> Result<(), CartridgeError> {
+        // 4 banks of 4kb filled with their number
+        let program = (0..4).flat_map(|bank| [bank; 0x1000]).collect::<Vec<u8>>();
+        let mut cartridge =
+            Cartridge::from_bytes(&nsf_file(&program, 0, [3, 2, 1, 0, 0, 0, 0, 5]), "test.nsf")?;
+
+        for (address, bank) in [(0x8000, 3), (0x9000, 2), (0xA000, 1), (0xB000, 0)] {
+            assert_eq!(cartridge.read(address, Device::Cpu), bank);
+        }
+        // banks wrap around the program size
+        assert_eq!(cartridge.read(0xF000, Device::Cpu), 1);
+
+        cartridge.write(0x5FF8, 2, Device::Cpu);
+        assert_eq!(cartridge.read(0x8000, Device::Cpu), 2);
+
+        // the banks are set again when the track starts
+        cartridge.nsf_mut().unwrap().set_track(0);
+        assert_eq!(cartridge.read(0x8000, Device::Cpu), 3);
+
+        // without bank switching, the program is mapped at the load address
+        let mut cartridge = Cartridge::from_bytes(&nsf_file(&program, 0, [0; 8]), "test.nsf")?;
+        cartridge.write(0x5FF8, 2, Device::Cpu);
+        for (address, bank) in [(0x8000, 0), (0x9000, 1), (0xB000, 3), (0xC000, 0)] {
+            assert_eq!(cartridge.read(address, Device::Cpu), bank);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_nsf_set_track() -> Result<(), CartridgeError> {
+        let mut cartridge = Cartridge::from_bytes(&nsf_file(&[0x60], 0x02, [0; 8]), "test.nsf")?;
+        assert_eq!(cartridge.nsf().unwrap().current_track(), 1);
+
+        // the driver calls the init routine with the track in `A` and the region in `X`
+        for (track, region, expected_track, expected_region) in [
+            (2, Region::Ntsc, 2, 0),
+            (3, Region::Pal, 0, 1),
+            (7, Region::Dendy, 1, 2),
+        ] {
+            cartridge.set_region(region);
+            cartridge.nsf_mut().unwrap().set_track(track);
+
+            assert_eq!(cartridge.nsf().unwrap().current_track(), expected_track);
+            // LDA #track, LDX #region, JSR init
+            assert_eq!(cartridge.read(0x4138, Device::Cpu), expected_track as u8);
+            assert_eq!(cartridge.read(0x413A, Device::Cpu), expected_region);
+            assert_eq!(cartridge.read(0x413C, Device::Cpu), 0x00);
+            assert_eq!(cartridge.read(0x413D, Device::Cpu), 0x80);
+        }
+        // the reset vector points to the driver
+        assert_eq!(cartridge.read(0xFFFC, Device::Cpu), 0x00);
+        assert_eq!(cartridge.read(0xFFFD, Device::Cpu), 0x41);
+
+        Ok(())
+    }
+
+    /// A VRC2/VRC4 cartridge with 128kb PRG ROM and 128kb CHR ROM, every 1kb CHR bank
+    /// is filled with its number, the header is iNES 1.0 for submapper 0
+    fn vrc_cartridge(mapper_id: u8, submapper_id: u8) -> Result<Cartridge, CartridgeError> {
+        let mut rom = b"NES\x1A\x08\x10".to_vec();
+        rom.push(mapper_id << 4);
+        if submapper_id == 0 {
+            rom.push(mapper_id & 0xF0);
+        } else {
+            rom.push(mapper_id & 0xF0 | 0x08);
+            rom.push(submapper_id << 4);
+        }
+        rom.resize(16 + 0x20000, 0);
+        for bank in 0..128 {
+            rom.extend_from_slice(&[bank; 0x400]);
+        }
+
+        Cartridge::from_bytes(&rom, "vrc.nes")
+    }
+
+    /// The address of `register` (0-3) in the `base` range, using the
+    /// address `lines` connected to the register select pins (bit0, bit1)
+    fn vrc_register(base: u16, register: u16, lines: (u16, u16)) -> u16 {
+        let mut address = base;
+        if register & 1 != 0 {
+            address |= lines.0;
+        }
+        if register & 2 != 0 {
+            address |= lines.1;
+        }
+        address
+    }
+
+    #[test]
+    fn test_vrc_register_address_lines() -> Result<(), CartridgeError> {
+        // (mapper, submapper, [(bit0 line, bit1 line)], CHR bank shift),
+        // iNES 1.0 files use all the lines of the boards of the same mapper
+        let boards: [(u8, u8, &[(u16, u16)], u8); 12] = [
+            (21, 0, &[(1 << 1, 1 << 2), (1 << 6, 1 << 7)], 0),
+            (21, 1, &[(1 << 1, 1 << 2)], 0),
+            (21, 2, &[(1 << 6, 1 << 7)], 0),
+            // VRC2a ignores the lowest bit of the CHR banks
+            (22, 0, &[(1 << 1, 1 << 0)], 1),
+            (23, 0, &[(1 << 0, 1 << 1), (1 << 2, 1 << 3)], 0),
+            (23, 1, &[(1 << 0, 1 << 1)], 0),
+            (23, 2, &[(1 << 2, 1 << 3)], 0),
+            (23, 3, &[(1 << 0, 1 << 1)], 0),
+            (25, 0, &[(1 << 1, 1 << 0), (1 << 3, 1 << 2)], 0),
+            (25, 1, &[(1 << 1, 1 << 0)], 0),
+            (25, 2, &[(1 << 3, 1 << 2)], 0),
+            (25, 3, &[(1 << 1, 1 << 0)], 0),
+        ];
+
+        for (mapper_id, submapper_id, lines, chr_shift) in boards {
+            for &lines in lines {
+                let mut cartridge = vrc_cartridge(mapper_id, submapper_id)?;
+
+                // CHR bank 0 low/high and CHR bank 1 low/high
+                for (register, data) in [0x5, 0x1, 0x3, 0x2].into_iter().enumerate() {
+                    let address = vrc_register(0xB000, register as u16, lines);
+                    cartridge.write(address, data, Device::Cpu);
+                }
+
+                let board = (mapper_id, submapper_id, lines);
+                assert_eq!(
+                    cartridge.read(0x0000, Device::Ppu),
+                    0x15 >> chr_shift,
+                    "{board:?}"
+                );
+                assert_eq!(
+                    cartridge.read(0x0400, Device::Ppu),
+                    0x23 >> chr_shift,
+                    "{board:?}"
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_vrc_irq_modes() -> Result<(), CartridgeError> {
+        // VRC4a, the registers are selected with A1 and A2
+        let mut cartridge = vrc_cartridge(21, 1)?;
+        let irq_register = |register| vrc_register(0xF000, register, (1 << 1, 1 << 2));
+
+        let set_irq = |cartridge: &mut Cartridge, latch: u8, control: u8| {
+            cartridge.write(irq_register(0), latch & 0xF, Device::Cpu);
+            cartridge.write(irq_register(1), latch >> 4, Device::Cpu);
+            cartridge.write(irq_register(2), control, Device::Cpu);
+        };
+
+        // cycle mode, the counter is clocked every CPU cycle
+        set_irq(&mut cartridge, 0xFE, 0x06);
+        cartridge.clock();
+        assert!(!cartridge.irq_pin_state());
+        cartridge.clock();
+        assert!(cartridge.irq_pin_state());
+
+        // acknowledging without enable after acknowledgement disables the IRQ
+        cartridge.write(irq_register(3), 0, Device::Cpu);
+        assert!(!cartridge.irq_pin_state());
+        for _ in 0..0x200 {
+            cartridge.clock();
+        }
+        assert!(!cartridge.irq_pin_state());
+
+        // scanline mode, 3 scanlines take 341 CPU cycles
+        set_irq(&mut cartridge, 0xFD, 0x02);
+        for _ in 0..340 {
+            cartridge.clock();
+        }
+        assert!(!cartridge.irq_pin_state());
+        cartridge.clock();
+        assert!(cartridge.irq_pin_state());
+
+        // with enable after acknowledgement, the counter keeps running from the latch
+        set_irq(&mut cartridge, 0xFF, 0x07);
+        cartridge.clock();
+        assert!(cartridge.irq_pin_state());
+        cartridge.write(irq_register(3), 0, Device::Cpu);
+        assert!(!cartridge.irq_pin_state());
+        cartridge.clock();
+        assert!(cartridge.irq_pin_state());
+
+        Ok(())
+    }
 }
@@ -0,0 +1,175 @@
+//! UNIF cartridge files.
+//!
+//! UNIF files have a `$20` bytes header followed by chunks, each chunk is
+//! id (4 bytes), length (u32 LE) and `length` bytes of data. The board is
+//! identified by name in the `MAPR` chunk instead of a mapper number, so the
+//! name is mapped to the iNES mapper that emulates the same board.
+
+use super::error::CartridgeError;
+use super::TimingMode;
+
+const UNIF_MAGIC: &[u8] = b"UNIF";
+const UNIF_HEADER_SIZE: usize = 0x20;
+
+/// the prefixes of the board names that are not part of the board itself
+const BOARD_PREFIXES: [&str; 6] = ["NES-", "HVC-", "UNL-", "BTL-", "BMC-", "AVE-"];
+
+/// The board names (without the prefix) and the iNES mapper and submapper
+/// emulating them
+const BOARDS: &[(&[&str], u16, u8)] = &[
+    (&["NROM", "NROM-128", "NROM-256", "RROM", "RROM-128"], 0, 0),
+    (
+        &[
+            "SAROM", "SBROM", "SCROM", "SC1ROM", "SEROM", "SFROM", "SGROM", "SHROM", "SH1ROM",
+            "SIROM", "SJROM", "SKROM", "SLROM", "SL1ROM", "SL2ROM", "SL3ROM", "SLRROM", "SMROM",
+            "SNROM", "SOROM", "SUROM", "SXROM",
+        ],
+        1,
+        0,
+    ),
+    (&["UNROM", "UOROM"], 2, 0),
+    (&["CNROM"], 3, 0),
+    (
+        &[
+            "TBROM", "TEROM", "TFROM", "TGROM", "TKROM", "TLROM", "TL1ROM", "TL2ROM", "TNROM",
+            "TR1ROM", "TSROM", "TVROM",
+        ],
+        4,
+        0,
+    ),
+    (&["HKROM"], 4, 1),
+    (&["EKROM", "ELROM", "ETROM", "EWROM"], 5, 0),
+    (&["AMROM", "ANROM", "AN1ROM", "AOROM"], 7, 0),
+    (&["PEEOROM", "PNROM"], 9, 0),
+    (&["FJROM", "FKROM"], 10, 0),
+    (&["CPROM"], 13, 0),
+    (&["UNROM-512-8", "UNROM-512-16", "UNROM-512-32"], 30, 0),
+    (&["NINA-001", "NINA-01"], 34, 1),
+    (&["BNROM"], 34, 2),
+    (&["GNROM", "MHROM"], 66, 0),
+    (&["NINA-03", "NINA-06"], 79, 0),
+    (&["BTR", "JLROM", "JSROM"], 69, 0),
+    (&["TKSROM", "TLSROM"], 118, 0),
+    (&["TQROM"], 119, 0),
+    (&["UNROM+74HC08"], 180, 0),
+];
+
+/// The content of a UNIF file
+pub(crate) struct UnifFile {
+    pub(crate) mapper_id: u16,
+    pub(crate) submapper_id: u8,
+    pub(crate) prg_data: Vec<u8>,
+    /// empty if the board uses CHR RAM
+    pub(crate) chr_data: Vec<u8>,
+    /// `None` if the mirroring is controlled by the mapper
+    pub(crate) hardwired_mirroring_vertical: Option<bool>,
+    pub(crate) four_screen_mirroring: bool,
+    pub(crate) has_battery: bool,
+    pub(crate) timing_mode: TimingMode,
+}
+
+impl UnifFile {
+    pub(super) fn is_unif(data: &[u8]) -> bool {
+        data.starts_with(UNIF_MAGIC)
+    }
+
+    pub(super) fn from_bytes(data: &[u8]) -> Result<Self, CartridgeError> {
+        if !Self::is_unif(data) || data.len() < UNIF_HEADER_SIZE {
+            return Err(CartridgeError::UnifFileError);
+        }
+
+        let mut board = None;
+        // PRG0..PRGF and CHR0..CHRF, concatenated in order
+        let mut prg_chunks: [&[u8]; 16] = [&[]; 16];
+        let mut chr_chunks: [&[u8]; 16] = [&[]; 16];
+        let mut mirroring = None;
+        let mut has_battery = false;
+        let mut timing_mode = TimingMode::Ntsc;
+
+        let mut rest = &data[UNIF_HEADER_SIZE..];
+        while !rest.is_empty() {
+            if rest.len() < 8 {
+                return Err(CartridgeError::UnifFileError);
+            }
+
+            let id = &rest[0..4];
+            let length = u32::from_le_bytes(rest[4..8].try_into().unwrap()) as usize;
+            rest = &rest[8..];
+            if length > rest.len() {
+                return Err(CartridgeError::UnifFileError);
+            }
+            let (chunk, next) = rest.split_at(length);
+            rest = next;
+
+            match id {
+                b"MAPR" => board = Some(read_string(chunk)),
+                [b'P', b'R', b'G', n] => prg_chunks[chunk_index(*n)?] = chunk,
+                [b'C', b'H', b'R', n] => chr_chunks[chunk_index(*n)?] = chunk,
+                b"MIRR" => mirroring = chunk.first().copied(),
+                b"BATR" => has_battery = true,
+                b"TVCI" => {
+                    timing_mode = match chunk.first() {
+                        Some(1) => TimingMode::Pal,
+                        Some(2) => TimingMode::MultipleRegion,
+                        _ => TimingMode::Ntsc,
+                    }
+                }
+                // names, checksums and other information that is not needed
+                _ => {}
+            }
+        }
+
+        let board = board.ok_or(CartridgeError::UnifFileError)?;
+        let (mapper_id, submapper_id) =
+            board_mapper(&board).ok_or_else(|| CartridgeError::UnknownUnifBoard(board.clone()))?;
+
+        let prg_data = prg_chunks.concat();
+        if prg_data.is_empty() {
+            return Err(CartridgeError::UnifFileError);
+        }
+
+        Ok(Self {
+            mapper_id,
+            submapper_id,
+            prg_data,
+            chr_data: chr_chunks.concat(),
+            hardwired_mirroring_vertical: match mirroring {
+                Some(0) => Some(false),
+                Some(1) => Some(true),
+                _ => None,
+            },
+            four_screen_mirroring: mirroring == Some(4),
+            has_battery,
+            timing_mode,
+        })
+    }
+}
+
+/// The iNES mapper and submapper for the UNIF board `name`
+fn board_mapper(name: &str) -> Option<(u16, u8)> {
+    let name = name.to_ascii_uppercase();
+    let name = BOARD_PREFIXES
+        .iter()
+        .find_map(|prefix| name.strip_prefix(prefix))
+        .unwrap_or(&name);
+
+    BOARDS
+        .iter()
+        .find(|(names, _, _)| names.contains(&name))
+        .map(|&(_, mapper_id, submapper_id)| (mapper_id, submapper_id))
+}
+
+/// The index of `PRGn`/`CHRn` chunks, `n` is a hex digit
+fn chunk_index(n: u8) -> Result<usize, CartridgeError> {
+    (n as char)
+        .to_digit(16)
+        .map(|n| n as usize)
+        .ok_or(CartridgeError::UnifFileError)
+}
+
+/// Read a string until the first null byte
+fn read_string(data: &[u8]) -> String {
+    let end = data.iter().position(|&c| c == 0).unwrap_or(data.len());
+
+    String::from_utf8_lossy(&data[..end]).into_owned()
+}
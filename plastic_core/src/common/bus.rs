@@ -7,6 +7,18 @@ pub enum Device {
 pub trait Bus {
     fn read(&self, address: u16, device: Device) -> u8;
     fn write(&mut self, address: u16, data: u8, device: Device);
+
+    /// Read without the side effects of the access on the device, used by the
+    /// debugger, it is the same as `read` by default
+    fn peek(&self, address: u16, device: Device) -> u8 {
+        self.read(address, device)
+    }
+
+    /// Write without the side effects of the access on the device, used by the
+    /// debugger, it is the same as `write` by default
+    fn poke(&mut self, address: u16, data: u8, device: Device) {
+        self.write(address, data, device)
+    }
 }
 
 /// macro used to generate binding for enum to convert it from u16
@@ -17,3 +17,10 @@ pub trait CPUIrqProvider {
     fn irq_pin_state(&self) -> bool;
     fn clear_irq_request_pin(&mut self);
 }
+
+/// Provides the output of the audio chips in the cartridge to the APU
+pub trait ExpansionAudioProvider {
+    /// The mixed output of all expansion audio chips, relative to the level of
+    /// one APU pulse channel at full volume
+    fn expansion_audio_output(&mut self) -> f32;
+}
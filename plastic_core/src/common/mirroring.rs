@@ -7,6 +7,41 @@ pub enum MirroringMode {
     FourScreen,
 }
 
+impl MirroringMode {
+    /// returns the 1kb VRAM block that the nametable `address` is mapped to
+    pub fn nametable_block(&self, address: u16) -> u8 {
+        (match self {
+            MirroringMode::Vertical => (address >> 10) & 1,
+            MirroringMode::Horizontal => (address >> 11) & 1,
+            MirroringMode::SingleScreenLowBank => 0,
+            MirroringMode::SingleScreenHighBank => 1,
+            MirroringMode::FourScreen => (address >> 10) & 3,
+        }) as u8
+    }
+}
+
+/// Where a PPU nametable access (`$2000-$2FFF`) is going to
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum NametableMapping {
+    /// One of the 1kb VRAM blocks, blocks `2` and `3` are only present
+    /// with `FourScreen` mirroring
+    Vram(u8),
+    /// The cartridge handles the access by itself (ExRAM, CHR ROM, fill-mode, ...)
+    Cartridge,
+}
+
 pub trait MirroringProvider {
     fn mirroring_mode(&self) -> MirroringMode;
+
+    /// Map a nametable `address` into its location, by default it depends only on
+    /// the `mirroring_mode`. This is also called on every nametable fetch by the PPU,
+    /// so it can be used to track what the PPU is rendering.
+    fn nametable_mapping(&self, address: u16) -> NametableMapping {
+        NametableMapping::Vram(self.mirroring_mode().nametable_block(address))
+    }
+
+    /// Same as `nametable_mapping`, without tracking the access
+    fn peek_nametable_mapping(&self, address: u16) -> NametableMapping {
+        self.nametable_mapping(address)
+    }
 }
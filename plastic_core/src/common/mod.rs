@@ -1,11 +1,11 @@
 #[macro_use]
 mod bus;
 mod mirroring;
+mod region;
 
 pub mod interconnection;
 pub mod save_state;
 
 pub use bus::{Bus, Device};
-pub use mirroring::{MirroringMode, MirroringProvider};
-
-pub const CPU_FREQ: f64 = 1.789773 * 1E6;
+pub use mirroring::{MirroringMode, MirroringProvider, NametableMapping};
+pub use region::Region;
@@ -0,0 +1,78 @@
+use serde::{Deserialize, Serialize};
+
+/// The console region, which controls the CPU/PPU timing, the APU tables and the frame rate
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Region {
+    /// North America and Japan, 60 frames per second
+    #[default]
+    Ntsc,
+    /// Europe and Australia, 50 frames per second
+    Pal,
+    /// Famiclones (mostly in Russia), 50 frames per second like PAL, but with the
+    /// NTSC CPU/PPU ratio and APU tables
+    Dendy,
+}
+
+impl Region {
+    /// The CPU clock frequency in Hz
+    pub fn cpu_frequency(&self) -> f64 {
+        match self {
+            Region::Ntsc => 1.789773 * 1E6,
+            Region::Pal => 1.662607 * 1E6,
+            Region::Dendy => 1.773448 * 1E6,
+        }
+    }
+
+    /// The number of frames per second
+    pub fn frame_rate(&self) -> f64 {
+        self.cpu_frequency() / self.cpu_cycles_per_frame() as f64
+    }
+
+    /// The number of PPU cycles for every 5 CPU cycles,
+    /// the PPU runs 3 times faster than the CPU on NTSC and Dendy, and 3.2 times on PAL
+    pub(crate) fn ppu_cycles_per_5_cpu_cycles(&self) -> u8 {
+        match self {
+            Region::Ntsc | Region::Dendy => 15,
+            Region::Pal => 16,
+        }
+    }
+
+    /// The average number of CPU cycles in one frame
+    pub(crate) fn cpu_cycles_per_frame(&self) -> f32 {
+        match self {
+            // 341 * 262 / 3, with the skipped cycle on odd frames
+            Region::Ntsc => 29780.5,
+            // 341 * 312 / 3.2
+            Region::Pal => 33247.5,
+            // 341 * 312 / 3
+            Region::Dendy => 35464.,
+        }
+    }
+
+    /// The last scanline in a frame, the PPU starts rendering after it
+    pub(crate) fn pre_render_scanline(&self) -> u16 {
+        match self {
+            Region::Ntsc => 261,
+            Region::Pal | Region::Dendy => 311,
+        }
+    }
+
+    /// The scanline where the vertical blank starts
+    pub(crate) fn vblank_scanline(&self) -> u16 {
+        match self {
+            Region::Ntsc | Region::Pal => 241,
+            // Dendy has 51 post-render scanlines so that vblank is as long as NTSC
+            Region::Dendy => 291,
+        }
+    }
+
+    /// Only the NTSC PPU skips one cycle on odd frames when rendering is enabled
+    pub(crate) fn skips_odd_frame_cycle(&self) -> bool {
+        *self == Region::Ntsc
+    }
+
+    /// Only PAL uses different APU tables, Dendy uses the NTSC ones
+    pub(crate) fn uses_pal_apu(&self) -> bool {
+        *self == Region::Pal
+    }
+}
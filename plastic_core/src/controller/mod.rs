@@ -1,3 +1,7 @@
+mod vs_system;
+
+pub use vs_system::VsSystem;
+
 use crate::common::{Bus, Device};
 use bitflags::bitflags;
 use std::cell::Cell;
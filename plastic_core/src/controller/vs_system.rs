@@ -0,0 +1,110 @@
+use crate::common::{Bus, Device};
+
+/// The coin slots are held for this many CPU cycles after inserting a coin,
+/// about 3 NTSC frames, games check them once every frame
+const COIN_HOLD_CYCLES: u32 = 29780 * 3;
+
+/// The arcade inputs and outputs of the Vs. System that are not part of the
+/// cartridge: the DIP switches, the coin slots, the service button and
+/// the coin counter.
+///
+/// The controllers are swapped on the Vs. System, the first controller is read
+/// from $4017 and the second from $4016, this only handles the other bits of
+/// these registers.
+pub struct VsSystem {
+    /// DIP switch 1 is bit 0, DIP switch 8 is bit 7
+    dip_switches: u8,
+    service_button: bool,
+    /// the CPU cycles left to hold each coin slot
+    coin_timers: [u32; 2],
+
+    coin_counter_line: bool,
+    /// number of pulses sent to the mechanical coin counter
+    coin_counter: u32,
+}
+
+impl VsSystem {
+    pub(crate) fn new() -> Self {
+        Self {
+            dip_switches: 0,
+            service_button: false,
+            coin_timers: [0; 2],
+            coin_counter_line: false,
+            coin_counter: 0,
+        }
+    }
+
+    pub fn dip_switches(&self) -> u8 {
+        self.dip_switches
+    }
+
+    pub fn set_dip_switches(&mut self, dip_switches: u8) {
+        self.dip_switches = dip_switches;
+    }
+
+    /// Insert a coin in `slot` (0 or 1), other slots are ignored
+    pub fn insert_coin(&mut self, slot: usize) {
+        if let Some(timer) = self.coin_timers.get_mut(slot) {
+            *timer = COIN_HOLD_CYCLES;
+        }
+    }
+
+    pub fn set_service_button(&mut self, pressed: bool) {
+        self.service_button = pressed;
+    }
+
+    pub fn coin_counter(&self) -> u32 {
+        self.coin_counter
+    }
+
+    /// Run for one CPU cycle
+    pub fn clock(&mut self) {
+        for timer in self.coin_timers.iter_mut() {
+            *timer = timer.saturating_sub(1);
+        }
+    }
+}
+
+impl Bus for VsSystem {
+    /// $4016 read:
+    /// 7  bit  0
+    /// ---- ----
+    /// xCCD DSxc
+    ///  ||| || |
+    ///  ||| || +- Second controller serial data (not handled here)
+    ///  ||| |+--- Service button
+    ///  ||+-+---- DIP switches 1 and 2
+    ///  ++------- Coin slots 1 and 2
+    ///
+    /// $4017 read:
+    /// 7  bit  0
+    /// ---- ----
+    /// DDDD DDxc
+    /// |||| || |
+    /// |||| || +- First controller serial data (not handled here)
+    /// ++++-++--- DIP switches 3 to 8
+    fn read(&self, address: u16, _device: Device) -> u8 {
+        match address {
+            0x4016 => {
+                let coins = ((self.coin_timers[1] != 0) as u8) << 6
+                    | ((self.coin_timers[0] != 0) as u8) << 5;
+
+                coins | (self.dip_switches & 0b11) << 3 | (self.service_button as u8) << 2
+            }
+            0x4017 => self.dip_switches & 0xFC,
+            _ => unreachable!(),
+        }
+    }
+
+    /// $4020 write, bit 0 drives the coin counter
+    fn write(&mut self, address: u16, data: u8, _device: Device) {
+        assert_eq!(address, 0x4020);
+
+        let line = data & 1 != 0;
+        // the counter is incremented on every pulse
+        if line && !self.coin_counter_line {
+            self.coin_counter += 1;
+        }
+        self.coin_counter_line = line;
+    }
+}
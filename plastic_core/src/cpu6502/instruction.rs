@@ -1,7 +1,7 @@
 use serde::{Deserialize, Serialize};
 use std::fmt::{Display, Formatter};
 
-#[derive(Serialize, Deserialize, Copy, Clone)]
+#[derive(Serialize, Deserialize, Debug, Copy, Clone)]
 pub struct Instruction {
     pub opcode_byte: u8,
     pub operand: u16,
@@ -9,7 +9,7 @@ pub struct Instruction {
     pub addressing_mode: AddressingMode,
 }
 
-#[derive(Serialize, Deserialize, PartialEq, Eq, Copy, Clone)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Copy, Clone)]
 pub enum Opcode {
     Adc, // Add with carry
     And, // And
@@ -103,7 +103,7 @@ pub enum Opcode {
     Kil, // Halt the CPU (CRASH)
 }
 
-#[derive(Serialize, Deserialize, PartialEq, Eq, Copy, Clone)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Copy, Clone)]
 pub enum AddressingMode {
     Immediate = 0,  // #$aa
     ZeroPage,       // $aa
@@ -261,6 +261,18 @@ impl Instruction {
     pub fn is_operand_address(&self) -> bool {
         self.addressing_mode.is_operand_address()
     }
+
+    /// The instruction is not one of the 151 official opcodes, this includes
+    /// the NOPs other than $EA and the SBC at $EB
+    pub fn is_unofficial(&self) -> bool {
+        match self.opcode {
+            Nop => self.opcode_byte != 0xEA,
+            Sbc => self.opcode_byte == 0xEB,
+            Slo | Sre | Rla | Rra | Isc | Dcp | Sax | Lax | Anc | Alr | Arr | Axs | Xaa | Ahx
+            | Shy | Shx | Tas | Las | Kil => true,
+            _ => false,
+        }
+    }
 }
 
 impl Display for Opcode {
@@ -35,6 +35,48 @@ pub enum CPURunState {
     NormalInstructionExecution,
 }
 
+/// The CPU registers, see [`NES::cpu_registers`][crate::NES::cpu_registers]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CPURegisters {
+    pub pc: u16,
+    pub sp: u8,
+    pub a: u8,
+    pub x: u8,
+    pub y: u8,
+    /// flags: [N, V, _, B, D, I, Z, C]
+    pub status: u8,
+}
+
+impl CPURegisters {
+    pub fn carry(&self) -> bool {
+        self.status & StatusFlag::Carry as u8 != 0
+    }
+
+    pub fn zero(&self) -> bool {
+        self.status & StatusFlag::Zero as u8 != 0
+    }
+
+    pub fn interrupt_disable(&self) -> bool {
+        self.status & StatusFlag::InterruptDisable as u8 != 0
+    }
+
+    pub fn decimal_mode(&self) -> bool {
+        self.status & StatusFlag::DecimalMode as u8 != 0
+    }
+
+    pub fn break_command(&self) -> bool {
+        self.status & StatusFlag::BreakCommand as u8 != 0
+    }
+
+    pub fn overflow(&self) -> bool {
+        self.status & StatusFlag::Overflow as u8 != 0
+    }
+
+    pub fn negative(&self) -> bool {
+        self.status & StatusFlag::Negative as u8 != 0
+    }
+}
+
 // helper function
 fn is_on_same_page(address1: u16, address2: u16) -> bool {
     address1 & 0xff00 == address2 & 0xff00
@@ -72,6 +114,9 @@ pub struct CPU6502<T: CPUBusTrait> {
     /// check `run_next` for more info
     next_instruction: Option<(Instruction, u8)>,
 
+    /// number of cycles run since power on, not saved in save states
+    cycles: u64,
+
     bus: T,
 }
 
@@ -99,6 +144,8 @@ where
 
             next_instruction: None,
 
+            cycles: 0,
+
             bus,
         }
     }
@@ -137,6 +184,8 @@ where
     }
 
     pub fn run_next(&mut self) -> CPURunState {
+        self.cycles += 1;
+
         self.check_and_run_dmc_transfer();
 
         if self.cycles_to_wait == 0 && self.next_instruction.is_none() {
@@ -219,6 +268,51 @@ where
         }
     }
 
+    pub fn registers(&self) -> CPURegisters {
+        CPURegisters {
+            pc: self.reg_pc,
+            sp: self.reg_sp,
+            a: self.reg_a,
+            x: self.reg_x,
+            y: self.reg_y,
+            status: self.reg_status,
+        }
+    }
+
+    /// Set the registers, should be used between instructions, when
+    /// [`next_instruction_address`](Self::next_instruction_address) is `Some`
+    pub fn set_registers(&mut self, registers: CPURegisters) {
+        self.reg_pc = registers.pc;
+        self.reg_sp = registers.sp;
+        self.reg_a = registers.a;
+        self.reg_x = registers.x;
+        self.reg_y = registers.y;
+        self.reg_status = registers.status;
+    }
+
+    pub fn cycles(&self) -> u64 {
+        self.cycles
+    }
+
+    /// The address of the instruction that the next call to `run_next` starts,
+    /// `None` if the CPU is in the middle of an instruction, or the next cycle
+    /// is an interrupt, a DMA transfer or a DMC read.
+    pub fn next_instruction_address(&self) -> Option<u16> {
+        let is_interrupt_pending = self.nmi_pin_status
+            || (self.irq_pin_status && self.reg_status & StatusFlag::InterruptDisable as u8 == 0);
+
+        if self.cycles_to_wait == 0
+            && self.next_instruction.is_none()
+            && self.dma_remaining == 0
+            && !is_interrupt_pending
+            && self.bus.request_dmc_reader_read().is_none()
+        {
+            Some(self.reg_pc)
+        } else {
+            None
+        }
+    }
+
     pub fn bus(&self) -> &T {
         &self.bus
     }
@@ -0,0 +1,218 @@
+use crate::cpu6502::instruction::{AddressingMode, Instruction};
+use std::fmt::{Display, Formatter};
+use std::ops::RangeInclusive;
+
+/// One disassembled CPU instruction, see [`NES::disassemble`][crate::NES::disassemble]
+#[derive(Debug, Clone)]
+pub struct DisassembledInstruction {
+    pub address: u16,
+    /// the opcode and operand bytes
+    pub bytes: Vec<u8>,
+    pub instruction: Instruction,
+    /// the label of `address` from the loaded symbols
+    pub label: Option<String>,
+    /// the instruction in assembly, with the operand address replaced by
+    /// its label if there is one, e.g. `LDA $10, X` or `JSR init`
+    pub text: String,
+    /// the address accessed or jumped to by the instruction, before indexing,
+    /// relative branches are resolved
+    pub target: Option<u16>,
+    /// the name of the hardware register at `target`, e.g. `PPUCTRL`
+    pub register: Option<&'static str>,
+}
+
+impl DisassembledInstruction {
+    /// The instruction is not one of the official opcodes
+    pub fn is_unofficial(&self) -> bool {
+        self.instruction.is_unofficial()
+    }
+}
+
+impl Display for DisassembledInstruction {
+    /// `8000  8D 00 20  STA $2000 ; PPUCTRL`, unofficial instructions
+    /// are marked with `*` like `*NOP`
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let bytes = self
+            .bytes
+            .iter()
+            .map(|byte| format!("{:02X}", byte))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let marker = if self.is_unofficial() { "*" } else { " " };
+
+        write!(
+            f,
+            "{:04X}  {:<8} {}{}",
+            self.address, bytes, marker, self.text
+        )?;
+
+        if let Some(register) = self.register {
+            write!(f, " ; {}", register)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The name of the PPU, APU and I/O register at `address`, PPU registers
+/// are mirrored up to $3FFF
+pub fn register_name(address: u16) -> Option<&'static str> {
+    let name = match address {
+        0x2000..=0x3FFF => match address & 7 {
+            0 => "PPUCTRL",
+            1 => "PPUMASK",
+            2 => "PPUSTATUS",
+            3 => "OAMADDR",
+            4 => "OAMDATA",
+            5 => "PPUSCROLL",
+            6 => "PPUADDR",
+            _ => "PPUDATA",
+        },
+        0x4000 => "SQ1_VOL",
+        0x4001 => "SQ1_SWEEP",
+        0x4002 => "SQ1_LO",
+        0x4003 => "SQ1_HI",
+        0x4004 => "SQ2_VOL",
+        0x4005 => "SQ2_SWEEP",
+        0x4006 => "SQ2_LO",
+        0x4007 => "SQ2_HI",
+        0x4008 => "TRI_LINEAR",
+        0x400A => "TRI_LO",
+        0x400B => "TRI_HI",
+        0x400C => "NOISE_VOL",
+        0x400E => "NOISE_LO",
+        0x400F => "NOISE_HI",
+        0x4010 => "DMC_FREQ",
+        0x4011 => "DMC_RAW",
+        0x4012 => "DMC_START",
+        0x4013 => "DMC_LEN",
+        0x4014 => "OAMDMA",
+        0x4015 => "SND_CHN",
+        0x4016 => "JOY1",
+        // the APU frame counter when written
+        0x4017 => "JOY2",
+        _ => return None,
+    };
+
+    Some(name)
+}
+
+/// Disassemble the instruction at `address`, `read` gives the bytes of memory,
+/// `None` if it can't be read without side effects, and `label` the label
+/// of an address.
+///
+/// Returns `None` if the opcode can't be read.
+pub(crate) fn disassemble_instruction<'a, R, L>(
+    address: u16,
+    read: R,
+    label: L,
+) -> Option<DisassembledInstruction>
+where
+    R: Fn(u16) -> Option<u8>,
+    L: Fn(u16) -> Option<&'a str>,
+{
+    let mut instruction = Instruction::from_byte(read(address)?);
+    let len = instruction.get_instruction_len();
+
+    // operand bytes that can't be read are shown as 0
+    let bytes = (0..len as u16)
+        .map(|i| read(address.wrapping_add(i)).unwrap_or(0))
+        .collect::<Vec<_>>();
+    instruction.operand = match len {
+        2 => bytes[1] as u16,
+        3 => u16::from_le_bytes([bytes[1], bytes[2]]),
+        _ => 0,
+    };
+
+    let target = match instruction.addressing_mode {
+        AddressingMode::Relative => Some(
+            address
+                .wrapping_add(2)
+                .wrapping_add(instruction.operand as i8 as u16),
+        ),
+        AddressingMode::Immediate | AddressingMode::Accumulator | AddressingMode::Implied => None,
+        _ => Some(instruction.operand),
+    };
+
+    let operand_label = target.and_then(&label);
+    let operand = match (operand_label, target) {
+        (Some(name), _) => name.to_owned(),
+        (None, Some(target)) if instruction.addressing_mode == AddressingMode::Relative => {
+            format!("${:04X}", target)
+        }
+        _ => String::new(),
+    };
+
+    use AddressingMode::*;
+    let operand_text = match instruction.addressing_mode {
+        Immediate => format!("#${:02X}", instruction.operand),
+        Accumulator => "A".to_owned(),
+        Implied => String::new(),
+        Relative => operand,
+        _ => {
+            let operand = if operand.is_empty() {
+                match instruction.addressing_mode {
+                    ZeroPage | ZeroPageIndexX | ZeroPageIndexY | XIndirect | IndirectY => {
+                        format!("${:02X}", instruction.operand)
+                    }
+                    _ => format!("${:04X}", instruction.operand),
+                }
+            } else {
+                operand
+            };
+
+            match instruction.addressing_mode {
+                ZeroPageIndexX | AbsoluteX => format!("{}, X", operand),
+                ZeroPageIndexY | AbsoluteY => format!("{}, Y", operand),
+                Indirect => format!("({})", operand),
+                XIndirect => format!("({}, X)", operand),
+                IndirectY => format!("({}), Y", operand),
+                _ => operand,
+            }
+        }
+    };
+
+    let text = if operand_text.is_empty() {
+        instruction.opcode.to_string()
+    } else {
+        format!("{} {}", instruction.opcode, operand_text)
+    };
+
+    Some(DisassembledInstruction {
+        address,
+        bytes,
+        instruction,
+        label: label(address).map(str::to_owned),
+        text,
+        target,
+        register: target.and_then(register_name),
+    })
+}
+
+/// Disassemble the instructions starting in `addresses`, addresses that
+/// can't be read are skipped.
+pub(crate) fn disassemble<'a, R, L>(
+    addresses: RangeInclusive<u16>,
+    read: R,
+    label: L,
+) -> Vec<DisassembledInstruction>
+where
+    R: Fn(u16) -> Option<u8>,
+    L: Fn(u16) -> Option<&'a str>,
+{
+    let mut result = Vec::new();
+
+    // u32 to not overflow at the end of the address space
+    let mut address = *addresses.start() as u32;
+    while address <= *addresses.end() as u32 {
+        match disassemble_instruction(address as u16, &read, &label) {
+            Some(instruction) => {
+                address += instruction.bytes.len() as u32;
+                result.push(instruction);
+            }
+            None => address += 1,
+        }
+    }
+
+    result
+}
@@ -0,0 +1,465 @@
+mod disassembler;
+mod symbols;
+mod trace;
+
+pub(crate) use disassembler::{disassemble, disassemble_instruction};
+pub use disassembler::{register_name, DisassembledInstruction};
+pub use symbols::{Symbols, SymbolsError};
+pub(crate) use trace::trace_line;
+pub use trace::TraceLogger;
+
+use crate::cpu6502::CPURegisters;
+use std::cell::{Cell, RefCell};
+use std::ops::RangeInclusive;
+use std::rc::Rc;
+
+const JSR_OPCODE: u8 = 0x20;
+const RTI_OPCODE: u8 = 0x40;
+const RTS_OPCODE: u8 = 0x60;
+
+/// The address space of a memory access
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemorySpace {
+    Cpu,
+    Ppu,
+}
+
+/// The kind of memory access to watch
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    Read,
+    Write,
+    ReadWrite,
+}
+
+impl Access {
+    fn contains(&self, access: Access) -> bool {
+        *self == Access::ReadWrite || *self == access
+    }
+}
+
+/// Break when the CPU or the PPU accesses memory in `addresses`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Watchpoint {
+    pub space: MemorySpace,
+    pub addresses: RangeInclusive<u16>,
+    pub access: Access,
+}
+
+/// A CPU register to check in a [`Condition`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Register {
+    A,
+    X,
+    Y,
+    Sp,
+    Pc,
+    Status,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+/// A condition on the value of a CPU register, e.g. `A == 0x10`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Condition {
+    pub register: Register,
+    pub comparison: Comparison,
+    pub value: u16,
+}
+
+impl Condition {
+    pub fn matches(&self, registers: &CPURegisters) -> bool {
+        let register = match self.register {
+            Register::A => registers.a as u16,
+            Register::X => registers.x as u16,
+            Register::Y => registers.y as u16,
+            Register::Sp => registers.sp as u16,
+            Register::Pc => registers.pc,
+            Register::Status => registers.status as u16,
+        };
+
+        match self.comparison {
+            Comparison::Equal => register == self.value,
+            Comparison::NotEqual => register != self.value,
+            Comparison::Less => register < self.value,
+            Comparison::LessOrEqual => register <= self.value,
+            Comparison::Greater => register > self.value,
+            Comparison::GreaterOrEqual => register >= self.value,
+        }
+    }
+}
+
+/// Break before executing an instruction at `pc` (any instruction if `None`),
+/// only if `condition` matches (if there is one)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Breakpoint {
+    pub pc: Option<u16>,
+    pub condition: Option<Condition>,
+}
+
+impl Breakpoint {
+    /// Break before executing the instruction at `pc`
+    pub fn at(pc: u16) -> Self {
+        Self {
+            pc: Some(pc),
+            condition: None,
+        }
+    }
+
+    /// Break before executing any instruction when `condition` matches
+    pub fn when(condition: Condition) -> Self {
+        Self {
+            pc: None,
+            condition: Some(condition),
+        }
+    }
+
+    pub fn with_condition(mut self, condition: Condition) -> Self {
+        self.condition = Some(condition);
+        self
+    }
+
+    fn matches(&self, registers: &CPURegisters) -> bool {
+        self.pc.map(|pc| pc == registers.pc).unwrap_or(true)
+            && self
+                .condition
+                .map(|condition| condition.matches(registers))
+                .unwrap_or(true)
+    }
+}
+
+/// Why the emulation is paused
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BreakReason {
+    /// The breakpoint at this index in [`Debugger::breakpoints`] was hit
+    Breakpoint(usize),
+    /// A watchpoint was hit by accessing `address`, the emulation stops after the
+    /// CPU cycle of the access, in the middle of the instruction
+    Watchpoint {
+        space: MemorySpace,
+        address: u16,
+        /// the data read or written
+        data: u8,
+        /// only `Read` or `Write`
+        access: Access,
+    },
+    /// A step command finished
+    Step,
+    /// Reached the scanline from [`Debugger::run_to_scanline`]
+    Scanline(u16),
+    /// Paused with [`Debugger::pause`], which can be in the middle of an instruction
+    Paused,
+}
+
+/// The cycle counters of the emulator, see [`NES::cycle_counters`][crate::NES::cycle_counters]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CycleCounters {
+    /// CPU cycles since power on
+    pub cpu_cycles: u64,
+    /// frames rendered by the PPU since power on
+    pub frames: u64,
+    /// the scanline being rendered, the pre-render scanline is the last one
+    pub scanline: u16,
+    /// the PPU cycle (dot) in the current scanline
+    pub dot: u16,
+}
+
+/// The watchpoints, shared with the CPU and PPU buses which check them on every access
+pub(crate) struct Watches {
+    watchpoints: RefCell<Vec<Watchpoint>>,
+    hit: Cell<Option<BreakReason>>,
+}
+
+impl Watches {
+    fn new() -> Self {
+        Self {
+            watchpoints: RefCell::new(Vec::new()),
+            hit: Cell::new(None),
+        }
+    }
+
+    /// Record the access if there is a watchpoint for it, only the first
+    /// access is kept until the debugger takes it
+    pub(crate) fn check(&self, space: MemorySpace, address: u16, data: u8, access: Access) {
+        if self.hit.get().is_some() {
+            return;
+        }
+
+        let is_watched = self.watchpoints.borrow().iter().any(|watchpoint| {
+            watchpoint.space == space
+                && watchpoint.access.contains(access)
+                && watchpoint.addresses.contains(&address)
+        });
+
+        if is_watched {
+            self.hit.set(Some(BreakReason::Watchpoint {
+                space,
+                address,
+                data,
+                access,
+            }));
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RunMode {
+    Continue,
+    StepInto,
+    /// stepping over the instruction at the first instruction boundary, with
+    /// the return address and the stack pointer once it is known to be a `JSR`
+    StepOver(Option<(u16, u8)>),
+    /// break after a `RTS` or `RTI` that pulls the stack above the stack pointer
+    /// at the first instruction boundary
+    StepOut(Option<u8>),
+    RunToScanline {
+        scanline: u16,
+        left_scanline: bool,
+    },
+}
+
+/// Breakpoints, watchpoints and stepping, enabled with
+/// [`NES::enable_debugger`][crate::NES::enable_debugger].
+///
+/// When a breakpoint or a watchpoint is hit, a step command finishes, or [`Debugger::pause`]
+/// is called, the emulation is paused and [`NES::clock_for_frame`][crate::NES::clock_for_frame] and
+/// [`NES::clock`][crate::NES::clock] do nothing until it is resumed with [`Debugger::resume`]
+/// or one of the step commands.
+///
+/// The step commands run while the emulator is clocked, so they can be used while running too,
+/// e.g. [`Debugger::step_into`] pauses before the next instruction.
+pub struct Debugger {
+    breakpoints: Vec<Breakpoint>,
+    watches: Rc<Watches>,
+
+    run_mode: RunMode,
+    break_reason: Option<BreakReason>,
+
+    /// paused before an instruction, which is not checked again when resuming
+    is_paused_at_instruction: bool,
+    skip_instruction: bool,
+
+    /// the opcode at the last instruction boundary, used by step out
+    last_opcode: Option<u8>,
+}
+
+impl Debugger {
+    pub(crate) fn new() -> Self {
+        Self {
+            breakpoints: Vec::new(),
+            watches: Rc::new(Watches::new()),
+            run_mode: RunMode::Continue,
+            break_reason: None,
+            is_paused_at_instruction: false,
+            skip_instruction: false,
+            last_opcode: None,
+        }
+    }
+
+    pub(crate) fn watches(&self) -> Rc<Watches> {
+        self.watches.clone()
+    }
+
+    pub fn breakpoints(&self) -> &[Breakpoint] {
+        &self.breakpoints
+    }
+
+    /// Add a breakpoint and return its index
+    pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) -> usize {
+        self.breakpoints.push(breakpoint);
+        self.breakpoints.len() - 1
+    }
+
+    /// Remove the breakpoint at `index`, the indices of the breakpoints after it
+    /// are shifted down
+    pub fn remove_breakpoint(&mut self, index: usize) {
+        if index < self.breakpoints.len() {
+            self.breakpoints.remove(index);
+        }
+    }
+
+    pub fn clear_breakpoints(&mut self) {
+        self.breakpoints.clear();
+    }
+
+    pub fn watchpoints(&self) -> Vec<Watchpoint> {
+        self.watches.watchpoints.borrow().clone()
+    }
+
+    /// Add a watchpoint and return its index
+    pub fn add_watchpoint(&mut self, watchpoint: Watchpoint) -> usize {
+        let mut watchpoints = self.watches.watchpoints.borrow_mut();
+        watchpoints.push(watchpoint);
+        watchpoints.len() - 1
+    }
+
+    /// Remove the watchpoint at `index`, the indices of the watchpoints after it
+    /// are shifted down
+    pub fn remove_watchpoint(&mut self, index: usize) {
+        let mut watchpoints = self.watches.watchpoints.borrow_mut();
+        if index < watchpoints.len() {
+            watchpoints.remove(index);
+        }
+    }
+
+    pub fn clear_watchpoints(&mut self) {
+        self.watches.watchpoints.borrow_mut().clear();
+    }
+
+    /// Why the emulation is paused, `None` if it is running
+    pub fn break_reason(&self) -> Option<BreakReason> {
+        self.break_reason
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.break_reason.is_some()
+    }
+
+    /// Pause the emulation where it is, without running until the next instruction
+    pub fn pause(&mut self) {
+        if !self.is_paused() {
+            self.break_with(BreakReason::Paused, false);
+        }
+    }
+
+    /// Continue running until the next break
+    pub fn resume(&mut self) {
+        self.run(RunMode::Continue);
+    }
+
+    /// Run until the next instruction
+    pub fn step_into(&mut self) {
+        self.run(RunMode::StepInto);
+    }
+
+    /// Run until the next instruction, subroutine calls (`JSR`) are run until
+    /// they return
+    pub fn step_over(&mut self) {
+        if self.is_paused_at_instruction {
+            self.run(RunMode::StepOver(None));
+        } else {
+            // in the middle of an instruction, finish it
+            self.run(RunMode::StepInto);
+        }
+    }
+
+    /// Run until the current subroutine or interrupt handler returns
+    pub fn step_out(&mut self) {
+        self.run(RunMode::StepOut(None));
+    }
+
+    /// Run until the first instruction in `scanline`, if the PPU is already in
+    /// `scanline`, run until it is reached in the next frame
+    pub fn run_to_scanline(&mut self, scanline: u16) {
+        self.run(RunMode::RunToScanline {
+            scanline,
+            left_scanline: false,
+        });
+    }
+
+    fn run(&mut self, run_mode: RunMode) {
+        self.run_mode = run_mode;
+        self.skip_instruction = self.break_reason.is_some() && self.is_paused_at_instruction;
+        self.break_reason = None;
+    }
+
+    fn break_with(&mut self, reason: BreakReason, is_at_instruction: bool) {
+        self.break_reason = Some(reason);
+        self.is_paused_at_instruction = is_at_instruction;
+        self.run_mode = RunMode::Continue;
+    }
+
+    /// Check the breakpoints and the step commands before the CPU starts the
+    /// instruction at `registers.pc`, return `true` if the emulation is paused.
+    ///
+    /// `read_opcode` reads the instruction opcode without side effects, only when stepping.
+    pub(crate) fn check_instruction<F: Fn(u16) -> Option<u8>>(
+        &mut self,
+        registers: &CPURegisters,
+        scanline: u16,
+        read_opcode: F,
+    ) -> bool {
+        // the instruction that was paused at is not checked again
+        let is_first_instruction = std::mem::take(&mut self.skip_instruction);
+
+        if !is_first_instruction {
+            if let Some(index) = self
+                .breakpoints
+                .iter()
+                .position(|breakpoint| breakpoint.matches(registers))
+            {
+                self.break_with(BreakReason::Breakpoint(index), true);
+                return true;
+            }
+        }
+
+        let is_step_done = match self.run_mode {
+            RunMode::Continue => false,
+            RunMode::StepInto => !is_first_instruction,
+            RunMode::StepOver(None) => {
+                if read_opcode(registers.pc) == Some(JSR_OPCODE) {
+                    self.run_mode =
+                        RunMode::StepOver(Some((registers.pc.wrapping_add(3), registers.sp)));
+                } else {
+                    self.run_mode = RunMode::StepInto;
+                }
+                false
+            }
+            RunMode::StepOver(Some((return_address, sp))) => {
+                registers.pc == return_address && registers.sp >= sp
+            }
+            RunMode::StepOut(None) => {
+                self.run_mode = RunMode::StepOut(Some(registers.sp));
+                self.last_opcode = read_opcode(registers.pc);
+                false
+            }
+            RunMode::StepOut(Some(sp)) => {
+                let last_opcode =
+                    std::mem::replace(&mut self.last_opcode, read_opcode(registers.pc));
+
+                matches!(last_opcode, Some(RTS_OPCODE | RTI_OPCODE)) && registers.sp > sp
+            }
+            RunMode::RunToScanline {
+                scanline: target,
+                left_scanline,
+            } => {
+                if scanline != target {
+                    self.run_mode = RunMode::RunToScanline {
+                        scanline: target,
+                        left_scanline: true,
+                    };
+                }
+                scanline == target && left_scanline
+            }
+        };
+
+        if is_step_done {
+            let reason = match self.run_mode {
+                RunMode::RunToScanline { scanline, .. } => BreakReason::Scanline(scanline),
+                _ => BreakReason::Step,
+            };
+            self.break_with(reason, true);
+        }
+
+        is_step_done
+    }
+
+    /// Check if a watchpoint was hit in the last cycle, return `true` if the
+    /// emulation is paused
+    pub(crate) fn check_watchpoints(&mut self) -> bool {
+        if let Some(reason) = self.watches.hit.take() {
+            self.break_with(reason, false);
+            true
+        } else {
+            false
+        }
+    }
+}
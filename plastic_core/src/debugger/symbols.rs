@@ -0,0 +1,299 @@
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt::{Debug, Display, Formatter, Result as fmtResult},
+    fs,
+    io::Error as ioError,
+    path::Path,
+};
+
+/// The size of a PRG ROM bank in FCEUX `.nl` files
+const FCEUX_BANK_SIZE: usize = 0x4000;
+/// The size of the iNES header, ca65 segment offsets are in the `.nes` file
+const INES_HEADER_SIZE: usize = 16;
+
+/// Error happening when loading a symbol file.
+pub enum SymbolsError {
+    /// Error with file input/output.
+    FileError(ioError),
+
+    /// The file extension is not `.dbg`, `.nl` or `.mlb`.
+    ExtensionError,
+}
+
+impl SymbolsError {
+    fn get_message(&self) -> String {
+        match self {
+            Self::FileError(err) => format!("FileError: {}", err),
+            Self::ExtensionError => {
+                "The symbol file must end with `.dbg`, `.nl` or `.mlb` extension".to_owned()
+            }
+        }
+    }
+}
+
+impl Error for SymbolsError {}
+
+impl Display for SymbolsError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmtResult {
+        write!(f, "{}", self.get_message())
+    }
+}
+
+impl Debug for SymbolsError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmtResult {
+        write!(f, "{}", self.get_message())
+    }
+}
+
+impl From<ioError> for SymbolsError {
+    fn from(from: ioError) -> Self {
+        Self::FileError(from)
+    }
+}
+
+/// Labels of CPU addresses, loaded from ca65 `.dbg`, FCEUX `.nl` and
+/// Mesen `.mlb` files, see [`NES::load_symbols`][crate::NES::load_symbols].
+///
+/// Labels in PRG ROM are kept by their offset in the PRG ROM when the file
+/// has it, so they follow the bank switching of the mapper, the other labels
+/// are for a fixed CPU address (RAM, registers, or ROM without banking info).
+///
+/// Malformed lines are ignored.
+#[derive(Debug, Default, Clone)]
+pub struct Symbols {
+    cpu: HashMap<u16, String>,
+    prg_rom: HashMap<usize, String>,
+}
+
+impl Symbols {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load the labels in a `.dbg`, `.nl` or `.mlb` file.
+    ///
+    /// FCEUX `.nl` files have the bank number before the extension
+    /// (`game.nes.0.nl`), and `ram` (`game.nes.ram.nl`) for RAM labels.
+    pub fn load_file<P: AsRef<Path>>(&mut self, path: P) -> Result<(), SymbolsError> {
+        let path = path.as_ref();
+        let extension = path
+            .extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .ok_or(SymbolsError::ExtensionError)?;
+
+        match extension.as_str() {
+            "dbg" => self.load_ca65_dbg(&fs::read_to_string(path)?),
+            "mlb" => self.load_mesen_mlb(&fs::read_to_string(path)?),
+            "nl" => {
+                // `game.nes.0.nl` => `0`
+                let bank = path
+                    .file_stem()
+                    .and_then(|stem| Path::new(stem).extension())
+                    .and_then(|bank| usize::from_str_radix(&bank.to_string_lossy(), 16).ok());
+
+                self.load_fceux_nl(&fs::read_to_string(path)?, bank);
+            }
+            _ => return Err(SymbolsError::ExtensionError),
+        }
+
+        Ok(())
+    }
+
+    /// Load the symbols of a ca65/ld65 debug file (`ld65 --dbgfile`).
+    ///
+    /// Labels in read only segments written to the `.nes` file are placed in
+    /// PRG ROM using the segment file offset.
+    pub fn load_ca65_dbg(&mut self, content: &str) {
+        // segment id => (start address, offset in PRG ROM)
+        let mut segments = HashMap::new();
+        let mut symbols = Vec::new();
+
+        for line in content.lines() {
+            let (kind, attributes) = match line.split_once(char::is_whitespace) {
+                Some(split) => split,
+                None => continue,
+            };
+            let attributes = parse_dbg_attributes(attributes);
+
+            match kind {
+                "seg" => {
+                    let is_rom = attributes.get("type") == Some(&"ro");
+                    let start = attributes.get("start").and_then(|v| parse_number(v));
+                    let offset = attributes.get("ooffs").and_then(|v| parse_number(v));
+                    let id = attributes.get("id").and_then(|v| parse_number(v));
+
+                    if let (true, Some(id), Some(start), Some(offset)) = (is_rom, id, start, offset)
+                    {
+                        if offset >= INES_HEADER_SIZE {
+                            segments.insert(id, (start, offset - INES_HEADER_SIZE));
+                        }
+                    }
+                }
+                "sym" => {
+                    // only labels and equates have addresses
+                    if !matches!(attributes.get("type"), Some(&"lab") | Some(&"equ")) {
+                        continue;
+                    }
+
+                    let name = attributes.get("name").map(|name| name.trim_matches('"'));
+                    let value = attributes.get("val").and_then(|v| parse_number(v));
+                    let segment = attributes.get("seg").and_then(|v| parse_number(v));
+
+                    if let (Some(name), Some(value)) = (name, value) {
+                        if value <= 0xFFFF {
+                            symbols.push((name.to_owned(), value, segment));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        for (name, value, segment) in symbols {
+            match segment.and_then(|id| segments.get(&id)) {
+                Some(&(start, offset)) if value >= 0x8000 && value >= start => {
+                    self.prg_rom.insert(offset + value - start, name);
+                }
+                _ => {
+                    self.cpu.insert(value as u16, name);
+                }
+            }
+        }
+    }
+
+    /// Load the labels of a FCEUX `.nl` file, lines are `$ADDR#name#comment`.
+    ///
+    /// `bank` is the 16kb PRG ROM bank of the file, `None` for the RAM file.
+    pub fn load_fceux_nl(&mut self, content: &str, bank: Option<usize>) {
+        for line in content.lines() {
+            let mut parts = line.splitn(3, '#');
+            let (address, name) = match (parts.next(), parts.next()) {
+                (Some(address), Some(name)) => (address, name),
+                _ => continue,
+            };
+            // arrays are `$ADDR/size`
+            let address = address.split('/').next().unwrap_or_default();
+            let address = match address
+                .strip_prefix('$')
+                .and_then(|a| u16::from_str_radix(a.trim(), 16).ok())
+            {
+                Some(address) => address,
+                None => continue,
+            };
+            let name = name.trim();
+            if name.is_empty() {
+                continue;
+            }
+
+            match bank {
+                Some(bank) if address >= 0x8000 => {
+                    let offset = bank * FCEUX_BANK_SIZE + (address as usize & 0x3FFF);
+                    self.prg_rom.insert(offset, name.to_owned());
+                }
+                _ => {
+                    self.cpu.insert(address, name.to_owned());
+                }
+            }
+        }
+    }
+
+    /// Load the labels of a Mesen `.mlb` file, lines are `TYPE:ADDR[-END]:name[:comment]`.
+    ///
+    /// Save and work RAM labels are placed at $6000, without PRG RAM banking.
+    pub fn load_mesen_mlb(&mut self, content: &str) {
+        for line in content.lines() {
+            let mut parts = line.splitn(4, ':');
+            let (kind, address, name) = match (parts.next(), parts.next(), parts.next()) {
+                (Some(kind), Some(address), Some(name)) => (kind, address, name),
+                _ => continue,
+            };
+            let address = address.split('-').next().unwrap_or_default();
+            let address = match usize::from_str_radix(address.trim(), 16) {
+                Ok(address) => address,
+                Err(_) => continue,
+            };
+            let name = name.trim();
+            if name.is_empty() {
+                continue;
+            }
+            let name = name.to_owned();
+
+            match kind.trim() {
+                "P" | "NesPrgRom" => {
+                    self.prg_rom.insert(address, name);
+                }
+                "R" | "NesInternalRam" if address < 0x800 => {
+                    self.cpu.insert(address as u16, name);
+                }
+                "S" | "W" | "NesSaveRam" | "NesWorkRam" if address < 0x2000 => {
+                    self.cpu.insert(0x6000 + address as u16, name);
+                }
+                "G" | "NesMemory" if address <= 0xFFFF => {
+                    self.cpu.insert(address as u16, name);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Add a label for a fixed CPU address
+    pub fn add_label(&mut self, address: u16, name: String) {
+        self.cpu.insert(address, name);
+    }
+
+    /// Add a label for an offset in the PRG ROM
+    pub fn add_prg_rom_label(&mut self, offset: usize, name: String) {
+        self.prg_rom.insert(offset, name);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cpu.is_empty() && self.prg_rom.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.cpu.clear();
+        self.prg_rom.clear();
+    }
+
+    /// The label of `address`, `prg_rom_offset` is where the address is mapped
+    /// in PRG ROM currently, PRG ROM labels are used first.
+    pub fn label(&self, address: u16, prg_rom_offset: Option<usize>) -> Option<&str> {
+        prg_rom_offset
+            .and_then(|offset| self.prg_rom.get(&offset))
+            .or_else(|| self.cpu.get(&address))
+            .map(String::as_str)
+    }
+}
+
+/// Split `name="x",val=0x10` attributes, quoted values may contain commas
+fn parse_dbg_attributes(attributes: &str) -> HashMap<&str, &str> {
+    let mut result = HashMap::new();
+    let mut rest = attributes.trim();
+
+    while !rest.is_empty() {
+        let (key, value) = match rest.split_once('=') {
+            Some(split) => split,
+            None => break,
+        };
+
+        let value_end = if let Some(quoted) = value.strip_prefix('"') {
+            quoted.find('"').map(|i| i + 2).unwrap_or(value.len())
+        } else {
+            value.find(',').unwrap_or(value.len())
+        };
+
+        result.insert(key.trim(), &value[..value_end]);
+        rest = value[value_end..].trim_start_matches(',');
+    }
+
+    result
+}
+
+/// Parse decimal or `0x` hex numbers
+fn parse_number(value: &str) -> Option<usize> {
+    match value.strip_prefix("0x") {
+        Some(hex) => usize::from_str_radix(hex, 16).ok(),
+        None => value.parse().ok(),
+    }
+}
@@ -0,0 +1,239 @@
+use super::disassembler::disassemble_instruction;
+use crate::cpu6502::instruction::{AddressingMode, Opcode};
+use crate::cpu6502::CPURegisters;
+use std::io::{Error as ioError, Write};
+use std::ops::RangeInclusive;
+
+/// Write one line per executed CPU instruction in the Nintendulator/nestest
+/// log format, see [`NES::start_trace`][crate::NES::start_trace]:
+///
+/// ```text
+/// C000  4C F5 C5  JMP $C5F5                       A:00 X:00 Y:00 P:24 SP:FD PPU:  0, 21 CYC:7
+/// ```
+///
+/// The registers are not read for the memory values, to avoid side effects,
+/// and are shown as `FF`.
+pub struct TraceLogger {
+    writer: Box<dyn Write>,
+    addresses: Option<RangeInclusive<u16>>,
+    frames: Option<RangeInclusive<u64>>,
+    use_symbols: bool,
+    /// the first write error, nothing is written after it
+    error: Option<ioError>,
+}
+
+impl TraceLogger {
+    pub fn new<W: Write + 'static>(writer: W) -> Self {
+        Self {
+            writer: Box::new(writer),
+            addresses: None,
+            frames: None,
+            use_symbols: false,
+            error: None,
+        }
+    }
+
+    /// Only log instructions starting in `addresses`
+    pub fn with_addresses(mut self, addresses: RangeInclusive<u16>) -> Self {
+        self.addresses = Some(addresses);
+        self
+    }
+
+    /// Only log instructions run in `frames`, counted from power on,
+    /// see [`CycleCounters::frames`][super::CycleCounters::frames]
+    pub fn with_frames(mut self, frames: RangeInclusive<u64>) -> Self {
+        self.frames = Some(frames);
+        self
+    }
+
+    /// Replace the operand addresses with the labels of the loaded
+    /// [`Symbols`][super::Symbols], the lines won't match reference logs anymore
+    pub fn with_symbols(mut self) -> Self {
+        self.use_symbols = true;
+        self
+    }
+
+    pub fn uses_symbols(&self) -> bool {
+        self.use_symbols
+    }
+
+    /// The error that stopped the logging, if any
+    pub fn error(&self) -> Option<&ioError> {
+        self.error.as_ref()
+    }
+
+    /// Flush the writer, and return the first write error if any
+    pub fn finish(mut self) -> Result<(), ioError> {
+        match self.error.take() {
+            Some(err) => Err(err),
+            None => self.writer.flush(),
+        }
+    }
+
+    pub(crate) fn is_traced(&self, pc: u16, frame: u64) -> bool {
+        self.error.is_none()
+            && self
+                .addresses
+                .as_ref()
+                .map(|addresses| addresses.contains(&pc))
+                .unwrap_or(true)
+            && self
+                .frames
+                .as_ref()
+                .map(|frames| frames.contains(&frame))
+                .unwrap_or(true)
+    }
+
+    pub(crate) fn log(&mut self, line: &str) {
+        if self.error.is_none() {
+            if let Err(err) = writeln!(self.writer, "{}", line) {
+                self.error = Some(err);
+            }
+        }
+    }
+}
+
+/// The trace line of the instruction at `registers.pc`, `position` is the
+/// PPU scanline and dot.
+///
+/// `read` reads memory without side effects, and `label` gives the label
+/// to use for an operand address.
+pub(crate) fn trace_line<'a, R, L>(
+    registers: &CPURegisters,
+    position: (u16, u16),
+    cycles: u64,
+    read: R,
+    label: L,
+) -> String
+where
+    R: Fn(u16) -> Option<u8>,
+    L: Fn(u16) -> Option<&'a str>,
+{
+    let instruction = match disassemble_instruction(registers.pc, &read, |_| None) {
+        Some(instruction) => instruction,
+        // should not happen, instructions run from RAM or the cartridge
+        None => return format!("{:04X}  ??", registers.pc),
+    };
+
+    let read = |address: u16| read(address).unwrap_or(0xFF);
+    let read_word_zero_page = |address: u8| {
+        u16::from_le_bytes([read(address as u16), read(address.wrapping_add(1) as u16)])
+    };
+
+    let operand = instruction.instruction.operand;
+    let address_text = |address: u16, digits: usize| match label(address) {
+        Some(label) => label.to_owned(),
+        None => format!("${:0digits$X}", address, digits = digits),
+    };
+
+    use AddressingMode::*;
+    let operand_text = match instruction.instruction.addressing_mode {
+        Implied => String::new(),
+        Accumulator => "A".to_owned(),
+        Immediate => format!("#${:02X}", operand),
+        Relative => address_text(instruction.target.unwrap(), 4),
+        ZeroPage => format!("{} = {:02X}", address_text(operand, 2), read(operand)),
+        ZeroPageIndexX | ZeroPageIndexY => {
+            let (index, name) = if instruction.instruction.addressing_mode == ZeroPageIndexX {
+                (registers.x, 'X')
+            } else {
+                (registers.y, 'Y')
+            };
+            let address = (operand as u8).wrapping_add(index) as u16;
+
+            format!(
+                "{},{} @ {:02X} = {:02X}",
+                address_text(operand, 2),
+                name,
+                address,
+                read(address)
+            )
+        }
+        Absolute => match instruction.instruction.opcode {
+            Opcode::Jmp | Opcode::Jsr => address_text(operand, 4),
+            _ => format!("{} = {:02X}", address_text(operand, 4), read(operand)),
+        },
+        AbsoluteX | AbsoluteY => {
+            let (index, name) = if instruction.instruction.addressing_mode == AbsoluteX {
+                (registers.x, 'X')
+            } else {
+                (registers.y, 'Y')
+            };
+            let address = operand.wrapping_add(index as u16);
+
+            format!(
+                "{},{} @ {:04X} = {:02X}",
+                address_text(operand, 4),
+                name,
+                address,
+                read(address)
+            )
+        }
+        Indirect => {
+            // the high byte is read from the same page
+            let high_address = (operand & 0xFF00) | (operand.wrapping_add(1) & 0xFF);
+            let target = u16::from_le_bytes([read(operand), read(high_address)]);
+
+            format!("({}) = {:04X}", address_text(operand, 4), target)
+        }
+        XIndirect => {
+            let pointer = (operand as u8).wrapping_add(registers.x);
+            let address = read_word_zero_page(pointer);
+
+            format!(
+                "({},X) @ {:02X} = {:04X} = {:02X}",
+                address_text(operand, 2),
+                pointer,
+                address,
+                read(address)
+            )
+        }
+        IndirectY => {
+            let base = read_word_zero_page(operand as u8);
+            let address = base.wrapping_add(registers.y as u16);
+
+            format!(
+                "({}),Y = {:04X} @ {:04X} = {:02X}",
+                address_text(operand, 2),
+                base,
+                address,
+                read(address)
+            )
+        }
+    };
+
+    let text = if operand_text.is_empty() {
+        instruction.instruction.opcode.to_string()
+    } else {
+        format!("{} {}", instruction.instruction.opcode, operand_text)
+    };
+    let bytes = instruction
+        .bytes
+        .iter()
+        .map(|byte| format!("{:02X}", byte))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let marker = if instruction.is_unofficial() {
+        '*'
+    } else {
+        ' '
+    };
+    // the break flag is not a real flag, and bit 5 is always set
+    let status = (registers.status | 0x20) & !0x10;
+
+    format!(
+        "{:04X}  {:<8} {}{:<31} A:{:02X} X:{:02X} Y:{:02X} P:{:02X} SP:{:02X} PPU:{:>3},{:>3} CYC:{}",
+        registers.pc,
+        bytes,
+        marker,
+        text,
+        registers.a,
+        registers.x,
+        registers.y,
+        status,
+        registers.sp,
+        position.0,
+        position.1,
+        cycles
+    )
+}
@@ -156,3 +156,86 @@ const DEFAULT_COLORS: [Color; 0x40] = [
 
 /// Selects which of the two colors to use
 pub const COLORS: [Color; 0x40] = NEW_COLORS;
+
+// the RGB PPUs output 3 bits per channel
+macro_rules! rgb_ppu_color {
+    ($r:expr, $g:expr, $b:expr) => {
+        color!(
+            ($r as u16 * 255 / 7) as u8,
+            ($g as u16 * 255 / 7) as u8,
+            ($b as u16 * 255 / 7) as u8
+        )
+    };
+}
+
+/// The colors of the RGB PPUs (2C03 and 2C05) used in the Vs. System and PlayChoice-10,
+/// the 2C04 variants have the same colors in a different order.
+///
+/// got these color data from
+/// https://www.nesdev.org/wiki/PPU_palettes#2C03_and_2C05
+pub const RGB_PPU_COLORS: [Color; 0x40] = [
+    rgb_ppu_color!(3, 3, 3),
+    rgb_ppu_color!(0, 1, 4),
+    rgb_ppu_color!(0, 0, 6),
+    rgb_ppu_color!(3, 2, 6),
+    rgb_ppu_color!(4, 0, 3),
+    rgb_ppu_color!(5, 0, 3),
+    rgb_ppu_color!(5, 1, 0),
+    rgb_ppu_color!(4, 2, 0),
+    rgb_ppu_color!(3, 2, 0),
+    rgb_ppu_color!(1, 2, 0),
+    rgb_ppu_color!(0, 3, 1),
+    rgb_ppu_color!(0, 4, 0),
+    rgb_ppu_color!(0, 2, 2),
+    rgb_ppu_color!(0, 0, 0),
+    rgb_ppu_color!(0, 0, 0),
+    rgb_ppu_color!(0, 0, 0),
+    rgb_ppu_color!(5, 5, 5),
+    rgb_ppu_color!(0, 3, 6),
+    rgb_ppu_color!(0, 2, 7),
+    rgb_ppu_color!(4, 0, 7),
+    rgb_ppu_color!(5, 0, 7),
+    rgb_ppu_color!(7, 0, 4),
+    rgb_ppu_color!(7, 0, 0),
+    rgb_ppu_color!(6, 3, 0),
+    rgb_ppu_color!(4, 3, 0),
+    rgb_ppu_color!(1, 4, 0),
+    rgb_ppu_color!(0, 4, 0),
+    rgb_ppu_color!(0, 5, 3),
+    rgb_ppu_color!(0, 4, 4),
+    rgb_ppu_color!(0, 0, 0),
+    rgb_ppu_color!(0, 0, 0),
+    rgb_ppu_color!(0, 0, 0),
+    rgb_ppu_color!(7, 7, 7),
+    rgb_ppu_color!(3, 5, 7),
+    rgb_ppu_color!(4, 4, 7),
+    rgb_ppu_color!(6, 3, 7),
+    rgb_ppu_color!(7, 0, 7),
+    rgb_ppu_color!(7, 3, 7),
+    rgb_ppu_color!(7, 4, 0),
+    rgb_ppu_color!(7, 5, 0),
+    rgb_ppu_color!(6, 6, 0),
+    rgb_ppu_color!(3, 6, 0),
+    rgb_ppu_color!(0, 7, 0),
+    rgb_ppu_color!(2, 7, 6),
+    rgb_ppu_color!(0, 7, 7),
+    rgb_ppu_color!(0, 0, 0),
+    rgb_ppu_color!(0, 0, 0),
+    rgb_ppu_color!(0, 0, 0),
+    rgb_ppu_color!(7, 7, 7),
+    rgb_ppu_color!(5, 6, 7),
+    rgb_ppu_color!(6, 5, 7),
+    rgb_ppu_color!(7, 5, 7),
+    rgb_ppu_color!(7, 4, 7),
+    rgb_ppu_color!(7, 5, 5),
+    rgb_ppu_color!(7, 6, 4),
+    rgb_ppu_color!(7, 7, 2),
+    rgb_ppu_color!(7, 7, 3),
+    rgb_ppu_color!(5, 7, 2),
+    rgb_ppu_color!(4, 7, 3),
+    rgb_ppu_color!(2, 7, 6),
+    rgb_ppu_color!(4, 6, 7),
+    rgb_ppu_color!(0, 0, 0),
+    rgb_ppu_color!(0, 0, 0),
+    rgb_ppu_color!(0, 0, 0),
+];
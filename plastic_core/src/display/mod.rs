@@ -3,5 +3,5 @@ mod color;
 mod tv;
 
 pub use color::Color;
-pub use color::COLORS;
+pub use color::{COLORS, RGB_PPU_COLORS};
 pub use tv::{COLOR_BYTES_LEN, TV, TV_BUFFER_SIZE, TV_HEIGHT, TV_WIDTH};
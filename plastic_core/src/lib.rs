@@ -31,6 +31,7 @@ mod apu2a03;
 mod cartridge;
 mod controller;
 mod cpu6502;
+mod debugger;
 mod display;
 #[cfg(feature = "frontend_misc")]
 pub mod misc;
@@ -42,17 +43,43 @@ mod tests;
 
 pub use cartridge::CartridgeError;
 pub use common::save_state::SaveError;
+pub use common::Region;
 pub use controller::NESKey;
 pub use nes::NES;
 
 /// Structures used when interacting with the CPU, see also [`NES::clock`][NES::clock]
 pub mod cpu {
-    pub use super::cpu6502::CPURunState;
+    pub use super::cpu6502::instruction::{AddressingMode, Instruction, Opcode};
+    pub use super::cpu6502::{CPURegisters, CPURunState};
+}
+
+/// Breakpoints, watchpoints and stepping, see [`NES::enable_debugger`][NES::enable_debugger],
+/// the disassembler with symbol files, see [`NES::disassemble`][NES::disassemble],
+/// the instruction trace, see [`NES::start_trace`][NES::start_trace],
+/// and the OAM sprites, see [`NES::oam_sprites`][NES::oam_sprites]
+pub mod debug {
+    pub use super::debugger::{
+        register_name, Access, BreakReason, Breakpoint, Comparison, Condition, CycleCounters,
+        Debugger, DisassembledInstruction, MemorySpace, Register, Symbols, SymbolsError,
+        TraceLogger, Watchpoint,
+    };
+    pub use super::ppu2c02::OamSprite;
 }
 
 /// Helper variables related to handling pixel buffers from the emulator
 pub mod nes_display {
     pub use super::display::{COLOR_BYTES_LEN, TV_BUFFER_SIZE, TV_HEIGHT, TV_WIDTH};
+    pub use super::ppu2c02::{
+        NAMETABLES_HEIGHT, NAMETABLES_WIDTH, PATTERN_TABLES_HEIGHT, PATTERN_TABLES_WIDTH,
+    };
+}
+/// Information about the game found in the game database, see [`NES::game_info`][NES::game_info]
+pub mod game_db {
+    pub use super::cartridge::GameInfo;
+}
+/// Information about NSF/NSFe music files, see [`NES::nsf_info`][NES::nsf_info]
+pub mod nsf {
+    pub use super::cartridge::{NsfInfo, NsfTrack};
 }
 /// Helper variables related to handling audio buffers from the emulator
 pub mod nes_audio {
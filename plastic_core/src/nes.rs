@@ -1,25 +1,33 @@
 use crate::apu2a03::APU2A03;
-use crate::cartridge::{Cartridge, CartridgeError};
+use crate::cartridge::{Cartridge, CartridgeError, ConsoleType, GameInfo, NsfInfo};
 use crate::common::{
     interconnection::*,
     save_state::{Savable, SaveError},
-    Bus, Device, MirroringProvider,
+    Bus, Device, MirroringProvider, NametableMapping, Region,
+};
+use crate::controller::{Controller, VsSystem};
+use crate::cpu6502::{CPUBusTrait, CPURegisters, CPURunState, CPU6502};
+use crate::debugger::{
+    disassemble, disassemble_instruction, trace_line, Access, CycleCounters, Debugger,
+    DisassembledInstruction, MemorySpace, Symbols, SymbolsError, TraceLogger, Watches,
 };
-use crate::controller::Controller;
-use crate::cpu6502::{CPUBusTrait, CPURunState, CPU6502};
 use crate::display::TV;
-use crate::ppu2c02::{Palette, VRam, PPU2C02};
+use crate::ppu2c02::{OamSprite, Palette, PpuModel, VRam, PPU2C02};
 use crate::NESKey;
 use std::cell::Cell;
 use std::cell::RefCell;
 use std::io::Read;
+use std::ops::RangeInclusive;
 use std::path::Path;
 use std::rc::Rc;
+use std::time::Duration;
 
 struct PPUBus {
     cartridge: Rc<RefCell<dyn Bus>>,
     vram: VRam,
     palettes: Palette,
+    /// the debugger watchpoints, if the debugger is enabled
+    watches: Option<Rc<Watches>>,
 }
 
 impl PPUBus {
@@ -31,27 +39,95 @@ impl PPUBus {
             cartridge: cartridge.clone(),
             vram: VRam::new(cartridge),
             palettes: Palette::new(),
+            watches: None,
+        }
+    }
+
+    fn read_memory(&self, address: u16, device: Device) -> u8 {
+        match address {
+            0x0000..=0x1FFF => self.cartridge.borrow().read(address, device),
+            0x2000..=0x3EFF => {
+                let address = address & 0x2FFF;
+                match self.vram.nametable_mapping(address) {
+                    NametableMapping::Vram(block) => self.vram.read_block(block, address),
+                    NametableMapping::Cartridge => self.cartridge.borrow().read(address, device),
+                }
+            }
+            0x3F00..=0x3FFF => self.palettes.read(address, device),
+            // mirror
+            0x4000..=0xFFFF => self.read_memory(address & 0x3FFF, device),
+        }
+    }
+
+    fn write_memory(&mut self, address: u16, data: u8, device: Device) {
+        match address {
+            0x0000..=0x1FFF => self.cartridge.borrow_mut().write(address, data, device),
+            0x2000..=0x3EFF => {
+                let address = address & 0x2FFF;
+                match self.vram.nametable_mapping(address) {
+                    NametableMapping::Vram(block) => self.vram.write_block(block, address, data),
+                    NametableMapping::Cartridge => {
+                        self.cartridge.borrow_mut().write(address, data, device)
+                    }
+                }
+            }
+            0x3F00..=0x3FFF => self.palettes.write(address, data, device),
+            // mirror
+            0x4000..=0xFFFF => self.write_memory(address & 0x3FFF, data, device),
         }
     }
 }
 
 impl Bus for PPUBus {
     fn read(&self, address: u16, device: Device) -> u8 {
+        let data = self.read_memory(address, device);
+
+        if let Some(watches) = &self.watches {
+            watches.check(MemorySpace::Ppu, address & 0x3FFF, data, Access::Read);
+        }
+
+        data
+    }
+
+    fn write(&mut self, address: u16, data: u8, device: Device) {
+        if let Some(watches) = &self.watches {
+            watches.check(MemorySpace::Ppu, address & 0x3FFF, data, Access::Write);
+        }
+
+        self.write_memory(address, data, device);
+    }
+
+    fn peek(&self, address: u16, device: Device) -> u8 {
         match address {
-            0x0000..=0x1FFF => self.cartridge.borrow().read(address, device),
-            0x2000..=0x3EFF => self.vram.read(address & 0x2FFF, device),
+            0x0000..=0x1FFF => self.cartridge.borrow().peek(address, device),
+            0x2000..=0x3EFF => {
+                let address = address & 0x2FFF;
+                match self.vram.peek_nametable_mapping(address) {
+                    NametableMapping::Vram(block) => self.vram.read_block(block, address),
+                    NametableMapping::Cartridge => self.cartridge.borrow().peek(address, device),
+                }
+            }
             0x3F00..=0x3FFF => self.palettes.read(address, device),
             // mirror
-            0x4000..=0xFFFF => self.read(address & 0x3FFF, device),
+            0x4000..=0xFFFF => self.peek(address & 0x3FFF, device),
         }
     }
-    fn write(&mut self, address: u16, data: u8, device: Device) {
+
+    fn poke(&mut self, address: u16, data: u8, device: Device) {
         match address {
-            0x0000..=0x1FFF => self.cartridge.borrow_mut().write(address, data, device),
-            0x2000..=0x3EFF => self.vram.write(address & 0x2FFF, data, device),
+            0x0000..=0x1FFF => self.cartridge.borrow_mut().poke(address, data, device),
+            0x2000..=0x3EFF => {
+                let address = address & 0x2FFF;
+                match self.vram.peek_nametable_mapping(address) {
+                    NametableMapping::Vram(block) => self.vram.write_block(block, address, data),
+                    NametableMapping::Cartridge => {
+                        self.cartridge.borrow_mut().poke(address, data, device)
+                    }
+                }
+            }
             0x3F00..=0x3FFF => self.palettes.write(address, data, device),
             // mirror
-            0x4000..=0xFFFF => self.write(address & 0x3FFF, data, device),
+            0x4000..=0xFFFF => self.poke(address & 0x3FFF, data, device),
         }
     }
 }
@@ -78,6 +154,9 @@ struct CPUBus {
     ppu: PPU2C02<PPUBus>,
     apu: APU2A03,
     contoller: Controller,
+    vs_system: Option<VsSystem>,
+    /// the debugger watchpoints, if the debugger is enabled
+    watches: Option<Rc<Watches>>,
     irq_pin_change_requested: Cell<bool>,
 }
 
@@ -87,6 +166,7 @@ impl CPUBus {
         ppu: PPU2C02<PPUBus>,
         apu: APU2A03,
         contoller: Controller,
+        vs_system: Option<VsSystem>,
     ) -> Self {
         CPUBus {
             cartridge,
@@ -94,6 +174,8 @@ impl CPUBus {
             ppu,
             apu,
             contoller,
+            vs_system,
+            watches: None,
             irq_pin_change_requested: Cell::new(false),
         }
     }
@@ -101,16 +183,28 @@ impl CPUBus {
     fn contoller_mut(&mut self) -> &mut Controller {
         &mut self.contoller
     }
-}
 
-impl CPUBusTrait for CPUBus {
-    fn read(&self, address: u16) -> u8 {
+    /// Read $4016/$4017 on the Vs. System, the controller is read from $4017
+    /// and the second controller in $4016 is not connected
+    fn read_vs_system_port(&self, address: u16) -> u8 {
+        let vs_system = self.vs_system.as_ref().unwrap();
+        let controller = if address == 0x4017 {
+            self.contoller.read(address, Device::Cpu)
+        } else {
+            0
+        };
+
+        vs_system.read(address, Device::Cpu) | controller
+    }
+
+    fn read_memory(&self, address: u16) -> u8 {
         match address {
             0x0000..=0x1FFF => self.ram[(address & 0x7FF) as usize],
             0x2000..=0x3FFF => self.ppu.read(0x2000 | (address & 0x7), Device::Cpu),
             0x4000..=0x4013 => self.apu.read(address, Device::Cpu),
             0x4014 => self.ppu.read(address, Device::Cpu),
             0x4015 => self.apu.read(address, Device::Cpu),
+            0x4016 | 0x4017 if self.vs_system.is_some() => self.read_vs_system_port(address),
             0x4016 => self.contoller.read(address, Device::Cpu),
             0x4017 => self.apu.read(address, Device::Cpu),
             0x4018..=0x401F => {
@@ -121,10 +215,17 @@ impl CPUBusTrait for CPUBus {
         }
     }
 
-    fn write(&mut self, address: u16, data: u8) {
+    fn write_memory(&mut self, address: u16, data: u8) {
         match address {
             0x0000..=0x1FFF => self.ram[(address & 0x7FF) as usize] = data,
-            0x2000..=0x3FFF => self.ppu.write(0x2000 | (address & 0x7), data, Device::Cpu),
+            0x2000..=0x3FFF => {
+                let address = 0x2000 | (address & 0x7);
+                self.ppu.write(address, data, Device::Cpu);
+                // some mappers (MMC5) listen to the writes to the PPU registers
+                self.cartridge
+                    .borrow_mut()
+                    .snoop_ppu_register_write(address, data);
+            }
             0x4000..=0x4013 => self.apu.write(address, data, Device::Cpu),
             0x4014 => self.ppu.write(address, data, Device::Cpu),
             0x4015 => self.apu.write(address, data, Device::Cpu),
@@ -133,12 +234,39 @@ impl CPUBusTrait for CPUBus {
             0x4018..=0x401F => {
                 // unused CPU test mode registers
             }
+            // the coin counter of the Vs. System
+            0x4020 if self.vs_system.is_some() => {
+                self.vs_system
+                    .as_mut()
+                    .unwrap()
+                    .write(address, data, Device::Cpu)
+            }
             0x4020..=0xFFFF => self
                 .cartridge
                 .borrow_mut()
                 .write(address, data, Device::Cpu),
         }
     }
+}
+
+impl CPUBusTrait for CPUBus {
+    fn read(&self, address: u16) -> u8 {
+        let data = self.read_memory(address);
+
+        if let Some(watches) = &self.watches {
+            watches.check(MemorySpace::Cpu, address, data, Access::Read);
+        }
+
+        data
+    }
+
+    fn write(&mut self, address: u16, data: u8) {
+        if let Some(watches) = &self.watches {
+            watches.check(MemorySpace::Cpu, address, data, Access::Write);
+        }
+
+        self.write_memory(address, data);
+    }
 
     fn reset(&mut self) {
         self.ram = [0; 0x800];
@@ -251,14 +379,92 @@ pub struct NES {
     /// CPU and containing all components through the `CPUBus`.
     cpu: CPU6502<CPUBus>,
 
+    region: Region,
+
     frame_counter: f32,
+    /// the PPU cycles to run, in fifths of a cycle
+    ppu_cycles_counter: u8,
+
+    debugger: Option<Box<Debugger>>,
+    symbols: Symbols,
+    tracer: Option<Box<TraceLogger>>,
 }
 
 impl NES {
     /// Creates a new NES instance from a given file path.
+    ///
+    /// Supports `.nes`, `.fds`, `.nsf` and `.nsfe` files, and `.zip`, `.7z` and `.gz`
+    /// archives containing one of them.
+    ///
+    /// If there is an `.ips`, `.bps` or `.ups` patch with the same name next to the file,
+    /// it is applied to the ROM, use [`NES::new_with_patches`] to select the patches.
+    ///
+    /// The region is taken from the file header (NTSC if not specified),
+    /// use [`NES::new_with_region`] to override it.
     pub fn new<P: AsRef<Path>>(filename: P) -> Result<Self, CartridgeError> {
         let cartridge = Cartridge::from_file(filename)?;
-        Ok(Self::create_nes(cartridge))
+        let region = cartridge.region();
+        Ok(Self::create_nes(cartridge, region))
+    }
+
+    /// Creates a new NES instance from a given file path, running in `region`
+    /// instead of the region from the file header.
+    pub fn new_with_region<P: AsRef<Path>>(
+        filename: P,
+        region: Region,
+    ) -> Result<Self, CartridgeError> {
+        let cartridge = Cartridge::from_file(filename)?;
+        Ok(Self::create_nes(cartridge, region))
+    }
+
+    /// Creates a new NES instance from a given file path, applying the IPS/BPS/UPS patches
+    /// at `patch_paths` in order before loading it.
+    ///
+    /// [`NES::new`] applies the patch file with the same name as the ROM automatically
+    /// (e.g. `game.ips` for `game.nes`), which is not done here.
+    pub fn new_with_patches<P: AsRef<Path>, Q: AsRef<Path>>(
+        filename: P,
+        patch_paths: &[Q],
+    ) -> Result<Self, CartridgeError> {
+        let cartridge = Cartridge::from_file_with_patches(filename, patch_paths)?;
+        let region = cartridge.region();
+        Ok(Self::create_nes(cartridge, region))
+    }
+
+    /// Creates a new NES instance from the content of a ROM file, which can be
+    /// any of the formats supported by [`NES::new`], including archives.
+    ///
+    /// `identity` is the path used to name the save files (SRAM and disk saves), as if
+    /// the data was loaded from that file. It doesn't need to exist.
+    pub fn from_bytes<P: AsRef<Path>>(data: &[u8], identity: P) -> Result<Self, CartridgeError> {
+        let cartridge = Cartridge::from_bytes(data, identity)?;
+        let region = cartridge.region();
+        Ok(Self::create_nes(cartridge, region))
+    }
+
+    /// Creates a new NES instance by reading a ROM file from `reader`,
+    /// see [`NES::from_bytes`].
+    pub fn from_reader<R: Read, P: AsRef<Path>>(
+        mut reader: R,
+        identity: P,
+    ) -> Result<Self, CartridgeError> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+
+        Self::from_bytes(&data, identity)
+    }
+
+    /// Creates a new NES instance from a Famicom Disk System image and the BIOS file (`disksys.rom`).
+    ///
+    /// [`NES::new`] can also load `.fds` files, but it searches for the BIOS next to the image
+    /// or in the current directory.
+    pub fn new_fds<P: AsRef<Path>, B: AsRef<Path>>(
+        filename: P,
+        bios_filename: B,
+    ) -> Result<Self, CartridgeError> {
+        let cartridge = Cartridge::from_fds_file(filename, bios_filename)?;
+        let region = cartridge.region();
+        Ok(Self::create_nes(cartridge, region))
     }
 
     /// Creates a new NES instance without loading a cartridge from a file.
@@ -268,22 +474,34 @@ impl NES {
     /// Do note that running [`NES::clock_for_frame`] or [`NES::clock`] will not do anything if the cartridge is empty.
     pub fn new_without_file() -> Self {
         let cartridge = Cartridge::new_without_file();
-        Self::create_nes(cartridge)
+        Self::create_nes(cartridge, Region::default())
     }
 
-    fn create_nes(cartridge: Cartridge) -> Self {
+    fn create_nes(mut cartridge: Cartridge, region: Region) -> Self {
+        cartridge.set_region(region);
+
+        let console_type = cartridge.console_type();
+
         let cartridge = Rc::new(RefCell::new(cartridge));
         let ppubus = PPUBus::new(cartridge.clone());
 
         let tv = TV::new();
 
-        let ppu = PPU2C02::new(ppubus, tv);
+        let mut ppu = PPU2C02::new(ppubus, tv, region);
+        ppu.set_model(match console_type {
+            ConsoleType::VsSystem { ppu_type, .. } => PpuModel::from_vs_ppu_type(ppu_type),
+            ConsoleType::Playchoice10 => PpuModel::Rp2c03,
+            _ => PpuModel::Rp2c02,
+        });
 
-        let apu = APU2A03::new();
+        let mut apu = APU2A03::new(region);
+        apu.set_expansion_audio_provider(cartridge.clone());
 
         let ctrl = Controller::new();
 
-        let cpubus = CPUBus::new(cartridge.clone(), ppu, apu, ctrl);
+        let vs_system = matches!(console_type, ConsoleType::VsSystem { .. }).then(VsSystem::new);
+
+        let cpubus = CPUBus::new(cartridge.clone(), ppu, apu, ctrl, vs_system);
 
         let mut cpu = CPU6502::new(cpubus);
 
@@ -292,7 +510,12 @@ impl NES {
         Self {
             cartridge,
             cpu,
+            region,
             frame_counter: 0.,
+            ppu_cycles_counter: 0,
+            debugger: None,
+            symbols: Symbols::new(),
+            tracer: None,
         }
     }
 
@@ -301,14 +524,30 @@ impl NES {
         self.cpu.reset();
         self.cpu.reset_bus();
 
-        let ppubus = PPUBus::new(self.cartridge.clone());
+        let mut ppubus = PPUBus::new(self.cartridge.clone());
+        ppubus.watches = self.debugger.as_ref().map(|debugger| debugger.watches());
 
         self.cpu.bus_mut().ppu.reset(ppubus);
 
-        self.cpu.bus_mut().apu = APU2A03::new();
+        let mut apu = APU2A03::new(self.region);
+        apu.set_expansion_audio_provider(self.cartridge.clone());
+
+        self.cpu.bus_mut().apu = apu;
+    }
+
+    /// The region the emulator is running in.
+    pub fn region(&self) -> Region {
+        self.region
     }
 
-    /// Run the NES emulator for one video frame, which is equal to `29780` CPU cycles.
+    /// The number of frames per second of the emulated region, i.e. how many times
+    /// [`NES::clock_for_frame`] should be called every second.
+    pub fn frame_rate(&self) -> f64 {
+        self.region.frame_rate()
+    }
+
+    /// Run the NES emulator for one video frame, which is equal to `29780` CPU cycles on NTSC,
+    /// `33247` on PAL, and `35464` on Dendy.
     ///
     /// This is the main function to run the emulator, call this once, and then render and play audio.
     pub fn clock_for_frame(&mut self) {
@@ -316,44 +555,183 @@ impl NES {
             return;
         }
 
-        const CPU_CYCLES_PER_FRAME: f32 = 29780.5; // number of CPU cycles per loop, one full frame
+        if self.debugger.is_some() || self.tracer.is_some() {
+            self.clock_for_frame_with_debugger();
+        } else {
+            // number of CPU cycles per loop, one full frame
+            self.frame_counter += self.region.cpu_cycles_per_frame();
+
+            while self.frame_counter >= 0. {
+                self.frame_counter -= 1.;
+                self.clock_cycle();
+            }
+        }
+
+        let nsf_track_finished = self
+            .cartridge
+            .borrow()
+            .nsf()
+            .map(|nsf| nsf.is_track_finished())
+            .unwrap_or(false);
+        if nsf_track_finished {
+            self.nsf_next_track();
+        }
+    }
 
-        self.frame_counter += CPU_CYCLES_PER_FRAME;
+    /// Same as [`NES::clock_for_frame`], but stops at the debugger breaks,
+    /// and logs the trace
+    fn clock_for_frame_with_debugger(&mut self) {
+        if let Some(debugger) = &self.debugger {
+            if debugger.is_paused() {
+                return;
+            }
+        }
+
+        self.frame_counter += self.region.cpu_cycles_per_frame();
 
         while self.frame_counter >= 0. {
+            if self.debugger.is_some() && self.check_debugger_instruction() {
+                // run a full frame after resuming
+                self.frame_counter = 0.;
+                break;
+            }
+            self.trace_instruction();
+
             self.frame_counter -= 1.;
-            self.cpu.run_next();
-            self.cpu.bus_mut().apu.clock();
-            {
-                let ppu = &mut self.cpu.bus_mut().ppu;
-                ppu.clock();
-                ppu.clock();
-                ppu.clock();
+            self.clock_cycle();
+
+            if let Some(debugger) = &mut self.debugger {
+                if debugger.check_watchpoints() {
+                    self.frame_counter = 0.;
+                    break;
+                }
             }
         }
     }
 
+    /// Run one CPU cycle, and the other components along with it
+    fn clock_cycle(&mut self) {
+        self.cpu.run_next();
+        self.cpu.bus_mut().apu.clock();
+        self.cartridge.borrow_mut().clock();
+        self.clock_vs_system();
+        self.clock_ppu();
+    }
+
     /// Run the NES emulator for one CPU cycle.
     ///
     /// This is useful for debugging and testing purposes.
+    ///
+    /// Returns `None` if the cartridge is empty, or if the [`Debugger`] paused the emulation.
     pub fn clock(&mut self) -> Option<CPURunState> {
         if self.cartridge.borrow().is_empty() {
             return None;
         }
 
+        if let Some(debugger) = &self.debugger {
+            if debugger.is_paused() || self.check_debugger_instruction() {
+                return None;
+            }
+        }
+        self.trace_instruction();
+
         self.cpu.bus_mut().apu.clock();
+        self.cartridge.borrow_mut().clock();
+        self.clock_vs_system();
 
         let r = self.cpu.run_next();
-        {
-            let ppu = &mut self.cpu.bus_mut().ppu;
-            ppu.clock();
-            ppu.clock();
-            ppu.clock();
+        self.clock_ppu();
+
+        if let Some(debugger) = &mut self.debugger {
+            debugger.check_watchpoints();
         }
 
         Some(r)
     }
 
+    /// Check the breakpoints and step commands if the CPU is about to start
+    /// an instruction, return `true` if the emulation is paused
+    fn check_debugger_instruction(&mut self) -> bool {
+        let mut debugger = self.debugger.take().unwrap();
+
+        let mut is_paused = false;
+        if self.cpu.next_instruction_address().is_some() {
+            let registers = self.cpu.registers();
+            let scanline = self.cpu.bus().ppu.scanline();
+
+            // the opcode is read without side effects and without checking the watchpoints
+            is_paused = debugger.check_instruction(&registers, scanline, |address| {
+                self.peek_cpu_memory(address)
+            });
+        }
+
+        self.debugger = Some(debugger);
+        is_paused
+    }
+
+    /// Log the instruction if the CPU is about to start one and the trace is enabled
+    fn trace_instruction(&mut self) {
+        let mut tracer = match self.tracer.take() {
+            Some(tracer) => tracer,
+            None => return,
+        };
+
+        if let Some(pc) = self.cpu.next_instruction_address() {
+            let ppu = &self.cpu.bus().ppu;
+
+            if tracer.is_traced(pc, ppu.frames()) {
+                // the PPU starts one dot before 0,0 at power on, Nintendulator
+                // counts the dots from 0,0, so the log shows the next dot
+                let position = if ppu.cycle() == 340 {
+                    let scanline = ppu.scanline() + 1;
+                    if scanline > self.region.pre_render_scanline() {
+                        (0, 0)
+                    } else {
+                        (scanline, 0)
+                    }
+                } else {
+                    (ppu.scanline(), ppu.cycle() + 1)
+                };
+
+                let use_symbols = tracer.uses_symbols();
+                let line = trace_line(
+                    &self.cpu.registers(),
+                    position,
+                    self.cpu.cycles(),
+                    |address| self.peek_cpu_memory(address),
+                    |address| {
+                        if use_symbols {
+                            self.cpu_label(address)
+                        } else {
+                            None
+                        }
+                    },
+                );
+                tracer.log(&line);
+            }
+        }
+
+        self.tracer = Some(tracer);
+    }
+
+    /// Clock the PPU for one CPU cycle, which is 3 PPU cycles on NTSC and Dendy,
+    /// and 3.2 on PAL
+    fn clock_ppu(&mut self) {
+        self.ppu_cycles_counter += self.region.ppu_cycles_per_5_cpu_cycles();
+
+        let ppu = &mut self.cpu.bus_mut().ppu;
+        while self.ppu_cycles_counter >= 5 {
+            self.ppu_cycles_counter -= 5;
+            ppu.clock();
+        }
+    }
+
+    fn clock_vs_system(&mut self) {
+        if let Some(vs_system) = &mut self.cpu.bus_mut().vs_system {
+            vs_system.clock();
+        }
+    }
+
     /// Return the pixel buffer as RGB format
     ///
     /// The size of the buffer will be [`TV_BUFFER_SIZE`][crate::nes_display::TV_BUFFER_SIZE]
@@ -361,6 +739,60 @@ impl NES {
         self.cpu.bus().ppu.tv().display_pixel_buffer()
     }
 
+    /// Run `f` with a function reading the PPU memory without side effects on
+    /// the mapper (e.g. the MMC2 latches or the MMC3 IRQ counter), and without
+    /// checking the debugger watchpoints
+    fn with_ppu_memory<R, F>(&self, f: F) -> R
+    where
+        F: FnOnce(&PPU2C02<PPUBus>, &dyn Fn(u16) -> u8) -> R,
+    {
+        let ppu = &self.cpu.bus().ppu;
+        let bus = ppu.ppu_bus();
+
+        f(ppu, &|address| bus.peek(address, Device::Ppu))
+    }
+
+    /// The 4 nametables, in the same format as [`NES::pixel_buffer`] with size
+    /// [`NAMETABLES_WIDTH`][crate::nes_display::NAMETABLES_WIDTH] *
+    /// [`NAMETABLES_HEIGHT`][crate::nes_display::NAMETABLES_HEIGHT].
+    ///
+    /// If `scroll_overlay` is `true`, the border of the screen at the scroll
+    /// position is drawn with inverted colors.
+    pub fn nametables_buffer(&self, scroll_overlay: bool) -> Vec<u8> {
+        self.with_ppu_memory(|ppu, read| ppu.render_nametables(read, scroll_overlay))
+    }
+
+    /// The 2 pattern tables side by side, using `palette` (0-3 for the background
+    /// palettes, 4-7 for the sprite palettes), in the same format as [`NES::pixel_buffer`]
+    /// with size [`PATTERN_TABLES_WIDTH`][crate::nes_display::PATTERN_TABLES_WIDTH] *
+    /// [`PATTERN_TABLES_HEIGHT`][crate::nes_display::PATTERN_TABLES_HEIGHT].
+    pub fn pattern_tables_buffer(&self, palette: u8) -> Vec<u8> {
+        self.with_ppu_memory(|ppu, read| ppu.render_pattern_tables(read, palette))
+    }
+
+    /// The 64 sprites in the OAM, with their attributes and pixels.
+    pub fn oam_sprites(&self) -> Vec<OamSprite> {
+        self.with_ppu_memory(|ppu, read| ppu.oam_sprites(read))
+    }
+
+    /// The colors of the 32 palette RAM entries, in the same format as
+    /// [`NES::pixel_buffer`] with size 32x1.
+    pub fn palette_buffer(&self) -> Vec<u8> {
+        self.with_ppu_memory(|ppu, read| ppu.render_palettes(read))
+    }
+
+    /// The 32 bytes of the palette RAM, the color indices of the palettes
+    pub fn palette_ram(&self) -> [u8; 32] {
+        let mut palette_ram = [0; 32];
+        let bus = self.cpu.bus().ppu.ppu_bus();
+
+        for (i, entry) in palette_ram.iter_mut().enumerate() {
+            *entry = bus.peek(0x3F00 + i as u16, Device::Ppu);
+        }
+
+        palette_ram
+    }
+
     /// Take and return the audio buffer as f32 format stereo (2 channels)
     ///
     /// **Take** here means that if you call the function again, it will return an empty buffer
@@ -369,7 +801,17 @@ impl NES {
     /// The emulator keeps accumulating audio samples until this function is called,
     /// so its better to call this function even if audio isn't needed in order to free up space.
     pub fn audio_buffer(&mut self) -> Vec<f32> {
-        self.cpu.bus_mut().apu.take_audio_buffer()
+        let mut buffer = self.cpu.bus_mut().apu.take_audio_buffer();
+
+        // fade out NSF tracks
+        if let Some(nsf) = self.cartridge.borrow().nsf() {
+            let volume = nsf.volume();
+            if volume < 1. {
+                buffer.iter_mut().for_each(|sample| *sample *= volume);
+            }
+        }
+
+        buffer
     }
 
     /// Check if there is no cartridge loaded in the emulator.
@@ -377,6 +819,95 @@ impl NES {
         self.cartridge.borrow().is_empty()
     }
 
+    /// The title, region and board of the loaded game from the game database.
+    ///
+    /// Only iNES 1.0 files are searched for in the database, and their header is
+    /// corrected using the database entry.
+    pub fn game_info(&self) -> Option<GameInfo> {
+        self.cartridge.borrow().game_info().cloned()
+    }
+
+    /// The number of disk sides of the loaded Famicom Disk System image,
+    /// `0` if the loaded game is a normal cartridge.
+    pub fn disk_sides_count(&self) -> usize {
+        self.cartridge.borrow().disk_sides_count()
+    }
+
+    /// The disk side currently inserted in the Famicom Disk System, `None` if the disk is ejected.
+    pub fn inserted_disk_side(&self) -> Option<usize> {
+        self.cartridge.borrow().inserted_disk_side()
+    }
+
+    /// Eject the disk from the Famicom Disk System.
+    pub fn eject_disk(&mut self) {
+        self.cartridge.borrow_mut().set_inserted_disk_side(None);
+    }
+
+    /// Insert a disk side into the Famicom Disk System, ejecting the current one.
+    ///
+    /// Sides are numbered from `0` (disk 1 side A, disk 1 side B, disk 2 side A, ...),
+    /// invalid sides are ignored.
+    pub fn insert_disk(&mut self, side: usize) {
+        self.cartridge
+            .borrow_mut()
+            .set_inserted_disk_side(Some(side));
+    }
+
+    /// Information about the loaded NSF/NSFe music file (title, tracks, ...),
+    /// `None` if the loaded game is not an NSF file.
+    pub fn nsf_info(&self) -> Option<NsfInfo> {
+        self.cartridge.borrow().nsf().map(|nsf| nsf.info().clone())
+    }
+
+    /// The number of tracks in the loaded NSF file, `0` if the loaded game is not an NSF file.
+    pub fn nsf_tracks_count(&self) -> usize {
+        self.cartridge
+            .borrow()
+            .nsf()
+            .map(|nsf| nsf.info().tracks.len())
+            .unwrap_or(0)
+    }
+
+    /// The track being played (0 based), `None` if the loaded game is not an NSF file.
+    pub fn nsf_current_track(&self) -> Option<usize> {
+        self.cartridge.borrow().nsf().map(|nsf| nsf.current_track())
+    }
+
+    /// The time since the start of the current NSF track.
+    pub fn nsf_elapsed(&self) -> Option<Duration> {
+        self.cartridge.borrow().nsf().map(|nsf| nsf.elapsed())
+    }
+
+    /// Start playing `track` (0 based) of the loaded NSF file, wraps around
+    /// if `track` is larger than the number of tracks.
+    ///
+    /// Does nothing if the loaded game is not an NSF file.
+    pub fn nsf_set_track(&mut self, track: usize) {
+        if let Some(nsf) = self.cartridge.borrow_mut().nsf_mut() {
+            nsf.set_track(track);
+        } else {
+            return;
+        }
+
+        // run the init routine of the new track
+        self.reset();
+    }
+
+    /// Start playing the next track of the loaded NSF file.
+    pub fn nsf_next_track(&mut self) {
+        if let Some(track) = self.nsf_current_track() {
+            self.nsf_set_track(track + 1);
+        }
+    }
+
+    /// Start playing the previous track of the loaded NSF file.
+    pub fn nsf_previous_track(&mut self) {
+        if let Some(track) = self.nsf_current_track() {
+            let count = self.nsf_tracks_count();
+            self.nsf_set_track((track + count - 1) % count);
+        }
+    }
+
     /// Set the state of a controller key. `pressed` or `released`.
     pub fn set_controller_state(&mut self, key: NESKey, pressed: bool) {
         self.cpu
@@ -385,6 +916,260 @@ impl NES {
             .set_controller_state(key, pressed);
     }
 
+    /// Check if the loaded game is for the Vs. System arcade, which has DIP switches
+    /// and coin slots, see [`NES::vs_insert_coin`].
+    pub fn is_vs_system(&self) -> bool {
+        self.cpu.bus().vs_system.is_some()
+    }
+
+    /// The DIP switches of the Vs. System, DIP switch 1 is bit 0 and DIP switch 8 is bit 7,
+    /// `0` if the loaded game is not for the Vs. System.
+    pub fn vs_dip_switches(&self) -> u8 {
+        self.cpu
+            .bus()
+            .vs_system
+            .as_ref()
+            .map(|vs_system| vs_system.dip_switches())
+            .unwrap_or(0)
+    }
+
+    /// Set the DIP switches of the Vs. System, the game settings they control
+    /// (difficulty, lives, coins per credit, ...) are different in each game.
+    ///
+    /// Most games only read them on reset. Does nothing if the loaded game is not
+    /// for the Vs. System.
+    pub fn vs_set_dip_switches(&mut self, dip_switches: u8) {
+        if let Some(vs_system) = &mut self.cpu.bus_mut().vs_system {
+            vs_system.set_dip_switches(dip_switches);
+        }
+    }
+
+    /// Insert a coin in the coin slot `slot` (`0` or `1`) of the Vs. System,
+    /// invalid slots are ignored.
+    ///
+    /// Does nothing if the loaded game is not for the Vs. System.
+    pub fn vs_insert_coin(&mut self, slot: usize) {
+        if let Some(vs_system) = &mut self.cpu.bus_mut().vs_system {
+            vs_system.insert_coin(slot);
+        }
+    }
+
+    /// Set the state of the service button of the Vs. System, which adds a credit
+    /// without inserting a coin.
+    pub fn vs_set_service_button(&mut self, pressed: bool) {
+        if let Some(vs_system) = &mut self.cpu.bus_mut().vs_system {
+            vs_system.set_service_button(pressed);
+        }
+    }
+
+    /// The number of coins counted by the coin counter of the Vs. System,
+    /// which the game drives through $4020.
+    pub fn vs_coin_counter(&self) -> u32 {
+        self.cpu
+            .bus()
+            .vs_system
+            .as_ref()
+            .map(|vs_system| vs_system.coin_counter())
+            .unwrap_or(0)
+    }
+
+    /// Enable the [`Debugger`], to use breakpoints, watchpoints and stepping.
+    ///
+    /// The emulator runs slower while the debugger is enabled.
+    pub fn enable_debugger(&mut self) {
+        if self.debugger.is_some() {
+            return;
+        }
+
+        let debugger = Debugger::new();
+        self.set_bus_watches(Some(debugger.watches()));
+        self.debugger = Some(Box::new(debugger));
+    }
+
+    /// Disable the debugger, removing all breakpoints and watchpoints
+    /// and resuming the emulation if it was paused.
+    pub fn disable_debugger(&mut self) {
+        self.debugger = None;
+        self.set_bus_watches(None);
+    }
+
+    fn set_bus_watches(&mut self, watches: Option<Rc<Watches>>) {
+        let bus = self.cpu.bus_mut();
+        bus.ppu.ppu_bus_mut().watches = watches.clone();
+        bus.watches = watches;
+    }
+
+    /// The debugger, `None` if it is not enabled with [`NES::enable_debugger`].
+    pub fn debugger(&self) -> Option<&Debugger> {
+        self.debugger.as_deref()
+    }
+
+    /// The debugger, `None` if it is not enabled with [`NES::enable_debugger`].
+    pub fn debugger_mut(&mut self) -> Option<&mut Debugger> {
+        self.debugger.as_deref_mut()
+    }
+
+    /// The CPU registers.
+    ///
+    /// The program counter is the address of the next instruction only between
+    /// instructions, e.g. when the [`Debugger`] is paused at a breakpoint.
+    pub fn cpu_registers(&self) -> CPURegisters {
+        self.cpu.registers()
+    }
+
+    /// Set the CPU registers, e.g. the program counter to start running from
+    /// a different address.
+    ///
+    /// Should only be used between instructions, e.g. when the [`Debugger`]
+    /// is paused, or before running the first instruction after power on.
+    pub fn set_cpu_registers(&mut self, registers: CPURegisters) {
+        self.cpu.set_registers(registers);
+    }
+
+    /// The CPU cycles and PPU frames since power on, and the current PPU position.
+    pub fn cycle_counters(&self) -> CycleCounters {
+        let ppu = &self.cpu.bus().ppu;
+
+        CycleCounters {
+            cpu_cycles: self.cpu.cycles(),
+            frames: ppu.frames(),
+            scanline: ppu.scanline(),
+            dot: ppu.cycle(),
+        }
+    }
+
+    /// The labels used by [`NES::disassemble`] and [`NES::cpu_label`].
+    pub fn symbols(&self) -> &Symbols {
+        &self.symbols
+    }
+
+    /// Add the labels of a ca65 `.dbg`, FCEUX `.nl` or Mesen `.mlb` file
+    /// to the [`Symbols`].
+    pub fn load_symbols<P: AsRef<Path>>(&mut self, path: P) -> Result<(), SymbolsError> {
+        self.symbols.load_file(path)
+    }
+
+    pub fn set_symbols(&mut self, symbols: Symbols) {
+        self.symbols = symbols;
+    }
+
+    /// The label of the CPU `address`, using the current PRG ROM banking.
+    pub fn cpu_label(&self, address: u16) -> Option<&str> {
+        let offset = self.cartridge.borrow().prg_rom_offset(address);
+        self.symbols.label(address, offset)
+    }
+
+    /// Read CPU memory without side effects, only RAM and cartridge memory
+    /// at $6000-$FFFF can be read, the registers return `None`.
+    pub fn peek_cpu_memory(&self, address: u16) -> Option<u8> {
+        match address {
+            0x0000..=0x1FFF => Some(self.cpu.bus().ram[(address & 0x7FF) as usize]),
+            0x6000..=0xFFFF => Some(self.cartridge.borrow().peek(address, Device::Cpu)),
+            _ => None,
+        }
+    }
+
+    /// Write CPU memory without side effects, only RAM and the PRG RAM mapped
+    /// at $6000-$FFFF can be written, other addresses are ignored.
+    pub fn poke_cpu_memory(&mut self, address: u16, data: u8) {
+        match address {
+            0x0000..=0x1FFF => self.cpu.bus_mut().ram[(address & 0x7FF) as usize] = data,
+            _ => {
+                let mut cartridge = self.cartridge.borrow_mut();
+                if let Some(offset) = cartridge.prg_ram_offset(address) {
+                    cartridge.prg_ram_mut()[offset] = data;
+                }
+            }
+        }
+    }
+
+    /// Read PPU memory in `addresses` without side effects, through the current
+    /// mapper banking and nametable mirroring.
+    pub fn peek_ppu_memory(&self, addresses: RangeInclusive<u16>) -> Vec<u8> {
+        self.with_ppu_memory(|_, read| addresses.map(read).collect())
+    }
+
+    /// Write PPU memory without side effects, CHR ROM can't be written.
+    pub fn poke_ppu_memory(&mut self, address: u16, data: u8) {
+        self.cpu
+            .bus_mut()
+            .ppu
+            .ppu_bus_mut()
+            .poke(address, data, Device::Ppu);
+    }
+
+    /// The 256 bytes of the OAM, 4 bytes per sprite
+    pub fn oam(&self) -> [u8; 256] {
+        let ppu = &self.cpu.bus().ppu;
+        let mut oam = [0; 256];
+
+        for (i, byte) in oam.iter_mut().enumerate() {
+            *byte = ppu.read_sprite_byte(i as u8);
+        }
+
+        oam
+    }
+
+    pub fn poke_oam(&mut self, address: u8, data: u8) {
+        self.cpu.bus_mut().ppu.write_sprite_byte(address, data);
+    }
+
+    /// The content of the cartridge PRG RAM, the battery backed save RAM (SRAM)
+    /// if any comes first, followed by the work RAM.
+    pub fn prg_ram(&self) -> Vec<u8> {
+        self.cartridge.borrow().prg_ram().to_vec()
+    }
+
+    /// Write the PRG RAM at `offset`, ignored if it is out of bounds.
+    pub fn poke_prg_ram(&mut self, offset: usize, data: u8) {
+        if let Some(byte) = self.cartridge.borrow_mut().prg_ram_mut().get_mut(offset) {
+            *byte = data;
+        }
+    }
+
+    /// Disassemble the CPU instruction at `address` through the current mapper
+    /// banking, `None` if the address can't be read without side effects.
+    pub fn disassemble_instruction(&self, address: u16) -> Option<DisassembledInstruction> {
+        disassemble_instruction(
+            address,
+            |address| self.peek_cpu_memory(address),
+            |address| self.cpu_label(address),
+        )
+    }
+
+    /// Disassemble the CPU instructions in `addresses` through the current
+    /// mapper banking, with the labels of the loaded [`Symbols`].
+    ///
+    /// The disassembly starts at the first address, and addresses that can't
+    /// be read without side effects (the registers) are skipped.
+    pub fn disassemble(&self, addresses: RangeInclusive<u16>) -> Vec<DisassembledInstruction> {
+        disassemble(
+            addresses,
+            |address| self.peek_cpu_memory(address),
+            |address| self.cpu_label(address),
+        )
+    }
+
+    /// Start logging every executed instruction to the [`TraceLogger`], replacing
+    /// the previous one.
+    ///
+    /// The emulator runs slower while tracing.
+    pub fn start_trace(&mut self, tracer: TraceLogger) {
+        self.tracer = Some(Box::new(tracer));
+    }
+
+    /// Stop the trace, flushing the writer, returns the first write error if any
+    pub fn stop_trace(&mut self) -> Result<(), std::io::Error> {
+        match self.tracer.take() {
+            Some(tracer) => tracer.finish(),
+            None => Ok(()),
+        }
+    }
+
+    pub fn is_tracing(&self) -> bool {
+        self.tracer.is_some()
+    }
+
     /// Get the name of the save state file that can be associated with the current cartridge.
     ///
     /// This is just a helper function, and the emulator implementation at [`save_state`] doesn't use it.
@@ -1,17 +1,21 @@
 mod palette;
 mod ppu2c02_registers;
 mod sprite;
+mod viewer;
 mod vram;
 
-pub use palette::Palette;
+pub use palette::{Palette, PpuModel};
+pub use viewer::{
+    OamSprite, NAMETABLES_HEIGHT, NAMETABLES_WIDTH, PATTERN_TABLES_HEIGHT, PATTERN_TABLES_WIDTH,
+};
 pub use vram::VRam;
 
 use crate::common::{
     interconnection::PPUCPUConnection,
     save_state::{Savable, SaveError},
-    Bus, Device,
+    Bus, Device, Region,
 };
-use crate::display::{Color, COLORS, TV};
+use crate::display::{Color, TV};
 use bitflags::bitflags;
 use ppu2c02_registers::Register;
 use serde::{Deserialize, Serialize};
@@ -160,13 +164,21 @@ pub struct PPU2C02<T: Bus + Savable> {
     dma_request_address: u8,
 
     is_odd_frame: bool,
+    /// number of frames since power on, not saved in save states
+    frames: u64,
+
+    region: Region,
+
+    model: PpuModel,
+    /// the RGB colors of the palette indices, depends on `model`
+    colors: [Color; 0x40],
 }
 
 impl<T> PPU2C02<T>
 where
     T: Bus + Savable,
 {
-    pub fn new(bus: T, tv: TV) -> Self {
+    pub fn new(bus: T, tv: TV, region: Region) -> Self {
         Self {
             reg_control: ControlReg::empty(),
             reg_mask: MaskReg::empty(),
@@ -174,8 +186,8 @@ where
             reg_oam_addr: Cell::new(0),
 
             // this would result in it starting from 0,0 next cycle
-            scanline: 261, // start from -1 scanline
-            cycle: 340,    // last cycle
+            scanline: region.pre_render_scanline(), // start from -1 scanline
+            cycle: 340,                             // last cycle
 
             vram_address_cur: Cell::new(0),
             vram_address_top_left: 0,
@@ -208,16 +220,37 @@ where
             dma_request_address: 0,
 
             is_odd_frame: false,
+            frames: 0,
+
+            region,
+
+            model: PpuModel::Rp2c02,
+            colors: PpuModel::Rp2c02.colors(),
+        }
+    }
+
+    /// Change the PPU chip to emulate, the NES PPU (2C02) is used by default
+    pub fn set_model(&mut self, model: PpuModel) {
+        self.model = model;
+        self.colors = model.colors();
+    }
+
+    /// The 2C05 has the control and mask registers swapped
+    fn model_register(&self, register: Register) -> Register {
+        match (self.model, register) {
+            (PpuModel::Rc2c05 { .. }, Register::Control) => Register::Mask,
+            (PpuModel::Rc2c05 { .. }, Register::Mask) => Register::Control,
+            (_, register) => register,
         }
     }
 
     pub(crate) fn read_register(&self, register: Register) -> u8 {
-        match register {
+        match self.model_register(register) {
             Register::Status => {
                 // reset w_mode
                 self.w_toggle.set(false);
 
-                if self.scanline == 241 {
+                if self.scanline == self.region.vblank_scanline() {
                     // Race Condition Warning: Reading PPUSTATUS within two
                     // cycles of the start of vertical blank will return 0 in bit 7
                     // but clear the latch anyway, causing NMI to not occur that frame
@@ -237,7 +270,11 @@ where
                 self.reg_status
                     .set(StatusReg::from_bits(result & 0x7F).unwrap());
 
-                result
+                if let PpuModel::Rc2c05 { status_id } = self.model {
+                    result | status_id
+                } else {
+                    result
+                }
             }
             Register::OmaData => self.read_sprite_byte(self.reg_oam_addr.get()),
             Register::PPUData => {
@@ -272,7 +309,7 @@ where
     }
 
     pub(crate) fn write_register(&mut self, register: Register, data: u8) {
-        match register {
+        match self.model_register(register) {
             // After power/reset, writes to this register are ignored for about 30,000 cycles
             // TODO: not sure, if I should account for that
             Register::Control => {
@@ -294,7 +331,7 @@ where
                     }
                 } else {
                     // if the NMI is disabled, stop the NMI (if the flag was set)
-                    if self.scanline == 241 && self.cycle <= 4 {
+                    if self.scanline == self.region.vblank_scanline() && self.cycle <= 4 {
                         self.nmi_pin_status.set(false);
                         self.nmi_occured_in_this_frame.set(true);
                     } else {
@@ -369,7 +406,6 @@ where
     }
 
     /// expose the bus for reading only
-    #[cfg(test)]
     pub fn ppu_bus(&self) -> &T {
         &self.bus
     }
@@ -382,12 +418,12 @@ where
         self.bus.write(address, data, Device::Ppu);
     }
 
-    fn read_sprite_byte(&self, address: u8) -> u8 {
+    pub(crate) fn read_sprite_byte(&self, address: u8) -> u8 {
         let sprite_location = address >> 2;
         self.primary_oam[sprite_location as usize].read_offset(address & 0b11)
     }
 
-    fn write_sprite_byte(&mut self, address: u8, data: u8) {
+    pub(crate) fn write_sprite_byte(&mut self, address: u8, data: u8) {
         let sprite_location = address >> 2;
         self.primary_oam[sprite_location as usize].write_offset(address & 0b11, data);
     }
@@ -582,7 +618,7 @@ where
     // this should only be called when rendering and a bit after that,
     // i.e. when scanline number is in range 0 >= scanline > 255
     fn get_next_scroll_y_render(&self) -> u8 {
-        if self.scanline == 261 {
+        if self.scanline == self.region.pre_render_scanline() {
             0
         } else if self.scanline < 255 {
             (self.scanline + 1) as u8
@@ -641,6 +677,13 @@ where
         }
     }
 
+    /// The PPU fetches the nametable byte of the next tile two times at the end
+    /// of each scanline without using them, some mappers (MMC5) depend on
+    /// these reads to detect scanlines
+    fn fetch_unused_nametable_byte(&self) {
+        let _ = self.read_bus(0x2000 | self.vram_address_cur.get() & 0xFFF);
+    }
+
     /// ## PPU pattern table addressing ##
     /// DCBA98 76543210
     /// ---------------
@@ -848,6 +891,15 @@ where
         let is_green_emph = self.reg_mask.intersects(MaskReg::EMPHASIZE_GREEN);
         let is_blue_emph = self.reg_mask.intersects(MaskReg::EMPHASIZE_BLUE);
 
+        if self.model.is_rgb() {
+            // the RGB PPUs turn the emphasized channels fully on instead
+            return Color {
+                r: if is_red_emph { 0xFF } else { color.r },
+                g: if is_green_emph { 0xFF } else { color.g },
+                b: if is_blue_emph { 0xFF } else { color.b },
+            };
+        }
+
         let mut red = 1.;
         let mut green = 1.;
         let mut blue = 1.;
@@ -888,25 +940,28 @@ where
         self.tv.set_pixel(
             self.cycle as u32,
             self.scanline as u32,
-            &self.emphasis_color(COLORS[color as usize]),
+            &self.emphasis_color(self.colors[color as usize]),
         );
     }
 
     // run one cycle, this should be fed from Master clock
     pub fn clock(&mut self) {
+        let pre_render_scanline = self.region.pre_render_scanline();
+        let vblank_scanline = self.region.vblank_scanline();
+
         // current scanline
         match (self.scanline, self.cycle) {
-            (261, 0) => {
+            (scanline, 0) if scanline == pre_render_scanline => {
                 // FIXME: for some reason the test only worked when doing it here
 
                 // clear sprite 0 hit
                 self.reg_status.get_mut().remove(StatusReg::SPRITE_0_HIT)
             }
-            (261, 2) => {
+            (scanline, 2) if scanline == pre_render_scanline => {
                 // reset nmi_occured_in_this_frame
                 self.nmi_occured_in_this_frame.set(false);
             }
-            (261, 1) => {
+            (scanline, 1) if scanline == pre_render_scanline => {
                 // clear sprite overflow
                 self.reg_status.get_mut().remove(StatusReg::SPRITE_OVERFLOW);
                 // clear v-blank
@@ -933,10 +988,15 @@ where
                     }
                 }
             }
-            (261, 257) => {
+            (scanline, 257) if scanline == pre_render_scanline => {
                 // reload all of them in one go
                 self.reload_sprite_shift_registers();
             }
+            (scanline, 337 | 339)
+                if scanline == pre_render_scanline && self.reg_mask.rendering_enabled() =>
+            {
+                self.fetch_unused_nametable_byte();
+            }
             (0..=239, _) => {
                 // render only if allowed
                 if self.reg_mask.rendering_enabled() {
@@ -948,7 +1008,7 @@ where
                 // idle
                 self.tv.signal_end_of_frame();
             }
-            (241, 1) => {
+            (scanline, 1) if scanline == vblank_scanline => {
                 // set v-blank
                 self.reg_status.get_mut().insert(StatusReg::VERTICAL_BLANK);
 
@@ -963,8 +1023,9 @@ where
 
         self.cycle += 1;
         if self.cycle > 340
-            || (self.scanline == 261
+            || (self.scanline == pre_render_scanline
                 && self.cycle == 340
+                && self.region.skips_odd_frame_cycle()
                 && self.is_odd_frame
                 && self.reg_mask.rendering_enabled())
         {
@@ -972,9 +1033,10 @@ where
             self.cycle = 0;
 
             // next frame
-            if self.scanline > 261 {
+            if self.scanline > pre_render_scanline {
                 self.scanline = 0;
                 self.is_odd_frame = !self.is_odd_frame;
+                self.frames += 1;
             }
         }
     }
@@ -1057,6 +1119,9 @@ where
                     self.increment_coarse_x_scroll();
                 }
             }
+            337 | 339 => {
+                self.fetch_unused_nametable_byte();
+            }
             _ => {}
         }
 
@@ -1143,6 +1208,24 @@ where
     pub fn tv(&self) -> &TV {
         &self.tv
     }
+
+    /// The scanline being rendered, the pre-render scanline is the last one
+    pub fn scanline(&self) -> u16 {
+        self.scanline
+    }
+
+    /// The PPU cycle (dot) in the current scanline
+    pub fn cycle(&self) -> u16 {
+        self.cycle
+    }
+
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+
+    pub(crate) fn ppu_bus_mut(&mut self) -> &mut T {
+        &mut self.bus
+    }
 }
 
 impl<T> PPUCPUConnection for PPU2C02<T>
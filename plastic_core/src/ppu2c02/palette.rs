@@ -2,6 +2,94 @@ use crate::common::{
     save_state::{Savable, SaveError},
     Bus, Device,
 };
+use crate::display::{Color, COLORS, RGB_PPU_COLORS};
+
+// the order of the colors in the 2C04 PPUs, as indices into the 2C03 palette
+// got these from
+// https://www.nesdev.org/wiki/PPU_palettes#2C04
+const RP2C04_0001_ORDER: [u8; 0x40] = [
+    0x35, 0x23, 0x16, 0x22, 0x1C, 0x09, 0x1D, 0x15, 0x20, 0x00, 0x27, 0x05, 0x04, 0x28, 0x08, 0x20,
+    0x21, 0x3E, 0x1F, 0x29, 0x3C, 0x32, 0x36, 0x12, 0x3F, 0x2B, 0x2E, 0x1E, 0x3D, 0x2D, 0x24, 0x01,
+    0x0E, 0x31, 0x33, 0x2A, 0x2C, 0x0C, 0x1B, 0x14, 0x2E, 0x07, 0x34, 0x06, 0x13, 0x02, 0x26, 0x2E,
+    0x2E, 0x19, 0x10, 0x0A, 0x39, 0x03, 0x37, 0x17, 0x0F, 0x11, 0x0B, 0x0D, 0x38, 0x25, 0x18, 0x3A,
+];
+const RP2C04_0002_ORDER: [u8; 0x40] = [
+    0x2E, 0x27, 0x18, 0x39, 0x3A, 0x25, 0x1C, 0x31, 0x16, 0x13, 0x38, 0x34, 0x20, 0x23, 0x3C, 0x0B,
+    0x0F, 0x21, 0x06, 0x3D, 0x1B, 0x29, 0x1E, 0x22, 0x1D, 0x24, 0x0E, 0x2B, 0x32, 0x08, 0x2E, 0x03,
+    0x04, 0x36, 0x26, 0x33, 0x11, 0x1F, 0x10, 0x02, 0x14, 0x3F, 0x00, 0x09, 0x12, 0x2E, 0x28, 0x20,
+    0x3E, 0x0D, 0x2A, 0x17, 0x0C, 0x01, 0x15, 0x19, 0x2E, 0x2C, 0x07, 0x37, 0x35, 0x05, 0x0A, 0x2D,
+];
+const RP2C04_0003_ORDER: [u8; 0x40] = [
+    0x14, 0x25, 0x3A, 0x10, 0x0B, 0x20, 0x31, 0x09, 0x01, 0x2E, 0x36, 0x08, 0x15, 0x3D, 0x3E, 0x3C,
+    0x22, 0x1C, 0x05, 0x12, 0x19, 0x18, 0x17, 0x1B, 0x00, 0x03, 0x2E, 0x02, 0x16, 0x06, 0x34, 0x35,
+    0x23, 0x0F, 0x0E, 0x37, 0x0D, 0x27, 0x26, 0x20, 0x29, 0x04, 0x21, 0x24, 0x11, 0x2D, 0x2E, 0x1F,
+    0x2C, 0x1E, 0x39, 0x33, 0x07, 0x2A, 0x28, 0x1D, 0x0A, 0x2E, 0x32, 0x38, 0x13, 0x2B, 0x3F, 0x0C,
+];
+const RP2C04_0004_ORDER: [u8; 0x40] = [
+    0x18, 0x03, 0x1C, 0x28, 0x2E, 0x35, 0x01, 0x17, 0x10, 0x1F, 0x2A, 0x0E, 0x36, 0x37, 0x0B, 0x39,
+    0x25, 0x1E, 0x12, 0x34, 0x2E, 0x1D, 0x06, 0x26, 0x3E, 0x1B, 0x22, 0x19, 0x04, 0x2E, 0x3A, 0x21,
+    0x05, 0x0A, 0x07, 0x02, 0x13, 0x14, 0x00, 0x15, 0x0C, 0x3D, 0x11, 0x0F, 0x0D, 0x38, 0x2D, 0x24,
+    0x33, 0x20, 0x08, 0x16, 0x3F, 0x2B, 0x20, 0x3C, 0x2E, 0x27, 0x23, 0x31, 0x29, 0x32, 0x2C, 0x09,
+];
+
+/// The PPU chip of the console, the Vs. System and PlayChoice-10 use RGB PPUs
+/// which have their own palette
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PpuModel {
+    /// The NES PPU
+    Rp2c02,
+    /// RGB PPU with the colors in the same order as the NES
+    Rp2c03,
+    /// RGB PPU with the colors in a scrambled order, there are 4 versions (1-4)
+    /// each with its own order
+    Rp2c04(u8),
+    /// RGB PPU with the control and mask registers swapped, and an ID
+    /// in the lower bits of the status register
+    Rc2c05 { status_id: u8 },
+}
+
+impl PpuModel {
+    /// The PPU from the Vs. System PPU type of the NES 2.0 header
+    pub fn from_vs_ppu_type(ppu_type: u8) -> Self {
+        match ppu_type {
+            // RP2C03B, RP2C03G, RC2C03B and RC2C03C
+            0 | 1 | 6 | 7 => Self::Rp2c03,
+            2..=5 => Self::Rp2c04(ppu_type - 1),
+            8 => Self::Rc2c05 { status_id: 0x1B },
+            9 => Self::Rc2c05 { status_id: 0x3D },
+            0xA => Self::Rc2c05 { status_id: 0x1C },
+            0xB => Self::Rc2c05 { status_id: 0x1B },
+            // RC2C05-05 and reserved types
+            _ => Self::Rc2c05 { status_id: 0 },
+        }
+    }
+
+    pub fn is_rgb(&self) -> bool {
+        *self != Self::Rp2c02
+    }
+
+    /// The RGB colors of the palette indices of this PPU
+    pub fn colors(&self) -> [Color; 0x40] {
+        match self {
+            Self::Rp2c02 => COLORS,
+            Self::Rp2c03 | Self::Rc2c05 { .. } => RGB_PPU_COLORS,
+            Self::Rp2c04(version) => {
+                let order = match version {
+                    1 => &RP2C04_0001_ORDER,
+                    2 => &RP2C04_0002_ORDER,
+                    3 => &RP2C04_0003_ORDER,
+                    _ => &RP2C04_0004_ORDER,
+                };
+
+                let mut colors = RGB_PPU_COLORS;
+                for (color, &index) in colors.iter_mut().zip(order.iter()) {
+                    *color = RGB_PPU_COLORS[index as usize];
+                }
+                colors
+            }
+        }
+    }
+}
 
 pub struct Palette {
     palette_data: [u8; 0x20],
@@ -0,0 +1,254 @@
+use super::PPU2C02;
+use crate::common::{save_state::Savable, Bus};
+use crate::display::{Color, COLOR_BYTES_LEN};
+
+/// The width of the nametables buffer in pixels, 2 nametables
+pub const NAMETABLES_WIDTH: usize = 512;
+/// The height of the nametables buffer in pixels, 2 nametables
+pub const NAMETABLES_HEIGHT: usize = 480;
+/// The width of the pattern tables buffer in pixels, 2 tables of 16x16 tiles
+pub const PATTERN_TABLES_WIDTH: usize = 256;
+/// The height of the pattern tables buffer in pixels
+pub const PATTERN_TABLES_HEIGHT: usize = 128;
+
+/// A sprite in the OAM, see [`NES::oam_sprites`][crate::NES::oam_sprites]
+#[derive(Debug, Clone)]
+pub struct OamSprite {
+    /// the index in the OAM (0-63)
+    pub index: u8,
+    pub x: u8,
+    /// the Y byte in the OAM, the sprite is drawn one line below it
+    pub y: u8,
+    /// the tile byte in the OAM, for 8x16 sprites, bit 0 is the pattern table
+    pub tile: u8,
+    /// the attribute byte in the OAM
+    pub attributes: u8,
+    /// the sprite palette (0-3), the colors are $3F11-$3F1F
+    pub palette: u8,
+    pub behind_background: bool,
+    pub flip_horizontal: bool,
+    pub flip_vertical: bool,
+    /// 8 or 16
+    pub height: u8,
+    /// the sprite as shown on the screen, 8 pixels wide and `height` high,
+    /// in the same format as [`NES::pixel_buffer`][crate::NES::pixel_buffer],
+    /// transparent pixels have the backdrop color
+    pub pixels: Vec<u8>,
+}
+
+/// The 2 bits color of pixel `x` in a tile row
+fn pattern_color(low: u8, high: u8, x: u8) -> u8 {
+    let bit = 7 - x;
+    (((high >> bit) & 1) << 1) | ((low >> bit) & 1)
+}
+
+fn set_pixel(buffer: &mut [u8], width: usize, x: usize, y: usize, color: Color) {
+    let index = (y * width + x) * COLOR_BYTES_LEN;
+    buffer[index..index + COLOR_BYTES_LEN].copy_from_slice(&[color.r, color.g, color.b]);
+}
+
+/// Debug views of the PPU memory, `read` must read the PPU bus without
+/// side effects.
+///
+/// The colors are the palette colors without grayscale and emphasis.
+impl<T> PPU2C02<T>
+where
+    T: Bus + Savable,
+{
+    /// The color of the palette RAM entry (0-31)
+    fn palette_entry_color<R: Fn(u16) -> u8>(&self, read: &R, entry: u8) -> Color {
+        // color 0 of every palette is the backdrop
+        let entry = if entry & 0b11 == 0 { 0 } else { entry };
+
+        self.colors[(read(0x3F00 | entry as u16) & 0x3F) as usize]
+    }
+
+    /// The 4 nametables, with a rectangle around the scroll position of the frame
+    /// if `scroll_overlay` is `true`
+    pub(crate) fn render_nametables<R: Fn(u16) -> u8>(
+        &self,
+        read: R,
+        scroll_overlay: bool,
+    ) -> Vec<u8> {
+        let mut buffer = vec![0; NAMETABLES_WIDTH * NAMETABLES_HEIGHT * COLOR_BYTES_LEN];
+        let pattern_table = self.reg_control.background_pattern_address();
+
+        for nametable in 0..4 {
+            let base = 0x2000 + nametable * 0x400;
+            let origin_x = (nametable as usize & 1) * 256;
+            let origin_y = (nametable as usize >> 1) * 240;
+
+            for tile_y in 0..30 {
+                for tile_x in 0..32 {
+                    let tile = read(base + tile_y * 32 + tile_x);
+                    let attribute = read(base + 0x3C0 + (tile_y / 4) * 8 + tile_x / 4);
+                    let shift = ((tile_y & 2) << 1) | (tile_x & 2);
+                    let palette = (attribute >> shift) & 0b11;
+
+                    let pattern = pattern_table + tile as u16 * 16;
+                    for row in 0..8 {
+                        let low = read(pattern + row);
+                        let high = read(pattern + row + 8);
+
+                        for column in 0..8 {
+                            let color = pattern_color(low, high, column);
+                            let color = self.palette_entry_color(&read, palette * 4 + color);
+
+                            set_pixel(
+                                &mut buffer,
+                                NAMETABLES_WIDTH,
+                                origin_x + tile_x as usize * 8 + column as usize,
+                                origin_y + tile_y as usize * 8 + row as usize,
+                                color,
+                            );
+                        }
+                    }
+                }
+            }
+        }
+
+        if scroll_overlay {
+            self.draw_scroll_overlay(&mut buffer);
+        }
+
+        buffer
+    }
+
+    /// Invert the pixels on the border of the screen at the scroll position
+    /// of the frame, wrapping around the nametables
+    fn draw_scroll_overlay(&self, buffer: &mut [u8]) {
+        let nametable = self.reg_control.nametable_selector() as usize;
+        let scroll_x = self.top_left_coarse_x_scroll() as usize * 8
+            + self.fine_x_scroll as usize
+            + (nametable & 1) * 256;
+        let scroll_y = self.top_left_coarse_y_scroll() as usize * 8
+            + self.top_left_fine_y_scroll() as usize
+            + (nametable >> 1) * 240;
+
+        let mut invert = |x: usize, y: usize| {
+            let x = (scroll_x + x) % NAMETABLES_WIDTH;
+            let y = (scroll_y + y) % NAMETABLES_HEIGHT;
+            let index = (y * NAMETABLES_WIDTH + x) * COLOR_BYTES_LEN;
+
+            for channel in &mut buffer[index..index + COLOR_BYTES_LEN] {
+                *channel = !*channel;
+            }
+        };
+
+        for x in 0..256 {
+            invert(x, 0);
+            invert(x, 239);
+        }
+        for y in 1..239 {
+            invert(0, y);
+            invert(255, y);
+        }
+    }
+
+    /// The 2 pattern tables side by side, with `palette` (0-3 background,
+    /// 4-7 sprites)
+    pub(crate) fn render_pattern_tables<R: Fn(u16) -> u8>(&self, read: R, palette: u8) -> Vec<u8> {
+        let mut buffer = vec![0; PATTERN_TABLES_WIDTH * PATTERN_TABLES_HEIGHT * COLOR_BYTES_LEN];
+        let palette = palette & 0b111;
+
+        for table in 0..2 {
+            for tile in 0..256 {
+                let pattern = table * 0x1000 + tile * 16;
+                let origin_x = table as usize * 128 + (tile as usize % 16) * 8;
+                let origin_y = (tile as usize / 16) * 8;
+
+                for row in 0..8 {
+                    let low = read(pattern + row);
+                    let high = read(pattern + row + 8);
+
+                    for column in 0..8 {
+                        let color = pattern_color(low, high, column);
+                        let color = self.palette_entry_color(&read, palette * 4 + color);
+
+                        set_pixel(
+                            &mut buffer,
+                            PATTERN_TABLES_WIDTH,
+                            origin_x + column as usize,
+                            origin_y + row as usize,
+                            color,
+                        );
+                    }
+                }
+            }
+        }
+
+        buffer
+    }
+
+    /// The 64 sprites in the primary OAM
+    pub(crate) fn oam_sprites<R: Fn(u16) -> u8>(&self, read: R) -> Vec<OamSprite> {
+        let height = self.reg_control.sprite_height();
+
+        self.primary_oam
+            .iter()
+            .enumerate()
+            .map(|(index, sprite)| {
+                let tile = sprite.get_tile();
+                let attributes = sprite.get_attribute();
+                let palette = attributes.palette();
+
+                let mut pixels = vec![0; 8 * height as usize * COLOR_BYTES_LEN];
+                for y in 0..height {
+                    let row = if attributes.is_flip_vertical() {
+                        height - 1 - y
+                    } else {
+                        y
+                    };
+
+                    let pattern = if height == 16 {
+                        let table = (tile as u16 & 1) << 12;
+                        // the bottom half is the next tile
+                        table + ((tile & 0xFE) as u16 + (row >> 3) as u16) * 16
+                    } else {
+                        self.reg_control.sprite_pattern_address() + tile as u16 * 16
+                    };
+                    let low = read(pattern + (row & 7) as u16);
+                    let high = read(pattern + (row & 7) as u16 + 8);
+
+                    for x in 0..8 {
+                        let column = if attributes.is_flip_horizontal() {
+                            7 - x
+                        } else {
+                            x
+                        };
+                        let color = pattern_color(low, high, column);
+                        let color = self.palette_entry_color(&read, 0x10 | (palette * 4 + color));
+
+                        set_pixel(&mut pixels, 8, x as usize, y as usize, color);
+                    }
+                }
+
+                OamSprite {
+                    index: index as u8,
+                    x: sprite.read_offset(3),
+                    y: sprite.read_offset(0),
+                    tile,
+                    attributes: sprite.read_offset(2),
+                    palette,
+                    behind_background: attributes.is_behind_background(),
+                    flip_horizontal: attributes.is_flip_horizontal(),
+                    flip_vertical: attributes.is_flip_vertical(),
+                    height,
+                    pixels,
+                }
+            })
+            .collect()
+    }
+
+    /// The colors of the 32 palette RAM entries, 32x1 pixels
+    pub(crate) fn render_palettes<R: Fn(u16) -> u8>(&self, read: R) -> Vec<u8> {
+        let mut buffer = vec![0; 32 * COLOR_BYTES_LEN];
+
+        for entry in 0..32 {
+            let color = self.colors[(read(0x3F00 | entry as u16) & 0x3F) as usize];
+            set_pixel(&mut buffer, 32, entry, 0, color);
+        }
+
+        buffer
+    }
+}
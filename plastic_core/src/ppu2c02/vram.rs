@@ -1,6 +1,6 @@
 use crate::common::{
     save_state::{Savable, SaveError},
-    Bus, Device, MirroringMode, MirroringProvider,
+    MirroringProvider, NametableMapping,
 };
 use std::{cell::RefCell, rc::Rc};
 
@@ -20,39 +20,28 @@ impl VRam {
         }
     }
 
-    fn map_address(&self, address: u16) -> usize {
-        let block_num = match self.mirroring_provider.borrow().mirroring_mode() {
-            MirroringMode::Vertical => (address >> 10) & 1,
-            MirroringMode::Horizontal => (address >> 11) & 1,
-            MirroringMode::SingleScreenLowBank => 0,
-            MirroringMode::SingleScreenHighBank => 1,
-            MirroringMode::FourScreen => {
-                // directly return the address, as there is no mirroring, and
-                // all the vram address is being used
-                return address as usize & 0xFFF;
-            }
-        } as usize;
-
-        let start_address = block_num << 10;
-
-        start_address + (address as usize & 0x3FF)
+    /// get where the nametable `address` is mapped to, VRAM block or the cartridge
+    pub fn nametable_mapping(&self, address: u16) -> NametableMapping {
+        self.mirroring_provider.borrow().nametable_mapping(address)
     }
-}
 
-impl Bus for VRam {
-    fn read(&self, address: u16, device: Device) -> u8 {
-        assert!(device == Device::Ppu);
-
-        let address = self.map_address(address);
+    /// same as `nametable_mapping`, without side effects on the cartridge
+    pub fn peek_nametable_mapping(&self, address: u16) -> NametableMapping {
+        self.mirroring_provider
+            .borrow()
+            .peek_nametable_mapping(address)
+    }
 
-        self.vram_data[address]
+    fn map_address(block: u8, address: u16) -> usize {
+        ((block as usize & 3) << 10) | (address as usize & 0x3FF)
     }
-    fn write(&mut self, address: u16, data: u8, device: Device) {
-        assert!(device == Device::Ppu);
 
-        let address = self.map_address(address);
+    pub fn read_block(&self, block: u8, address: u16) -> u8 {
+        self.vram_data[Self::map_address(block, address)]
+    }
 
-        self.vram_data[address] = data;
+    pub fn write_block(&mut self, block: u8, address: u16, data: u8) {
+        self.vram_data[Self::map_address(block, address)] = data;
     }
 }
 
@@ -0,0 +1,453 @@
+use super::SharedBuffer;
+use crate::cpu6502::instruction::Instruction;
+use crate::debugger::{
+    Access, BreakReason, Breakpoint, Comparison, Condition, MemorySpace, Register, Symbols,
+    TraceLogger, Watchpoint,
+};
+use crate::display::COLORS;
+use crate::nes::NES;
+
+/// A NROM ROM running `program` at $8000
+fn program_rom(program: &[(u16, &[u8])]) -> Vec<u8> {
+    let mut rom = b"NES\x1A\x01\x01\x00\x00".to_vec();
+    rom.resize(16, 0);
+
+    let mut prg = vec![0xEA; 0x4000];
+    for (address, code) in program {
+        let start = (address - 0x8000) as usize;
+        prg[start..start + code.len()].copy_from_slice(code);
+    }
+    // reset vector
+    prg[0x3FFC] = 0x00;
+    prg[0x3FFD] = 0x80;
+
+    rom.extend_from_slice(&prg);
+    rom.resize(rom.len() + 0x2000, 0);
+    rom
+}
+
+fn test_nes() -> NES {
+    let mut nes = test_nes_without_debugger();
+    nes.enable_debugger();
+    nes
+}
+
+fn test_nes_without_debugger() -> NES {
+    let rom = program_rom(&[
+        (
+            0x8000,
+            &[
+                0xA9, 0x20, // LDA #$20
+                0x8D, 0x06, 0x20, // STA $2006
+                0xA9, 0x00, // LDA #$00
+                0x8D, 0x06, 0x20, // STA $2006
+                0xA9, 0xAB, // LDA #$AB
+                0x8D, 0x07, 0x20, // STA $2007
+                0xA2, 0x00, // LDX #$00
+                0x20, 0x30, 0x80, // JSR $8030
+                0xE8, // INX
+                0x4C, 0x11, 0x80, // JMP $8011
+            ],
+        ),
+        (
+            0x8030,
+            &[
+                0xA9, 0x05, // LDA #$05
+                0x85, 0x10, // STA $10
+                0x20, 0x40, 0x80, // JSR $8040
+                0x60, // RTS
+            ],
+        ),
+        (
+            0x8040,
+            &[
+                0xEA, // NOP
+                0x60, // RTS
+            ],
+        ),
+    ]);
+
+    NES::from_bytes(&rom, "debugger.nes").unwrap()
+}
+
+/// Run until the debugger pauses and return the break reason
+fn run_until_break(nes: &mut NES) -> BreakReason {
+    for _ in 0..3 {
+        nes.clock_for_frame();
+
+        if let Some(reason) = nes.debugger().unwrap().break_reason() {
+            return reason;
+        }
+    }
+    panic!("the debugger did not pause");
+}
+
+#[test]
+fn breakpoints_and_stepping_test() {
+    let mut nes = test_nes();
+
+    nes.debugger_mut()
+        .unwrap()
+        .add_breakpoint(Breakpoint::at(0x8030));
+    assert_eq!(run_until_break(&mut nes), BreakReason::Breakpoint(0));
+    assert_eq!(nes.cpu_registers().pc, 0x8030);
+    // paused
+    assert_eq!(nes.clock(), None);
+
+    nes.debugger_mut().unwrap().step_into();
+    assert_eq!(run_until_break(&mut nes), BreakReason::Step);
+    assert_eq!(nes.cpu_registers().pc, 0x8032);
+    assert_eq!(nes.cpu_registers().a, 0x05);
+
+    // STA
+    nes.debugger_mut().unwrap().step_over();
+    assert_eq!(run_until_break(&mut nes), BreakReason::Step);
+    assert_eq!(nes.cpu_registers().pc, 0x8034);
+
+    // JSR $8040
+    nes.debugger_mut().unwrap().step_over();
+    assert_eq!(run_until_break(&mut nes), BreakReason::Step);
+    assert_eq!(nes.cpu_registers().pc, 0x8037);
+
+    // RTS
+    nes.debugger_mut().unwrap().step_into();
+    assert_eq!(run_until_break(&mut nes), BreakReason::Step);
+    assert_eq!(nes.cpu_registers().pc, 0x8014);
+
+    let debugger = nes.debugger_mut().unwrap();
+    debugger.clear_breakpoints();
+    debugger.add_breakpoint(Breakpoint::at(0x8040));
+    debugger.resume();
+    assert_eq!(run_until_break(&mut nes), BreakReason::Breakpoint(0));
+
+    // back to the first subroutine
+    nes.debugger_mut().unwrap().step_out();
+    assert_eq!(run_until_break(&mut nes), BreakReason::Step);
+    assert_eq!(nes.cpu_registers().pc, 0x8037);
+
+    // X is incremented every loop at $8014
+    let debugger = nes.debugger_mut().unwrap();
+    debugger.clear_breakpoints();
+    debugger.add_breakpoint(Breakpoint::at(0x8014).with_condition(Condition {
+        register: Register::X,
+        comparison: Comparison::Equal,
+        value: 3,
+    }));
+    debugger.resume();
+    assert_eq!(run_until_break(&mut nes), BreakReason::Breakpoint(0));
+    assert_eq!(nes.cpu_registers().pc, 0x8014);
+    assert_eq!(nes.cpu_registers().x, 3);
+
+    nes.disable_debugger();
+    let cycles = nes.cycle_counters().cpu_cycles;
+    nes.clock_for_frame();
+    assert!(nes.cycle_counters().cpu_cycles > cycles);
+}
+
+#[test]
+fn pause_test() {
+    let mut nes = test_nes();
+    nes.clock_for_frame();
+
+    let debugger = nes.debugger_mut().unwrap();
+    debugger.pause();
+    assert_eq!(debugger.break_reason(), Some(BreakReason::Paused));
+
+    // nothing runs until resumed
+    let cycles = nes.cycle_counters().cpu_cycles;
+    let registers = nes.cpu_registers();
+    nes.clock_for_frame();
+    assert_eq!(nes.clock(), None);
+    assert_eq!(nes.cycle_counters().cpu_cycles, cycles);
+    assert_eq!(nes.cpu_registers().pc, registers.pc);
+
+    nes.debugger_mut().unwrap().resume();
+    nes.clock_for_frame();
+    assert!(nes.cycle_counters().cpu_cycles > cycles);
+    assert_eq!(nes.debugger().unwrap().break_reason(), None);
+}
+
+#[test]
+fn watchpoints_test() {
+    let mut nes = test_nes();
+
+    nes.debugger_mut().unwrap().add_watchpoint(Watchpoint {
+        space: MemorySpace::Ppu,
+        addresses: 0x2000..=0x23FF,
+        access: Access::Write,
+    });
+    assert_eq!(
+        run_until_break(&mut nes),
+        BreakReason::Watchpoint {
+            space: MemorySpace::Ppu,
+            address: 0x2000,
+            data: 0xAB,
+            access: Access::Write,
+        }
+    );
+
+    let debugger = nes.debugger_mut().unwrap();
+    debugger.clear_watchpoints();
+    debugger.add_watchpoint(Watchpoint {
+        space: MemorySpace::Cpu,
+        addresses: 0x0010..=0x0010,
+        access: Access::ReadWrite,
+    });
+    debugger.resume();
+    assert_eq!(
+        run_until_break(&mut nes),
+        BreakReason::Watchpoint {
+            space: MemorySpace::Cpu,
+            address: 0x0010,
+            data: 0x05,
+            access: Access::Write,
+        }
+    );
+
+    let debugger = nes.debugger_mut().unwrap();
+    debugger.clear_watchpoints();
+    debugger.run_to_scanline(100);
+    assert_eq!(run_until_break(&mut nes), BreakReason::Scanline(100));
+    assert_eq!(nes.cycle_counters().scanline, 100);
+}
+
+#[test]
+fn disassembler_test() {
+    let mut nes = test_nes();
+
+    let lines = nes
+        .disassemble(0x8000..=0x8015)
+        .iter()
+        .map(|instruction| instruction.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        lines,
+        [
+            "8000  A9 20     LDA #$20",
+            "8002  8D 06 20  STA $2006 ; PPUADDR",
+            "8005  A9 00     LDA #$00",
+            "8007  8D 06 20  STA $2006 ; PPUADDR",
+            "800A  A9 AB     LDA #$AB",
+            "800C  8D 07 20  STA $2007 ; PPUDATA",
+            "800F  A2 00     LDX #$00",
+            "8011  20 30 80  JSR $8030",
+            "8014  E8        INX",
+            "8015  4C 11 80  JMP $8011",
+        ]
+    );
+    // registers are not read
+    assert!(nes.disassemble(0x2000..=0x2007).is_empty());
+
+    // official opcodes, and the unofficial ones
+    let unofficial = (0..=0xFF)
+        .filter(|&byte| Instruction::from_byte(byte).is_unofficial())
+        .count();
+    assert_eq!(unofficial, 256 - 151);
+
+    let mut symbols = Symbols::new();
+    symbols.load_fceux_nl("$8030#subroutine#first level\n$8011#loop#\n", Some(0));
+    symbols.load_mesen_mlb("R:0010:counter:comment\nP:0040:inner\n");
+    symbols.load_ca65_dbg(
+        "version major=2,minor=0\n\
+         seg id=0,name=\"CODE\",start=0x008000,size=0x4000,addrsize=absolute,type=ro,oname=\"game.nes\",ooffs=16\n\
+         sym id=0,name=\"reset\",addrsize=absolute,scope=0,def=1,val=0x8000,seg=0,type=lab\n\
+         sym id=1,name=\"PPUADDR\",addrsize=absolute,scope=0,def=2,val=0x2006,type=equ\n",
+    );
+    nes.set_symbols(symbols);
+
+    // NROM-128 is mirrored at $C000
+    assert_eq!(nes.cpu_label(0xC000), Some("reset"));
+
+    let instruction = nes.disassemble_instruction(0x8002).unwrap();
+    assert_eq!(instruction.text, "STA PPUADDR");
+    assert_eq!(instruction.register, Some("PPUADDR"));
+
+    let instruction = nes.disassemble_instruction(0x8011).unwrap();
+    assert_eq!(instruction.label.as_deref(), Some("loop"));
+    assert_eq!(instruction.text, "JSR subroutine");
+    assert_eq!(
+        nes.disassemble_instruction(0x8015).unwrap().text,
+        "JMP loop"
+    );
+    assert_eq!(
+        nes.disassemble_instruction(0x8032).unwrap().text,
+        "STA counter"
+    );
+    assert_eq!(
+        nes.disassemble_instruction(0x8034).unwrap().text,
+        "JSR inner"
+    );
+}
+
+#[test]
+fn trace_test() {
+    let mut nes = test_nes_without_debugger();
+    let buffer = SharedBuffer::default();
+
+    nes.start_trace(TraceLogger::new(buffer.clone()));
+    for _ in 0..40 {
+        nes.clock();
+    }
+    nes.stop_trace().unwrap();
+
+    let lines = buffer.lines();
+    assert_eq!(lines.len(), 11);
+    assert_eq!(
+        lines[0],
+        "8000  A9 20     LDA #$20                        A:00 X:00 Y:00 P:24 SP:FD PPU:  0, 21 CYC:7"
+    );
+    assert_eq!(
+        lines[9],
+        "8032  85 10     STA $10 = 00                    A:05 X:00 Y:00 P:24 SP:FB PPU:  0,105 CYC:35"
+    );
+
+    // only the inner subroutine
+    let buffer = SharedBuffer::default();
+    nes.start_trace(TraceLogger::new(buffer.clone()).with_addresses(0x8040..=0x8041));
+    nes.clock_for_frame();
+    nes.stop_trace().unwrap();
+
+    let lines = buffer.lines();
+    assert!(!lines.is_empty());
+    assert!(lines
+        .iter()
+        .all(|line| line.starts_with("8040  EA") || line.starts_with("8041  60")));
+
+    // only two frames after the current one
+    let frame = nes.cycle_counters().frames + 2;
+    let buffer = SharedBuffer::default();
+    nes.start_trace(TraceLogger::new(buffer.clone()).with_frames(frame..=frame));
+    nes.clock_for_frame();
+    assert!(buffer.lines().is_empty());
+    nes.clock_for_frame();
+    nes.clock_for_frame();
+    nes.stop_trace().unwrap();
+    assert!(!buffer.lines().is_empty());
+
+    let mut symbols = Symbols::new();
+    symbols.add_label(0x8030, "subroutine".to_owned());
+    nes.set_symbols(symbols);
+
+    let buffer = SharedBuffer::default();
+    nes.start_trace(
+        TraceLogger::new(buffer.clone())
+            .with_addresses(0x8011..=0x8011)
+            .with_symbols(),
+    );
+    nes.clock_for_frame();
+    nes.stop_trace().unwrap();
+    assert!(buffer.lines()[0].starts_with("8011  20 30 80  JSR subroutine  "));
+}
+
+#[test]
+fn ppu_viewers_test() {
+    let mut rom = program_rom(&[(
+        0x8000,
+        &[
+            0xA9, 0x3F, 0x8D, 0x06, 0x20, // $3F00
+            0xA9, 0x00, 0x8D, 0x06, 0x20, //
+            0xA9, 0x0F, 0x8D, 0x07, 0x20, // backdrop
+            0xA9, 0x30, 0x8D, 0x07, 0x20, // $3F01
+            0xA9, 0x3F, 0x8D, 0x06, 0x20, // $3F15
+            0xA9, 0x15, 0x8D, 0x06, 0x20, //
+            0xA9, 0x16, 0x8D, 0x07, 0x20, // sprite palette 1, color 1
+            0xA9, 0x20, 0x8D, 0x06, 0x20, // $2000
+            0xA9, 0x00, 0x8D, 0x06, 0x20, //
+            0xA9, 0x01, 0x8D, 0x07, 0x20, // tile 1
+            0xA9, 0x00, 0x8D, 0x03, 0x20, // OAM address 0
+            0xA9, 0x10, 0x8D, 0x04, 0x20, // Y
+            0xA9, 0x01, 0x8D, 0x04, 0x20, // tile 1
+            0xA9, 0x41, 0x8D, 0x04, 0x20, // flip horizontally, palette 1
+            0xA9, 0x20, 0x8D, 0x04, 0x20, // X
+            0x4C, 0x4B, 0x80, // JMP $804B
+        ],
+    )]);
+    // tile 1: the top left pixel is color 1
+    rom[16 + 0x4000 + 16] = 0x80;
+
+    let mut nes = NES::from_bytes(&rom, "viewers.nes").unwrap();
+    nes.clock_for_frame();
+
+    let color = |index: usize| {
+        let color = COLORS[index];
+        [color.r, color.g, color.b]
+    };
+
+    let palette_ram = nes.palette_ram();
+    assert_eq!(palette_ram[0x00], 0x0F);
+    assert_eq!(palette_ram[0x01], 0x30);
+    assert_eq!(palette_ram[0x15], 0x16);
+    let palettes = nes.palette_buffer();
+    assert_eq!(palettes.len(), 32 * 3);
+    assert_eq!(palettes[0x15 * 3..0x16 * 3], color(0x16));
+
+    let nametables = nes.nametables_buffer(false);
+    assert_eq!(nametables.len(), 512 * 480 * 3);
+    assert_eq!(nametables[0..3], color(0x30));
+    assert_eq!(nametables[3..6], color(0x0F));
+    // writing $2000 to PPUADDR sets the scroll to X=0, Y=2 (fine Y is bit 12-14)
+    let nametables = nes.nametables_buffer(true);
+    assert_eq!(nametables[0..3], color(0x30));
+    let scroll_top = 512 * 2 * 3;
+    assert_eq!(
+        nametables[scroll_top..scroll_top + 3],
+        color(0x0F).map(|channel| !channel)
+    );
+
+    let pattern_tables = nes.pattern_tables_buffer(0);
+    assert_eq!(pattern_tables.len(), 256 * 128 * 3);
+    assert_eq!(pattern_tables[8 * 3..9 * 3], color(0x30));
+    let pattern_tables = nes.pattern_tables_buffer(5);
+    assert_eq!(pattern_tables[8 * 3..9 * 3], color(0x16));
+
+    let sprites = nes.oam_sprites();
+    assert_eq!(sprites.len(), 64);
+    let sprite = &sprites[0];
+    assert_eq!((sprite.x, sprite.y, sprite.tile), (0x20, 0x10, 1));
+    assert_eq!(sprite.palette, 1);
+    assert!(sprite.flip_horizontal && !sprite.flip_vertical && !sprite.behind_background);
+    assert_eq!(sprite.height, 8);
+    assert_eq!(sprite.pixels.len(), 8 * 8 * 3);
+    assert_eq!(sprite.pixels[0..3], color(0x0F));
+    assert_eq!(sprite.pixels[7 * 3..8 * 3], color(0x16));
+}
+
+#[test]
+fn memory_editing_test() {
+    let mut nes = test_nes_without_debugger();
+
+    nes.poke_cpu_memory(0x0810, 0x42);
+    assert_eq!(nes.peek_cpu_memory(0x0010), Some(0x42));
+    // PRG ROM, registers and missing PRG RAM are not written
+    nes.poke_cpu_memory(0x6001, 0x43);
+    assert_eq!(nes.peek_cpu_memory(0x6001), Some(0x00));
+    nes.poke_cpu_memory(0x8000, 0x00);
+    assert_eq!(nes.peek_cpu_memory(0x8000), Some(0xA9));
+    nes.poke_cpu_memory(0x2000, 0x80);
+    assert_eq!(nes.peek_cpu_memory(0x2000), None);
+
+    nes.poke_ppu_memory(0x2001, 0x45);
+    nes.poke_ppu_memory(0x3F01, 0x16);
+    // the CHR ROM is not written
+    nes.poke_ppu_memory(0x0000, 0x46);
+    assert_eq!(nes.peek_ppu_memory(0x2000..=0x2001), [0x00, 0x45]);
+    assert_eq!(nes.palette_ram()[1], 0x16);
+    assert_eq!(nes.peek_ppu_memory(0x0000..=0x0000), [0x00]);
+
+    nes.poke_oam(4, 0x20);
+    nes.poke_oam(7, 0x30);
+    let oam = nes.oam();
+    assert_eq!(oam[4..8], [0x20, 0x00, 0x00, 0x30]);
+    let sprite = &nes.oam_sprites()[1];
+    assert_eq!((sprite.x, sprite.y), (0x30, 0x20));
+
+    // MMC3 with PRG RAM
+    let mut rom = program_rom(&[]);
+    rom[6] = 0x40;
+    let mut nes = NES::from_bytes(&rom, "prg_ram.nes").unwrap();
+    nes.poke_cpu_memory(0x6001, 0x43);
+    assert_eq!(nes.peek_cpu_memory(0x6001), Some(0x43));
+    assert_eq!(nes.prg_ram()[1], 0x43);
+    nes.poke_prg_ram(2, 0x44);
+    assert_eq!(nes.peek_cpu_memory(0x6002), Some(0x44));
+}
@@ -4,13 +4,20 @@ use crate::cpu6502::{CPUBusTrait, CPURunState};
 use crate::display::{COLORS, TV_WIDTH};
 use crate::nes::NES;
 use std::{
+    cell::RefCell,
     convert::From,
     error::Error,
     fmt::{Debug, Display, Formatter, Result as fmtResult},
+    io::Write,
+    rc::Rc,
 };
 
 mod blargg_tests;
+mod debugger;
+mod nestest;
+mod nsf;
 mod save_state;
+mod vs_system;
 
 pub enum TestError {
     CartridgeError(CartridgeError),
@@ -46,6 +53,31 @@ impl From<CartridgeError> for TestError {
     }
 }
 
+/// A trace writer that can be read while the emulator owns it
+#[derive(Clone, Default)]
+pub struct SharedBuffer(Rc<RefCell<Vec<u8>>>);
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl SharedBuffer {
+    pub fn lines(&self) -> Vec<String> {
+        String::from_utf8(self.0.borrow().clone())
+            .unwrap()
+            .lines()
+            .map(str::to_owned)
+            .collect()
+    }
+}
+
 pub struct NesTester {
     nes: NES,
 }
@@ -127,3 +159,24 @@ impl NesTester {
         self.nes.clock_for_frame()
     }
 }
+
+/// An NSF file with 3 tracks (starting with the second one) and `program` loaded
+/// at `$8000`, the init routine is at `$8000` and the play routine at `$8010`.
+///
+/// `region` is the region byte of the header, and `bank_init` the initial banks
+/// (all zeros if the file does not use bank switching)
+pub fn nsf_file(program: &[u8], region: u8, bank_init: [u8; 8]) -> Vec<u8> {
+    let mut nsf = b"NESM\x1A\x01\x03\x02\x00\x80\x00\x80\x10\x80".to_vec();
+    nsf.resize(0x80, 0);
+    nsf[0x0E..0x17].copy_from_slice(b"Test Song");
+    nsf[0x2E..0x36].copy_from_slice(b"Composer");
+    nsf[0x4E..0x52].copy_from_slice(b"2024");
+    // play rates: 10ms on NTSC and 20ms on PAL
+    nsf[0x6E..0x70].copy_from_slice(&10000u16.to_le_bytes());
+    nsf[0x70..0x78].copy_from_slice(&bank_init);
+    nsf[0x78..0x7A].copy_from_slice(&20000u16.to_le_bytes());
+    nsf[0x7A] = region;
+
+    nsf.extend_from_slice(program);
+    nsf
+}
@@ -0,0 +1,102 @@
+use super::{SharedBuffer, TestError};
+use crate::cpu6502::CPURegisters;
+use crate::debugger::TraceLogger;
+use crate::nes::NES;
+
+/// Run `nestest.nes` in automation mode and compare the trace with the
+/// Nintendulator log
+#[test]
+#[ignore = "needs `nestest.nes` and `nestest.log` in `test_roms/nestest`"]
+fn nestest_trace_test() -> Result<(), TestError> {
+    let golden_log = std::fs::read_to_string("../test_roms/nestest/nestest.log").unwrap();
+    let golden_lines = golden_log.lines().collect::<Vec<_>>();
+
+    let mut nes = NES::new("../test_roms/nestest/nestest.nes")?;
+
+    // automation mode starts at $C000 instead of the reset vector
+    let registers = nes.cpu_registers();
+    nes.set_cpu_registers(CPURegisters {
+        pc: 0xC000,
+        ..registers
+    });
+
+    let buffer = SharedBuffer::default();
+    nes.start_trace(TraceLogger::new(buffer.clone()));
+
+    // run until the last instruction in the log is started
+    let last_cycle = golden_lines
+        .last()
+        .and_then(|line| line.rsplit("CYC:").next())
+        .and_then(|cycle| cycle.trim().parse::<u64>().ok())
+        .unwrap();
+    while nes.cycle_counters().cpu_cycles <= last_cycle {
+        nes.clock();
+    }
+    nes.stop_trace().unwrap();
+
+    let lines = buffer.lines();
+    for (i, (line, expected)) in lines.iter().zip(golden_lines.iter()).enumerate() {
+        assert_eq!(line, expected, "trace differs at line {}", i + 1);
+    }
+    assert!(lines.len() >= golden_lines.len());
+
+    // the official and unofficial opcodes results
+    let result = nes.peek_cpu_memory(0x0002).unwrap() | nes.peek_cpu_memory(0x0003).unwrap();
+    if result != 0 {
+        Err(TestError::ResultError(result))
+    } else {
+        Ok(())
+    }
+}
+
+/// The first lines of `nestest.log`
+const NESTEST_LOG_START: [&str; 10] = [
+    "C000  4C F5 C5  JMP $C5F5                       A:00 X:00 Y:00 P:24 SP:FD PPU:  0, 21 CYC:7",
+    "C5F5  A2 00     LDX #$00                        A:00 X:00 Y:00 P:24 SP:FD PPU:  0, 30 CYC:10",
+    "C5F7  86 00     STX $00 = 00                    A:00 X:00 Y:00 P:26 SP:FD PPU:  0, 36 CYC:12",
+    "C5F9  86 10     STX $10 = 00                    A:00 X:00 Y:00 P:26 SP:FD PPU:  0, 45 CYC:15",
+    "C5FB  86 11     STX $11 = 00                    A:00 X:00 Y:00 P:26 SP:FD PPU:  0, 54 CYC:18",
+    "C5FD  20 2D C7  JSR $C72D                       A:00 X:00 Y:00 P:26 SP:FD PPU:  0, 63 CYC:21",
+    "C72D  EA        NOP                             A:00 X:00 Y:00 P:26 SP:FB PPU:  0, 81 CYC:27",
+    "C72E  38        SEC                             A:00 X:00 Y:00 P:26 SP:FB PPU:  0, 87 CYC:29",
+    "C72F  B0 04     BCS $C735                       A:00 X:00 Y:00 P:27 SP:FB PPU:  0, 93 CYC:31",
+    "C735  EA        NOP                             A:00 X:00 Y:00 P:27 SP:FB PPU:  0,102 CYC:34",
+];
+
+/// Run the first instructions of `nestest.nes`, at the same addresses,
+/// and compare the trace with the start of the Nintendulator log
+#[test]
+fn nestest_log_start_test() {
+    let mut prg = vec![0xEA; 0x4000];
+    for (address, code) in [
+        (0xC000, &[0x4C, 0xF5, 0xC5][..]),
+        (
+            0xC5F5,
+            &[
+                0xA2, 0x00, 0x86, 0x00, 0x86, 0x10, 0x86, 0x11, 0x20, 0x2D, 0xC7,
+            ],
+        ),
+        (0xC72D, &[0xEA, 0x38, 0xB0, 0x04]),
+    ] {
+        let start = address - 0xC000;
+        prg[start..start + code.len()].copy_from_slice(code);
+    }
+    // reset vector, the same state as the automation mode at $C000
+    prg[0x3FFC] = 0x00;
+    prg[0x3FFD] = 0xC0;
+
+    let mut rom = b"NES\x1A\x01\x01\x00\x00".to_vec();
+    rom.resize(16, 0);
+    rom.extend_from_slice(&prg);
+    rom.resize(rom.len() + 0x2000, 0);
+
+    let mut nes = NES::from_bytes(&rom, "nestest_start.nes").unwrap();
+    let buffer = SharedBuffer::default();
+    nes.start_trace(TraceLogger::new(buffer.clone()));
+    while nes.cycle_counters().cpu_cycles <= 34 {
+        nes.clock();
+    }
+    nes.stop_trace().unwrap();
+
+    assert_eq!(buffer.lines(), NESTEST_LOG_START);
+}
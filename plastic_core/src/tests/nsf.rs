@@ -0,0 +1,67 @@
+use super::nsf_file;
+use crate::nes::NES;
+
+/// The init routine stores `A` (the track) at $00 and `X` (the region) at $01,
+/// and the play routine counts its calls at $02
+fn nsf_test_program() -> Vec<u8> {
+    let mut program = vec![
+        0x85, 0x00, // STA $00
+        0x86, 0x01, // STX $01
+        0x60, // RTS
+    ];
+    program.resize(0x10, 0xEA);
+    program.extend_from_slice(&[
+        0xE6, 0x02, // INC $02
+        0x60, // RTS
+    ]);
+    program
+}
+
+/// The track and region the init routine was called with, after running one frame
+fn run_init(nes: &mut NES) -> (u8, u8) {
+    nes.clock_for_frame();
+
+    (
+        nes.peek_cpu_memory(0x00).unwrap(),
+        nes.peek_cpu_memory(0x01).unwrap(),
+    )
+}
+
+#[test]
+fn nsf_set_track_runs_init_test() {
+    let mut nes =
+        NES::from_bytes(&nsf_file(&nsf_test_program(), 0x00, [0; 8]), "test.nsf").unwrap();
+
+    assert_eq!(nes.nsf_tracks_count(), 3);
+    assert_eq!(nes.nsf_current_track(), Some(1));
+    assert_eq!(run_init(&mut nes), (1, 0));
+    // the play routine runs every 10ms
+    for _ in 0..59 {
+        nes.clock_for_frame();
+    }
+    assert!((99..=101).contains(&nes.peek_cpu_memory(0x02).unwrap()));
+
+    nes.nsf_set_track(5);
+    assert_eq!(nes.nsf_current_track(), Some(2));
+    assert_eq!(run_init(&mut nes), (2, 0));
+
+    // wraps around in both directions
+    nes.nsf_next_track();
+    assert_eq!(nes.nsf_current_track(), Some(0));
+    assert_eq!(run_init(&mut nes), (0, 0));
+
+    nes.nsf_previous_track();
+    assert_eq!(nes.nsf_current_track(), Some(2));
+    assert_eq!(run_init(&mut nes), (2, 0));
+}
+
+#[test]
+fn nsf_pal_region_test() {
+    let mut nes =
+        NES::from_bytes(&nsf_file(&nsf_test_program(), 0x01, [0; 8]), "test.nsf").unwrap();
+
+    assert_eq!(run_init(&mut nes), (1, 1));
+
+    nes.nsf_set_track(0);
+    assert_eq!(run_init(&mut nes), (0, 1));
+}
@@ -0,0 +1,56 @@
+use crate::nes::NES;
+use crate::tests::NesTester;
+
+/// A Vs. System NES 2.0 ROM with a RC2C05-01 PPU, running a program that
+/// pulses the coin counter once and then loops forever
+fn vs_system_rom() -> Vec<u8> {
+    let mut rom = b"NES\x1A\x01\x01\x00\x09".to_vec();
+    rom.resize(13, 0);
+    // PPU type 8 (RC2C05-01), normal Vs. UniSystem hardware
+    rom.push(0x08);
+    rom.resize(16, 0);
+
+    let mut prg = vec![0xEA; 0x4000];
+    let program = [
+        0xA9, 0x01, // LDA #$01
+        0x8D, 0x20, 0x40, // STA $4020
+        0xA9, 0x00, // LDA #$00
+        0x8D, 0x20, 0x40, // STA $4020
+        0x4C, 0x0A, 0x80, // JMP $800A
+    ];
+    prg[..program.len()].copy_from_slice(&program);
+    // reset vector
+    prg[0x3FFC] = 0x00;
+    prg[0x3FFD] = 0x80;
+
+    rom.extend_from_slice(&prg);
+    rom.resize(rom.len() + 0x2000, 0);
+    rom
+}
+
+#[test]
+fn vs_system_ports_test() {
+    let nes = NES::from_bytes(&vs_system_rom(), "vs_system.nes").unwrap();
+    let mut nes = NesTester { nes };
+
+    assert!(nes.nes.is_vs_system());
+
+    nes.nes.vs_set_dip_switches(0b1010_0110);
+    nes.nes.vs_insert_coin(0);
+
+    // coin 1 and DIP switches 1-2
+    assert_eq!(nes.cpu_read_address(0x4016), 0b0011_0000);
+    // DIP switches 3-8, the controller is not pressed
+    assert_eq!(nes.cpu_read_address(0x4017), 0b1010_0100);
+    // the RC2C05-01 ID
+    assert_eq!(nes.cpu_read_address(0x2002) & 0x1F, 0x1B);
+
+    nes.clock_until_infinite_loop();
+    assert_eq!(nes.nes.vs_coin_counter(), 1);
+
+    // the coin is released after a few frames
+    for _ in 0..4 {
+        nes.clock_for_frame();
+    }
+    assert_eq!(nes.cpu_read_address(0x4016) & 0b0110_0000, 0);
+}
@@ -34,6 +34,11 @@ use crossterm::{
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 
+/// The file extensions that can be opened from the file explorer
+const SUPPORTED_EXTENSIONS: [&str; 9] = [
+    "nes", "unf", "unif", "fds", "nsf", "nsfe", "zip", "7z", "gz",
+];
+
 fn base_save_state_folder() -> Option<PathBuf> {
     if let Some(proj_dirs) = ProjectDirs::from("Amjad50", "Plastic", "Plastic") {
         let base_saved_states_dir = proj_dirs.data_local_dir().join("saved_states");
@@ -79,6 +84,9 @@ enum MenuEvent {
 
     SaveState(u8),
     LoadState(u8),
+
+    NsfPreviousTrack,
+    NsfNextTrack,
 }
 
 pub struct Ui {
@@ -119,7 +127,7 @@ impl Ui {
                     .bg(Color::DarkGray),
             )
             .add_default_title()
-            .with_title_bottom(|_| "Select .nes file".into());
+            .with_title_bottom(|_| "Select .nes, .fds or .nsf file".into());
 
         Ui {
             nes,
@@ -206,7 +214,7 @@ impl Ui {
             }
         }
 
-        self.menu = MenuState::new(vec![
+        let mut groups = vec![
             MenuItem::group(
                 "File",
                 vec![
@@ -222,7 +230,18 @@ impl Ui {
             ),
             MenuItem::group("Save State", save_state_items),
             MenuItem::group("Load State", load_state_items),
-        ]);
+        ];
+        if self.nes.nsf_info().is_some() {
+            groups.push(MenuItem::group(
+                "Track",
+                vec![
+                    MenuItem::item("Previous", MenuEvent::NsfPreviousTrack),
+                    MenuItem::item("Next", MenuEvent::NsfNextTrack),
+                ],
+            ));
+        }
+
+        self.menu = MenuState::new(groups);
     }
 
     fn display<T: Backend>(&mut self, terminal: &mut Terminal<T>, fps: &Fps) {
@@ -268,6 +287,43 @@ impl Ui {
                         .block(block.padding(Padding::top(main.height / 2)))
                         .alignment(Alignment::Center);
                     f.render_widget(paragraph, main);
+                } else if let Some(info) = self.nes.nsf_info() {
+                    let current_track = self.nes.nsf_current_track().unwrap_or(0);
+                    let track = &info.tracks[current_track];
+                    let elapsed = self.nes.nsf_elapsed().unwrap_or_default().as_secs();
+
+                    let lines: Vec<Line> = vec![
+                        info.title.as_str().bold().into(),
+                        info.artist.as_str().into(),
+                        info.copyright.as_str().into(),
+                        "".into(),
+                        format!(
+                            "Track {}/{}{}",
+                            current_track + 1,
+                            info.tracks.len(),
+                            track
+                                .label
+                                .as_ref()
+                                .map(|label| format!(": {}", label))
+                                .unwrap_or_default()
+                        )
+                        .into(),
+                        match track.length {
+                            Some(length) => format!(
+                                "{}:{:02} / {}:{:02}",
+                                elapsed / 60,
+                                elapsed % 60,
+                                length.as_secs() / 60,
+                                length.as_secs() % 60
+                            ),
+                            None => format!("{}:{:02}", elapsed / 60, elapsed % 60),
+                        }
+                        .into(),
+                    ];
+                    let paragraph = Paragraph::new(lines)
+                        .block(block.padding(Padding::top(main.height.saturating_sub(6) / 2)))
+                        .alignment(Alignment::Center);
+                    f.render_widget(paragraph, main);
                 } else {
                     let canvas = Canvas::default()
                         .block(block)
@@ -343,7 +399,12 @@ impl Ui {
                         if self.is_file_explorer_open {
                             let file = self.file_explorer.current();
                             if !file.is_dir() {
-                                if file.path().extension().map(|e| e == "nes").unwrap_or(false) {
+                                if file
+                                    .path()
+                                    .extension()
+                                    .map(|e| SUPPORTED_EXTENSIONS.iter().any(|ext| e == *ext))
+                                    .unwrap_or(false)
+                                {
                                     let new_nes = NES::new(file.path());
                                     match new_nes {
                                         Ok(nes) => {
@@ -442,6 +503,8 @@ impl Ui {
                     MenuEvent::FileExit => return true,
                     MenuEvent::SaveState(i) => self.save_state(i),
                     MenuEvent::LoadState(i) => self.load_state(i),
+                    MenuEvent::NsfPreviousTrack => self.nes.nsf_previous_track(),
+                    MenuEvent::NsfNextTrack => self.nes.nsf_next_track(),
                 },
             }
             self.reset_menu();
@@ -501,7 +564,8 @@ impl Ui {
 
         let backend = CrosstermBackend::new(stdout);
         let mut terminal = Terminal::new(backend).unwrap();
-        let mut fps = Fps::new(61.0);
+        // running at the exact console frame rate gives audio glitches
+        let mut fps = Fps::new(self.nes.frame_rate() + 1.);
 
         loop {
             if let Some(ref mut player) = self.audio_player {
@@ -520,6 +584,8 @@ impl Ui {
                 break;
             }
             self.handle_gamepad();
+            // the loaded game may be for another region
+            fps.target_fps = self.nes.frame_rate() + 1.;
 
             if !self.paused {
                 self.nes.clock_for_frame();
@@ -0,0 +1,732 @@
+use plastic_core::{
+    debug::{BreakReason, Breakpoint, Debugger, DisassembledInstruction},
+    nes_display::{
+        NAMETABLES_HEIGHT, NAMETABLES_WIDTH, PATTERN_TABLES_HEIGHT, PATTERN_TABLES_WIDTH,
+    },
+    NES,
+};
+
+/// The sprites are shown in a grid of 8x8 sprites, each cell fits an 8x16 sprite
+const SPRITES_WIDTH: usize = 8 * 8;
+const SPRITES_HEIGHT: usize = 8 * 16;
+
+/// The number of bytes in a row of the memory editor
+const MEMORY_ROW_LEN: usize = 16;
+
+/// The number of instructions shown before and after the program counter
+const INSTRUCTIONS_BEFORE_PC: usize = 8;
+const INSTRUCTIONS_AFTER_PC: usize = 24;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MemoryRegion {
+    Cpu,
+    Ppu,
+    Oam,
+    Sram,
+}
+
+impl MemoryRegion {
+    const ALL: [Self; 4] = [Self::Cpu, Self::Ppu, Self::Oam, Self::Sram];
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Cpu => "CPU",
+            Self::Ppu => "PPU",
+            Self::Oam => "OAM",
+            Self::Sram => "SRAM",
+        }
+    }
+
+    fn size(self, nes: &NES) -> usize {
+        match self {
+            Self::Cpu => 0x10000,
+            Self::Ppu => 0x4000,
+            Self::Oam => 0x100,
+            Self::Sram => nes.prg_ram().len(),
+        }
+    }
+
+    /// Read the memory in `addresses` without side effects, `None` for the
+    /// bytes that can't be read (the CPU registers)
+    fn read(self, nes: &NES, addresses: std::ops::Range<usize>) -> Vec<Option<u8>> {
+        if addresses.is_empty() {
+            return Vec::new();
+        }
+
+        match self {
+            Self::Cpu => addresses
+                .map(|address| nes.peek_cpu_memory(address as u16))
+                .collect(),
+            Self::Ppu => nes
+                .peek_ppu_memory(addresses.start as u16..=(addresses.end - 1) as u16)
+                .into_iter()
+                .map(Some)
+                .collect(),
+            Self::Oam => nes.oam()[addresses].iter().copied().map(Some).collect(),
+            Self::Sram => nes.prg_ram()[addresses].iter().copied().map(Some).collect(),
+        }
+    }
+
+    fn write(self, nes: &mut NES, address: usize, data: u8) {
+        match self {
+            Self::Cpu => nes.poke_cpu_memory(address as u16, data),
+            Self::Ppu => nes.poke_ppu_memory(address as u16, data),
+            Self::Oam => nes.poke_oam(address as u8, data),
+            Self::Sram => nes.poke_prg_ram(address, data),
+        }
+    }
+}
+
+fn texture_options() -> egui::TextureOptions {
+    egui::TextureOptions {
+        magnification: egui::TextureFilter::Nearest,
+        minification: egui::TextureFilter::Nearest,
+        ..Default::default()
+    }
+}
+
+fn empty_texture(
+    ctx: &egui::Context,
+    name: &str,
+    width: usize,
+    height: usize,
+) -> egui::TextureHandle {
+    ctx.load_texture(
+        name,
+        egui::ColorImage::from_rgb([width, height], vec![0; width * height * 3].as_slice()),
+        texture_options(),
+    )
+}
+
+fn parse_hex(text: &str) -> Option<usize> {
+    let text = text.trim();
+    let text = text.strip_prefix('$').unwrap_or(text);
+
+    usize::from_str_radix(text, 16).ok()
+}
+
+/// Disassemble the instructions around `pc`, starting from the furthest address
+/// before it that decodes into instructions reaching `pc` exactly
+fn disassemble_around(nes: &NES, pc: u16) -> Vec<DisassembledInstruction> {
+    let end = pc.saturating_add(INSTRUCTIONS_AFTER_PC as u16 * 3);
+
+    for start in pc.saturating_sub(INSTRUCTIONS_BEFORE_PC as u16 * 3)..pc {
+        let instructions = nes.disassemble(start..=end);
+
+        if let Some(pc_index) = instructions.iter().position(|i| i.address == pc) {
+            let first = pc_index.saturating_sub(INSTRUCTIONS_BEFORE_PC);
+            return instructions
+                .into_iter()
+                .skip(first)
+                .take(INSTRUCTIONS_BEFORE_PC + INSTRUCTIONS_AFTER_PC)
+                .collect();
+        }
+    }
+
+    nes.disassemble(pc..=end)
+}
+
+/// The debugger panels, opened from the `Debug` menu and docked around the screen.
+///
+/// The CPU panel enables the debugger of the emulator while it is open,
+/// the other panels only read the emulator state.
+pub struct DebuggerPanels {
+    cpu_open: bool,
+    memory_open: bool,
+    nametables_open: bool,
+    pattern_tables_open: bool,
+    sprites_open: bool,
+    palettes_open: bool,
+
+    /// the breakpoints, kept while the CPU panel is closed
+    breakpoints: Vec<Breakpoint>,
+    breakpoint_input: String,
+
+    memory_region: MemoryRegion,
+    memory_goto: String,
+    memory_scroll_to: Option<usize>,
+    /// the address being edited and the text typed so far
+    memory_edit: Option<(usize, String)>,
+    memory_edit_started: bool,
+
+    scroll_overlay: bool,
+    pattern_tables_palette: u8,
+
+    nametables_texture: egui::TextureHandle,
+    pattern_tables_texture: egui::TextureHandle,
+    sprites_texture: egui::TextureHandle,
+}
+
+impl DebuggerPanels {
+    pub fn new(ctx: &egui::Context) -> Self {
+        Self {
+            cpu_open: false,
+            memory_open: false,
+            nametables_open: false,
+            pattern_tables_open: false,
+            sprites_open: false,
+            palettes_open: false,
+            breakpoints: Vec::new(),
+            breakpoint_input: String::new(),
+            memory_region: MemoryRegion::Cpu,
+            memory_goto: String::new(),
+            memory_scroll_to: None,
+            memory_edit: None,
+            memory_edit_started: false,
+            scroll_overlay: true,
+            pattern_tables_palette: 0,
+            nametables_texture: empty_texture(
+                ctx,
+                "nametables",
+                NAMETABLES_WIDTH,
+                NAMETABLES_HEIGHT,
+            ),
+            pattern_tables_texture: empty_texture(
+                ctx,
+                "pattern-tables",
+                PATTERN_TABLES_WIDTH,
+                PATTERN_TABLES_HEIGHT,
+            ),
+            sprites_texture: empty_texture(ctx, "sprites", SPRITES_WIDTH, SPRITES_HEIGHT),
+        }
+    }
+
+    pub fn show_menu(&mut self, ui: &mut egui::Ui) {
+        ui.menu_button("Debug", |ui| {
+            ui.checkbox(&mut self.cpu_open, "CPU");
+            ui.checkbox(&mut self.memory_open, "Memory");
+            ui.separator();
+            ui.checkbox(&mut self.nametables_open, "Nametables");
+            ui.checkbox(&mut self.pattern_tables_open, "Pattern Tables");
+            ui.checkbox(&mut self.sprites_open, "Sprites");
+            ui.checkbox(&mut self.palettes_open, "Palettes");
+        });
+    }
+
+    /// Show the open panels, `paused` is the pause state of the emulator,
+    /// the stepping commands resume it, since they run in `clock_for_frame`.
+    ///
+    /// The panels are docked around the screen, so this must be called before
+    /// showing the central panel.
+    pub fn show(&mut self, ctx: &egui::Context, nes: &mut NES, paused: &mut bool) {
+        self.update_debugger(nes);
+
+        if self.cpu_open {
+            egui::SidePanel::right("debugger_cpu")
+                .resizable(true)
+                .default_width(420.0)
+                .show(ctx, |ui| {
+                    ui.heading("CPU");
+                    self.show_cpu(ui, nes, paused);
+                });
+        }
+
+        if self.memory_open {
+            egui::TopBottomPanel::bottom("debugger_memory")
+                .resizable(true)
+                .default_height(260.0)
+                .show(ctx, |ui| {
+                    ui.heading("Memory");
+                    self.show_memory(ui, nes);
+                });
+        }
+
+        if self.nametables_open
+            || self.pattern_tables_open
+            || self.sprites_open
+            || self.palettes_open
+        {
+            egui::SidePanel::left("debugger_ppu_viewers")
+                .resizable(true)
+                .default_width(540.0)
+                .show(ctx, |ui| {
+                    egui::ScrollArea::vertical().show(ui, |ui| self.show_ppu_viewers(ui, nes));
+                });
+        }
+    }
+
+    /// The PPU viewers opened from the menu, stacked in collapsing sections
+    fn show_ppu_viewers(&mut self, ui: &mut egui::Ui, nes: &NES) {
+        if self.nametables_open {
+            egui::CollapsingHeader::new("Nametables")
+                .default_open(true)
+                .show(ui, |ui| self.show_nametables(ui, nes));
+        }
+        if self.pattern_tables_open {
+            egui::CollapsingHeader::new("Pattern Tables")
+                .default_open(true)
+                .show(ui, |ui| self.show_pattern_tables(ui, nes));
+        }
+        if self.sprites_open {
+            egui::CollapsingHeader::new("Sprites")
+                .default_open(true)
+                .show(ui, |ui| self.show_sprites(ui, nes));
+        }
+        if self.palettes_open {
+            egui::CollapsingHeader::new("Palettes")
+                .default_open(true)
+                .show(ui, |ui| self.show_palettes(ui, nes));
+        }
+    }
+
+    /// Enable the debugger while the CPU panel is open, the debugger slows down
+    /// the emulation and breakpoints would stop it without a way to resume
+    fn update_debugger(&mut self, nes: &mut NES) {
+        match (self.cpu_open, nes.debugger().is_some()) {
+            (true, false) => {
+                nes.enable_debugger();
+                let debugger = nes.debugger_mut().unwrap();
+                for breakpoint in &self.breakpoints {
+                    debugger.add_breakpoint(*breakpoint);
+                }
+            }
+            (false, true) => {
+                self.breakpoints = nes.debugger().unwrap().breakpoints().to_vec();
+                nes.disable_debugger();
+            }
+            _ => {}
+        }
+    }
+
+    fn show_cpu(&mut self, ui: &mut egui::Ui, nes: &mut NES, paused: &mut bool) {
+        let Some(debugger) = nes.debugger() else {
+            return;
+        };
+        let is_paused = debugger.is_paused();
+        let status = match debugger.break_reason() {
+            None if *paused => "Paused".to_owned(),
+            None => "Running".to_owned(),
+            Some(BreakReason::Breakpoint(index)) => format!("Breakpoint {}", index),
+            Some(BreakReason::Watchpoint {
+                space,
+                address,
+                data,
+                access,
+            }) => format!(
+                "Watchpoint: {:?} {:?} ${:04X} = ${:02X}",
+                space, access, address, data
+            ),
+            Some(BreakReason::Step) => "Step".to_owned(),
+            Some(BreakReason::Scanline(scanline)) => format!("Scanline {}", scanline),
+            Some(BreakReason::Paused) => "Paused".to_owned(),
+        };
+
+        let mut command: Option<fn(&mut Debugger)> = None;
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(is_paused, egui::Button::new("Continue"))
+                .clicked()
+            {
+                command = Some(Debugger::resume);
+            }
+            if ui
+                .add_enabled(!is_paused, egui::Button::new("Break"))
+                .clicked()
+            {
+                command = Some(Debugger::pause);
+            }
+            if ui
+                .add_enabled(is_paused, egui::Button::new("Step Into"))
+                .clicked()
+            {
+                command = Some(Debugger::step_into);
+            }
+            if ui
+                .add_enabled(is_paused, egui::Button::new("Step Over"))
+                .clicked()
+            {
+                command = Some(Debugger::step_over);
+            }
+            if ui
+                .add_enabled(is_paused, egui::Button::new("Step Out"))
+                .clicked()
+            {
+                command = Some(Debugger::step_out);
+            }
+        });
+        if let Some(command) = command {
+            command(nes.debugger_mut().unwrap());
+            *paused = false;
+        }
+        ui.label(status);
+        ui.separator();
+
+        let registers = nes.cpu_registers();
+        let counters = nes.cycle_counters();
+        let flags = "NV-BDIZC"
+            .chars()
+            .enumerate()
+            .map(|(i, flag)| {
+                if registers.status & (0x80 >> i) != 0 {
+                    flag
+                } else {
+                    '.'
+                }
+            })
+            .collect::<String>();
+        ui.monospace(format!(
+            "A:{:02X} X:{:02X} Y:{:02X} SP:{:02X} PC:{:04X} P:{:02X} {}",
+            registers.a,
+            registers.x,
+            registers.y,
+            registers.sp,
+            registers.pc,
+            registers.status,
+            flags
+        ));
+        ui.monospace(format!(
+            "Frame:{} Scanline:{} Dot:{} CPU cycles:{}",
+            counters.frames, counters.scanline, counters.dot, counters.cpu_cycles
+        ));
+        ui.separator();
+
+        ui.push_id("disassembly", |ui| {
+            egui::ScrollArea::vertical()
+                .max_height(360.0)
+                .auto_shrink([false, true])
+                .show(ui, |ui| self.show_disassembly(ui, nes, registers.pc));
+        });
+        ui.separator();
+
+        self.show_breakpoints(ui, nes);
+    }
+
+    fn show_disassembly(&mut self, ui: &mut egui::Ui, nes: &mut NES, pc: u16) {
+        let mut toggled = None;
+
+        for instruction in disassemble_around(nes, pc) {
+            if let Some(label) = &instruction.label {
+                ui.monospace(format!("{}:", label));
+            }
+
+            let breakpoint = nes
+                .debugger()
+                .unwrap()
+                .breakpoints()
+                .iter()
+                .position(|b| *b == Breakpoint::at(instruction.address));
+
+            ui.horizontal(|ui| {
+                if ui
+                    .selectable_label(breakpoint.is_some(), "●")
+                    .on_hover_text("Toggle breakpoint")
+                    .clicked()
+                {
+                    toggled = Some((instruction.address, breakpoint));
+                }
+
+                let text = egui::RichText::new(instruction.to_string()).monospace();
+                if instruction.address == pc {
+                    ui.label(text.strong().color(ui.visuals().warn_fg_color));
+                } else {
+                    ui.label(text);
+                }
+            });
+        }
+
+        if let Some((address, breakpoint)) = toggled {
+            let debugger = nes.debugger_mut().unwrap();
+            match breakpoint {
+                Some(index) => debugger.remove_breakpoint(index),
+                None => {
+                    debugger.add_breakpoint(Breakpoint::at(address));
+                }
+            }
+        }
+    }
+
+    fn show_breakpoints(&mut self, ui: &mut egui::Ui, nes: &mut NES) {
+        let debugger = nes.debugger_mut().unwrap();
+
+        ui.horizontal(|ui| {
+            ui.label("Breakpoint at");
+            let response = ui.add(
+                egui::TextEdit::singleline(&mut self.breakpoint_input)
+                    .desired_width(48.0)
+                    .font(egui::TextStyle::Monospace),
+            );
+            let submitted = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+            if ui.button("Add").clicked() || submitted {
+                if let Some(address) = parse_hex(&self.breakpoint_input) {
+                    debugger.add_breakpoint(Breakpoint::at(address as u16));
+                    self.breakpoint_input.clear();
+                }
+            }
+        });
+
+        let mut removed = None;
+        for (index, breakpoint) in debugger.breakpoints().iter().enumerate() {
+            ui.horizontal(|ui| {
+                let mut text = match breakpoint.pc {
+                    Some(pc) => format!("{}: ${:04X}", index, pc),
+                    None => format!("{}: any", index),
+                };
+                if let Some(condition) = breakpoint.condition {
+                    text += &format!(
+                        " if {:?} {:?} ${:X}",
+                        condition.register, condition.comparison, condition.value
+                    );
+                }
+                ui.monospace(text);
+                if ui.small_button("Remove").clicked() {
+                    removed = Some(index);
+                }
+            });
+        }
+        if let Some(index) = removed {
+            debugger.remove_breakpoint(index);
+        }
+    }
+
+    fn show_memory(&mut self, ui: &mut egui::Ui, nes: &mut NES) {
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_label("Region")
+                .selected_text(self.memory_region.name())
+                .show_ui(ui, |ui| {
+                    for region in MemoryRegion::ALL {
+                        if ui
+                            .selectable_value(&mut self.memory_region, region, region.name())
+                            .changed()
+                        {
+                            self.memory_edit = None;
+                        }
+                    }
+                });
+
+            ui.label("Go to");
+            let response = ui.add(
+                egui::TextEdit::singleline(&mut self.memory_goto)
+                    .desired_width(48.0)
+                    .font(egui::TextStyle::Monospace),
+            );
+            if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                self.memory_scroll_to = parse_hex(&self.memory_goto);
+            }
+        });
+        ui.separator();
+
+        let size = self.memory_region.size(nes);
+        if size == 0 {
+            ui.label(format!(
+                "No {} in this cartridge",
+                self.memory_region.name()
+            ));
+            return;
+        }
+
+        let row_height = ui.text_style_height(&egui::TextStyle::Monospace);
+        let byte_width = ui.fonts(|fonts| {
+            fonts.glyph_width(&egui::TextStyle::Monospace.resolve(ui.style()), '0') * 2.0
+        });
+
+        let mut scroll_area = egui::ScrollArea::vertical().auto_shrink([false, false]);
+        if let Some(address) = self.memory_scroll_to.take() {
+            let row = address.min(size - 1) / MEMORY_ROW_LEN;
+            scroll_area = scroll_area
+                .vertical_scroll_offset(row as f32 * (row_height + ui.spacing().item_spacing.y));
+        }
+
+        scroll_area.show_rows(ui, row_height, size.div_ceil(MEMORY_ROW_LEN), |ui, rows| {
+            let start = rows.start * MEMORY_ROW_LEN;
+            let data = self
+                .memory_region
+                .read(nes, start..(rows.end * MEMORY_ROW_LEN).min(size));
+
+            for (row, row_data) in rows.zip(data.chunks(MEMORY_ROW_LEN)) {
+                ui.horizontal(|ui| {
+                    let row_start = row * MEMORY_ROW_LEN;
+                    ui.monospace(format!("{:04X}:", row_start));
+
+                    for (i, byte) in row_data.iter().enumerate() {
+                        self.show_memory_byte(ui, nes, row_start + i, *byte, byte_width);
+                    }
+
+                    let ascii = row_data
+                        .iter()
+                        .map(|byte| match byte {
+                            Some(byte) if byte.is_ascii_graphic() => *byte as char,
+                            _ => '.',
+                        })
+                        .collect::<String>();
+                    ui.monospace(ascii);
+                });
+            }
+        });
+    }
+
+    /// Show one byte of the memory editor, clicking on it starts editing it,
+    /// and the value is written when the edit loses focus (except with Escape)
+    fn show_memory_byte(
+        &mut self,
+        ui: &mut egui::Ui,
+        nes: &mut NES,
+        address: usize,
+        byte: Option<u8>,
+        width: f32,
+    ) {
+        let edited_text = match &mut self.memory_edit {
+            Some((edit_address, text)) if *edit_address == address => Some(text),
+            _ => None,
+        };
+
+        match (edited_text, byte) {
+            (Some(text), _) => {
+                let response = ui.add(
+                    egui::TextEdit::singleline(text)
+                        .desired_width(width)
+                        .char_limit(2)
+                        .margin(egui::vec2(0.0, 0.0))
+                        .font(egui::TextStyle::Monospace),
+                );
+                let data = parse_hex(text);
+
+                if self.memory_edit_started {
+                    response.request_focus();
+                    self.memory_edit_started = false;
+                }
+
+                if response.lost_focus() {
+                    if let (false, Some(data)) =
+                        (ui.input(|i| i.key_pressed(egui::Key::Escape)), data)
+                    {
+                        self.memory_region.write(nes, address, data as u8);
+                    }
+                    self.memory_edit = None;
+                }
+            }
+            (None, Some(byte)) => {
+                let response = ui.add(
+                    egui::Label::new(egui::RichText::new(format!("{:02X}", byte)).monospace())
+                        .sense(egui::Sense::click()),
+                );
+                if response.clicked() {
+                    self.memory_edit = Some((address, format!("{:02X}", byte)));
+                    self.memory_edit_started = true;
+                }
+            }
+            (None, None) => {
+                ui.monospace("--");
+            }
+        }
+    }
+
+    fn show_nametables(&mut self, ui: &mut egui::Ui, nes: &NES) {
+        ui.checkbox(&mut self.scroll_overlay, "Show scroll position");
+
+        self.nametables_texture.set(
+            egui::ColorImage::from_rgb(
+                [NAMETABLES_WIDTH, NAMETABLES_HEIGHT],
+                &nes.nametables_buffer(self.scroll_overlay),
+            ),
+            texture_options(),
+        );
+        ui.add(
+            egui::Image::from_texture(&self.nametables_texture).fit_to_exact_size(egui::vec2(
+                NAMETABLES_WIDTH as f32,
+                NAMETABLES_HEIGHT as f32,
+            )),
+        );
+    }
+
+    fn show_pattern_tables(&mut self, ui: &mut egui::Ui, nes: &NES) {
+        ui.add(
+            egui::Slider::new(&mut self.pattern_tables_palette, 0..=7)
+                .text("Palette (4-7 sprites)"),
+        );
+
+        self.pattern_tables_texture.set(
+            egui::ColorImage::from_rgb(
+                [PATTERN_TABLES_WIDTH, PATTERN_TABLES_HEIGHT],
+                &nes.pattern_tables_buffer(self.pattern_tables_palette),
+            ),
+            texture_options(),
+        );
+        ui.add(
+            egui::Image::from_texture(&self.pattern_tables_texture).fit_to_exact_size(egui::vec2(
+                PATTERN_TABLES_WIDTH as f32 * 2.,
+                PATTERN_TABLES_HEIGHT as f32 * 2.,
+            )),
+        );
+    }
+
+    fn show_sprites(&mut self, ui: &mut egui::Ui, nes: &NES) {
+        let sprites = nes.oam_sprites();
+
+        let mut pixels = vec![0; SPRITES_WIDTH * SPRITES_HEIGHT * 3];
+        for sprite in &sprites {
+            let x = (sprite.index as usize % 8) * 8;
+            let y = (sprite.index as usize / 8) * 16;
+
+            for (row, row_pixels) in sprite.pixels.chunks(8 * 3).enumerate() {
+                let start = ((y + row) * SPRITES_WIDTH + x) * 3;
+                pixels[start..start + 8 * 3].copy_from_slice(row_pixels);
+            }
+        }
+        self.sprites_texture.set(
+            egui::ColorImage::from_rgb([SPRITES_WIDTH, SPRITES_HEIGHT], &pixels),
+            texture_options(),
+        );
+
+        ui.horizontal_top(|ui| {
+            ui.add(
+                egui::Image::from_texture(&self.sprites_texture).fit_to_exact_size(egui::vec2(
+                    SPRITES_WIDTH as f32 * 3.,
+                    SPRITES_HEIGHT as f32 * 3.,
+                )),
+            );
+
+            egui::ScrollArea::vertical()
+                .max_height(SPRITES_HEIGHT as f32 * 3.)
+                .show(ui, |ui| {
+                    egui::Grid::new("sprites").striped(true).show(ui, |ui| {
+                        for header in ["#", "X", "Y", "Tile", "Attr", "Flags"] {
+                            ui.strong(header);
+                        }
+                        ui.end_row();
+
+                        for sprite in &sprites {
+                            ui.monospace(sprite.index.to_string());
+                            ui.monospace(format!("{:02X}", sprite.x));
+                            ui.monospace(format!("{:02X}", sprite.y));
+                            ui.monospace(format!("{:02X}", sprite.tile));
+                            ui.monospace(format!("{:02X}", sprite.attributes));
+                            ui.monospace(format!(
+                                "P{} {}{}{}",
+                                sprite.palette,
+                                if sprite.flip_horizontal { 'H' } else { '.' },
+                                if sprite.flip_vertical { 'V' } else { '.' },
+                                if sprite.behind_background { 'B' } else { '.' },
+                            ));
+                            ui.end_row();
+                        }
+                    });
+                });
+        });
+    }
+
+    fn show_palettes(&mut self, ui: &mut egui::Ui, nes: &NES) {
+        let colors = nes.palette_buffer();
+        let palette_ram = nes.palette_ram();
+
+        for (title, entries) in [("Background", 0..16), ("Sprites", 16..32)] {
+            ui.label(title);
+            ui.horizontal(|ui| {
+                for entry in entries {
+                    let (rect, response) =
+                        ui.allocate_exact_size(egui::vec2(24., 24.), egui::Sense::hover());
+                    let color = &colors[entry * 3..entry * 3 + 3];
+                    ui.painter().rect_filled(
+                        rect,
+                        2.0,
+                        egui::Color32::from_rgb(color[0], color[1], color[2]),
+                    );
+                    response.on_hover_text(format!(
+                        "${:04X}: ${:02X}",
+                        0x3F00 + entry,
+                        palette_ram[entry]
+                    ));
+                }
+            });
+        }
+    }
+}
@@ -1,5 +1,9 @@
+mod debugger;
+
 use std::{fs, path::PathBuf};
 
+use debugger::DebuggerPanels;
+
 use directories::ProjectDirs;
 use dynwave::AudioPlayer;
 use gilrs::{Button, Event as GilrsEvent, EventType, Gilrs};
@@ -7,11 +11,20 @@ use plastic_core::{
     misc::{process_audio, Fps},
     nes_audio::SAMPLE_RATE,
     nes_display::{TV_HEIGHT, TV_WIDTH},
+    nsf::NsfInfo,
     NESKey, NES,
 };
 
-// 60 FPS gives audio glitches
-const TARGET_FPS: f64 = 61.;
+/// The emulation speed (at 1x) for the region of `nes`,
+/// running at the exact console frame rate gives audio glitches
+fn base_fps(nes: &NES) -> f64 {
+    nes.frame_rate() + 1.
+}
+
+/// The file extensions that can be opened or dropped into the window
+const SUPPORTED_EXTENSIONS: [&str; 9] = [
+    "nes", "unf", "unif", "fds", "nsf", "nsfe", "zip", "7z", "gz",
+];
 
 const MIN_STATE_SLOT: u8 = 0;
 const MAX_STATE_SLOT: u8 = 9;
@@ -48,12 +61,13 @@ struct App {
     active_gamepad: Option<gilrs::GamepadId>,
     image_texture: egui::TextureHandle,
     paused: bool,
+    debugger: DebuggerPanels,
 }
 
 impl App {
     pub fn new(ctx: &egui::Context, nes: NES) -> Self {
         Self {
-            fps: Fps::new(TARGET_FPS),
+            fps: Fps::new(base_fps(&nes)),
             nes,
             audio_player: AudioPlayer::new(SAMPLE_RATE, dynwave::BufferSize::QuarterSecond).ok(),
             gilrs: Gilrs::new().ok(),
@@ -71,6 +85,7 @@ impl App {
                     ..Default::default()
                 },
             ),
+            debugger: DebuggerPanels::new(ctx),
         }
     }
 
@@ -157,13 +172,20 @@ impl App {
                     .dropped_files
                     .iter()
                     .filter_map(|f| f.path.as_ref())
-                    .find(|f| f.extension().map(|e| e == "nes").unwrap_or(false));
+                    .find(|f| {
+                        f.extension()
+                            .map(|e| SUPPORTED_EXTENSIONS.iter().any(|ext| e == *ext))
+                            .unwrap_or(false)
+                    });
 
                 if let Some(file) = file {
-                    self.nes = NES::new(file).unwrap();
+                    self.set_nes(NES::new(file).unwrap());
                 } else {
                     // convert to error alert
-                    println!("[ERROR] Dropped file is not a NES ROM, must have .nes extension");
+                    println!(
+                        "[ERROR] Dropped file is not a NES ROM, must have one of {:?} extensions",
+                        SUPPORTED_EXTENSIONS
+                    );
                 }
             }
             if !i.focused {
@@ -184,7 +206,7 @@ impl App {
                 }
             }
             if i.consume_shortcut(&CLOSE_SHORTCUT) {
-                self.nes = NES::new_without_file();
+                self.set_nes(NES::new_without_file());
             }
 
             if !self.nes.is_empty() {
@@ -224,13 +246,20 @@ impl App {
         ctx.send_viewport_cmd(egui::ViewportCommand::Title(title));
     }
 
+    /// Replace the emulator, keeping the same speed relative to the new region's frame rate
+    fn set_nes(&mut self, nes: NES) {
+        let speed = self.fps.target_fps / base_fps(&self.nes);
+        self.nes = nes;
+        self.fps.target_fps = base_fps(&self.nes) * speed;
+    }
+
     fn open_file(&mut self) {
         if let Some(file) = rfd::FileDialog::new()
             .set_title("Open NES ROM")
-            .add_filter("NES ROM", &["nes"])
+            .add_filter("NES ROM", &SUPPORTED_EXTENSIONS)
             .pick_file()
         {
-            self.nes = NES::new(file).unwrap();
+            self.set_nes(NES::new(file).unwrap());
         }
     }
 
@@ -276,7 +305,7 @@ impl App {
                     )
                     .clicked()
                 {
-                    self.nes = NES::new_without_file();
+                    self.set_nes(NES::new_without_file());
                 }
                 if ui.button("Exit").clicked() {
                     ui.ctx().send_viewport_cmd(egui::ViewportCommand::Close);
@@ -311,14 +340,106 @@ impl App {
                     }
                 }
             });
+            if self.nes.disk_sides_count() > 0 {
+                ui.menu_button("Disk", |ui| {
+                    let inserted = self.nes.inserted_disk_side();
+                    if ui
+                        .add(egui::Button::new("Eject").selected(inserted.is_none()))
+                        .clicked()
+                    {
+                        self.nes.eject_disk();
+                    }
+                    for side in 0..self.nes.disk_sides_count() {
+                        if ui
+                            .add(
+                                egui::Button::new(format!(
+                                    "Disk {} Side {}",
+                                    side / 2 + 1,
+                                    if side & 1 == 0 { 'A' } else { 'B' }
+                                ))
+                                .selected(inserted == Some(side)),
+                            )
+                            .clicked()
+                        {
+                            self.nes.insert_disk(side);
+                        }
+                    }
+                });
+            }
+            if self.nes.is_vs_system() {
+                ui.menu_button("Vs. System", |ui| {
+                    for slot in 0..2 {
+                        if ui.button(format!("Insert Coin {}", slot + 1)).clicked() {
+                            self.nes.vs_insert_coin(slot);
+                        }
+                    }
+                    let service = ui.button("Service");
+                    self.nes
+                        .vs_set_service_button(service.is_pointer_button_down_on());
+                    ui.label(format!("Coin counter: {}", self.nes.vs_coin_counter()));
+                    ui.separator();
+
+                    let mut dip_switches = self.nes.vs_dip_switches();
+                    for i in 0..8 {
+                        let mut on = dip_switches & (1 << i) != 0;
+                        if ui.checkbox(&mut on, format!("DIP {}", i + 1)).changed() {
+                            dip_switches ^= 1 << i;
+                        }
+                    }
+                    self.nes.vs_set_dip_switches(dip_switches);
+                });
+            }
             ui.menu_button("Speed", |ui| {
-                let mut speed = self.fps.target_fps / TARGET_FPS;
+                let mut speed = self.fps.target_fps / base_fps(&self.nes);
                 ui.add(
                     egui::Slider::new(&mut speed, 0.1..=10.0)
                         .text("Emulation Speed")
                         .clamping(egui::SliderClamping::Always),
                 );
-                self.fps.target_fps = TARGET_FPS * speed;
+                self.fps.target_fps = base_fps(&self.nes) * speed;
+            });
+            self.debugger.show_menu(ui);
+        });
+    }
+
+    /// Show the NSF player instead of the screen, since NSF files only play music
+    fn show_nsf_player(&mut self, ui: &mut egui::Ui, info: NsfInfo) {
+        let current_track = self.nes.nsf_current_track().unwrap_or(0);
+        let track = &info.tracks[current_track];
+        let elapsed = self.nes.nsf_elapsed().unwrap_or_default().as_secs();
+
+        ui.vertical_centered(|ui| {
+            ui.heading(&info.title);
+            ui.label(&info.artist);
+            ui.label(&info.copyright);
+            ui.separator();
+            ui.label(format!(
+                "Track {}/{}{}",
+                current_track + 1,
+                info.tracks.len(),
+                track
+                    .label
+                    .as_ref()
+                    .map(|label| format!(": {}", label))
+                    .unwrap_or_default()
+            ));
+            ui.label(match track.length {
+                Some(length) => format!(
+                    "{}:{:02} / {}:{:02}",
+                    elapsed / 60,
+                    elapsed % 60,
+                    length.as_secs() / 60,
+                    length.as_secs() % 60
+                ),
+                None => format!("{}:{:02}", elapsed / 60, elapsed % 60),
+            });
+            ui.horizontal(|ui| {
+                if ui.button("Previous").clicked() {
+                    self.nes.nsf_previous_track();
+                }
+                if ui.button("Next").clicked() {
+                    self.nes.nsf_next_track();
+                }
             });
         });
     }
@@ -345,7 +466,7 @@ impl eframe::App for App {
                 if let Some(audio_player) = &mut self.audio_player {
                     audio_player.queue(&process_audio(
                         &audio_buffer,
-                        (TARGET_FPS / self.fps.target_fps) as f32,
+                        (base_fps(&self.nes) / self.fps.target_fps) as f32,
                     ));
                     audio_player.play().unwrap();
                 }
@@ -354,8 +475,15 @@ impl eframe::App for App {
             audio_player.pause().unwrap();
         }
 
+        // the debugger panels are docked around the screen
+        self.debugger.show(ctx, &mut self.nes, &mut self.paused);
+
         egui::CentralPanel::default().show(ctx, |ui| {
             self.show_menu(ui);
+            if let Some(info) = self.nes.nsf_info() {
+                self.show_nsf_player(ui, info);
+                return;
+            }
             ui.centered_and_justified(|ui| {
                 if !self.nes.is_empty() {
                     {